@@ -0,0 +1,88 @@
+//! Keeps the frames within their time budget on slower machines. The time the work of a frame takes is averaged,
+//! when it exceeds the budget the fields are computed at a lower resolution and when there is headroom again the
+//! resolution climbs back. The iteration limit is a compile time constant and stays untouched.
+
+use crate::config::CONFIG;
+
+/// The weight of the latest frame in the average frame time.
+const AVERAGE_WEIGHT: f64 = 0.1;
+
+/// Above this share of the budget the resolution is lowered.
+const OVER_BUDGET: f64 = 1.05;
+
+/// Below this share of the budget the resolution is raised again.
+const HEADROOM: f64 = 0.7;
+
+/// The factor the resolution changes by per step.
+const SCALE_STEP: f32 = 0.9;
+
+/// The frames between two changes of the resolution, so the average can settle.
+const SETTLE_FRAMES: u32 = 10;
+
+/// The frame rate the budget is derived from if there is no cap.
+const DEFAULT_FPS: f64 = 60.0;
+
+/// The state of the adaptive resolution.
+#[derive(Debug)]
+pub struct AdaptiveQuality {
+    /// The fraction of the window resolution the fields are computed in.
+    scale: f32,
+    /// The average time of the work of a frame in seconds.
+    average: f64,
+    /// The frames since the last change.
+    since_change: u32,
+}
+
+impl Default for AdaptiveQuality {
+    fn default() -> Self {
+        AdaptiveQuality {
+            scale: 1.0,
+            average: 0.0,
+            since_change: 0,
+        }
+    }
+}
+
+impl AdaptiveQuality {
+    /// The fraction of the window resolution the fields are computed in.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Takes the time the work of the last frame took in seconds and adapts the resolution.
+    pub fn update(&mut self, work_time: f64) {
+        if !CONFIG.adaptive_quality {
+            self.scale = 1.0;
+            return;
+        }
+        self.average += (work_time - self.average) * AVERAGE_WEIGHT;
+        self.since_change += 1;
+        if self.since_change < SETTLE_FRAMES {
+            return;
+        }
+
+        let budget = frame_budget();
+        let min_scale = CONFIG.adaptive_min_scale.clamp(0.1, 1.0);
+        let scale = if self.average > budget * OVER_BUDGET {
+            (self.scale * SCALE_STEP).max(min_scale)
+        } else if self.average < budget * HEADROOM {
+            (self.scale / SCALE_STEP).min(1.0)
+        } else {
+            self.scale
+        };
+        if scale != self.scale {
+            self.scale = scale;
+            self.since_change = 0;
+        }
+    }
+}
+
+/// The time the work of a frame may take in seconds, from the frame rate cap if there is one.
+fn frame_budget() -> f64 {
+    let fps = if CONFIG.fps_cap > 0.0 {
+        CONFIG.fps_cap
+    } else {
+        DEFAULT_FPS
+    };
+    1.0 / fps
+}
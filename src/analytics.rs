@@ -0,0 +1,213 @@
+//! The focus analytics enabled with `--analytics report.txt`. Over a long run it collects the scores of the
+//! autofocus targets and the start points, what the autofocus did with its targets, and why and after how long the
+//! dives ended. The report is rewritten after every dive, so the score thresholds and the hysteresis can be tuned
+//! with data from the running explorer instead of guesswork.
+
+use crate::config::CONFIG;
+use crate::focus_system::Retargets;
+use crate::zoom_director::{DiveEnd, DiveSummary, ZoomDirector, ZoomState};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// The number of buckets of the score histograms, each one twice as wide as the one before.
+const SCORE_BUCKETS: usize = 14;
+
+/// The width of the longest bar of the histograms in characters.
+const BAR_WIDTH: f64 = 40.0;
+
+/// Counts scores in buckets doubling in width, as the scores spread over several orders of magnitude.
+#[derive(Debug, Clone, Default)]
+struct ScoreHistogram {
+    /// The amount of scores per bucket, the first one holds everything below 1.
+    counts: [u64; SCORE_BUCKETS],
+    /// The sum of the scores.
+    sum: f64,
+    /// The lowest score.
+    min: f32,
+    /// The highest score.
+    max: f32,
+}
+
+impl ScoreHistogram {
+    /// Counts the score.
+    fn add(&mut self, score: f32) {
+        let bucket = if score < 1.0 {
+            0
+        } else {
+            (score.log2() as usize + 1).min(SCORE_BUCKETS - 1)
+        };
+        if self.total() == 0 {
+            (self.min, self.max) = (score, score);
+        }
+        self.counts[bucket] += 1;
+        self.sum += score as f64;
+        self.min = self.min.min(score);
+        self.max = self.max.max(score);
+    }
+
+    /// The amount of scores counted.
+    fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Writes the summary and a bar per bucket.
+    fn write(&self, report: &mut String) {
+        let total = self.total();
+        if total == 0 {
+            report.push_str("  none yet\n");
+            return;
+        }
+        let _ = writeln!(
+            report,
+            "  {total} scores, min {:.1}, mean {:.1}, max {:.1}",
+            self.min,
+            self.sum / total as f64,
+            self.max
+        );
+        let largest = self.counts.iter().copied().max().unwrap_or(1) as f64;
+        for (bucket, &count) in self.counts.iter().enumerate() {
+            let low = if bucket == 0 { 0 } else { 1u32 << (bucket - 1) };
+            let range = if bucket == SCORE_BUCKETS - 1 {
+                format!("{low}+")
+            } else {
+                format!("{low}-{}", 1u32 << bucket)
+            };
+            let bar = "#".repeat((count as f64 / largest * BAR_WIDTH).round() as usize);
+            let _ = writeln!(
+                report,
+                "  {range:>11} {count:>9} {:>5.1}% {bar}",
+                count as f64 / total as f64 * 100.0
+            );
+        }
+    }
+}
+
+/// Collects the statistics of the run and writes the report.
+#[derive(Debug)]
+pub struct FocusAnalytics {
+    /// The file the report goes to.
+    path: PathBuf,
+    /// The time the collection started.
+    start: f64,
+    /// The dives that ended.
+    dives: Vec<DiveSummary>,
+    /// The scores of the autofocus targets, sampled every frame of the dives.
+    target_scores: ScoreHistogram,
+}
+
+impl FocusAnalytics {
+    /// Starts collecting for the report in the file.
+    pub fn new(path: PathBuf, time: f64) -> FocusAnalytics {
+        FocusAnalytics {
+            path,
+            start: time,
+            dives: Vec::new(),
+            target_scores: ScoreHistogram::default(),
+        }
+    }
+
+    /// Samples the director after its update and rewrites the report once a dive ended.
+    pub fn observe(&mut self, director: &mut ZoomDirector, time: f64) -> std::io::Result<()> {
+        if director.zoom_state() == ZoomState::ZoomingInAndFollowing
+            && let Some(score) = director.focus_score()
+        {
+            self.target_scores.add(score);
+        }
+        match director.take_finished_dive() {
+            Some(dive) => {
+                self.dives.push(dive);
+                self.write(time)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the report for the run up to the time.
+    pub fn write(&self, time: f64) -> std::io::Result<()> {
+        std::fs::write(&self.path, self.report(time))
+    }
+
+    /// Formats the report.
+    fn report(&self, time: f64) -> String {
+        let mut report = String::new();
+        let _ = writeln!(
+            report,
+            "Focus analytics over {} dives in {:.0} s\n",
+            self.dives.len(),
+            time - self.start
+        );
+
+        report.push_str("Dive ends\n");
+        for end in DiveEnd::ALL {
+            let count = self.dives.iter().filter(|dive| dive.end == end).count();
+            let _ = writeln!(report, "  {:<12} {count:>6}", end.name());
+        }
+
+        report.push_str("\nDive durations in seconds\n");
+        write_spread(
+            &mut report,
+            self.dives.iter().map(|dive| dive.duration).collect(),
+        );
+        report.push_str("\nDive depths in halvings of the radius\n");
+        write_spread(
+            &mut report,
+            self.dives.iter().map(|dive| dive.depth as f64).collect(),
+        );
+
+        let start_scores: Vec<f32> = self
+            .dives
+            .iter()
+            .filter_map(|dive| dive.start_score)
+            .collect();
+        let fallbacks = start_scores
+            .iter()
+            .filter(|&&score| score <= CONFIG.start_min_score)
+            .count();
+        let _ = writeln!(
+            report,
+            "\nStart point scores, {fallbacks} of {} searches found nothing above the minimum of \
+             {} and used the fallback point",
+            start_scores.len(),
+            CONFIG.start_min_score
+        );
+        let mut histogram = ScoreHistogram::default();
+        for &score in &start_scores {
+            histogram.add(score);
+        }
+        histogram.write(&mut report);
+
+        report.push_str("\nAutofocus target scores, sampled every frame of the dives\n");
+        self.target_scores.write(&mut report);
+
+        let retargets = self
+            .dives
+            .iter()
+            .fold(Retargets::default(), |mut sum, dive| {
+                sum += dive.retargets;
+                sum
+            });
+        let _ = writeln!(
+            report,
+            "\nAutofocus scans\n  kept the target   {:>9}\n  outscored         {:>9}\n  target left view  {:>9}",
+            retargets.kept, retargets.outscored, retargets.left_view
+        );
+        report
+    }
+}
+
+/// Writes minimum, median, mean and maximum of the values.
+fn write_spread(report: &mut String, mut values: Vec<f64>) {
+    if values.is_empty() {
+        report.push_str("  none yet\n");
+        return;
+    }
+    values.sort_by(f64::total_cmp);
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let _ = writeln!(
+        report,
+        "  min {:.1}, median {:.1}, mean {mean:.1}, max {:.1}",
+        values[0],
+        values[values.len() / 2],
+        values[values.len() - 1]
+    );
+}
@@ -0,0 +1,140 @@
+//! Keeps the frames with the highest autofocus scores of every dive and saves them into the gallery directory once
+//! the dive ends, each as PNG with a JSON file next to it holding the view, the depth, the palette and the score. An
+//! unattended run leaves behind a curated set of stills this way.
+
+use crate::color_generation::Palette;
+use crate::config::CONFIG;
+use crate::export::{GALLERY_DIRECTORY, ViewMetadata, save_png, time_stamp};
+use crate::math::Fractal;
+use crate::transition::START_RADIUS;
+use crate::zoom_director::{ZoomDirector, ZoomState};
+use macroquad::texture::Image;
+use std::path::Path;
+
+/// The time between two frames considered for the gallery in seconds, so the stills of a dive differ.
+const SAMPLE_INTERVAL: f64 = 2.0;
+
+/// A frame kept as candidate for the gallery.
+struct Candidate {
+    /// The score of the autofocus target in the frame.
+    score: f32,
+    /// The view of the frame.
+    metadata: ViewMetadata,
+    /// The width of the frame in pixels.
+    width: u32,
+    /// The height of the frame in pixels.
+    height: u32,
+    /// The frame as RGBA8 buffer.
+    pixels: Vec<u8>,
+}
+
+impl Candidate {
+    /// Formats the description of the frame as JSON object.
+    fn to_json(&self) -> String {
+        let ViewMetadata {
+            fractal,
+            viewport,
+            palette,
+        } = self.metadata;
+        let julia = match fractal {
+            Fractal::Mandelbrot => "null".to_owned(),
+            Fractal::Julia(c) => format!("[{},{}]", c.real, c.imag),
+        };
+        format!(
+            "{{\"real\":{},\"imag\":{},\"radius\":{:e},\"depth\":{:.2},\"julia\":{julia},\"palette\":\"{}\",\
+             \"score\":{}}}\n",
+            viewport.center.real,
+            viewport.center.imag,
+            viewport.radius,
+            (START_RADIUS / viewport.radius).log2().max(0.0),
+            palette.name(),
+            self.score
+        )
+    }
+}
+
+/// Collects the best frames of the current dive.
+#[derive(Default)]
+pub struct BestOfGallery {
+    /// The best frames so far, sorted by descending score.
+    candidates: Vec<Candidate>,
+    /// The time the last frame was considered.
+    last_sample: f64,
+}
+
+impl BestOfGallery {
+    /// Considers the frame while the autofocus follows a target and saves the best ones once the dive is over.
+    pub fn observe(&mut self, director: &ZoomDirector, palette: Palette, image: &Image, time: f64) {
+        if director.zoom_state() != ZoomState::ZoomingInAndFollowing {
+            if !self.candidates.is_empty() {
+                let candidates = std::mem::take(&mut self.candidates);
+                // Encoding the PNGs takes a moment, so we do not stall the render loop.
+                std::thread::spawn(move || save_candidates(&candidates));
+            }
+            return;
+        }
+        let Some(score) = director.focus_score() else {
+            return;
+        };
+        if time - self.last_sample < SAMPLE_INTERVAL {
+            return;
+        }
+        self.last_sample = time;
+        let count = CONFIG.best_of;
+        let index = self
+            .candidates
+            .partition_point(|candidate| candidate.score >= score);
+        if index >= count {
+            return;
+        }
+        self.candidates.insert(
+            index,
+            Candidate {
+                score,
+                metadata: ViewMetadata {
+                    fractal: director.fractal(),
+                    viewport: director.viewport(),
+                    palette,
+                },
+                width: image.width as u32,
+                height: image.height as u32,
+                pixels: image.bytes.clone(),
+            },
+        );
+        self.candidates.truncate(count);
+    }
+
+    /// Saves the best frames of the dive in progress, as done when the explorer closes.
+    pub fn finish(self) {
+        save_candidates(&self.candidates);
+    }
+}
+
+/// Writes the frames and their descriptions into the gallery directory, ranked by score.
+fn save_candidates(candidates: &[Candidate]) {
+    if candidates.is_empty() {
+        return;
+    }
+    let stamp = time_stamp();
+    let result = std::fs::create_dir_all(GALLERY_DIRECTORY).and_then(|_| {
+        for (rank, candidate) in candidates.iter().enumerate() {
+            let stem = Path::new(GALLERY_DIRECTORY).join(format!("best_{stamp}_{}", rank + 1));
+            save_png(
+                &stem.with_extension("png"),
+                candidate.width,
+                candidate.height,
+                &candidate.pixels,
+                Some(&candidate.metadata),
+            )?;
+            std::fs::write(stem.with_extension("json"), candidate.to_json())?;
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => println!(
+            "Saved the {} best frames of the dive to {GALLERY_DIRECTORY}",
+            candidates.len()
+        ),
+        Err(err) => eprintln!("Could not save the best frames of the dive: {err}"),
+    }
+}
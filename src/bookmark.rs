@@ -0,0 +1,78 @@
+//! Exported PNGs double as bookmarks. Dropping one onto the window, or passing it with `--bookmark shot.png`, reads
+//! the view from its text chunks and flies there: the Mandelbrot views are dived into at their center and radius,
+//! the Julia views start a dive through their Julia set. The iteration limit of the image is restored, and the palette
+//! if it is known.
+
+use crate::color_generation::Palette;
+use crate::commands::Command;
+use crate::math::ComplexNumber;
+use macroquad::input::get_dropped_files;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Adds the commands for the PNG files dropped onto the window since the last frame. Files without a view are
+/// reported.
+pub fn poll_dropped_files(commands: &mut Vec<Command>) {
+    for file in get_dropped_files() {
+        let name = file.path.as_ref().map_or_else(
+            || "the dropped file".to_owned(),
+            |path| path.display().to_string(),
+        );
+        let bytes = match (file.bytes, &file.path) {
+            (Some(bytes), _) => Ok(bytes),
+            (None, Some(path)) => std::fs::read(path).map_err(|err| err.to_string()),
+            (None, None) => Err("no content".to_owned()),
+        };
+        match bytes.and_then(|bytes| bookmark_commands(&bytes)) {
+            Ok(bookmark) => commands.extend(bookmark),
+            Err(err) => eprintln!("Could not open {name} as bookmark: {err}"),
+        }
+    }
+}
+
+/// The commands that fly to the view stored in the PNG file.
+pub fn load_bookmark(path: &Path) -> Result<Vec<Command>, String> {
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    bookmark_commands(&bytes)
+}
+
+/// The commands that fly to the view stored in the PNG, see [`crate::export::ViewMetadata`].
+fn bookmark_commands(png: &[u8]) -> Result<Vec<Command>, String> {
+    let reader = png::Decoder::new(Cursor::new(png))
+        .read_info()
+        .map_err(|err| err.to_string())?;
+    let texts = &reader.info().uncompressed_latin1_text;
+    let text = |keyword: &str| {
+        texts
+            .iter()
+            .find(|chunk| chunk.keyword == keyword)
+            .map(|chunk| chunk.text.as_str())
+    };
+    let complex = |keyword: &str| {
+        let (real, imag) = text(keyword)?.split_once(',')?;
+        Some(ComplexNumber::new(
+            real.trim().parse().ok()?,
+            imag.trim().parse().ok()?,
+        ))
+    };
+
+    let mut commands = Vec::new();
+    if let Some(limit) = text("max-iter").and_then(|limit| limit.parse().ok()) {
+        commands.push(Command::SetMaxIter(limit));
+    }
+    if let Some(palette) = text("palette").and_then(Palette::from_name) {
+        commands.push(Command::SetPalette(palette));
+    }
+    if text("fractal") == Some("julia") {
+        let c = complex("julia").ok_or("the Julia parameter is missing")?;
+        commands.push(Command::JuliaDive(c));
+    } else {
+        let center = complex("center").ok_or("the image carries no view")?;
+        let radius = text("radius")
+            .and_then(|radius| radius.parse().ok())
+            .ok_or("the radius is missing")?;
+        let center_low = complex("center-low").unwrap_or_default();
+        commands.push(Command::GoTo(center, center_low, radius));
+    }
+    Ok(commands)
+}
@@ -0,0 +1,90 @@
+//! A large clock with the date above the fractal, for displays that serve as wall clock in offices. Position, size
+//! and 12 or 24 hour format are configurable, the text gets a shadow so it stays readable on bright colors.
+
+use crate::config::CONFIG;
+use crate::night::dimmed;
+use crate::schedule::local_time;
+use macroquad::prelude::*;
+
+/// The size of the date relative to the time.
+const DATE_SCALE: f32 = 0.35;
+
+/// The distance of the clock to the screen border relative to its size.
+const BORDER_SCALE: f32 = 0.3;
+
+/// The offset of the shadow relative to the size of the text.
+const SHADOW_SCALE: f32 = 0.03;
+
+/// The color of the shadow.
+const SHADOW_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+
+/// The names of the days of the week, starting on Sunday.
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// The names of the months.
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Draws the clock with the configured position, size and format, dimmed to the brightness.
+pub fn draw_clock(brightness: f32) {
+    let now = local_time();
+    let time = if CONFIG.clock_12h {
+        let hour = (now.hour + 11) % 12 + 1;
+        let suffix = if now.hour < 12 { "AM" } else { "PM" };
+        format!("{hour}:{:02} {suffix}", now.minute)
+    } else {
+        format!("{:02}:{:02}", now.hour, now.minute)
+    };
+    let date = format!(
+        "{}, {} {} {}",
+        WEEKDAYS[now.weekday as usize % 7],
+        now.day,
+        MONTHS[(now.month as usize + 11) % 12],
+        now.year
+    );
+
+    let size = CONFIG.clock_size;
+    let date_size = size * DATE_SCALE;
+    let time_extent = measure_text(&time, None, size as u16, 1.0);
+    let date_extent = measure_text(&date, None, date_size as u16, 1.0);
+    let width = time_extent.width.max(date_extent.width);
+    let height = time_extent.height + date_size;
+    let (left, top) = CONFIG
+        .clock_position
+        .place(width, height, size * BORDER_SCALE);
+    // Both lines are centered on the wider one.
+    let time_x = left + (width - time_extent.width) * 0.5;
+    let time_y = top + time_extent.offset_y;
+    let date_x = left + (width - date_extent.width) * 0.5;
+    let date_y = top + height;
+    let color = dimmed(WHITE, brightness);
+    draw_shadowed_text(&time, time_x, time_y, size, color);
+    draw_shadowed_text(&date, date_x, date_y, date_size, color);
+}
+
+/// Draws the text over its shadow.
+fn draw_shadowed_text(text: &str, x: f32, y: f32, size: f32, color: Color) {
+    let offset = (size * SHADOW_SCALE).max(1.0);
+    draw_text(text, x + offset, y + offset, size, SHADOW_COLOR);
+    draw_text(text, x, y, size, color);
+}
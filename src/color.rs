@@ -0,0 +1,45 @@
+//! The color the palettes hand out and the colored fields are made of. It has the channel layout of the screen
+//! image, which the explorer converts it into when uploading a frame.
+
+/// An sRGB encoded color with its channels and alpha in [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Color {
+    /// The red channel.
+    pub r: f32,
+    /// The green channel.
+    pub g: f32,
+    /// The blue channel.
+    pub b: f32,
+    /// The opacity.
+    pub a: f32,
+}
+
+/// The color of the points in the set.
+pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+
+impl Color {
+    /// Constructor.
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /// The color of 8 bit channels.
+    pub fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color::new(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        )
+    }
+}
+
+/// Converts a color into RGBA8 the same way the screen image does.
+pub fn color_to_rgba8(color: Color) -> [u8; 4] {
+    [
+        (color.r * 255.0) as u8,
+        (color.g * 255.0) as u8,
+        (color.b * 255.0) as u8,
+        (color.a * 255.0) as u8,
+    ]
+}
@@ -1,33 +1,456 @@
 //! Module is responsible for mapping the iteration field to a color value. It us using a cyclicle color map here.
+//!
+//! All colors handed out are sRGB encoded. Interpolating and averaging happens in linear light, otherwise mixed
+//! colors come out too dark.
 
-use crate::math::MAX_ITER;
-use macroquad::color::{BLACK, Color};
-use std::sync::LazyLock;
+use crate::color::{BLACK, Color};
+use crate::colormaps::Cubehelix;
+use crate::gradient::Gradient;
+use crate::image_palette::ImagePalette;
+use crate::math::{Escape, OrbitStatistic, OrbitTracking};
+use crate::normalization::IterationRange;
+use crate::palette_registry::PALETTES;
+use crate::profile_scope;
+use rayon::prelude::*;
 
-/// The amount of complete cycles we do on the hue for the complete stretch.
+/// The default amount of complete cycles we do on the hue for the complete stretch.
 const HUE_CYCLES: f32 = 10.0;
-/// The light intensity we use on the color.
+/// The default light intensity we use on the color.
 const COLOR_VALUE: f32 = 0.8;
-/// The color saturation we use.
+/// The default color saturation we use.
 const COLOR_SATURATION: f32 = 0.8;
+/// The range the amount of hue cycles can be tuned in.
+const HUE_CYCLES_RANGE: (f32, f32) = (1.0, 50.0);
 
-/// The lookup table for all entries as static array.
-static COLOR_ARRAY: LazyLock<Vec<Color>> = LazyLock::new(create_all_colors);
+/// The step within the palette cycle between two neighboring atom domains. The golden ratio keeps the colors of
+/// nearby periods apart.
+const ATOM_DOMAIN_STEP: f32 = 0.618_034;
+
+/// The brightness factor for the darker half of the binary decomposition.
+const BINARY_DECOMPOSITION_SHADE: f32 = 0.55;
+
+/// How the points outside the set are colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Coloring {
+    /// The palette color of the iteration count.
+    #[default]
+    Iteration,
+    /// The iteration color, darkened where the orbit escapes below the real axis. Gives the classic checker pattern.
+    BinaryDecomposition,
+    /// The triangle inequality average along the orbit picks the position within the palette cycle.
+    TriangleInequality,
+    /// The stripe average along the orbit picks the position within the palette cycle, which traces filaments.
+    Stripes,
+    /// The iteration at which the orbit comes closest to zero picks the color, inside and outside the set. Reveals
+    /// the cells around the minibrots, every cell belongs to the period of the minibrot in it.
+    AtomDomain,
+}
+
+impl Coloring {
+    /// All colorings in cycling order.
+    pub const ALL: [Coloring; 5] = [
+        Coloring::Iteration,
+        Coloring::BinaryDecomposition,
+        Coloring::TriangleInequality,
+        Coloring::Stripes,
+        Coloring::AtomDomain,
+    ];
+
+    /// Gets the coloring following this one.
+    pub fn next(self) -> Coloring {
+        Coloring::ALL[(self as usize + 1) % Coloring::ALL.len()]
+    }
+
+    /// The human readable name of the coloring.
+    pub fn name(self) -> &'static str {
+        match self {
+            Coloring::Iteration => "iteration",
+            Coloring::BinaryDecomposition => "binary",
+            Coloring::TriangleInequality => "tia",
+            Coloring::Stripes => "stripes",
+            Coloring::AtomDomain => "atom",
+        }
+    }
+
+    /// Looks up a coloring by its name.
+    pub fn from_name(name: &str) -> Option<Coloring> {
+        Coloring::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    /// Whether the coloring needs more than the iteration count, see [`generate_escape_colors`].
+    pub fn needs_escape(self) -> bool {
+        self != Coloring::Iteration
+    }
+
+    /// The statistic that has to be accumulated along the orbit for this coloring.
+    pub fn orbit_statistic(self, color_settings: &ColorSettings) -> OrbitStatistic {
+        match self {
+            Coloring::Iteration | Coloring::BinaryDecomposition | Coloring::AtomDomain => {
+                OrbitStatistic::None
+            }
+            Coloring::TriangleInequality => OrbitStatistic::TriangleInequality,
+            Coloring::Stripes => OrbitStatistic::Stripes(color_settings.stripe_frequency),
+        }
+    }
+}
+
+/// The parameters of the colorings and shadings that stay the same for a run, next to the palette.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorSettings {
+    /// The angular frequency of the stripe average coloring.
+    pub stripe_frequency: f64,
+    /// The direction the light of the shadings comes from, counterclockwise in degrees with 0 to the right.
+    pub light_azimuth: f32,
+    /// The height of the light of the shadings above the image plane in degrees.
+    pub light_elevation: f32,
+    /// Applies ordered dithering before the colors are quantized to 8 bits.
+    pub dither: bool,
+}
+
+impl Default for ColorSettings {
+    /// The settings the explorer starts with without a config.
+    fn default() -> Self {
+        ColorSettings {
+            stripe_frequency: 5.0,
+            light_azimuth: 135.0,
+            light_elevation: 45.0,
+            dither: false,
+        }
+    }
+}
+
+/// Scales the smooth iteration differences into heights for the slope shading.
+const SLOPE_HEIGHT: f32 = 4.0;
+
+/// The part of the brightness that does not depend on the slope, keeps steep walls from turning black.
+const SLOPE_AMBIENT: f32 = 0.4;
+
+/// The lowest height of the light the slope shading divides by, a light at the horizon would divide by zero.
+const SLOPE_MIN_LIGHT_HEIGHT: f32 = 0.05;
+
+/// The ambient part of the Blinn-Phong relief lighting.
+const RELIEF_AMBIENT: f32 = 0.2;
+
+/// The diffuse part of the Blinn-Phong relief lighting.
+const RELIEF_DIFFUSE: f32 = 0.8;
+
+/// The specular part of the Blinn-Phong relief lighting.
+const RELIEF_SPECULAR: f32 = 0.35;
+
+/// The shininess exponent of the Blinn-Phong relief lighting.
+const RELIEF_SHININESS: f32 = 24.0;
+
+/// The steepness of the relief, the normal is tilted by the distance estimation direction over this value.
+const RELIEF_NORMAL_HEIGHT: f32 = 0.6;
+
+/// How the colors are lit on top of the coloring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Shading {
+    /// No lighting.
+    #[default]
+    Flat,
+    /// A directional light on the relief formed by the smooth iteration count, which gives an embossed look.
+    Slope,
+    /// Blinn-Phong lighting of the surface normal derived from the distance estimation, the classic lit renders.
+    Relief,
+}
+
+impl Shading {
+    /// All shadings in cycling order.
+    pub const ALL: [Shading; 3] = [Shading::Flat, Shading::Slope, Shading::Relief];
+
+    /// Gets the shading following this one.
+    pub fn next(self) -> Shading {
+        Shading::ALL[(self as usize + 1) % Shading::ALL.len()]
+    }
+
+    /// The human readable name of the shading.
+    pub fn name(self) -> &'static str {
+        match self {
+            Shading::Flat => "flat",
+            Shading::Slope => "slope",
+            Shading::Relief => "relief",
+        }
+    }
+
+    /// Looks up a shading by its name.
+    pub fn from_name(name: &str) -> Option<Shading> {
+        Shading::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// Gets what has to be tracked along the orbits for the coloring and shading. None means the iteration count is
+/// enough and [`generate_colors`] can be used.
+pub fn escape_tracking(
+    coloring: Coloring,
+    shading: Shading,
+    color_settings: &ColorSettings,
+) -> Option<OrbitTracking> {
+    (coloring.needs_escape() || shading != Shading::Flat).then(|| OrbitTracking {
+        statistic: coloring.orbit_statistic(color_settings),
+        derivative: shading == Shading::Relief,
+        atom_domain: coloring == Coloring::AtomDomain,
+    })
+}
+
+/// The unit vector towards the light of the settings in screen space: x to the right, y down and z to the viewer.
+fn light_direction(color_settings: &ColorSettings) -> (f32, f32, f32) {
+    let azimuth = color_settings.light_azimuth.to_radians();
+    let elevation = color_settings.light_elevation.to_radians();
+    (
+        azimuth.cos() * elevation.cos(),
+        -azimuth.sin() * elevation.cos(),
+        elevation.sin(),
+    )
+}
+
+/// A palette of the [`PALETTES`] registry, the built-in ones and those loaded from the palettes directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Palette(usize);
+
+impl Palette {
+    /// All registered palettes in cycling order.
+    pub fn all() -> impl Iterator<Item = Palette> {
+        (0..PALETTES.count()).map(Palette)
+    }
+
+    /// Gets the palette following this one.
+    pub fn next(self) -> Palette {
+        Palette((self.0 + 1) % PALETTES.count())
+    }
+
+    /// The name the palette is registered under.
+    pub fn name(self) -> &'static str {
+        PALETTES.name(self.0)
+    }
+
+    /// Looks up a palette by its name.
+    pub fn from_name(name: &str) -> Option<Palette> {
+        PALETTES.find(name).map(Palette)
+    }
+
+    /// Gets the color for a relative position in [0, 1) within one palette cycle.
+    fn color_at(self, rel_val: f32, settings: &PaletteSettings) -> Color {
+        PALETTES.color_at(self.0, rel_val, settings)
+    }
+}
+
+/// A palette parameter that can be tuned live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteParameter {
+    /// The amount of palette cycles over the iteration range.
+    HueCycles,
+    /// The saturation of the rainbow palette.
+    Saturation,
+    /// The brightness of the rainbow palette.
+    Value,
+}
+
+/// The parameters that shape the color lookup table and can be changed at runtime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaletteSettings {
+    /// The palette we use.
+    pub palette: Palette,
+    /// Shifts the position within the palette cycle, in [0, 1).
+    pub hue_offset: f32,
+    /// The amount of palette cycles over the iteration range.
+    pub hue_cycles: f32,
+    /// The saturation of the rainbow palette, in [0, 1].
+    pub saturation: f32,
+    /// The brightness of the rainbow palette, in [0, 1].
+    pub value: f32,
+    /// The parameters of the cubehelix palette.
+    pub cubehelix: Cubehelix,
+    /// A palette derived from an image, which replaces the palette if set.
+    pub image: Option<&'static ImagePalette>,
+    /// A user defined gradient, which replaces the palette and the image palette if set.
+    pub gradient: Option<Gradient>,
+}
+
+impl Default for PaletteSettings {
+    fn default() -> Self {
+        PaletteSettings {
+            palette: Palette::default(),
+            hue_offset: 0.0,
+            hue_cycles: HUE_CYCLES,
+            saturation: COLOR_SATURATION,
+            value: COLOR_VALUE,
+            cubehelix: Cubehelix::default(),
+            image: None,
+            gradient: None,
+        }
+    }
+}
+
+impl PaletteSettings {
+    /// Changes the parameter by the given amount and keeps it in its valid range.
+    pub fn adjust(&mut self, parameter: PaletteParameter, delta: f32) {
+        match parameter {
+            PaletteParameter::HueCycles => {
+                self.hue_cycles =
+                    (self.hue_cycles + delta).clamp(HUE_CYCLES_RANGE.0, HUE_CYCLES_RANGE.1)
+            }
+            PaletteParameter::Saturation => {
+                self.saturation = (self.saturation + delta).clamp(0.0, 1.0)
+            }
+            PaletteParameter::Value => self.value = (self.value + delta).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Gets the color for a relative position in [0, 1) within one palette cycle.
+    pub fn sample(&self, rel_val: f32) -> Color {
+        match (&self.gradient, self.image) {
+            (Some(gradient), _) => gradient.color_at(rel_val),
+            (None, Some(image)) => image.color_at(rel_val),
+            (None, None) => self.palette.color_at(rel_val, self),
+        }
+    }
+}
+
+/// The amount of palette cycles over the range [0, 1] of an orbit statistic. The values cluster in the middle of
+/// the range, so they are spread over several cycles to get enough contrast.
+const ORBIT_STATISTIC_CYCLES: f32 = 3.0;
+
+/// The amount of samples of one palette cycle in the lookup table for continuous values.
+const CYCLE_SAMPLES: usize = 256;
+
+/// The lookup table from iteration count to color, which gets rebuilt when the settings change.
+pub struct ColorLookup {
+    /// The settings the table was built with.
+    settings: PaletteSettings,
+    /// The iteration limit the table was built for, counts reaching it are inside the set.
+    max_iter: u16,
+    /// One color per iteration count, the last entry is used for the set itself.
+    color_array: Vec<Color>,
+    /// One palette cycle sampled evenly, for colorings that pick the position within the cycle themselves.
+    cycle_array: Vec<Color>,
+}
+
+impl ColorLookup {
+    /// Gets the color at a relative position in [0, 1] within one palette cycle.
+    fn cycle_color(&self, rel_val: f32) -> Color {
+        self.cycle_array[(rel_val * CYCLE_SAMPLES as f32) as usize % CYCLE_SAMPLES]
+    }
+
+    /// Gets the color of an iteration count. Fields computed before the limit got lowered may hold larger counts,
+    /// those get the color of the set.
+    fn iteration_color(&self, iterations: u16) -> Color {
+        self.color_array[(iterations as usize).min(self.color_array.len() - 1)]
+    }
+
+    /// The iteration limit the table was built for.
+    pub fn max_iter(&self) -> u16 {
+        self.max_iter
+    }
+
+    /// Builds the lookup table for the settings and the iteration limit.
+    pub fn new(settings: PaletteSettings, max_iter: u16) -> ColorLookup {
+        ColorLookup {
+            settings,
+            max_iter,
+            color_array: create_all_colors(settings, max_iter),
+            cycle_array: (0..CYCLE_SAMPLES)
+                .map(|i| {
+                    settings.sample((i as f32 / CYCLE_SAMPLES as f32 + settings.hue_offset).fract())
+                })
+                .collect(),
+        }
+    }
+
+    /// Rebuilds the lookup table if the settings or the iteration limit differ from the ones it was built with.
+    pub fn update(&mut self, settings: PaletteSettings, max_iter: u16) {
+        if settings != self.settings || max_iter != self.max_iter {
+            *self = ColorLookup::new(settings, max_iter);
+        }
+    }
+
+    /// A copy of the lookup table whose iteration colors stretch the range over the whole palette, see
+    /// [`IterationRange`]. The colors of the continuous values stay.
+    pub fn stretched(&self, range: IterationRange) -> ColorLookup {
+        let mut color_array: Vec<_> = (0..self.max_iter)
+            .map(|i| {
+                let position = range.stretch(i as f32, self.max_iter);
+                iteration_color(self.settings, position, self.max_iter)
+            })
+            .collect();
+        color_array.push(BLACK);
+        ColorLookup {
+            settings: self.settings,
+            max_iter: self.max_iter,
+            color_array,
+            cycle_array: self.cycle_array.clone(),
+        }
+    }
+
+    /// A copy of the lookup table with the given color per iteration count, the last one for the set itself.
+    #[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+    pub fn with_iteration_colors(&self, colors: &[Color]) -> ColorLookup {
+        ColorLookup {
+            settings: self.settings,
+            max_iter: self.max_iter,
+            color_array: colors.to_vec(),
+            cycle_array: self.cycle_array.clone(),
+        }
+    }
+}
 
 /// Helper function to build the lookup table.
-fn create_all_colors() -> Vec<Color> {
-    let mut vec: Vec<_> = (0..MAX_ITER)
-        .map(|i| {
-            let rel_val = (i as f32 * HUE_CYCLES / MAX_ITER as f32).fract();
-            hsv_to_rgb_color(rel_val, COLOR_SATURATION, COLOR_VALUE)
-        })
+fn create_all_colors(settings: PaletteSettings, max_iter: u16) -> Vec<Color> {
+    let mut vec: Vec<_> = (0..max_iter)
+        .map(|i| iteration_color(settings, i as f32, max_iter))
         .collect();
     vec.push(BLACK);
     vec
 }
 
+/// The palette color of the position in the iteration range up to the limit.
+fn iteration_color(settings: PaletteSettings, position: f32, max_iter: u16) -> Color {
+    let rel_val = (position * settings.hue_cycles / max_iter as f32 + settings.hue_offset).fract();
+    settings.sample(rel_val)
+}
+
+/// Converts an sRGB encoded channel value into linear light.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a channel value in linear light into the sRGB encoding.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Interpolates between two sRGB colors in linear light.
+pub fn mix_linear(from: Color, to: Color, t: f32) -> Color {
+    let mix = |a: f32, b: f32| {
+        let (a, b) = (srgb_to_linear(a), srgb_to_linear(b));
+        linear_to_srgb(a + (b - a) * t)
+    };
+    Color::new(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b), 1.0)
+}
+
+/// Interpolates between the sRGB color stops in linear light, wrapping around from the last to the first one.
+pub fn cyclic_gradient(stops: &[(f32, f32, f32)], rel_val: f32) -> Color {
+    let scaled = rel_val * stops.len() as f32;
+    let index = scaled.floor() as usize % stops.len();
+    let (r0, g0, b0) = stops[index];
+    let (r1, g1, b1) = stops[(index + 1) % stops.len()];
+    mix_linear(
+        Color::new(r0, g0, b0, 1.0),
+        Color::new(r1, g1, b1, 1.0),
+        scaled.fract(),
+    )
+}
+
 /// Converts hsv to rgb color.
-fn hsv_to_rgb_color(h: f32, s: f32, v: f32) -> Color {
+pub fn hsv_to_rgb_color(h: f32, s: f32, v: f32) -> Color {
     let mut r = 0.0;
     let mut g = 0.0;
     let mut b = 0.0;
@@ -80,7 +503,214 @@ fn hsv_to_rgb_color(h: f32, s: f32, v: f32) -> Color {
     Color::new(r, g, b, 1.0)
 }
 
-/// Takes a field with iterations and converts it into a color array.
-pub fn generate_colors(in_field: &[u16]) -> Vec<Color> {
-    in_field.iter().map(|i| COLOR_ARRAY[*i as usize]).collect()
+/// The 8x8 Bayer matrix used for ordered dithering, with thresholds in [0, 64).
+const BAYER_MATRIX: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Adds the ordered dithering offset of the pixel, so the truncation to 8 bits rounds up or down in a pattern
+/// that averages to the exact color.
+fn dither(color: Color, x: usize, y: usize) -> Color {
+    let offset = (BAYER_MATRIX[y % 8][x % 8] as f32 + 0.5) / (64.0 * 255.0);
+    Color::new(
+        (color.r + offset).min(1.0),
+        (color.g + offset).min(1.0),
+        (color.b + offset).min(1.0),
+        color.a,
+    )
+}
+
+/// Takes a field with iterations of the given width and converts it into a color array.
+pub fn generate_colors(
+    in_field: &[u16],
+    width: usize,
+    lookup: &ColorLookup,
+    color_settings: &ColorSettings,
+) -> Vec<Color> {
+    profile_scope!("color iterations");
+    if !color_settings.dither {
+        return in_field
+            .iter()
+            .map(|i| lookup.iteration_color(*i))
+            .collect();
+    }
+    in_field
+        .iter()
+        .enumerate()
+        .map(|(idx, i)| dither(lookup.iteration_color(*i), idx % width, idx / width))
+        .collect()
+}
+
+/// Takes a field with escape results of the given width and converts it into a color array with the coloring and
+/// shading.
+pub fn generate_escape_colors(
+    in_field: &[Escape],
+    width: usize,
+    lookup: &ColorLookup,
+    coloring: Coloring,
+    shading: Shading,
+    color_settings: &ColorSettings,
+) -> Vec<Color> {
+    profile_scope!("color escapes");
+    let apply_dither = color_settings.dither;
+    let light = light_direction(color_settings);
+    (0..in_field.len())
+        .into_par_iter()
+        .map(|idx| {
+            let escape = &in_field[idx];
+            let color = lookup.iteration_color(escape.iterations);
+            let escaped = escape.iterations < lookup.max_iter;
+            let color = match coloring {
+                Coloring::Iteration => color,
+                Coloring::BinaryDecomposition if escaped && escape.z.imag < 0.0 => {
+                    scale_brightness(color, BINARY_DECOMPOSITION_SHADE)
+                }
+                Coloring::BinaryDecomposition => color,
+                Coloring::TriangleInequality | Coloring::Stripes if escaped => {
+                    lookup.cycle_color(escape.statistic * ORBIT_STATISTIC_CYCLES)
+                }
+                Coloring::TriangleInequality | Coloring::Stripes => color,
+                Coloring::AtomDomain => {
+                    lookup.cycle_color((escape.atom_domain as f32 * ATOM_DOMAIN_STEP).fract())
+                }
+            };
+            let color = match shading {
+                Shading::Slope if escaped => {
+                    let brightness = slope_light(in_field, width, idx, light, lookup.max_iter);
+                    scale_brightness(color, brightness)
+                }
+                Shading::Relief if escaped => relief_light(color, escape, light),
+                _ => color,
+            };
+            if apply_dither {
+                dither(color, idx % width, idx / width)
+            } else {
+                color
+            }
+        })
+        .collect()
+}
+
+/// Computes the brightness factor of the pixel from the slope of the smooth iteration count, 1 is a flat area.
+/// Counts reaching the limit are points in the set.
+fn slope_light(
+    in_field: &[Escape],
+    width: usize,
+    idx: usize,
+    light: (f32, f32, f32),
+    limit: u16,
+) -> f32 {
+    let height = in_field[idx].smooth_iterations(limit);
+    // Points in the set are treated as having the same height, otherwise their border would be a cliff.
+    let neighbor_height = |neighbor: usize| {
+        if in_field[neighbor].iterations >= limit {
+            height
+        } else {
+            in_field[neighbor].smooth_iterations(limit)
+        }
+    };
+    let x = idx % width;
+    let dx = if x + 1 < width {
+        neighbor_height(idx + 1) - height
+    } else {
+        height - neighbor_height(idx - 1)
+    };
+    let dy = if idx + width < in_field.len() {
+        neighbor_height(idx + width) - height
+    } else {
+        height - neighbor_height(idx - width)
+    };
+
+    // The normal of the height field (-dx, -dy, 1) scaled with the height, lit from the light direction.
+    let (nx, ny, nz) = (-dx * SLOPE_HEIGHT, -dy * SLOPE_HEIGHT, 1.0);
+    let length = (nx * nx + ny * ny + nz * nz).sqrt();
+    let lambert = (nx * light.0 + ny * light.1 + nz * light.2) / length;
+    SLOPE_AMBIENT + (1.0 - SLOPE_AMBIENT) * (lambert / light.2.max(SLOPE_MIN_LIGHT_HEIGHT)).max(0.0)
+}
+
+/// Lights the color with Blinn-Phong. The normal tilts in the direction z / z', which points away from the set
+/// along the gradient of the distance estimation, so the set appears as a plateau.
+fn relief_light(color: Color, escape: &Escape, light: (f32, f32, f32)) -> Color {
+    let (z, dz) = (escape.z, escape.derivative);
+    let (ux, uy) = (
+        z.real * dz.real + z.imag * dz.imag,
+        z.imag * dz.real - z.real * dz.imag,
+    );
+    let u_length = (ux * ux + uy * uy).sqrt();
+    if u_length == 0.0 || !u_length.is_finite() {
+        return color;
+    }
+    let (nx, ny, nz) = (
+        (ux / u_length) as f32,
+        (uy / u_length) as f32,
+        RELIEF_NORMAL_HEIGHT,
+    );
+    let n_length = (nx * nx + ny * ny + nz * nz).sqrt();
+    let (nx, ny, nz) = (nx / n_length, ny / n_length, nz / n_length);
+
+    // The half vector between the light and the viewer, who looks straight down.
+    let (hx, hy, hz) = (light.0, light.1, light.2 + 1.0);
+    let h_length = (hx * hx + hy * hy + hz * hz).sqrt();
+
+    let diffuse = (nx * light.0 + ny * light.1 + nz * light.2).max(0.0);
+    let specular = ((nx * hx + ny * hy + nz * hz) / h_length)
+        .max(0.0)
+        .powf(RELIEF_SHININESS);
+    let lit = |value: f32| {
+        let linear = srgb_to_linear(value) * (RELIEF_AMBIENT + RELIEF_DIFFUSE * diffuse)
+            + RELIEF_SPECULAR * specular;
+        linear_to_srgb(linear.min(1.0))
+    };
+    Color::new(lit(color.r), lit(color.g), lit(color.b), color.a)
+}
+
+/// Scales the brightness of an sRGB color in linear light.
+fn scale_brightness(color: Color, factor: f32) -> Color {
+    let scale = |value: f32| linear_to_srgb((srgb_to_linear(value) * factor).min(1.0));
+    Color::new(scale(color.r), scale(color.g), scale(color.b), color.a)
+}
+
+/// The maximum horizontal parallax in pixels, reached by the deepest (interior) points.
+const MAX_PARALLAX: f32 = 6.0;
+
+/// Takes a field with iterations of the given width and converts it into a red-cyan anaglyph. The iteration count
+/// is interpreted as depth, the left view goes into the red channel and the right view into green and blue.
+pub fn generate_anaglyph_colors(
+    in_field: &[u16],
+    width: usize,
+    lookup: &ColorLookup,
+    color_settings: &ColorSettings,
+) -> Vec<Color> {
+    profile_scope!("color anaglyph");
+    (0..in_field.len())
+        .into_par_iter()
+        .map(|idx| {
+            let x = idx % width;
+            let row_start = idx - x;
+            let depth = (in_field[idx] as f32 / lookup.max_iter as f32).min(1.0);
+            let shift = (depth * MAX_PARALLAX * 0.5).round() as usize;
+
+            let left = lookup.iteration_color(in_field[row_start + (x + shift).min(width - 1)]);
+            let right = lookup.iteration_color(in_field[row_start + x.saturating_sub(shift)]);
+            let left_luminance = linear_to_srgb(
+                0.2126 * srgb_to_linear(left.r)
+                    + 0.7152 * srgb_to_linear(left.g)
+                    + 0.0722 * srgb_to_linear(left.b),
+            );
+
+            let color = Color::new(left_luminance, right.g, right.b, 1.0);
+            if color_settings.dither {
+                dither(color, x, idx / width)
+            } else {
+                color
+            }
+        })
+        .collect()
 }
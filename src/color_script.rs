@@ -0,0 +1,184 @@
+//! Color functions written in Rhai, so coloring schemes can be shared as small script files instead of patches.
+//! With the `scripting` feature, `color-script` loads a file defining
+//!
+//! ```text
+//! fn color(iter, smooth, re, im, trap) { [r, g, b] }
+//! ```
+//!
+//! which returns the channels in [0, 1]. It gets the iteration count, the continuous iteration count, the final
+//! orbit value and its closest distance to zero, `iter` is the iteration limit for points in the set. By default the function
+//! is evaluated once per iteration count into the lookup table, where only `iter` and `smooth` are known and the
+//! rest is zero. With `color-script-per-pixel` it runs for every pixel with all values, which is much slower.
+//! Without the feature loading a script fails with a message.
+
+use crate::color::Color;
+use crate::color_generation::ColorLookup;
+use crate::math::{Escape, OrbitTracking};
+use std::path::Path;
+
+#[cfg(feature = "scripting")]
+pub use rhai_script::ColorScript;
+
+/// Stands in for the script without the `scripting` feature, no script can be loaded.
+#[cfg(not(feature = "scripting"))]
+pub enum ColorScript {}
+
+#[cfg(not(feature = "scripting"))]
+impl ColorScript {
+    /// Fails, as the scripting engine is not compiled in.
+    pub fn load(_path: &Path, _per_pixel: bool) -> Result<ColorScript, String> {
+        Err("the explorer was built without the `scripting` feature".to_owned())
+    }
+
+    /// What the script needs tracked along the orbits.
+    pub fn tracking(&self) -> Option<OrbitTracking> {
+        match *self {}
+    }
+
+    /// The lookup table with the colors of the script.
+    pub fn lookup(&mut self, _lookup: &ColorLookup) -> Option<ColorLookup> {
+        match *self {}
+    }
+
+    /// The colors of the script per pixel.
+    pub fn colors(&self, _escapes: &[Escape]) -> Option<Vec<Color>> {
+        match *self {}
+    }
+}
+
+#[cfg(feature = "scripting")]
+mod rhai_script {
+    use super::*;
+    use crate::color::BLACK;
+    use crate::math::max_iter;
+    use rayon::prelude::*;
+    use rhai::{AST, Array, Dynamic, Engine, Scope};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// The name of the function the script has to define.
+    const FUNCTION_NAME: &str = "color";
+
+    /// A compiled color script.
+    pub struct ColorScript {
+        /// The engine running the script.
+        engine: Engine,
+        /// The compiled script.
+        ast: AST,
+        /// The colors per iteration count up to the iteration limit they were evaluated for, None if the script
+        /// runs per pixel.
+        iteration_colors: Option<Vec<Color>>,
+        /// Whether a failing call was already reported, so a broken script does not flood the output.
+        reported: AtomicBool,
+    }
+
+    impl ColorScript {
+        /// Compiles the script and tries it once, so mistakes show at the start.
+        pub fn load(path: &Path, per_pixel: bool) -> Result<ColorScript, String> {
+            let engine = Engine::new();
+            let ast = engine
+                .compile_file(path.to_path_buf())
+                .map_err(|err| err.to_string())?;
+            let mut script = ColorScript {
+                engine,
+                ast,
+                iteration_colors: None,
+                reported: AtomicBool::new(false),
+            };
+            script.call(0, 0.0, 0.0, 0.0, 0.0)?;
+            if !per_pixel {
+                script.iteration_colors = Some(script.evaluate_iterations(max_iter()));
+            }
+            Ok(script)
+        }
+
+        /// What the script needs tracked along the orbits, None if it runs into the lookup table.
+        pub fn tracking(&self) -> Option<OrbitTracking> {
+            self.iteration_colors.is_none().then_some(OrbitTracking {
+                atom_domain: true,
+                ..Default::default()
+            })
+        }
+
+        /// The lookup table with the colors of the script, None if it runs per pixel. The colors are evaluated
+        /// again when the iteration limit of the lookup table changed.
+        pub fn lookup(&mut self, lookup: &ColorLookup) -> Option<ColorLookup> {
+            let limit = lookup.max_iter();
+            if self
+                .iteration_colors
+                .as_ref()
+                .is_some_and(|colors| colors.len() != limit as usize + 1)
+            {
+                self.iteration_colors = Some(self.evaluate_iterations(limit));
+            }
+            self.iteration_colors
+                .as_ref()
+                .map(|colors| lookup.with_iteration_colors(colors))
+        }
+
+        /// Evaluates the script once per iteration count up to the limit, the last one for the set itself.
+        fn evaluate_iterations(&self, limit: u16) -> Vec<Color> {
+            (0..=limit)
+                .map(|iter| self.color(iter, iter as f32, 0.0, 0.0, 0.0))
+                .collect()
+        }
+
+        /// The colors of the script per pixel, None if it runs into the lookup table.
+        pub fn colors(&self, escapes: &[Escape]) -> Option<Vec<Color>> {
+            self.iteration_colors.is_none().then(|| {
+                escapes
+                    .par_iter()
+                    .map(|escape| {
+                        self.color(
+                            escape.iterations,
+                            escape.smooth_iterations(max_iter()),
+                            escape.z.real,
+                            escape.z.imag,
+                            escape.trap_distance,
+                        )
+                    })
+                    .collect()
+            })
+        }
+
+        /// Calls the script, failures are reported once and come out black.
+        fn color(&self, iter: u16, smooth: f32, re: f64, im: f64, trap: f32) -> Color {
+            self.call(iter, smooth, re, im, trap).unwrap_or_else(|err| {
+                if !self.reported.swap(true, Ordering::Relaxed) {
+                    eprintln!("The color script failed: {err}");
+                }
+                BLACK
+            })
+        }
+
+        /// Calls the color function of the script and converts its result.
+        fn call(
+            &self,
+            iter: u16,
+            smooth: f32,
+            re: f64,
+            im: f64,
+            trap: f32,
+        ) -> Result<Color, String> {
+            let result: Array = self
+                .engine
+                .call_fn(
+                    &mut Scope::new(),
+                    &self.ast,
+                    FUNCTION_NAME,
+                    (iter as i64, smooth as f64, re, im, trap as f64),
+                )
+                .map_err(|err| err.to_string())?;
+            let channel = |value: &Dynamic| {
+                value
+                    .as_float()
+                    .or_else(|_| value.as_int().map(|int| int as f64))
+                    .map(|value| value.clamp(0.0, 1.0) as f32)
+                    .map_err(|kind| format!("expected numbers as channels, got {kind}"))
+            };
+            match result.as_slice() {
+                [r, g, b] => Ok(Color::new(channel(r)?, channel(g)?, channel(b)?, 1.0)),
+                _ => Err(format!("expected [r, g, b], got {} values", result.len())),
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! The perceptually uniform colormaps of matplotlib plus turbo, given as evenly spaced sRGB control points.
+//! The maps are not cyclic, so they are traversed forth and back within one palette cycle to avoid a seam.
+//! Next to them lives the cubehelix generator, which produces a whole family of smooth colormaps.
+
+use crate::color::Color;
+use crate::color_generation::mix_linear;
+use std::f32::consts::TAU;
+
+/// The viridis colormap from dark blue over green to yellow.
+pub const VIRIDIS: [u32; 9] = [
+    0x440154, 0x472c7a, 0x3b518b, 0x2c718e, 0x21908d, 0x27ad81, 0x5cc863, 0xaadc32, 0xfde725,
+];
+
+/// The magma colormap from black over purple to light yellow.
+pub const MAGMA: [u32; 9] = [
+    0x000004, 0x1c1044, 0x4f127b, 0x812581, 0xb5367a, 0xe55064, 0xfb8761, 0xfec287, 0xfcfdbf,
+];
+
+/// The inferno colormap from black over red to yellow.
+pub const INFERNO: [u32; 9] = [
+    0x000004, 0x1f0c48, 0x550f6d, 0x88226a, 0xba3655, 0xe35933, 0xf98c0a, 0xf9c932, 0xfcffa4,
+];
+
+/// The plasma colormap from blue over pink to yellow.
+pub const PLASMA: [u32; 9] = [
+    0x0d0887, 0x4c02a1, 0x7e03a8, 0xa92395, 0xcc4778, 0xe56b5d, 0xf89540, 0xfdc328, 0xf0f921,
+];
+
+/// The cividis colormap from blue to yellow, which is also readable with color vision deficiency.
+pub const CIVIDIS: [u32; 9] = [
+    0x00224e, 0x123570, 0x3b496c, 0x575d6d, 0x707173, 0x8a8678, 0xa59c74, 0xc3b369, 0xfee838,
+];
+
+/// The turbo rainbow colormap from dark blue over green to dark red.
+pub const TURBO: [u32; 9] = [
+    0x30123b, 0x4662d7, 0x36aaf9, 0x1ae4b6, 0x72fe5e, 0xc7ef34, 0xfaba39, 0xf66b19, 0x7a0403,
+];
+
+/// Gets the color for a relative position in [0, 1) within one palette cycle. The first half of the cycle runs
+/// through the colormap, the second half runs back.
+pub fn colormap_at(colormap: &[u32], rel_val: f32) -> Color {
+    let position = ping_pong(rel_val);
+    let scaled = position * (colormap.len() - 1) as f32;
+    let index = (scaled.floor() as usize).min(colormap.len() - 2);
+    mix_linear(
+        hex_to_color(colormap[index]),
+        hex_to_color(colormap[index + 1]),
+        scaled - index as f32,
+    )
+}
+
+/// Converts a color given as 0xRRGGBB.
+fn hex_to_color(hex: u32) -> Color {
+    Color::from_rgba((hex >> 16) as u8, (hex >> 8) as u8, hex as u8, 255)
+}
+
+/// Maps the position within the palette cycle to the position within a colormap, running forth and back.
+fn ping_pong(rel_val: f32) -> f32 {
+    1.0 - (2.0 * rel_val - 1.0).abs()
+}
+
+/// The parameters of a cubehelix colormap (D. A. Green, 2011), which runs from black to white while the hue
+/// rotates around the gray diagonal of the color cube.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cubehelix {
+    /// The hue we start with, in [0, 3] (0 blue, 1 red, 2 green).
+    pub start: f32,
+    /// The amount of hue rotations from black to white, negative values rotate backwards.
+    pub rotations: f32,
+    /// The saturation of the hue, 0 gives a pure gray scale.
+    pub hue: f32,
+    /// Values below 1 brighten the dark end, values above 1 darken the bright end.
+    pub gamma: f32,
+}
+
+impl Default for Cubehelix {
+    fn default() -> Self {
+        Cubehelix {
+            start: 0.5,
+            rotations: -1.5,
+            hue: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl Cubehelix {
+    /// Draws random parameters that still give a pleasant colormap. The generator returns a value between its
+    /// bounds, so the parameters follow the seed of the caller.
+    pub fn random(mut gen_range: impl FnMut(f32, f32) -> f32) -> Cubehelix {
+        Cubehelix {
+            start: gen_range(0.0, 3.0),
+            rotations: gen_range(-2.0, 2.0),
+            hue: gen_range(0.6, 1.6),
+            gamma: gen_range(0.7, 1.3),
+        }
+    }
+
+    /// Parses the parameters given as `start,rotations,hue,gamma`.
+    pub fn parse(value: &str) -> Result<Cubehelix, String> {
+        let values: Vec<f32> = value
+            .split(',')
+            .map(|v| v.trim().parse::<f32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("can not parse `{value}`"))?;
+        match values[..] {
+            [start, rotations, hue, gamma] => Ok(Cubehelix {
+                start,
+                rotations,
+                hue,
+                gamma,
+            }),
+            _ => Err("expected start,rotations,hue,gamma".to_owned()),
+        }
+    }
+
+    /// Gets the color for a relative position in [0, 1) within one palette cycle, see [`colormap_at`].
+    pub fn color_at(&self, rel_val: f32) -> Color {
+        let fraction = ping_pong(rel_val);
+        let angle = TAU * (self.start / 3.0 + 1.0 + self.rotations * fraction);
+        let lightness = fraction.powf(self.gamma);
+        let amplitude = self.hue * lightness * (1.0 - lightness) * 0.5;
+        let (sin, cos) = angle.sin_cos();
+        Color::new(
+            (lightness + amplitude * (-0.14861 * cos + 1.78277 * sin)).clamp(0.0, 1.0),
+            (lightness + amplitude * (-0.29227 * cos - 0.90649 * sin)).clamp(0.0, 1.0),
+            (lightness + amplitude * (1.97294 * cos)).clamp(0.0, 1.0),
+            1.0,
+        )
+    }
+}
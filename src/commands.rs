@@ -0,0 +1,124 @@
+//! Commands are the common language of all control surfaces. The keyboard and the optional remote controls
+//! only produce commands, the main loop is the only place that executes them.
+
+use crate::color_generation::{Coloring, Palette, PaletteParameter, Shading};
+use crate::focus_system::FocusParameter;
+use crate::math::ComplexNumber;
+use macroquad::prelude::*;
+
+/// An action requested by the user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// Stops or resumes the animation.
+    TogglePause,
+    /// Stops (true) or resumes (false) the animation.
+    SetPaused(bool),
+    /// Switches between manual exploration and the automatic.
+    ToggleManual,
+    /// Leaves the current dive and moves on to the next target.
+    NextTarget,
+    /// Saves the current frame as PNG.
+    Screenshot,
+    /// Exports the path of the current or the last dive as CSV and SVG plot.
+    ExportTrajectory,
+    /// Starts or stops recording the frames.
+    ToggleRecording,
+    /// Starts (true) or stops (false) recording the frames.
+    SetRecording(bool),
+    /// Switches to the next color palette.
+    NextPalette,
+    /// Switches to the given color palette.
+    SetPalette(Palette),
+    /// Shifts the position within the palette cycle, in [0, 1).
+    SetHueOffset(f32),
+    /// Switches to the next exterior coloring.
+    NextColoring,
+    /// Switches to the given exterior coloring.
+    SetColoring(Coloring),
+    /// Switches to the next shading.
+    NextShading,
+    /// Switches to the given shading.
+    SetShading(Shading),
+    /// Changes a palette parameter by the given amount.
+    AdjustPalette(PaletteParameter, f32),
+    /// Sets the zoom speed as multiple of the default speed.
+    SetZoomSpeed(f64),
+    /// Sets the maximum amount of iterations per point, see [`crate::math::max_iter`].
+    SetMaxIter(u16),
+    /// Sets a parameter of the autofocus.
+    SetFocusParameter(FocusParameter, f64),
+    /// Moves the view to the given center and radius. The second number is the part of the center below the
+    /// resolution of f64, zero for locations typed in or sent by the controls.
+    GoTo(ComplexNumber, ComplexNumber, f64),
+    /// Reseeds the random generator that picks the targets.
+    SetSeed(u64),
+    /// Dives through the Julia set of the given parameter and returns to the Mandelbrot set afterwards.
+    JuliaDive(ComplexNumber),
+    /// Ends the program.
+    Quit,
+}
+
+impl Command {
+    /// Whether the command moves the view elsewhere, which makes a running field computation obsolete.
+    pub fn retargets(self) -> bool {
+        matches!(
+            self,
+            Command::NextTarget
+                | Command::ToggleManual
+                | Command::GoTo(..)
+                | Command::SetMaxIter(_)
+                | Command::JuliaDive(_)
+                | Command::Quit
+        )
+    }
+}
+
+/// The change of the hue cycles per key press.
+const HUE_CYCLES_STEP: f32 = 1.0;
+
+/// The change of saturation and value per key press.
+const COLOR_STEP: f32 = 0.05;
+
+/// Collects the commands triggered by the keyboard in this frame.
+pub fn keyboard_commands() -> Vec<Command> {
+    [
+        (KeyCode::Escape, Command::Quit),
+        (KeyCode::P, Command::TogglePause),
+        (KeyCode::N, Command::NextTarget),
+        (KeyCode::S, Command::Screenshot),
+        (KeyCode::W, Command::ExportTrajectory),
+        (KeyCode::C, Command::NextPalette),
+        (KeyCode::D, Command::NextColoring),
+        (KeyCode::L, Command::NextShading),
+        (KeyCode::M, Command::ToggleManual),
+        (KeyCode::R, Command::ToggleRecording),
+        (
+            KeyCode::Key1,
+            Command::AdjustPalette(PaletteParameter::HueCycles, -HUE_CYCLES_STEP),
+        ),
+        (
+            KeyCode::Key2,
+            Command::AdjustPalette(PaletteParameter::HueCycles, HUE_CYCLES_STEP),
+        ),
+        (
+            KeyCode::Key3,
+            Command::AdjustPalette(PaletteParameter::Saturation, -COLOR_STEP),
+        ),
+        (
+            KeyCode::Key4,
+            Command::AdjustPalette(PaletteParameter::Saturation, COLOR_STEP),
+        ),
+        (
+            KeyCode::Key5,
+            Command::AdjustPalette(PaletteParameter::Value, -COLOR_STEP),
+        ),
+        (
+            KeyCode::Key6,
+            Command::AdjustPalette(PaletteParameter::Value, COLOR_STEP),
+        ),
+    ]
+    .into_iter()
+    .filter(|(key, _)| is_key_pressed(*key))
+    .map(|(_, command)| command)
+    .collect()
+}
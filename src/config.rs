@@ -0,0 +1,747 @@
+//! Contains the start-up configuration. Values are read from an optional config file first and can then be
+//! overridden on the command line. Both use the same keys, in the file as `key = value` and on the command line
+//! as `--key value`. Flags without a value are interpreted as `true`. A leading argument without dashes selects
+//! a command instead of the interactive explorer.
+
+use crate::color_generation::{ColorSettings, Coloring, Palette, Shading};
+use crate::colormaps::Cubehelix;
+use crate::focus_system::{DEFAULT_START_MIN_SCORE, max_depth_limit};
+use crate::formula::Formula;
+use crate::gradient::Gradient;
+use crate::image_palette::{ImagePalette, ImagePaletteMode};
+use crate::math::{
+    ComplexNumber, DEFAULT_MAX_ITER, IterationSettings, MAX_ITER_RANGE, fractal_formula, max_iter,
+};
+use crate::overlays::ScreenPosition;
+use crate::post::{
+    DEFAULT_BLOOM_INTENSITY, DEFAULT_BLOOM_THRESHOLD, DEFAULT_CHROMATIC_ABERRATION,
+    DEFAULT_GRAIN_STRENGTH, DEFAULT_SHARPEN_STRENGTH, DEFAULT_VIGNETTE_STRENGTH, PostEffectKind,
+};
+use crate::precision::{DEEPEST_RADIUS, DOUBLE_RADIUS_LIMIT};
+use crate::preferences::preferences_path;
+use crate::recording::RecordingFormat;
+use crate::schedule::{Schedule, parse_duration};
+use crate::terminal::TerminalGraphics;
+use crate::transition::{START_RADIUS, TransitionKind};
+use crate::wall::WallRole;
+use crate::zoom_director::DEFAULT_FOCUS_RADIUS;
+use macroquad::texture::Image;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// The name of the config file we look for in the working directory.
+pub const CONFIG_FILE_NAME: &str = "mandelbrot.cfg";
+
+/// The configuration, loaded once on first access.
+pub static CONFIG: LazyLock<Config> = LazyLock::new(Config::load);
+
+/// All settings that can be changed without recompiling.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The command given as first argument, None for the interactive explorer.
+    pub command: Option<String>,
+    /// The palette we start with.
+    pub palette: Palette,
+    /// A user defined gradient that replaces the palette.
+    pub gradient: Option<Gradient>,
+    /// The image file the palette is derived from.
+    pub palette_image_path: Option<String>,
+    /// How the palette is derived from the image.
+    pub palette_image_mode: ImagePaletteMode,
+    /// The palette derived from the image, which replaces the palette.
+    pub palette_image: Option<ImagePalette>,
+    /// How the points outside the set are colored.
+    pub coloring: Coloring,
+    /// How the colors are lit.
+    pub shading: Shading,
+    /// The direction the light of the shadings comes from, counterclockwise in degrees with 0 to the right.
+    pub light_azimuth: f32,
+    /// The height of the light of the shadings above the image plane in degrees.
+    pub light_elevation: f32,
+    /// The angular frequency of the stripe average coloring.
+    pub stripe_frequency: f64,
+    /// The parameters of the cubehelix palette.
+    pub cubehelix: Cubehelix,
+    /// Draws new cubehelix parameters for every dive.
+    pub cubehelix_per_dive: bool,
+    /// If set, the app waits hidden and only starts the zoom after this many minutes without user input.
+    pub idle_start_minutes: Option<f64>,
+    /// Runs the zoom as animated desktop wallpaper behind the desktop icons.
+    pub wallpaper: bool,
+    /// Runs fullscreen for a screensaver or lock screen host, keeps the display awake and quits on input.
+    pub kiosk: bool,
+    /// The position of the window on the desktop, which selects the output it goes fullscreen on.
+    pub window_position: Option<(u32, u32)>,
+    /// Computes deep views with the 128-bit fixed point backend instead of double-double.
+    pub fixed_point: bool,
+    /// Computes the views beyond double precision as f64 differences to a reference orbit.
+    pub perturbation: bool,
+    /// The part we play in a video wall, if any.
+    pub wall_role: Option<WallRole>,
+    /// The UDP port video wall followers listen on.
+    pub wall_port: u16,
+    /// The comma separated addresses the video wall director sends to.
+    pub wall_targets: String,
+    /// The columns and rows of the video wall.
+    pub wall_grid: (u32, u32),
+    /// The column and row of the tile we render in the video wall.
+    pub wall_tile: (u32, u32),
+    /// The port the HTTP remote control listens on, if compiled in.
+    pub remote_port: u16,
+    /// The address the HTTP remote control listens on, the local machine unless opened to the network.
+    pub remote_bind: String,
+    /// The UDP port we receive OSC messages on, if any.
+    pub osc_port: Option<u16>,
+    /// The port the MJPEG stream is served on, if compiled in.
+    pub stream_port: Option<u16>,
+    /// The file receiving the frames as raw video, if compiled in.
+    pub virtual_camera: Option<String>,
+    /// Renders the zoom into the terminal instead of a window.
+    pub terminal: bool,
+    /// The size of the terminal in characters, None to detect it.
+    pub terminal_size: Option<(u32, u32)>,
+    /// How the terminal mode draws the frames.
+    pub terminal_graphics: TerminalGraphics,
+    /// Continues the session saved on the last exit.
+    pub resume: bool,
+    /// Saves every dive as animated GIF into the gallery directory.
+    pub dive_gif: bool,
+    /// The amount of best frames of every dive saved into the gallery, 0 saves none.
+    pub best_of: usize,
+    /// Saves the path of every dive as CSV and SVG plot into the gallery directory.
+    pub dive_trajectories: bool,
+    /// The file format recordings are written in.
+    pub record_format: RecordingFormat,
+    /// Applies ordered dithering before the colors are quantized to 8 bits.
+    pub dither: bool,
+    /// Stretches the populated iteration range of every frame over the whole palette.
+    pub normalize_iterations: bool,
+    /// The smooth time in seconds the normalized range follows the frames with.
+    pub normalize_smooth_time: f64,
+    /// Starts with the heads-up display shown.
+    pub hud: bool,
+    /// Starts with the window covering the screen.
+    pub fullscreen: bool,
+    /// The zoom speed we start with, as multiple of the default speed.
+    pub zoom_speed: f64,
+    /// The iteration limit we start with, see [`crate::math::max_iter`].
+    pub max_iter: u16,
+    /// The amount of worker threads, 0 uses one per core.
+    pub threads: usize,
+    /// Lowers the priority of the computation, so other programs take precedence.
+    pub background: bool,
+    /// The factor the iteration field is downsampled by before the focus scoring, 1 scores every pixel.
+    pub focus_downsample: u32,
+    /// How much a new focus point has to beat the tracked one to replace it, as fraction of the tracked score.
+    pub focus_hysteresis: f32,
+    /// The amount of strongest focus candidates a new target is drawn from, 1 always takes the best.
+    pub focus_candidates: usize,
+    /// How much unexplored regions are favored when picking the next dive, 0 ignores where we already were.
+    pub novelty_weight: f32,
+    /// The score a start point has to beat, the search falls back to a point near the real axis otherwise.
+    pub start_min_score: f32,
+    /// The radius at which the autofocus takes over, also the size of the views the start points are scored in.
+    pub focus_radius: f64,
+    /// The radius at which the dives end.
+    pub depth_limit: f64,
+    /// The seconds after which a dive moves on to a new target however interesting the view is, 0 dives on.
+    pub max_dive_duration: f64,
+    /// The iteration formula replacing z² + c, None for the built-in one.
+    pub formula: Option<Formula>,
+    /// The Rhai script defining the color function, needs the scripting feature.
+    pub color_script: Option<String>,
+    /// Runs the color script for every pixel instead of once per iteration count.
+    pub color_script_per_pixel: bool,
+    /// Starts with the external rays and equipotentials drawn over the view.
+    pub rays_overlay: bool,
+    /// Starts with the iteration contour lines drawn over the view.
+    pub contour_overlay: bool,
+    /// Starts with the axes and the coordinate grid drawn over the view.
+    pub grid_overlay: bool,
+    /// Starts with the Julia set of the view center shown next to the Mandelbrot set.
+    pub julia_split: bool,
+    /// The share of dives in [0, 1] that end centered on a minibrot.
+    pub minibrot_dive_chance: f64,
+    /// Plays a morphing Julia set between two dives.
+    pub julia_interludes: bool,
+    /// The length of the morphing interludes in seconds.
+    pub julia_interlude_duration: f64,
+    /// The transition between two dives, None picks one at random for every transition.
+    pub transition: Option<TransitionKind>,
+    /// The angle the view starts turned by in degrees, also used by offline renders.
+    pub rotation: f64,
+    /// The speed the view turns with while zooming in, in degrees per second.
+    pub rotation_speed: f64,
+    /// The seconds without input after which manual mode hands back to the automatic, 0 stays manual.
+    pub manual_idle_return: f64,
+    /// An exported PNG whose view the explorer flies to at the start.
+    pub bookmark: Option<String>,
+    /// The file the timings of every frame are written to, CSV or JSON lines by extension.
+    pub telemetry: Option<String>,
+    /// Writes a JSON status line per second to stdout.
+    pub status_json: bool,
+    /// The file the focus analytics report is written to.
+    pub analytics: Option<String>,
+    /// Shows the clock with the date from the start.
+    pub clock: bool,
+    /// Where the clock is placed.
+    pub clock_position: ScreenPosition,
+    /// The font size of the clock.
+    pub clock_size: f32,
+    /// Shows the time in 12 hour format instead of 24 hours.
+    pub clock_12h: bool,
+    /// The text of the watermark.
+    pub watermark_text: Option<String>,
+    /// The PNG logo of the watermark.
+    pub watermark_image: Option<String>,
+    /// Where the watermark is placed.
+    pub watermark_position: ScreenPosition,
+    /// The opacity of the watermark from 0 to 1.
+    pub watermark_opacity: f32,
+    /// The highest frame rate, 0 leaves it uncapped.
+    pub fps_cap: f64,
+    /// Waits for the vertical blank before showing a frame, None keeps the default of the platform.
+    pub vsync: Option<bool>,
+    /// Lowers the resolution the fields are computed in when the frames take too long.
+    pub adaptive_quality: bool,
+    /// The lowest fraction of the window resolution the adaptive quality goes down to.
+    pub adaptive_min_scale: f32,
+    /// The amount of recently computed fields kept for views that come back, 0 turns the cache off.
+    pub field_cache: usize,
+    /// Reduces the quality while running on battery.
+    pub battery_saver: bool,
+    /// The fraction of the window resolution the fields are computed in on battery.
+    pub battery_render_scale: f32,
+    /// The frame rate cap on battery, 0 leaves the frame rate alone.
+    pub battery_fps: f64,
+    /// The threads of the computation on battery, 0 takes half of them.
+    pub battery_threads: usize,
+    /// The seconds after which the explorer quits.
+    pub duration: Option<f64>,
+    /// The daily hours the zoom is shown, a black screen outside.
+    pub schedule: Option<Schedule>,
+    /// The daily hours the display is dimmed.
+    pub night_hours: Option<Schedule>,
+    /// The brightness in [0, 1] during the night hours.
+    pub night_brightness: f32,
+    /// Alternates the random dives with a tour of famous places.
+    pub demo_tour: bool,
+    /// Introduces the named places with a caption when the view enters them.
+    pub location_captions: bool,
+    /// The amount of pixels per frame checked against the high precision reference, 0 turns the check off.
+    pub verify_pixels: usize,
+    /// The post-processing effects applied to the displayed image in this order.
+    pub post_effects: Vec<PostEffectKind>,
+    /// Brightness in [0, 1) above which a pixel contributes to the bloom.
+    pub bloom_threshold: f32,
+    /// Strength of the bloom.
+    pub bloom_intensity: f32,
+    /// Scales the motion blur, 1 blurs over exactly the motion of one frame.
+    pub motion_blur_strength: f32,
+    /// Darkening of the corners by the vignette.
+    pub vignette_strength: f32,
+    /// Amplitude of the film grain.
+    pub grain_strength: f32,
+    /// Offset of the red and blue channels at the corners in pixels.
+    pub chromatic_aberration: f32,
+    /// Weight of the sharpening.
+    pub sharpen_strength: f32,
+    /// The center of offline renders.
+    pub render_center: ComplexNumber,
+    /// The radius of offline renders.
+    pub render_radius: f64,
+    /// The size of offline renders in pixels.
+    pub render_size: (u32, u32),
+    /// The output file of offline renders, each command has its own default.
+    pub render_out: Option<String>,
+    /// Writes offline renders as Deep Zoom tile pyramid instead of a single PNG.
+    pub render_pyramid: bool,
+    /// Writes the smooth iteration values of offline renders instead of colors.
+    pub render_raw: bool,
+    /// The amount of frames resampled from an exponential map, 0 only writes the map.
+    pub expmap_frames: usize,
+    /// The amount of frames of offline videos per keyframe.
+    pub video_frames_per_keyframe: usize,
+    /// Stores the current hashes in the golden check instead of comparing them.
+    pub golden_update: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            command: None,
+            palette: Palette::default(),
+            gradient: None,
+            palette_image_path: None,
+            palette_image_mode: ImagePaletteMode::default(),
+            palette_image: None,
+            coloring: Coloring::default(),
+            shading: Shading::default(),
+            light_azimuth: 135.0,
+            light_elevation: 45.0,
+            stripe_frequency: 5.0,
+            cubehelix: Cubehelix::default(),
+            cubehelix_per_dive: false,
+            idle_start_minutes: None,
+            wallpaper: false,
+            kiosk: false,
+            window_position: None,
+            fixed_point: false,
+            perturbation: true,
+            wall_role: None,
+            wall_port: 7880,
+            wall_targets: "255.255.255.255:7880".to_owned(),
+            wall_grid: (1, 1),
+            wall_tile: (0, 0),
+            remote_port: 7878,
+            remote_bind: "127.0.0.1".to_owned(),
+            osc_port: None,
+            stream_port: None,
+            virtual_camera: None,
+            terminal: false,
+            terminal_size: None,
+            terminal_graphics: TerminalGraphics::HalfBlocks,
+            resume: false,
+            dive_gif: false,
+            best_of: 0,
+            dive_trajectories: false,
+            record_format: RecordingFormat::Y4m,
+            dither: false,
+            normalize_iterations: false,
+            normalize_smooth_time: 0.5,
+            hud: false,
+            fullscreen: true,
+            zoom_speed: 1.0,
+            max_iter: DEFAULT_MAX_ITER,
+            threads: 0,
+            background: false,
+            focus_downsample: 1,
+            focus_hysteresis: 0.25,
+            focus_candidates: 3,
+            novelty_weight: 0.5,
+            start_min_score: DEFAULT_START_MIN_SCORE,
+            focus_radius: DEFAULT_FOCUS_RADIUS,
+            depth_limit: DEEPEST_RADIUS,
+            max_dive_duration: 0.0,
+            formula: None,
+            color_script: None,
+            color_script_per_pixel: false,
+            rays_overlay: false,
+            contour_overlay: false,
+            grid_overlay: false,
+            julia_split: false,
+            minibrot_dive_chance: 0.0,
+            julia_interludes: false,
+            julia_interlude_duration: 12.0,
+            transition: Some(TransitionKind::ZoomOut),
+            rotation: 0.0,
+            rotation_speed: 0.0,
+            manual_idle_return: 0.0,
+            bookmark: None,
+            telemetry: None,
+            status_json: false,
+            analytics: None,
+            clock: false,
+            clock_position: ScreenPosition::BottomRight,
+            clock_size: 120.0,
+            clock_12h: false,
+            watermark_text: None,
+            watermark_image: None,
+            watermark_position: ScreenPosition::BottomLeft,
+            watermark_opacity: 0.8,
+            fps_cap: 0.0,
+            vsync: None,
+            adaptive_quality: true,
+            adaptive_min_scale: 0.35,
+            field_cache: 4,
+            battery_saver: true,
+            battery_render_scale: 0.5,
+            battery_fps: 30.0,
+            battery_threads: 0,
+            duration: None,
+            schedule: None,
+            night_hours: None,
+            night_brightness: 0.5,
+            demo_tour: false,
+            location_captions: true,
+            verify_pixels: 0,
+            post_effects: Vec::new(),
+            bloom_threshold: DEFAULT_BLOOM_THRESHOLD,
+            bloom_intensity: DEFAULT_BLOOM_INTENSITY,
+            motion_blur_strength: 1.0,
+            vignette_strength: DEFAULT_VIGNETTE_STRENGTH,
+            grain_strength: DEFAULT_GRAIN_STRENGTH,
+            chromatic_aberration: DEFAULT_CHROMATIC_ABERRATION,
+            sharpen_strength: DEFAULT_SHARPEN_STRENGTH,
+            render_center: ComplexNumber::new(-0.5, 0.0),
+            render_radius: 1.5,
+            render_size: (1920, 1080),
+            render_out: None,
+            render_pyramid: false,
+            render_raw: false,
+            expmap_frames: 0,
+            video_frames_per_keyframe: 30,
+            golden_update: false,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file (if present) and applies the command line overrides on top.
+    fn load() -> Config {
+        let mut config = Config::default();
+
+        // The preferences saved by the explorer come first, so the explicit settings win.
+        if let Some(content) =
+            preferences_path().and_then(|path| std::fs::read_to_string(path).ok())
+        {
+            config.apply_file(&content);
+        }
+        if let Ok(content) = std::fs::read_to_string(CONFIG_FILE_NAME) {
+            config.apply_file(&content);
+        }
+
+        let mut args = std::env::args().skip(1).peekable();
+        config.command = args.next_if(|first| !first.starts_with("--"));
+        while let Some(arg) = args.next() {
+            let Some(key) = arg.strip_prefix("--") else {
+                eprintln!("Ignoring unexpected argument: {arg}");
+                continue;
+            };
+            if let Some((key, value)) = key.split_once('=') {
+                config.apply(key, value);
+            } else if let Some(value) = args.next_if(|next| !next.starts_with("--")) {
+                config.apply(key, &value);
+            } else {
+                config.apply(key, "true");
+            }
+        }
+
+        // The image is only loaded once all settings are known, as the mode may come after the path.
+        if let Some(path) = &config.palette_image_path {
+            config.palette_image = load_image_palette(Path::new(path), config.palette_image_mode)
+                .inspect_err(|err| eprintln!("Could not load palette image `{path}`: {err}"))
+                .ok();
+        }
+        config.validate_thresholds();
+
+        config
+    }
+
+    /// Resets the thresholds of the state machine to their defaults if they are out of range. They depend on each
+    /// other, so they can only be checked once all settings are known.
+    fn validate_thresholds(&mut self) {
+        if !(self.start_min_score >= 0.0 && self.start_min_score.is_finite()) {
+            eprintln!(
+                "Ignoring start-min-score {}: expected a score of at least 0",
+                self.start_min_score
+            );
+            self.start_min_score = DEFAULT_START_MIN_SCORE;
+        }
+        if !(self.focus_radius > DEEPEST_RADIUS && self.focus_radius < START_RADIUS) {
+            eprintln!(
+                "Ignoring focus-radius {}: expected a radius between {DEEPEST_RADIUS:e} and {START_RADIUS}",
+                self.focus_radius
+            );
+            self.focus_radius = DEFAULT_FOCUS_RADIUS;
+        }
+        let max_depth = max_depth_limit(self.focus_radius);
+        if !(self.depth_limit >= DEEPEST_RADIUS && self.depth_limit <= max_depth) {
+            eprintln!(
+                "Ignoring depth-limit {}: expected a radius from {DEEPEST_RADIUS:e} up to half the focus radius, {max_depth:e}",
+                self.depth_limit
+            );
+            self.depth_limit = DEEPEST_RADIUS;
+        }
+        // Custom formulas are computed in double precision, deeper views would only show blocks.
+        if self.formula.is_some() {
+            self.depth_limit = self.depth_limit.max(DOUBLE_RADIUS_LIMIT);
+        }
+    }
+
+    /// The settings of the config file content on top of the defaults, without the command line.
+    pub fn from_file(content: &str) -> Config {
+        let mut config = Config::default();
+        config.apply_file(content);
+        config
+    }
+
+    /// The settings the fields are computed with: the active formula and iteration limit, with the configured
+    /// arithmetic beyond double precision.
+    pub fn iteration_settings(&self) -> IterationSettings {
+        IterationSettings {
+            formula: fractal_formula(),
+            max_iter: max_iter(),
+            perturbation: self.perturbation,
+            fixed_point: self.fixed_point,
+        }
+    }
+
+    /// The configured parameters of the colorings and shadings.
+    pub fn color_settings(&self) -> ColorSettings {
+        ColorSettings {
+            stripe_frequency: self.stripe_frequency,
+            light_azimuth: self.light_azimuth,
+            light_elevation: self.light_elevation,
+            dither: self.dither,
+        }
+    }
+
+    /// Applies the `key = value` lines of a file, `#` starts a comment.
+    fn apply_file(&mut self, content: &str) {
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once('=').unwrap_or((line, "true"));
+            self.apply(key.trim(), value.trim());
+        }
+    }
+
+    /// Applies a single key value pair. Unknown keys and malformed values are reported and ignored.
+    fn apply(&mut self, key: &str, value: &str) {
+        let result = match key {
+            "palette" => Palette::from_name(value)
+                .map(|v| self.palette = v)
+                .ok_or("unknown palette".to_owned()),
+            "palette-image" => {
+                self.palette_image_path = Some(value.to_owned());
+                Ok(())
+            }
+            "palette-image-mode" => ImagePaletteMode::from_name(value)
+                .map(|v| self.palette_image_mode = v)
+                .ok_or("expected dominant or scanline".to_owned()),
+            "gradient" => Gradient::load(Path::new(value)).map(|v| self.gradient = Some(v)),
+            "coloring" => Coloring::from_name(value)
+                .map(|v| self.coloring = v)
+                .ok_or("unknown coloring".to_owned()),
+            "shading" => Shading::from_name(value)
+                .map(|v| self.shading = v)
+                .ok_or("expected flat, slope or relief".to_owned()),
+            "light-azimuth" => parse_value(value).map(|v| self.light_azimuth = v),
+            "light-elevation" => parse_value(value).and_then(|v: f32| {
+                if v > 0.0 && v <= 90.0 {
+                    self.light_elevation = v;
+                    Ok(())
+                } else {
+                    Err("expected an angle above 0 up to 90 degrees".to_owned())
+                }
+            }),
+            "stripe-frequency" => parse_value(value).map(|v| self.stripe_frequency = v),
+            "cubehelix" => Cubehelix::parse(value).map(|v| self.cubehelix = v),
+            "cubehelix-per-dive" => parse_value(value).map(|v| self.cubehelix_per_dive = v),
+            "center" => parse_pair(value, ',').map(|(re, im)| {
+                self.render_center = ComplexNumber::new(re, im);
+            }),
+            "radius" => parse_value(value).map(|v| self.render_radius = v),
+            "size" => parse_pair(value, 'x').map(|v| self.render_size = v),
+            "out" => {
+                self.render_out = Some(value.to_owned());
+                Ok(())
+            }
+            "pyramid" => parse_value(value).map(|v| self.render_pyramid = v),
+            "raw" => parse_value(value).map(|v| self.render_raw = v),
+            "expmap-frames" => parse_value(value).map(|v| self.expmap_frames = v),
+            "video-frames-per-keyframe" => {
+                parse_value(value).map(|v| self.video_frames_per_keyframe = v)
+            }
+            "update" => parse_value(value).map(|v| self.golden_update = v),
+            "idle-start" => parse_value(value).map(|v| self.idle_start_minutes = Some(v)),
+            "wallpaper" => parse_value(value).map(|v| self.wallpaper = v),
+            "kiosk" => parse_value(value).map(|v| self.kiosk = v),
+            "window-position" => parse_pair(value, ',').map(|v| self.window_position = Some(v)),
+            "fixed-point" => parse_value(value).map(|v| self.fixed_point = v),
+            "perturbation" => parse_value(value).map(|v| self.perturbation = v),
+            "wall-role" => WallRole::from_name(value)
+                .map(|v| self.wall_role = Some(v))
+                .ok_or(format!(
+                    "unknown wall role `{value}`, expected `director` or `follower`"
+                )),
+            "wall-port" => parse_value(value).map(|v| self.wall_port = v),
+            "wall-targets" => {
+                self.wall_targets = value.to_owned();
+                Ok(())
+            }
+            "wall-grid" => parse_pair(value, 'x').map(|v| self.wall_grid = v),
+            "wall-tile" => parse_pair(value, ',').map(|v| self.wall_tile = v),
+            "remote-port" => parse_value(value).map(|v| self.remote_port = v),
+            "remote-bind" => {
+                self.remote_bind = value.to_owned();
+                Ok(())
+            }
+            "osc-port" => parse_value(value).map(|v| self.osc_port = Some(v)),
+            "stream-port" => parse_value(value).map(|v| self.stream_port = Some(v)),
+            "terminal" => parse_value(value).map(|v| self.terminal = v),
+            "terminal-size" => parse_pair(value, 'x').map(|v| self.terminal_size = Some(v)),
+            "terminal-graphics" => TerminalGraphics::from_name(value)
+                .map(|v| self.terminal_graphics = v)
+                .ok_or("expected ansi, sixel or kitty".to_owned()),
+            "virtual-camera" => {
+                self.virtual_camera = Some(value.to_owned());
+                Ok(())
+            }
+            "resume" => parse_value(value).map(|v| self.resume = v),
+            "dither" => parse_value(value).map(|v| self.dither = v),
+            "normalize-iterations" => parse_value(value).map(|v| self.normalize_iterations = v),
+            "normalize-smooth-time" => parse_value(value).map(|v| self.normalize_smooth_time = v),
+            "hud" => parse_value(value).map(|v| self.hud = v),
+            "fullscreen" => parse_value(value).map(|v| self.fullscreen = v),
+            "zoom-speed" => parse_value(value).map(|v| self.zoom_speed = v),
+            "max-iter" => parse_value(value).and_then(|v| {
+                if MAX_ITER_RANGE.contains(&v) {
+                    self.max_iter = v;
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "expected a limit between {} and {}",
+                        MAX_ITER_RANGE.start(),
+                        MAX_ITER_RANGE.end()
+                    ))
+                }
+            }),
+            "threads" => parse_value(value).map(|v| self.threads = v),
+            "background" => parse_value(value).map(|v| self.background = v),
+            "focus-downsample" => parse_value(value).map(|v| self.focus_downsample = v),
+            "focus-hysteresis" => parse_value(value).map(|v| self.focus_hysteresis = v),
+            "focus-candidates" => parse_value(value).map(|v| self.focus_candidates = v),
+            "novelty-weight" => parse_value(value).map(|v| self.novelty_weight = v),
+            "start-min-score" => parse_value(value).map(|v| self.start_min_score = v),
+            "focus-radius" => parse_value(value).map(|v| self.focus_radius = v),
+            "depth-limit" => parse_value(value).map(|v| self.depth_limit = v),
+            "max-dive-duration" => parse_value(value).map(|v| self.max_dive_duration = v),
+            "formula" => Formula::parse(value).map(|v| self.formula = Some(v)),
+            "color-script" => {
+                self.color_script = Some(value.to_owned());
+                Ok(())
+            }
+            "color-script-per-pixel" => parse_value(value).map(|v| self.color_script_per_pixel = v),
+            "rays-overlay" => parse_value(value).map(|v| self.rays_overlay = v),
+            "contour-overlay" => parse_value(value).map(|v| self.contour_overlay = v),
+            "grid-overlay" => parse_value(value).map(|v| self.grid_overlay = v),
+            "julia-split" => parse_value(value).map(|v| self.julia_split = v),
+            "minibrot-dives" => parse_value(value).map(|v| self.minibrot_dive_chance = v),
+            "julia-interludes" => parse_value(value).map(|v| self.julia_interludes = v),
+            "transition" if value == "random" => {
+                self.transition = None;
+                Ok(())
+            }
+            "transition" => TransitionKind::from_name(value)
+                .map(|v| self.transition = Some(v))
+                .ok_or("expected zoom-out, fly-through, fade, iris or random".to_owned()),
+            "rotation" => parse_value(value).map(|v| self.rotation = v),
+            "rotation-speed" => parse_value(value).map(|v| self.rotation_speed = v),
+            "manual-idle-return" => parse_value(value).map(|v| self.manual_idle_return = v),
+            "bookmark" => {
+                self.bookmark = Some(value.to_owned());
+                Ok(())
+            }
+            "telemetry" => {
+                self.telemetry = Some(value.to_owned());
+                Ok(())
+            }
+            "status-json" => parse_value(value).map(|v| self.status_json = v),
+            "analytics" => {
+                self.analytics = Some(value.to_owned());
+                Ok(())
+            }
+            "clock" => parse_value(value).map(|v| self.clock = v),
+            "clock-position" => ScreenPosition::from_name(value)
+                .map(|v| self.clock_position = v)
+                .ok_or(unknown_position(value)),
+            "clock-size" => parse_value(value).map(|v| self.clock_size = v),
+            "clock-12h" => parse_value(value).map(|v| self.clock_12h = v),
+            "watermark-text" => {
+                self.watermark_text = Some(value.to_owned());
+                Ok(())
+            }
+            "watermark-image" => {
+                self.watermark_image = Some(value.to_owned());
+                Ok(())
+            }
+            "watermark-position" => ScreenPosition::from_name(value)
+                .map(|v| self.watermark_position = v)
+                .ok_or(unknown_position(value)),
+            "watermark-opacity" => parse_value(value).map(|v| self.watermark_opacity = v),
+            "fps-cap" => parse_value(value).map(|v| self.fps_cap = v),
+            "vsync" => parse_value(value).map(|v| self.vsync = Some(v)),
+            "adaptive-quality" => parse_value(value).map(|v| self.adaptive_quality = v),
+            "adaptive-min-scale" => parse_value(value).map(|v| self.adaptive_min_scale = v),
+            "field-cache" => parse_value(value).map(|v| self.field_cache = v),
+            "battery-saver" => parse_value(value).map(|v| self.battery_saver = v),
+            "battery-render-scale" => parse_value(value).map(|v| self.battery_render_scale = v),
+            "battery-fps" => parse_value(value).map(|v| self.battery_fps = v),
+            "battery-threads" => parse_value(value).map(|v| self.battery_threads = v),
+            "duration" => parse_duration(value).map(|v| self.duration = Some(v)),
+            "schedule" => Schedule::parse(value).map(|v| self.schedule = Some(v)),
+            "night-hours" => Schedule::parse(value).map(|v| self.night_hours = Some(v)),
+            "night-brightness" => parse_value(value).map(|v| self.night_brightness = v),
+            "demo-tour" => parse_value(value).map(|v| self.demo_tour = v),
+            "location-captions" => parse_value(value).map(|v| self.location_captions = v),
+            "verify-pixels" => parse_value(value).map(|v| self.verify_pixels = v),
+            "julia-interlude-duration" => {
+                parse_value(value).map(|v| self.julia_interlude_duration = v)
+            }
+            "post-effects" => value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    PostEffectKind::from_name(name).ok_or(format!("unknown post effect `{name}`"))
+                })
+                .collect::<Result<_, _>>()
+                .map(|v| self.post_effects = v),
+            "bloom-threshold" => parse_value(value).map(|v| self.bloom_threshold = v),
+            "bloom-intensity" => parse_value(value).map(|v| self.bloom_intensity = v),
+            "motion-blur-strength" => parse_value(value).map(|v| self.motion_blur_strength = v),
+            "vignette-strength" => parse_value(value).map(|v| self.vignette_strength = v),
+            "grain-strength" => parse_value(value).map(|v| self.grain_strength = v),
+            "chromatic-aberration" => parse_value(value).map(|v| self.chromatic_aberration = v),
+            "sharpen-strength" => parse_value(value).map(|v| self.sharpen_strength = v),
+            "dive-gif" => parse_value(value).map(|v| self.dive_gif = v),
+            "best-of" => parse_value(value).map(|v| self.best_of = v),
+            "dive-trajectories" => parse_value(value).map(|v| self.dive_trajectories = v),
+            "record-format" => RecordingFormat::from_name(value)
+                .map(|v| self.record_format = v)
+                .ok_or("expected png or y4m".to_owned()),
+            _ => Err(format!("unknown key `{key}`")),
+        };
+        if let Err(message) = result {
+            eprintln!("Ignoring setting `{key} = {value}`: {message}");
+        }
+    }
+}
+
+/// Loads the image file (PNG or TGA) and builds the palette from its pixels.
+fn load_image_palette(path: &Path, mode: ImagePaletteMode) -> Result<ImagePalette, String> {
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    let image = Image::from_file_with_format(&bytes, None).map_err(|err| err.to_string())?;
+    ImagePalette::from_rgba8(
+        image.width as usize,
+        image.height as usize,
+        &image.bytes,
+        mode,
+    )
+}
+
+/// Parses a value and converts the error into a printable message.
+fn parse_value<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("can not parse `{value}`"))
+}
+
+/// Parses two values separated by the given character, like `1920x1080`.
+fn parse_pair<T: std::str::FromStr>(value: &str, separator: char) -> Result<(T, T), String> {
+    let (first, second) = value
+        .split_once(separator)
+        .ok_or(format!("expected two values separated by `{separator}`"))?;
+    Ok((parse_value(first.trim())?, parse_value(second.trim())?))
+}
+
+/// The message for an unknown screen position.
+fn unknown_position(value: &str) -> String {
+    format!(
+        "unknown position `{value}`, expected `top-left`, `top-right`, `bottom-left`, `bottom-right` or `center`"
+    )
+}
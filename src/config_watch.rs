@@ -0,0 +1,76 @@
+//! Watches the config file while the explorer runs, so a display wall can be retuned without a restart. The file
+//! is checked once a second, when it changed, the settings that differ from the last version turn into commands.
+//! Settings that were not touched in the file keep what was chosen in the running explorer.
+
+use crate::commands::Command;
+use crate::config::{CONFIG_FILE_NAME, Config};
+use macroquad::time::get_time;
+use std::time::SystemTime;
+
+/// The seconds between two checks of the file.
+const CHECK_INTERVAL: f64 = 1.0;
+
+/// Notices changes of the config file.
+pub struct ConfigWatcher {
+    /// The modification time of the file when we read it last, None if it was missing.
+    modified: Option<SystemTime>,
+    /// The settings of the file when we read it last.
+    settings: Config,
+    /// The time of the last check.
+    last_check: f64,
+}
+
+impl ConfigWatcher {
+    /// Starts watching from the current state of the file.
+    pub fn new() -> ConfigWatcher {
+        ConfigWatcher {
+            modified: modification_time(),
+            settings: read_settings(),
+            last_check: get_time(),
+        }
+    }
+
+    /// Checks the file and adds the commands for the changed settings.
+    pub fn poll(&mut self, commands: &mut Vec<Command>) {
+        if get_time() - self.last_check < CHECK_INTERVAL {
+            return;
+        }
+        self.last_check = get_time();
+        let modified = modification_time();
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        let settings = read_settings();
+        let previous = std::mem::replace(&mut self.settings, settings);
+        let settings = &self.settings;
+
+        if settings.palette != previous.palette {
+            commands.push(Command::SetPalette(settings.palette));
+        }
+        if settings.coloring != previous.coloring {
+            commands.push(Command::SetColoring(settings.coloring));
+        }
+        if settings.shading != previous.shading {
+            commands.push(Command::SetShading(settings.shading));
+        }
+        if settings.zoom_speed != previous.zoom_speed {
+            commands.push(Command::SetZoomSpeed(settings.zoom_speed));
+        }
+        if settings.max_iter != previous.max_iter {
+            commands.push(Command::SetMaxIter(settings.max_iter));
+        }
+    }
+}
+
+/// The modification time of the config file, None if it is missing.
+fn modification_time() -> Option<SystemTime> {
+    std::fs::metadata(CONFIG_FILE_NAME)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// The settings in the config file, the defaults if it is missing.
+fn read_settings() -> Config {
+    Config::from_file(&std::fs::read_to_string(CONFIG_FILE_NAME).unwrap_or_default())
+}
@@ -0,0 +1,270 @@
+//! The drop-down console, opened with the backtick key. Every line typed there is translated into a [`Command`],
+//! so the console controls everything the other control surfaces can and is the place for features without a key
+//! of their own. The answers and errors show up in the log above the input line.
+
+use crate::color_generation::{Coloring, Palette, Shading};
+use crate::commands::Command;
+use crate::focus_system::FocusParameter;
+use crate::goto_dialog::{edit_text, parse_location};
+use crate::locations::{LOCATIONS, find_location};
+use crate::math::{ComplexNumber, MAX_ITER_RANGE};
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+/// The font size of the console.
+const FONT_SIZE: f32 = 20.0;
+
+/// The padding around the text in pixels.
+const PADDING: f32 = 8.0;
+
+/// The part of the screen height the open console covers.
+const CONSOLE_HEIGHT: f32 = 0.4;
+
+/// How much of the console height slides in per second.
+const SLIDE_SPEED: f32 = 6.0;
+
+/// The amount of log lines kept.
+const LOG_LINES: usize = 64;
+
+/// The summary printed by the help command.
+const HELP: [&str; 18] = [
+    "goto <re> <im> <radius>    fly to a location",
+    "goto <name>                fly to a named place",
+    "locations                  list the named places",
+    "palette <name>|next        switch the palette",
+    "palettes                   list the palettes",
+    "coloring <name>            switch the exterior coloring",
+    "shading <name>             switch the shading",
+    "hue <offset>               shift the palette cycle",
+    "speed <factor>             set the zoom speed",
+    "set maxiter <n>            set the iteration limit",
+    "set <focus setting> <v>    start-min-score, focus-radius, depth-limit,",
+    "                           focus-hysteresis or focus-candidates",
+    "record on|off              start or stop recording",
+    "seed <number>              reseed the target selection",
+    "pause | resume | next      control the dive",
+    "manual | screenshot | quit",
+    "trajectory                 export the path of the dive",
+    "clear                      empty the log",
+];
+
+/// The state of the console.
+#[derive(Debug, Default)]
+pub struct Console {
+    /// Whether the console is open or sliding open.
+    open: bool,
+    /// How far the console has slid down, from 0 to 1.
+    openness: f32,
+    /// The line being typed.
+    input: String,
+    /// The entered lines and the answers, the newest last.
+    log: VecDeque<String>,
+    /// The lines entered so far, for recalling them with the arrow keys.
+    history: Vec<String>,
+    /// The position in the history while recalling, None while typing a new line.
+    recalled: Option<usize>,
+}
+
+impl Console {
+    /// Whether the console takes the keyboard input.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens or closes the console.
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        // The backtick must not end up in the input.
+        clear_input_queue();
+    }
+
+    /// Adds a line to the log.
+    pub fn log(&mut self, line: impl Into<String>) {
+        if self.log.len() == LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(line.into());
+    }
+
+    /// Slides the console and processes the keyboard input of this frame, the commands of entered lines are added.
+    pub fn update(&mut self, commands: &mut Vec<Command>) {
+        let target = if self.open { 1.0 } else { 0.0 };
+        let step = SLIDE_SPEED * get_frame_time();
+        self.openness += (target - self.openness).clamp(-step, step);
+        if !self.open {
+            return;
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.toggle();
+            return;
+        }
+        if is_key_pressed(KeyCode::Up) && !self.history.is_empty() {
+            let index = self
+                .recalled
+                .map_or(self.history.len() - 1, |index| index.saturating_sub(1));
+            self.recalled = Some(index);
+            self.input = self.history[index].clone();
+        }
+        if is_key_pressed(KeyCode::Down)
+            && let Some(index) = self.recalled
+        {
+            self.recalled = (index + 1 < self.history.len()).then_some(index + 1);
+            self.input = self
+                .recalled
+                .map_or(String::new(), |index| self.history[index].clone());
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            let line = std::mem::take(&mut self.input);
+            self.recalled = None;
+            self.execute(line.trim(), commands);
+            return;
+        }
+        edit_text(&mut self.input);
+    }
+
+    /// Runs an entered line.
+    fn execute(&mut self, line: &str, commands: &mut Vec<Command>) {
+        if line.is_empty() {
+            return;
+        }
+        self.log(format!("> {line}"));
+        self.history.push(line.to_owned());
+        match line {
+            "help" => HELP.into_iter().for_each(|line| self.log(line)),
+            "palettes" => {
+                let names: Vec<_> = Palette::all().map(Palette::name).collect();
+                self.log(names.join(" "));
+            }
+            "locations" => {
+                let names: Vec<_> = LOCATIONS.iter().map(|location| location.name).collect();
+                self.log(names.join(", "));
+            }
+            "clear" => self.log.clear(),
+            _ => match parse_console_command(line) {
+                Ok(command) => commands.push(command),
+                Err(err) => self.log(err),
+            },
+        }
+    }
+
+    /// Draws the console at the top of the screen.
+    pub fn draw(&self) {
+        if self.openness <= 0.0 {
+            return;
+        }
+        let height = screen_height() * CONSOLE_HEIGHT;
+        let bottom = height * self.openness;
+        draw_rectangle(
+            0.0,
+            bottom - height,
+            screen_width(),
+            height,
+            Color::new(0.0, 0.0, 0.0, 0.85),
+        );
+        draw_line(0.0, bottom, screen_width(), bottom, 2.0, GRAY);
+
+        // The cursor blinks twice a second.
+        let cursor = if get_time().fract() < 0.5 { "_" } else { "" };
+        let mut y = bottom - PADDING;
+        draw_text(
+            &format!("] {}{cursor}", self.input),
+            PADDING,
+            y,
+            FONT_SIZE,
+            WHITE,
+        );
+        for line in self.log.iter().rev() {
+            y -= FONT_SIZE;
+            if y < bottom - height {
+                break;
+            }
+            draw_text(line, PADDING, y, FONT_SIZE, LIGHTGRAY);
+        }
+    }
+}
+
+/// Translates a console line into a command.
+fn parse_console_command(line: &str) -> Result<Command, String> {
+    let (name, argument) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(name, argument)| (name, argument.trim()));
+    let number = |argument: &str| {
+        argument
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| format!("{argument} is not a number"))
+    };
+
+    match name {
+        "goto" => match find_location(argument) {
+            Some(location) => Ok(Command::GoTo(
+                location.center,
+                ComplexNumber::default(),
+                location.radius,
+            )),
+            None => parse_location(argument)
+                .map(|(center, radius)| Command::GoTo(center, ComplexNumber::default(), radius)),
+        },
+        "palette" if argument == "next" => Ok(Command::NextPalette),
+        "palette" => Palette::from_name(argument)
+            .map(Command::SetPalette)
+            .ok_or(format!("unknown palette {argument}")),
+        "coloring" => Coloring::from_name(argument)
+            .map(Command::SetColoring)
+            .ok_or(format!("unknown coloring {argument}")),
+        "shading" => Shading::from_name(argument)
+            .map(Command::SetShading)
+            .ok_or(format!("unknown shading {argument}")),
+        "hue" => Ok(Command::SetHueOffset(
+            number(argument)?.rem_euclid(1.0) as f32
+        )),
+        "speed" => Ok(Command::SetZoomSpeed(number(argument)?)),
+        "record" => match argument {
+            "on" => Ok(Command::SetRecording(true)),
+            "off" => Ok(Command::SetRecording(false)),
+            _ => Err("expected record on or off".to_owned()),
+        },
+        "seed" => argument
+            .parse()
+            .map(Command::SetSeed)
+            .map_err(|_| format!("{argument} is not a seed")),
+        "pause" => Ok(Command::SetPaused(true)),
+        "resume" => Ok(Command::SetPaused(false)),
+        "next" => Ok(Command::NextTarget),
+        "manual" => Ok(Command::ToggleManual),
+        "screenshot" => Ok(Command::Screenshot),
+        "trajectory" => Ok(Command::ExportTrajectory),
+        "quit" => Ok(Command::Quit),
+        "set" => {
+            let (setting, value) = argument
+                .split_once(char::is_whitespace)
+                .map_or((argument, ""), |(setting, value)| (setting, value.trim()));
+            let parameter = match setting {
+                "maxiter" => {
+                    return value
+                        .parse()
+                        .ok()
+                        .filter(|limit| MAX_ITER_RANGE.contains(limit))
+                        .map(Command::SetMaxIter)
+                        .ok_or_else(|| {
+                            format!(
+                                "the iteration limit must be between {} and {}",
+                                MAX_ITER_RANGE.start(),
+                                MAX_ITER_RANGE.end()
+                            )
+                        });
+                }
+                "start-min-score" => FocusParameter::StartMinScore,
+                "focus-radius" => FocusParameter::FocusRadius,
+                "depth-limit" => FocusParameter::DepthLimit,
+                "focus-hysteresis" => FocusParameter::Hysteresis,
+                "focus-candidates" => FocusParameter::Candidates,
+                _ => return Err(format!("unknown setting {setting}, try help")),
+            };
+            Ok(Command::SetFocusParameter(parameter, number(value)?))
+        }
+        _ => Err(format!("unknown command {name}, try help")),
+    }
+}
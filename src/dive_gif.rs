@@ -0,0 +1,111 @@
+//! Collects downscaled keyframes during a dive and assembles them into an animated GIF once the dive ends,
+//! so memorable dives of an unattended run are preserved.
+
+use crate::color::Color;
+use crate::color_generation::{linear_to_srgb, srgb_to_linear};
+use crate::export::{GALLERY_DIRECTORY, time_stamp};
+use crate::zoom_director::ZoomState;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use macroquad::time::get_time;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// The factor by which the keyframes are downscaled in each direction.
+const DOWNSCALE: usize = 4;
+/// The width of the keyframes.
+const GIF_WIDTH: usize = WINDOW_WIDTH as usize / DOWNSCALE;
+/// The height of the keyframes.
+const GIF_HEIGHT: usize = WINDOW_HEIGHT as usize / DOWNSCALE;
+/// The time in seconds between two keyframes.
+const KEYFRAME_INTERVAL: f64 = 0.25;
+/// The display time of a frame in the GIF in hundredths of a second.
+const FRAME_DELAY: u16 = 8;
+/// Dives with fewer keyframes are not worth saving.
+const MIN_KEYFRAMES: usize = 8;
+/// Speed of the color quantization from 1 (best) to 30 (fastest).
+const QUANTIZATION_SPEED: i32 = 10;
+
+/// Collects the keyframes of the current dive.
+#[derive(Default)]
+pub struct DiveGifRecorder {
+    /// The keyframes as RGBA8 buffers.
+    keyframes: Vec<Vec<u8>>,
+    /// The time the last keyframe was taken.
+    last_capture: f64,
+}
+
+impl DiveGifRecorder {
+    /// Takes a keyframe while we dive and writes the GIF once the dive is over.
+    pub fn capture(&mut self, zoom_state: ZoomState, colors: &[Color]) {
+        if matches!(
+            zoom_state,
+            ZoomState::StartZooming | ZoomState::ZoomingInAndFollowing
+        ) {
+            if get_time() - self.last_capture >= KEYFRAME_INTERVAL {
+                self.last_capture = get_time();
+                self.keyframes.push(downscale(colors));
+            }
+        } else if !self.keyframes.is_empty() {
+            let keyframes = std::mem::take(&mut self.keyframes);
+            if keyframes.len() >= MIN_KEYFRAMES {
+                // Color quantization takes a while, so we do not stall the render loop.
+                std::thread::spawn(move || {
+                    if let Err(err) = write_gif(keyframes) {
+                        eprintln!("Could not save dive animation: {err}");
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Averages blocks of pixels in linear light into an RGBA8 keyframe.
+fn downscale(colors: &[Color]) -> Vec<u8> {
+    let samples = (DOWNSCALE * DOWNSCALE) as f32;
+    (0..GIF_WIDTH * GIF_HEIGHT)
+        .flat_map(|idx| {
+            let (x, y) = ((idx % GIF_WIDTH) * DOWNSCALE, (idx / GIF_WIDTH) * DOWNSCALE);
+            let mut sum = [0.0; 3];
+            for dy in 0..DOWNSCALE {
+                for dx in 0..DOWNSCALE {
+                    let color = colors[x + dx + (y + dy) * WINDOW_WIDTH as usize];
+                    sum[0] += srgb_to_linear(color.r);
+                    sum[1] += srgb_to_linear(color.g);
+                    sum[2] += srgb_to_linear(color.b);
+                }
+            }
+            let channel = |value: f32| (linear_to_srgb(value / samples) * 255.0) as u8;
+            [channel(sum[0]), channel(sum[1]), channel(sum[2]), 255]
+        })
+        .collect()
+}
+
+/// Encodes the keyframes as looping GIF into the gallery directory.
+fn write_gif(keyframes: Vec<Vec<u8>>) -> std::io::Result<()> {
+    std::fs::create_dir_all(GALLERY_DIRECTORY)?;
+    let path = Path::new(GALLERY_DIRECTORY).join(format!("dive_{}.gif", time_stamp()));
+    let mut encoder = gif::Encoder::new(
+        BufWriter::new(File::create(&path)?),
+        GIF_WIDTH as u16,
+        GIF_HEIGHT as u16,
+        &[],
+    )
+    .map_err(std::io::Error::other)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(std::io::Error::other)?;
+
+    for mut keyframe in keyframes {
+        let mut frame = gif::Frame::from_rgba_speed(
+            GIF_WIDTH as u16,
+            GIF_HEIGHT as u16,
+            &mut keyframe,
+            QUANTIZATION_SPEED,
+        );
+        frame.delay = FRAME_DELAY;
+        encoder.write_frame(&frame).map_err(std::io::Error::other)?;
+    }
+    println!("Saved dive animation to {}", path.display());
+    Ok(())
+}
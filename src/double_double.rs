@@ -0,0 +1,217 @@
+//! Double-double arithmetic, which carries a number as unevaluated sum of two f64 for about 106 bits of mantissa.
+//! It backs the precision tier below double precision and the reference of the verification mode.
+
+use crate::math::{CancellationToken, ComplexNumber, Fractal, Viewport};
+use rayon::prelude::*;
+use std::ops::{Add, Mul, Sub};
+
+/// A number as unevaluated sum of two f64, the low part holds the rounding error of the high part.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DoubleDouble {
+    /// The leading part.
+    pub hi: f64,
+    /// The rounding error of the leading part, at most half an ulp of it.
+    pub lo: f64,
+}
+
+impl DoubleDouble {
+    /// The exact value of a f64.
+    pub fn from_f64(value: f64) -> DoubleDouble {
+        DoubleDouble { hi: value, lo: 0.0 }
+    }
+
+    /// The exact sum of two f64 as double-double.
+    pub fn two_sum(a: f64, b: f64) -> DoubleDouble {
+        let hi = a + b;
+        let b_virtual = hi - a;
+        let lo = (a - (hi - b_virtual)) + (b - b_virtual);
+        DoubleDouble { hi, lo }
+    }
+
+    /// Renormalizes a sum whose parts may overlap, requires |hi| >= |lo|.
+    pub fn quick_two_sum(hi: f64, lo: f64) -> DoubleDouble {
+        let sum = hi + lo;
+        DoubleDouble {
+            hi: sum,
+            lo: lo - (sum - hi),
+        }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    /// Sums the low parts exactly as well, the leading parts of z² cancel near the boundary of the set and leave the
+    /// low parts in charge.
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let high = DoubleDouble::two_sum(self.hi, other.hi);
+        let low = DoubleDouble::two_sum(self.lo, other.lo);
+        let sum = DoubleDouble::quick_two_sum(high.hi, high.lo + low.hi);
+        DoubleDouble::quick_two_sum(sum.hi, sum.lo + low.lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + DoubleDouble {
+            hi: -other.hi,
+            lo: -other.lo,
+        }
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        // The fused multiply add yields the exact rounding error of the leading product.
+        let hi = self.hi * other.hi;
+        let error = self.hi.mul_add(other.hi, -hi);
+        DoubleDouble::quick_two_sum(hi, error + self.hi * other.lo + self.lo * other.hi)
+    }
+}
+
+/// A complex number in double-double arithmetic.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreciseComplex {
+    /// The real part.
+    pub real: DoubleDouble,
+    /// The imaginary part.
+    pub imag: DoubleDouble,
+}
+
+impl PreciseComplex {
+    /// The point of the pixel including the low part of the center. The offset in the size of the view only needs
+    /// f64, the sum with the center is computed without rounding it to f64.
+    pub fn pixel(viewport: &Viewport, x: u32, y: u32) -> PreciseComplex {
+        let offset = viewport.pixel_offset(x as i64, y as i64);
+        PreciseComplex {
+            real: DoubleDouble::two_sum(viewport.center.real, viewport.center_low.real)
+                + DoubleDouble::from_f64(offset.real),
+            imag: DoubleDouble::two_sum(viewport.center.imag, viewport.center_low.imag)
+                + DoubleDouble::from_f64(offset.imag),
+        }
+    }
+
+    /// The exact value of a f64 complex number.
+    pub fn from_complex(value: ComplexNumber) -> PreciseComplex {
+        PreciseComplex {
+            real: DoubleDouble::from_f64(value.real),
+            imag: DoubleDouble::from_f64(value.imag),
+        }
+    }
+
+    /// The leading parts.
+    pub fn to_complex(self) -> ComplexNumber {
+        ComplexNumber::new(self.real.hi, self.imag.hi)
+    }
+
+    /// One step of the iteration, z² + c.
+    pub fn square_add(self, c: PreciseComplex) -> PreciseComplex {
+        let cross = self.real * self.imag;
+        PreciseComplex {
+            real: self.real * self.real - self.imag * self.imag + c.real,
+            imag: cross + cross + c.imag,
+        }
+    }
+
+    /// The amount of iterations of z² + c from the start till the orbit leaves the radius 2 or reaches the limit,
+    /// counted like the f64 computation does.
+    pub fn iterations(start: PreciseComplex, c: PreciseComplex, limit: u16) -> u16 {
+        let mut z = start;
+        let mut iterations = 0;
+        while iterations < limit {
+            let sq_real = z.real * z.real;
+            let sq_imag = z.imag * z.imag;
+            // The leading part alone rounds magnitudes just below 4 up to it.
+            let magnitude = sq_real + sq_imag;
+            if magnitude.hi > 4.0 || (magnitude.hi == 4.0 && magnitude.lo >= 0.0) {
+                break;
+            }
+            let cross = z.real * z.imag;
+            z = PreciseComplex {
+                real: sq_real - sq_imag + c.real,
+                imag: cross + cross + c.imag,
+            };
+            iterations += 1;
+        }
+        iterations
+    }
+}
+
+/// Computes the iterations of the whole viewport in double-double arithmetic up to the limit. Returns None as soon
+/// as the token is cancelled.
+pub fn try_iteration_field_double_double(
+    viewport: &Viewport,
+    fractal: Fractal,
+    limit: u16,
+    cancel: &CancellationToken,
+) -> Option<Vec<u16>> {
+    let width = viewport.width as usize;
+    let mut field = vec![0; width * viewport.height as usize];
+    field
+        .par_chunks_mut(width)
+        .enumerate()
+        .try_for_each(|(y, row)| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            cancel.advance(width);
+            for (x, value) in row.iter_mut().enumerate() {
+                let pixel = PreciseComplex::pixel(viewport, x as u32, y as u32);
+                *value = match fractal {
+                    Fractal::Mandelbrot => {
+                        PreciseComplex::iterations(PreciseComplex::default(), pixel, limit)
+                    }
+                    Fractal::Julia(c) => {
+                        PreciseComplex::iterations(pixel, PreciseComplex::from_complex(c), limit)
+                    }
+                };
+            }
+            Some(())
+        })?;
+    Some(field)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_sum_keeps_the_rounding_error() {
+        let sum = DoubleDouble::two_sum(1.0, 1e-20);
+        assert_eq!(sum, DoubleDouble { hi: 1.0, lo: 1e-20 });
+        let sum = DoubleDouble::two_sum(0.1, 0.2);
+        assert_eq!(sum.hi, 0.1 + 0.2);
+        assert_ne!(sum.lo, 0.0);
+    }
+
+    #[test]
+    fn addition_and_subtraction_are_exact_below_f64() {
+        let one = DoubleDouble::from_f64(1.0);
+        let tiny = DoubleDouble::from_f64(1e-30);
+        assert_eq!((one + tiny) - one, tiny);
+        assert_eq!((one + tiny) - tiny, one);
+    }
+
+    #[test]
+    fn multiplication_keeps_the_low_part() {
+        // (1 + 2⁻⁶⁰)² = 1 + 2⁻⁵⁹ + 2⁻¹²⁰, the last term is below the resolution of double-double.
+        let x = DoubleDouble {
+            hi: 1.0,
+            lo: 2f64.powi(-60),
+        };
+        assert_eq!(
+            x * x,
+            DoubleDouble {
+                hi: 1.0,
+                lo: 2f64.powi(-59)
+            }
+        );
+        // The leading parts cancel in x² - 1, which leaves the low part as result.
+        let one = DoubleDouble::from_f64(1.0);
+        assert_eq!(x * x - one, DoubleDouble::from_f64(2f64.powi(-59)));
+    }
+}
@@ -0,0 +1,130 @@
+//! Keeps an unattended display running. A frame that fails is logged and skipped, so the last good picture stays on
+//! screen, and a panic in the computation or the autofocus resets the view instead of ending the process. Panics are
+//! also appended to `panic.log` next to the preferences, since the windows build has no console to print them to.
+
+use crate::preferences::preferences_path;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use macroquad::prelude::get_time;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The name of the file panics are appended to.
+const PANIC_LOG_FILE_NAME: &str = "panic.log";
+
+/// The shortest time between two reports of failed frames in seconds, so a failure in every frame does not flood
+/// the log.
+const REPORT_INTERVAL: f64 = 5.0;
+
+/// The reasons a frame can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    /// A computed field does not cover the window.
+    FieldSize { expected: usize, actual: usize },
+    /// The computation panicked with the given message.
+    Panic(String),
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::FieldSize { expected, actual } => {
+                write!(f, "the field has {actual} instead of {expected} pixels")
+            }
+            FrameError::Panic(message) => write!(f, "the computation panicked: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+/// Passes the field on if it covers the window.
+pub fn check_field<T>(field: Vec<T>) -> Result<Vec<T>, FrameError> {
+    let expected = (WINDOW_WIDTH * WINDOW_HEIGHT) as usize;
+    if field.len() == expected {
+        Ok(field)
+    } else {
+        Err(FrameError::FieldSize {
+            expected,
+            actual: field.len(),
+        })
+    }
+}
+
+/// Runs a step of the frame and turns a panic in it, also one in a worker thread, into an error. The caller has to
+/// bring the state the step touched back into shape.
+pub fn catch_frame_panic<R>(step: impl FnOnce() -> R) -> Result<R, FrameError> {
+    std::panic::catch_unwind(AssertUnwindSafe(step)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown cause".to_owned());
+        FrameError::Panic(message)
+    })
+}
+
+/// Reports failed frames, at most once per interval.
+#[derive(Debug)]
+pub struct ErrorLog {
+    /// The time of the last report.
+    last_report: f64,
+    /// The failed frames since the last report.
+    suppressed: u32,
+}
+
+impl Default for ErrorLog {
+    fn default() -> Self {
+        ErrorLog {
+            last_report: f64::NEG_INFINITY,
+            suppressed: 0,
+        }
+    }
+}
+
+impl ErrorLog {
+    /// Reports the error of a skipped frame if the last report is long enough ago.
+    pub fn report(&mut self, err: &FrameError) {
+        if get_time() - self.last_report < REPORT_INTERVAL {
+            self.suppressed += 1;
+            return;
+        }
+        if self.suppressed > 0 {
+            eprintln!(
+                "Skipped frame: {err} ({} more since the last report)",
+                self.suppressed
+            );
+        } else {
+            eprintln!("Skipped frame: {err}");
+        }
+        self.last_report = get_time();
+        self.suppressed = 0;
+    }
+}
+
+/// Appends every panic to the panic log before the default hook prints it.
+pub fn install_panic_log() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(path) = panic_log_path() {
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs());
+            let _ = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "[{seconds}] {info}"));
+        }
+        default_hook(info);
+    }));
+}
+
+/// The panic log lives next to the preferences file, None if there is no config directory.
+fn panic_log_path() -> Option<PathBuf> {
+    let path = preferences_path()?.with_file_name(PANIC_LOG_FILE_NAME);
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    Some(path)
+}
@@ -0,0 +1,229 @@
+//! Exponential map rendering for zoom videos, which bypasses the interactive loop:
+//! `mandelbrot-explorer expmap --center -0.75,0.1 --radius 1e-9 --size 1280x720 --out dive.png`
+//!
+//! Instead of one frame per step, the whole zoom from the start radius down to the target radius is rendered once in
+//! log-polar coordinates around the center: the columns run around the center, the rows go inwards with the radius
+//! shrinking by the same factor from row to row. The rows are spaced so the pixels are square, so every frame of
+//! the zoom can be resampled from the map at its full resolution, at any zoom depth and frame rate. A zoom video
+//! thus costs the pixels of about two and a half frames per halving of the radius, no matter how smooth it is.
+//!
+//! The map is sized for frames of `--size`, with `--expmap-frames` it is resampled into that many frames zooming
+//! from the start radius to the target, into `<out>_frames/`. The map is written to `expmap.png` without `--out`.
+//! The points lie on rings instead of a grid, which the deeper precision tiers do not compute, so they are iterated
+//! in double precision and radii below its limit are refused.
+
+use crate::color::color_to_rgba8;
+use crate::color_generation::{
+    ColorLookup, escape_tracking, generate_colors, generate_escape_colors,
+};
+use crate::config::CONFIG;
+use crate::export::{ViewMetadata, save_png};
+use crate::math::{ComplexNumber, Fractal, Viewport, max_iter};
+use crate::poster::{palette_settings_from_config, render_radius_from_config, report_progress};
+use crate::precision::DOUBLE_RADIUS_LIMIT;
+use crate::transition::START_RADIUS;
+use rayon::prelude::*;
+use std::f64::consts::TAU;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The file the map is written to without `--out`.
+const DEFAULT_OUT: &str = "expmap.png";
+
+/// The amount of rows computed and colored at once.
+const STRIP_ROWS: u32 = 64;
+
+/// A zoom rendered in log-polar coordinates around its center.
+struct ExpMap {
+    /// The center of the zoom.
+    center: ComplexNumber,
+    /// The distance from the center of the first row.
+    outer_radius: f64,
+    /// The factor the distance shrinks by from row to row as logarithm, equal to the angle between two columns.
+    log_step: f64,
+    /// The amount of columns around the center.
+    width: u32,
+    /// The amount of rows.
+    height: u32,
+    /// The colors as RGBA8, row by row.
+    pixels: Vec<u8>,
+}
+
+impl ExpMap {
+    /// Renders the map for frames of the viewport size, from the start radius down to the radius of the viewport.
+    fn render(viewport: &Viewport) -> ExpMap {
+        // The outermost ring has to resolve the corners of the first frame, the innermost one a pixel of the last.
+        let corner_distance = (viewport.width as f64).hypot(viewport.height as f64) * 0.5;
+        let width = (TAU * corner_distance).ceil() as u32;
+        let log_step = TAU / width as f64;
+        let outer_radius =
+            START_RADIUS.max(viewport.radius) * corner_distance * 2.0 / viewport.height as f64;
+        let inner_radius = viewport.pixel_step() * 0.5;
+        let height = ((outer_radius / inner_radius).ln() / log_step).ceil() as u32;
+        let mut map = ExpMap {
+            center: viewport.center,
+            outer_radius,
+            log_step,
+            width,
+            height,
+            pixels: Vec::with_capacity(width as usize * height as usize * 4),
+        };
+
+        let color_settings = CONFIG.color_settings();
+        let tracking = escape_tracking(CONFIG.coloring, CONFIG.shading, &color_settings);
+        let lookup = ColorLookup::new(palette_settings_from_config(), max_iter());
+        let strip_count = height.div_ceil(STRIP_ROWS);
+        for strip in 0..strip_count {
+            let first_row = strip * STRIP_ROWS;
+            let points: Vec<ComplexNumber> = (first_row..(first_row + STRIP_ROWS).min(height))
+                .flat_map(|row| (0..width).map(move |column| (column, row)))
+                .map(|(column, row)| map.point(column, row))
+                .collect();
+            let colors = if let Some(tracking) = tracking {
+                let field: Vec<_> = points
+                    .par_iter()
+                    .map(|point| Fractal::Mandelbrot.escape(*point, tracking))
+                    .collect();
+                generate_escape_colors(
+                    &field,
+                    width as usize,
+                    &lookup,
+                    CONFIG.coloring,
+                    CONFIG.shading,
+                    &color_settings,
+                )
+            } else {
+                let field: Vec<u16> = points
+                    .par_iter()
+                    .map(|point| Fractal::Mandelbrot.iterations(*point))
+                    .collect();
+                generate_colors(&field, width as usize, &lookup, &color_settings)
+            };
+            map.pixels
+                .extend(colors.into_iter().flat_map(color_to_rgba8));
+            report_progress(strip as usize + 1, strip_count as usize);
+        }
+        map
+    }
+
+    /// The point in the complex number pane at the center of the pixel.
+    fn point(&self, column: u32, row: u32) -> ComplexNumber {
+        let distance = self.outer_radius * (-(row as f64 + 0.5) * self.log_step).exp();
+        let angle = (column as f64 + 0.5) * self.log_step;
+        self.center + ComplexNumber::from_angle(angle) * ComplexNumber::new(distance, 0.0)
+    }
+
+    /// Resamples the frame of the viewport, which has to share the center, bilinearly from the map. Points closer
+    /// to the center than the innermost row take its colors.
+    fn frame(&self, viewport: &Viewport) -> Vec<u8> {
+        let mut rgba = vec![0u8; viewport.width as usize * viewport.height as usize * 4];
+        rgba.par_chunks_exact_mut(viewport.width as usize * 4)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                    let offset = viewport.pixel_offset(x as i64, y as i64);
+                    let distance = offset.sq_mag().sqrt().max(f64::MIN_POSITIVE);
+                    let column =
+                        offset.imag.atan2(offset.real).rem_euclid(TAU) / self.log_step - 0.5;
+                    let row = (self.outer_radius / distance).ln() / self.log_step - 0.5;
+                    pixel.copy_from_slice(&self.sample(column, row));
+                }
+            });
+        rgba
+    }
+
+    /// Interpolates the color at the fractional position, wrapping around the center and clamping the rows.
+    fn sample(&self, column: f64, row: f64) -> [u8; 4] {
+        let row = row.clamp(0.0, (self.height - 1) as f64);
+        let (column_floor, row_floor) = (column.floor(), row.floor());
+        let (fx, fy) = (column - column_floor, row - row_floor);
+        let left = (column_floor as i64).rem_euclid(self.width as i64) as usize;
+        let right = (left + 1) % self.width as usize;
+        let top = row_floor as usize;
+        let bottom = (top + 1).min(self.height as usize - 1);
+        let at = |x: usize, y: usize, channel: usize| {
+            self.pixels[(y * self.width as usize + x) * 4 + channel] as f64
+        };
+        std::array::from_fn(|channel| {
+            let upper = at(left, top, channel) * (1.0 - fx) + at(right, top, channel) * fx;
+            let lower = at(left, bottom, channel) * (1.0 - fx) + at(right, bottom, channel) * fx;
+            (upper * (1.0 - fy) + lower * fy).round() as u8
+        })
+    }
+}
+
+/// Renders the exponential map described by the config, and the frames resampled from it if requested.
+pub fn render_exp_map_from_config() {
+    let radius = match render_radius_from_config(DOUBLE_RADIUS_LIMIT) {
+        Ok(radius) => radius,
+        Err(err) => {
+            eprintln!("Could not save the exponential map: {err}");
+            return;
+        }
+    };
+    let (width, height) = CONFIG.render_size;
+    let viewport = Viewport {
+        center: CONFIG.render_center,
+        center_low: ComplexNumber::default(),
+        radius,
+        rotation: ComplexNumber::from_angle(CONFIG.rotation.to_radians()),
+        width,
+        height,
+    };
+    let map = ExpMap::render(&viewport);
+    let out = Path::new(CONFIG.render_out.as_deref().unwrap_or(DEFAULT_OUT));
+    let metadata = ViewMetadata {
+        fractal: Fractal::Mandelbrot,
+        viewport,
+        palette: CONFIG.palette,
+    };
+    match save_png(out, map.width, map.height, &map.pixels, Some(&metadata)) {
+        Ok(()) => println!(
+            "Saved {}x{} exponential map to {}",
+            map.width,
+            map.height,
+            out.display()
+        ),
+        Err(err) => {
+            eprintln!("Could not save the exponential map: {err}");
+            return;
+        }
+    }
+
+    if CONFIG.expmap_frames > 0 {
+        let stem = out.file_stem().unwrap_or_default().to_string_lossy();
+        let directory = out.with_file_name(format!("{stem}_frames"));
+        match write_frames(&map, &viewport, &directory) {
+            Ok(()) => println!(
+                "Saved {} frames to {}",
+                CONFIG.expmap_frames,
+                directory.display()
+            ),
+            Err(err) => eprintln!("Could not save the frames: {err}"),
+        }
+    }
+}
+
+/// Resamples the frames zooming from the start radius to the one of the viewport at a constant speed.
+fn write_frames(map: &ExpMap, viewport: &Viewport, directory: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(directory)?;
+    let count = CONFIG.expmap_frames;
+    let start_radius = START_RADIUS.max(viewport.radius);
+    let finished = AtomicUsize::new(0);
+    (0..count).into_par_iter().try_for_each(|index| {
+        let progress = index as f64 / (count - 1).max(1) as f64;
+        let frame_viewport = Viewport {
+            radius: start_radius * (viewport.radius / start_radius).powf(progress),
+            ..*viewport
+        };
+        save_png(
+            &directory.join(format!("frame_{index:06}.png")),
+            viewport.width,
+            viewport.height,
+            &map.frame(&frame_viewport),
+            None,
+        )?;
+        report_progress(finished.fetch_add(1, Ordering::Relaxed) + 1, count);
+        Ok(())
+    })
+}
@@ -0,0 +1,123 @@
+//! Writes rendered frames to disk. The PNG files carry the view they show as text chunks, keyed like the config
+//! settings that reproduce it.
+
+use crate::color::{Color, color_to_rgba8};
+use crate::color_generation::Palette;
+use crate::math::{Fractal, Viewport, max_iter};
+use macroquad::texture::Image;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The directory screenshots are stored in.
+const SCREENSHOT_DIRECTORY: &str = "screenshots";
+
+/// The directory automatically generated images and animations are stored in.
+pub const GALLERY_DIRECTORY: &str = "gallery";
+
+/// What an exported image shows, so it can be reproduced.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewMetadata {
+    /// The fractal of the image.
+    pub fractal: Fractal,
+    /// The view of the image, its size is not written.
+    pub viewport: Viewport,
+    /// The palette of the image.
+    pub palette: Palette,
+}
+
+impl ViewMetadata {
+    /// The keywords and texts of the chunks. The low part of the center, the rotation and the Julia parameter are
+    /// only written when they are used.
+    fn text_chunks(&self) -> Vec<(&'static str, String)> {
+        let viewport = &self.viewport;
+        let mut chunks = vec![
+            (
+                "center",
+                format!("{},{}", viewport.center.real, viewport.center.imag),
+            ),
+            ("radius", viewport.radius.to_string()),
+            ("max-iter", max_iter().to_string()),
+        ];
+        if viewport.center_low != Default::default() {
+            chunks.push((
+                "center-low",
+                format!("{},{}", viewport.center_low.real, viewport.center_low.imag),
+            ));
+        }
+        let rotation = viewport.rotation.imag.atan2(viewport.rotation.real);
+        if rotation != 0.0 {
+            chunks.push(("rotation", rotation.to_degrees().to_string()));
+        }
+        match self.fractal {
+            Fractal::Mandelbrot => chunks.push(("fractal", "mandelbrot".to_owned())),
+            Fractal::Julia(c) => {
+                chunks.push(("fractal", "julia".to_owned()));
+                chunks.push(("julia", format!("{},{}", c.real, c.imag)));
+            }
+        }
+        chunks.push(("palette", self.palette.name().to_owned()));
+        chunks
+    }
+
+    /// Adds the chunks to the PNG before its header is written.
+    pub fn write_to<W: std::io::Write>(
+        &self,
+        encoder: &mut png::Encoder<W>,
+    ) -> std::io::Result<()> {
+        for (keyword, text) in self.text_chunks() {
+            encoder.add_text_chunk(keyword.to_owned(), text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the colors into the RGBA8 pixels of an image of their size.
+pub fn fill_image(image: &mut Image, colors: &[Color]) {
+    for (pixel, color) in image.bytes.chunks_exact_mut(4).zip(colors) {
+        pixel.copy_from_slice(&color_to_rgba8(*color));
+    }
+}
+
+/// Writes an RGBA8 buffer as PNG file, with the view it shows if given.
+pub fn save_png(
+    path: &Path,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+    metadata: Option<&ViewMetadata>,
+) -> std::io::Result<()> {
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    if let Some(metadata) = metadata {
+        metadata.write_to(&mut encoder)?;
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Saves the image of the view into the screenshot directory under a time stamped name and returns the path.
+pub fn save_screenshot(image: &Image, metadata: &ViewMetadata) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(SCREENSHOT_DIRECTORY)?;
+    let path = Path::new(SCREENSHOT_DIRECTORY).join(format!("mandelbrot_{}.png", time_stamp()));
+    save_png(
+        &path,
+        image.width as u32,
+        image.height as u32,
+        &image.bytes,
+        Some(metadata),
+    )?;
+    Ok(path)
+}
+
+/// Milliseconds since the epoch, used to generate unique file names.
+pub fn time_stamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
@@ -0,0 +1,93 @@
+//! Keeps the last computed fields, so views that come back do not get computed again: the manual mode without
+//! input, the holds at the end of minibrot dives and between the phases of a transition, or repeated visits of a
+//! bookmark. A field is reused when the view matches within a small fraction of a pixel.
+
+use crate::math::{Escape, Fractal, OrbitTracking, Viewport};
+use std::collections::VecDeque;
+
+/// How far the centers of matching views may be apart in pixels.
+const CENTER_TOLERANCE: f64 = 0.01;
+
+/// How much the radii and the rotations of matching views may differ relative to the radius, which moves the
+/// border pixels by about a thousandth of a pixel.
+const RADIUS_TOLERANCE: f64 = 1e-6;
+
+/// What a field was computed for.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldKey {
+    /// The fractal.
+    pub fractal: Fractal,
+    /// The view.
+    pub viewport: Viewport,
+    /// The fraction of the resolution the field was computed in.
+    pub scale: f32,
+    /// What was tracked besides the iterations, None for a plain iteration field.
+    pub tracking: Option<OrbitTracking>,
+    /// The iteration limit, see [`crate::math::max_iter`].
+    pub max_iter: u16,
+}
+
+impl FieldKey {
+    /// Whether a field computed for the other key serves this one.
+    pub fn matches(&self, other: &FieldKey) -> bool {
+        let (view, other_view) = (&self.viewport, &other.viewport);
+        let offset = (view.center - other_view.center) + (view.center_low - other_view.center_low);
+        self.fractal == other.fractal
+            && self.scale == other.scale
+            && self.tracking == other.tracking
+            && self.max_iter == other.max_iter
+            && view.width == other_view.width
+            && view.height == other_view.height
+            && (view.radius / other_view.radius - 1.0).abs() < RADIUS_TOLERANCE
+            && (view.rotation - other_view.rotation).sq_mag() < RADIUS_TOLERANCE * RADIUS_TOLERANCE
+            && offset.sq_mag().sqrt() < CENTER_TOLERANCE * view.pixel_step()
+    }
+}
+
+/// The iteration field with the escape results, which are empty unless the coloring tracks the orbit.
+pub type CachedField = (Vec<u16>, Vec<Escape>);
+
+/// The most recently used fields, the latest first.
+#[derive(Debug)]
+pub struct FieldCache {
+    /// The most fields kept, 0 turns the cache off.
+    capacity: usize,
+    /// The fields with their keys.
+    entries: VecDeque<(FieldKey, CachedField)>,
+}
+
+impl FieldCache {
+    /// Creates an empty cache keeping up to the given amount of fields.
+    pub fn new(capacity: usize) -> FieldCache {
+        FieldCache {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// A copy of the field computed for a matching key, which becomes the most recently used one.
+    pub fn get(&mut self, key: &FieldKey) -> Option<CachedField> {
+        let index = self
+            .entries
+            .iter()
+            .position(|(cached, _)| key.matches(cached))?;
+        let entry = self.entries.remove(index)?;
+        let field = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(field)
+    }
+
+    /// Whether a field computed for a matching key is kept.
+    pub fn contains(&self, key: &FieldKey) -> bool {
+        self.entries.iter().any(|(cached, _)| key.matches(cached))
+    }
+
+    /// Stores a copy of the field, dropping the least recently used one when full.
+    pub fn insert(&mut self, key: FieldKey, field: &CachedField) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.truncate(self.capacity - 1);
+        self.entries.push_front((key, field.clone()));
+    }
+}
@@ -0,0 +1,196 @@
+//! Computes the fields on a worker thread, so the window keeps being presented while a slow frame is computed. Once
+//! a computation takes longer than [`PROGRESS_DELAY`], for example deep in a dive, the last frame stays on screen
+//! with a thin progress bar fed by the computation, instead of the explorer appearing frozen.
+//!
+//! Work that falls behind is dropped instead of queued. The state machine advances by the time that actually passed,
+//! so after a slow frame the next one computes the most recent view and skips the intermediate zoom steps, which
+//! keeps the perceived zoom speed constant. Keys that move the view elsewhere cancel the field being waited for.
+
+use crate::commands::{Command, keyboard_commands};
+use crate::config::CONFIG;
+use crate::errors::{FrameError, catch_frame_panic, check_field};
+use crate::field_cache::FieldKey;
+use crate::math::{
+    CancellationToken, Escape, IterationSettings, get_escape_field, get_iteration_field,
+};
+use macroquad::prelude::*;
+use rayon::ThreadPool;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, TryRecvError, channel};
+use std::time::Duration;
+
+/// The time in seconds a computation may take before its progress is shown.
+const PROGRESS_DELAY: f64 = 0.1;
+
+/// The longest step of the state machine in seconds, so a stalled process does not jump through half a dive.
+const MAX_STEP: f64 = 1.0;
+
+/// The height of the progress bar in pixels.
+const BAR_HEIGHT: f32 = 4.0;
+
+/// The color of the finished part of the progress bar.
+const BAR_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.7);
+
+/// The color of the remaining part of the progress bar.
+const BAR_BACKGROUND: Color = Color::new(0.0, 0.0, 0.0, 0.4);
+
+/// The iterations of a field and its escape results, which are empty if nothing was tracked.
+pub type ComputedField = (Vec<u16>, Vec<Escape>);
+
+/// The outcome of a computation, None if it was cancelled.
+type FieldResult = Result<Option<ComputedField>, FrameError>;
+
+/// A field being computed on a worker thread.
+pub struct FieldJob {
+    /// Receives the field once it is done.
+    receiver: Receiver<FieldResult>,
+    /// The time the computation started.
+    start: f64,
+}
+
+impl FieldJob {
+    /// Starts computing the field for the key, on the pool if given. A panic in the computation comes back as
+    /// error.
+    pub fn spawn(
+        key: FieldKey,
+        cancel: &CancellationToken,
+        pool: Option<Arc<ThreadPool>>,
+    ) -> FieldJob {
+        let (sender, receiver) = channel();
+        let cancel = cancel.clone();
+        std::thread::spawn(move || {
+            let compute = || compute_field(key, &cancel);
+            let result = catch_frame_panic(|| match pool {
+                Some(pool) => pool.install(compute),
+                None => compute(),
+            })
+            .flatten();
+            // The receiver only goes away when the explorer closes.
+            let _ = sender.send(result);
+        });
+        FieldJob {
+            receiver,
+            start: get_time(),
+        }
+    }
+
+    /// Cancels the computation and waits for the worker to give up, so the next field has the pool and the progress
+    /// counter to itself. The token is cleared again afterwards.
+    pub fn abandon(self, cancel: &CancellationToken) {
+        cancel.cancel();
+        let _ = self.receiver.recv();
+        cancel.reset();
+    }
+
+    /// Waits for the field. While it takes longer than the delay, the texture is presented every frame with the
+    /// progress of the computation drawn over it. If listening, the keys pressed meanwhile are deferred to the next
+    /// frame, and those moving the view elsewhere cancel the computation.
+    pub async fn finish(
+        self,
+        texture: &Texture2D,
+        cancel: &CancellationToken,
+        deferred: &mut Vec<Command>,
+        listen: bool,
+    ) -> FieldResult {
+        let remaining = (PROGRESS_DELAY - (get_time() - self.start)).max(0.0);
+        match self
+            .receiver
+            .recv_timeout(Duration::from_secs_f64(remaining))
+        {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Disconnected) => return Err(lost_worker()),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        loop {
+            draw_texture_ex(
+                texture,
+                0.0,
+                0.0,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(Vec2::new(screen_width(), screen_height())),
+                    ..Default::default()
+                },
+            );
+            draw_progress(cancel.progress());
+            next_frame().await;
+            if listen {
+                let commands = keyboard_commands();
+                if commands.iter().any(|command| command.retargets()) {
+                    cancel.cancel();
+                }
+                deferred.extend(commands);
+            }
+            match self.receiver.try_recv() {
+                Ok(result) => return result,
+                Err(TryRecvError::Disconnected) => return Err(lost_worker()),
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+    }
+}
+
+/// Measures the steps of the state machine in wall clock time, including the frames spent waiting for a field.
+#[derive(Debug)]
+pub struct StepClock {
+    /// The time of the last step.
+    last: f64,
+}
+
+impl StepClock {
+    /// Starts measuring now.
+    pub fn new() -> StepClock {
+        StepClock { last: get_time() }
+    }
+
+    /// Starts over without a step, as done while paused, so the pause is not caught up on.
+    pub fn restart(&mut self) {
+        self.last = get_time();
+    }
+
+    /// The time since the last step, at most [`MAX_STEP`], and begins the next one.
+    pub fn step(&mut self) -> f64 {
+        let now = get_time();
+        let step = (now - self.last).min(MAX_STEP);
+        self.last = now;
+        step
+    }
+}
+
+/// Computes the field for the key with its iteration limit, None if the token was cancelled.
+fn compute_field(key: FieldKey, cancel: &CancellationToken) -> FieldResult {
+    let settings = IterationSettings {
+        max_iter: key.max_iter,
+        ..CONFIG.iteration_settings()
+    };
+    Ok(if let Some(tracking) = key.tracking {
+        get_escape_field(
+            key.fractal,
+            &key.viewport,
+            tracking,
+            &settings,
+            key.scale,
+            cancel,
+        )
+        .map(check_field)
+        .transpose()?
+        .map(|field| (field.iter().map(|e| e.iterations).collect(), field))
+    } else {
+        get_iteration_field(key.fractal, &key.viewport, &settings, key.scale, cancel)
+            .map(check_field)
+            .transpose()?
+            .map(|field| (field, Vec::new()))
+    })
+}
+
+/// The error for a worker that ended without sending its field.
+fn lost_worker() -> FrameError {
+    FrameError::Panic("the field thread ended without a result".to_owned())
+}
+
+/// Draws the bar along the bottom of the screen.
+fn draw_progress(progress: f32) {
+    let top = screen_height() - BAR_HEIGHT;
+    draw_rectangle(0.0, top, screen_width(), BAR_HEIGHT, BAR_BACKGROUND);
+    draw_rectangle(0.0, top, screen_width() * progress, BAR_HEIGHT, BAR_COLOR);
+}
@@ -1,19 +1,102 @@
 //! The focus system searches for interesting spots based on variance.
 
-use crate::math::{ComplexNumber, get_iteration_field};
-use crate::{START_FOCUS_RADIUS, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::config::CONFIG;
+use crate::math::{
+    CancellationToken, ComplexNumber, Fractal, Viewport, get_iteration_field, max_iter,
+};
+use crate::precision::{DEEPEST_RADIUS, DOUBLE_RADIUS_LIMIT};
+use crate::profiling::profile_scope;
+use crate::transition::START_RADIUS;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
 use itertools::Itertools;
 use macroquad::rand::gen_range;
 use rayon::iter::*;
+use std::ops::Range;
 
 /// The window size we use for variance calculation is this size * 2 + 1
 const WINDOW_STEP: i32 = 5;
+/// The largest downsampling factor, beyond it the coarse field gets too small for the window.
+const MAX_DOWNSAMPLE: u32 = 8;
+/// The largest fraction of the window that may lie inside the set for the point to count as interesting.
+const MAX_INTERIOR_FRACTION: f32 = 0.5;
 /// The amount of samples we generate in the window.
 const SAMPLE_SIZE: f32 = ((2 * WINDOW_STEP + 1) * (2 * WINDOW_STEP + 1)) as f32;
-/// The maximum distance a pixel can be away from the center squared.
-const MAX_DIST_SQ: f32 = ((WINDOW_WIDTH / 2).pow(2) + (WINDOW_HEIGHT / 2).pow(2)) as f32;
+
+/// A parameter of the autofocus that can be changed at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusParameter {
+    /// The score a start point has to beat.
+    StartMinScore,
+    /// The radius at which the autofocus takes over.
+    FocusRadius,
+    /// The radius at which the dives end.
+    DepthLimit,
+    /// How much a new focus point has to beat the tracked one.
+    Hysteresis,
+    /// The amount of strongest candidates a new target is drawn from.
+    Candidates,
+}
+
+/// The largest depth limit for the focus radius, the dives go at least an octave below the radius the autofocus
+/// takes over at. The config and the runtime changes both check against it.
+pub fn max_depth_limit(focus_radius: f64) -> f64 {
+    focus_radius * 0.5
+}
+
+/// The parameters of the autofocus, which start with the configured values and can be changed at runtime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocusParameters {
+    /// The score a start point has to beat, the search falls back to a point near the real axis otherwise.
+    pub start_min_score: f32,
+    /// The radius at which the autofocus takes over, also the size of the views the start points are scored in.
+    pub focus_radius: f64,
+    /// The radius at which the dives end.
+    pub depth_limit: f64,
+    /// How much a new focus point has to beat the tracked one to replace it, as fraction of the tracked score.
+    pub hysteresis: f32,
+    /// The amount of strongest focus candidates a new target is drawn from, 1 always takes the best.
+    pub candidates: usize,
+}
+
+impl FocusParameters {
+    /// The parameters of the config.
+    pub fn from_config() -> FocusParameters {
+        FocusParameters {
+            start_min_score: CONFIG.start_min_score,
+            focus_radius: CONFIG.focus_radius,
+            depth_limit: CONFIG.depth_limit,
+            hysteresis: CONFIG.focus_hysteresis,
+            candidates: CONFIG.focus_candidates,
+        }
+    }
+
+    /// Sets a parameter, clamped to the range the config accepts for it. The depth limit stays at least an octave
+    /// below the focus radius, and custom formulas keep it within double precision.
+    pub fn set(&mut self, parameter: FocusParameter, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        match parameter {
+            FocusParameter::StartMinScore => self.start_min_score = value.max(0.0) as f32,
+            FocusParameter::FocusRadius => {
+                self.focus_radius = value.clamp(DEEPEST_RADIUS, START_RADIUS)
+            }
+            FocusParameter::DepthLimit => self.depth_limit = value,
+            FocusParameter::Hysteresis => self.hysteresis = value.max(0.0) as f32,
+            FocusParameter::Candidates => self.candidates = (value.round() as usize).max(1),
+        }
+        let deepest = if CONFIG.formula.is_some() {
+            DOUBLE_RADIUS_LIMIT
+        } else {
+            DEEPEST_RADIUS
+        };
+        let max_depth = max_depth_limit(self.focus_radius);
+        self.depth_limit = self.depth_limit.clamp(deepest.min(max_depth), max_depth);
+    }
+}
 
 /// Contains a point to focus on with an evaluation-
+#[derive(Debug, Clone, Copy, Default)]
 pub struct FocusPointWithScore {
     /// Contains the x position of the focus-point in screen space pixel coordinates.
     x_pos: f32,
@@ -26,63 +109,62 @@ pub struct FocusPointWithScore {
 impl FocusPointWithScore {
     /// Gets a focus point (including score) from the iteration field handed over.
     pub fn new(in_field: &[u16]) -> FocusPointWithScore {
-        let (best_index, score) = (0..WINDOW_WIDTH * WINDOW_HEIGHT)
-            .into_par_iter()
-            .map(|idx| {
-                let x = idx % WINDOW_WIDTH;
-                let y = idx / WINDOW_WIDTH;
-
-                // Exclude border stripe.
-                if x < WINDOW_STEP
-                    || y < WINDOW_STEP
-                    || x >= WINDOW_WIDTH - WINDOW_STEP
-                    || y >= WINDOW_HEIGHT - WINDOW_STEP
-                {
-                    return 0.0;
-                }
-
-                // Calculate variance in window.
-                let (sum, sq_sum) = (-WINDOW_STEP..=WINDOW_STEP)
-                    .cartesian_product(-WINDOW_STEP..=WINDOW_STEP)
-                    .map(|(dx, dy)| {
-                        in_field[(x + dx) as usize + ((y + dy) * WINDOW_WIDTH) as usize] as f32
-                    })
-                    .fold((0.0, 0.0), |(s, sq), v| (s + v, sq + v * v));
-
-                let mean = sum / SAMPLE_SIZE;
-                let variance = sq_sum / SAMPLE_SIZE - mean * mean;
-
-                // Get center bias.
-                let dx = (x - WINDOW_WIDTH / 2) as f32;
-                let dy = (y - WINDOW_HEIGHT / 2) as f32;
-                let center_bias = 1.0 - 0.5 * (dx * dx + dy * dy) / MAX_DIST_SQ;
-
-                variance * center_bias
-            })
-            .enumerate()
-            .max_by(|(_, a), (_, b)| a.total_cmp(b))
-            .unwrap();
-
-        let best_index = best_index as i32;
-
-        FocusPointWithScore {
-            x_pos: (best_index % WINDOW_WIDTH - WINDOW_WIDTH / 2) as f32,
-            y_pos: (best_index / WINDOW_WIDTH - WINDOW_HEIGHT / 2) as f32,
-            score,
+        FocusPointWithScore::in_rect(in_field, 0..WINDOW_WIDTH, 0..WINDOW_HEIGHT)
+    }
+
+    /// Gets the best focus point within the given columns and rows of the iteration field.
+    pub fn in_rect(in_field: &[u16], columns: Range<i32>, rows: Range<i32>) -> FocusPointWithScore {
+        FocusPointWithScore::candidates(in_field, columns, rows)
+            .first()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Gets the best point of every cell of the candidate grid within the given columns and rows, the best first.
+    /// With downsampling configured, the field is averaged over blocks first, which divides the cost by the square
+    /// of the factor.
+    pub fn candidates(
+        in_field: &[u16],
+        columns: Range<i32>,
+        rows: Range<i32>,
+    ) -> Vec<FocusPointWithScore> {
+        let factor = CONFIG.focus_downsample.clamp(1, MAX_DOWNSAMPLE) as i32;
+        if factor == 1 {
+            return score_rect(
+                in_field,
+                None,
+                WINDOW_WIDTH,
+                WINDOW_HEIGHT,
+                columns,
+                rows,
+                1,
+            );
         }
+        let (width, height) = (WINDOW_WIDTH / factor, WINDOW_HEIGHT / factor);
+        let (coarse, interior) = downsample(in_field, factor, width, height);
+        let columns = columns.start / factor..(columns.end / factor).min(width);
+        let rows = rows.start / factor..(rows.end / factor).min(height);
+        score_rect(
+            &coarse,
+            Some(&interior),
+            width,
+            height,
+            columns,
+            rows,
+            factor,
+        )
     }
 
-    /// Given a screen center in the complex number pane and an applied radius the focus gets converted into a target position in the complex number pane.
+    /// Given a screen center in the complex number pane, an applied radius and the rotation of the view the focus
+    /// gets converted into a target position in the complex number pane.
     pub fn get_absolute_focus_in_complex_number_pane(
         &self,
         center: ComplexNumber,
         radius: f64,
+        rotation: ComplexNumber,
     ) -> ComplexNumber {
         let step = radius / (WINDOW_HEIGHT as f64 * 0.5);
-        ComplexNumber::new(
-            center.real + self.x_pos as f64 * step,
-            center.imag + self.y_pos as f64 * step,
-        )
+        ComplexNumber::new(self.x_pos as f64 * step, self.y_pos as f64 * step) * rotation + center
     }
 
     pub fn score(&self) -> f32 {
@@ -90,23 +172,383 @@ impl FocusPointWithScore {
     }
 }
 
-/// The score we minimally want to get as a starting position.
-const ITER_MINIMUM_SCORE: f32 = 50.0;
+/// Scores the rectangle of a field with the given size by the variance around each pixel, weighted towards the
+/// center, and returns the best pixel of every cell of the candidate grid, the best first. The positions are scaled
+/// by the factor back to window pixels, to the middle of the block a downsampled pixel averages. The interior
+/// counts of downsampled fields hold how many pixels of each block are inside the set, without them a pixel counts
+/// as inside when it reaches the iteration limit.
+fn score_rect(
+    in_field: &[u16],
+    interior_counts: Option<&[u8]>,
+    width: i32,
+    height: i32,
+    columns: Range<i32>,
+    rows: Range<i32>,
+    factor: i32,
+) -> Vec<FocusPointWithScore> {
+    profile_scope!("score focus");
+    // The maximum distance a pixel can be away from the center squared.
+    let max_dist_sq = ((width / 2).pow(2) + (height / 2).pow(2)) as f32;
+    let rect_width = columns.len() as i32;
+    let rect_height = rows.len() as i32;
+    let limit = max_iter();
+    let scores: Vec<f32> = (0..rect_width * rect_height)
+        .into_par_iter()
+        .map(|idx| {
+            let x = columns.start + idx % rect_width;
+            let y = rows.start + idx / rect_width;
+
+            // Exclude border stripe.
+            if x < WINDOW_STEP
+                || y < WINDOW_STEP
+                || x >= width - WINDOW_STEP
+                || y >= height - WINDOW_STEP
+            {
+                return 0.0;
+            }
+
+            // Calculate variance in window.
+            let (sum, sq_sum, interior) = (-WINDOW_STEP..=WINDOW_STEP)
+                .cartesian_product(-WINDOW_STEP..=WINDOW_STEP)
+                .map(|(dx, dy)| (x + dx) as usize + ((y + dy) * width) as usize)
+                .fold((0.0, 0.0, 0.0), |(s, sq, inside), index| {
+                    let v = in_field[index];
+                    let inside = inside
+                        + interior_counts.map_or((v >= limit) as u8, |counts| counts[index]) as f32;
+                    let v = v as f32;
+                    (s + v, sq + v * v, inside)
+                });
+
+            // Windows mostly inside the set sit on a featureless black bulb, where the dive would sink in.
+            if interior / (SAMPLE_SIZE * (factor * factor) as f32) > MAX_INTERIOR_FRACTION {
+                return 0.0;
+            }
+
+            let mean = sum / SAMPLE_SIZE;
+            let variance = sq_sum / SAMPLE_SIZE - mean * mean;
+
+            // Get center bias.
+            let dx = (x - width / 2) as f32;
+            let dy = (y - height / 2) as f32;
+            let center_bias = 1.0 - 0.5 * (dx * dx + dy * dy) / max_dist_sq;
+
+            variance * center_bias
+        })
+        .collect();
+
+    let cell = (CANDIDATE_SEPARATION / factor).max(1);
+    let cells_x = (rect_width + cell - 1) / cell;
+    let cells_y = (rect_height + cell - 1) / cell;
+    let mut candidates: Vec<FocusPointWithScore> = (0..cells_x * cells_y)
+        .into_par_iter()
+        .filter_map(|cell_index| {
+            let (cell_x, cell_y) = (cell_index % cells_x * cell, cell_index / cells_x * cell);
+            (cell_y..(cell_y + cell).min(rect_height))
+                .cartesian_product(cell_x..(cell_x + cell).min(rect_width))
+                .map(|(y, x)| (x, y, scores[(y * rect_width + x) as usize]))
+                .max_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+        })
+        .map(|(x, y, score)| FocusPointWithScore {
+            x_pos: ((columns.start + x) * factor + factor / 2 - WINDOW_WIDTH / 2) as f32,
+            y_pos: ((rows.start + y) * factor + factor / 2 - WINDOW_HEIGHT / 2) as f32,
+            score,
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+/// Averages the iteration field of the window over blocks of factor × factor pixels. Also returns how many pixels
+/// of every block reach the iteration limit, as the average of a block on the border of the set stays below it.
+fn downsample(in_field: &[u16], factor: i32, width: i32, height: i32) -> (Vec<u16>, Vec<u8>) {
+    let block_size = (factor * factor) as u32;
+    let limit = max_iter();
+    (0..width * height)
+        .into_par_iter()
+        .map(|idx| {
+            let (x, y) = ((idx % width) * factor, (idx / width) * factor);
+            let (sum, interior) = (0..factor)
+                .cartesian_product(0..factor)
+                .map(|(dx, dy)| in_field[(x + dx + (y + dy) * WINDOW_WIDTH) as usize])
+                .fold((0u32, 0u8), |(sum, interior), v| {
+                    (sum + v as u32, interior + (v >= limit) as u8)
+                });
+            (((sum + block_size / 2) / block_size) as u16, interior)
+        })
+        .unzip()
+}
+
+/// The amount of frames one scan of the field for the focus point is spread over.
+const FOCUS_SCAN_FRAMES: i32 = 4;
+
+/// The size of the cells of the candidate grid in window pixels. Every cell contributes its best point, so the
+/// candidates show different features.
+const CANDIDATE_SEPARATION: i32 = 96;
+
+/// The half size of the square around the tracked target, in which it is refined, in window pixels.
+const REFINE_NEIGHBORHOOD: i32 = 32;
+
+/// Scans the field for the focus point band by band, one band per frame, so the scoring costs only a fraction of
+/// the fractal per frame. The target changes once per completed scan. A distant point only replaces the tracked
+/// target if it beats the best point around the target by the configured hysteresis margin, otherwise the target
+/// is refined within its neighborhood. That keeps the pan from twitching between similar candidates. On a switch the
+/// new target is drawn from the strongest separated candidates weighted by score, so similar regions lead to
+/// varied dives.
+#[derive(Debug, Default)]
+pub struct FocusScan {
+    /// The band we score next.
+    band: i32,
+    /// The candidates of the running scan in the complex number pane with their scores.
+    candidates: Vec<(ComplexNumber, f32)>,
+    /// The result of the last completed scan with its score.
+    target: Option<(ComplexNumber, f32)>,
+    /// What the completed scans did with the target.
+    retargets: Retargets,
+}
+
+/// Counts what the completed scans of a dive did with the target they had, for tuning the hysteresis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retargets {
+    /// The target stayed, it was within the hysteresis of the best candidate.
+    pub kept: u32,
+    /// A candidate beat the target by more than the hysteresis.
+    pub outscored: u32,
+    /// The target had left the view.
+    pub left_view: u32,
+}
+
+impl std::ops::AddAssign for Retargets {
+    fn add_assign(&mut self, other: Retargets) {
+        self.kept += other.kept;
+        self.outscored += other.outscored;
+        self.left_view += other.left_view;
+    }
+}
+
+impl FocusScan {
+    /// Scores the next band of the field, which shows the view with the given center, radius and rotation, and
+    /// returns the point to focus on.
+    pub fn update(
+        &mut self,
+        in_field: &[u16],
+        center: ComplexNumber,
+        radius: f64,
+        rotation: ComplexNumber,
+        parameters: &FocusParameters,
+    ) -> ComplexNumber {
+        let rows = self.band * WINDOW_HEIGHT / FOCUS_SCAN_FRAMES
+            ..(self.band + 1) * WINDOW_HEIGHT / FOCUS_SCAN_FRAMES;
+        self.candidates.extend(
+            FocusPointWithScore::candidates(in_field, 0..WINDOW_WIDTH, rows)
+                .into_iter()
+                .map(|focus| {
+                    (
+                        focus.get_absolute_focus_in_complex_number_pane(center, radius, rotation),
+                        focus.score(),
+                    )
+                }),
+        );
+
+        self.band += 1;
+        if self.band == FOCUS_SCAN_FRAMES {
+            self.band = 0;
+            let step = radius / (WINDOW_HEIGHT as f64 * 0.5);
+            let candidates = strongest_separated(
+                std::mem::take(&mut self.candidates),
+                CANDIDATE_SEPARATION as f64 * step,
+                parameters.candidates,
+            );
+            if let Some(&(_, best_score)) = candidates.first() {
+                let refined = self
+                    .target
+                    .and_then(|(target, _)| refine(in_field, center, radius, rotation, target));
+                self.target = Some(match refined {
+                    Some((point, score)) if best_score <= score * (1.0 + parameters.hysteresis) => {
+                        self.retargets.kept += 1;
+                        (point, score)
+                    }
+                    refined => {
+                        if refined.is_some() {
+                            self.retargets.outscored += 1;
+                        } else if self.target.is_some() {
+                            self.retargets.left_view += 1;
+                        }
+                        pick_weighted(&candidates)
+                    }
+                });
+            }
+        }
+        // Until the first scan completes we follow the best point so far.
+        self.target
+            .or(self
+                .candidates
+                .iter()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .copied())
+            .map_or(center, |(point, _)| point)
+    }
+
+    /// The score of the target, None until the first scan completed.
+    pub fn target_score(&self) -> Option<f32> {
+        self.target.map(|(_, score)| score)
+    }
+
+    /// What the completed scans since the last reset did with the target.
+    pub fn retargets(&self) -> Retargets {
+        self.retargets
+    }
+
+    /// Moves the target and the candidates by the negated offset, after the center moved by it. Together with a
+    /// center of zero the scan then works relative to the center.
+    pub fn shift(&mut self, offset: ComplexNumber) {
+        if let Some((target, _)) = self.target.as_mut() {
+            *target = *target - offset;
+        }
+        for (point, _) in &mut self.candidates {
+            *point = *point - offset;
+        }
+    }
+
+    /// Forgets the target, the next dive starts a fresh scan.
+    pub fn reset(&mut self) {
+        *self = FocusScan::default();
+    }
+}
+
+/// Keeps the given amount of the best candidates that are at least the distance apart, the best first.
+fn strongest_separated(
+    mut candidates: Vec<(ComplexNumber, f32)>,
+    distance: f64,
+    count: usize,
+) -> Vec<(ComplexNumber, f32)> {
+    candidates.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let mut selected: Vec<(ComplexNumber, f32)> = Vec::new();
+    for (point, score) in candidates {
+        if selected.len() >= count.max(1) {
+            break;
+        }
+        // Neighboring cells may have their best points side by side, those show the same feature.
+        if selected
+            .iter()
+            .all(|(other, _)| (*other - point).sq_mag() >= distance * distance)
+        {
+            selected.push((point, score));
+        }
+    }
+    selected
+}
+
+/// Draws one of the candidates with a probability proportional to its score.
+fn pick_weighted(candidates: &[(ComplexNumber, f32)]) -> (ComplexNumber, f32) {
+    let total: f32 = candidates.iter().map(|(_, score)| score.max(0.0)).sum();
+    let mut remaining = gen_range(0.0, total);
+    for &(point, score) in candidates {
+        remaining -= score.max(0.0);
+        if remaining <= 0.0 {
+            return (point, score);
+        }
+    }
+    candidates.first().copied().unwrap_or_default()
+}
+
+/// Finds the best point within the neighborhood of the target, None if the target has left the view.
+fn refine(
+    in_field: &[u16],
+    center: ComplexNumber,
+    radius: f64,
+    rotation: ComplexNumber,
+    target: ComplexNumber,
+) -> Option<(ComplexNumber, f32)> {
+    let step = radius / (WINDOW_HEIGHT as f64 * 0.5);
+    let offset = (target - center) / rotation;
+    let x = (offset.real / step) as i32 + WINDOW_WIDTH / 2;
+    let y = (offset.imag / step) as i32 + WINDOW_HEIGHT / 2;
+    if !(0..WINDOW_WIDTH).contains(&x) || !(0..WINDOW_HEIGHT).contains(&y) {
+        return None;
+    }
+    let columns = (x - REFINE_NEIGHBORHOOD).max(0)..(x + REFINE_NEIGHBORHOOD).min(WINDOW_WIDTH);
+    let rows = (y - REFINE_NEIGHBORHOOD).max(0)..(y + REFINE_NEIGHBORHOOD).min(WINDOW_HEIGHT);
+    let focus = FocusPointWithScore::in_rect(in_field, columns, rows);
+    Some((
+        focus.get_absolute_focus_in_complex_number_pane(center, radius, rotation),
+        focus.score(),
+    ))
+}
+
+/// The score we minimally want to get as a starting position, unless configured otherwise.
+pub const DEFAULT_START_MIN_SCORE: f32 = 50.0;
 
 /// The amount of random samples we draw for finding a focus point.
 const NUM_OF_SAMPLES_FOR_FOCUS: u8 = 10;
 
+/// The amount of coverage cells along the real axis of the sampled area.
+const COVERAGE_COLUMNS: usize = 48;
+/// The amount of coverage cells along the imaginary axis of the sampled area.
+const COVERAGE_ROWS: usize = 32;
+/// The real range the random start candidates are drawn from.
+const SAMPLE_REAL: Range<f64> = -2.0..1.0;
+/// The imaginary range the random start candidates are drawn from.
+const SAMPLE_IMAG: Range<f64> = -1.0..1.0;
+
+/// A coarse grid over the sampled part of the parameter plane remembering how deep we already dove into each cell,
+/// so long sessions keep finding new territory.
+#[derive(Debug, Clone)]
+pub struct CoverageGrid {
+    /// The deepest dive per cell in octaves below the start radius, row by row.
+    depth: Vec<f32>,
+}
+
+impl Default for CoverageGrid {
+    fn default() -> Self {
+        CoverageGrid {
+            depth: vec![0.0; COVERAGE_COLUMNS * COVERAGE_ROWS],
+        }
+    }
+}
+
+impl CoverageGrid {
+    /// The index of the cell containing the point, None outside the sampled area.
+    fn cell(point: ComplexNumber) -> Option<usize> {
+        let column = (point.real - SAMPLE_REAL.start) / (SAMPLE_REAL.end - SAMPLE_REAL.start);
+        let row = (point.imag - SAMPLE_IMAG.start) / (SAMPLE_IMAG.end - SAMPLE_IMAG.start);
+        if !(0.0..1.0).contains(&column) || !(0.0..1.0).contains(&row) {
+            return None;
+        }
+        let column = (column * COVERAGE_COLUMNS as f64) as usize;
+        let row = (row * COVERAGE_ROWS as f64) as usize;
+        Some(row * COVERAGE_COLUMNS + column)
+    }
+
+    /// Notes a dive that reached the given depth in octaves at the point.
+    pub fn record(&mut self, point: ComplexNumber, depth: f32) {
+        if let Some(cell) = CoverageGrid::cell(point) {
+            self.depth[cell] = self.depth[cell].max(depth);
+        }
+    }
+
+    /// The factor a score at the point is multiplied with: up to 1 + novelty weight for untouched cells, falling
+    /// towards 1 the deeper we already went there.
+    pub fn novelty_factor(&self, point: ComplexNumber) -> f32 {
+        let depth = CoverageGrid::cell(point).map_or(0.0, |cell| self.depth[cell]);
+        1.0 + CONFIG.novelty_weight.max(0.0) / (1.0 + depth)
+    }
+}
+
 /// This is a helper struct generate an interesting start point for zoom.
 #[derive(Default)]
 pub struct StartPointForZoom {
     /// The current starting point we estimate.
     starting_point: ComplexNumber,
+    /// The part of a fixed starting point below the resolution of f64, zero for the points of the search.
+    starting_point_low: ComplexNumber,
     /// The score of the current starting point.
     score: f32,
     /// The amount of improvements attempts we still make.
     remaining_iteration: u8,
     /// The buffer to split computations over two frames.
     precomputed_field: Option<(Vec<u16>, ComplexNumber)>,
+    /// Where we already dove, to favor new territory.
+    coverage: CoverageGrid,
 }
 
 impl StartPointForZoom {
@@ -115,45 +557,111 @@ impl StartPointForZoom {
         self.starting_point
     }
 
-    /// Generates a new sample and sees if this is better than the old one. It distributes the computation
-    /// over two phases.
-    pub fn try_improve(&mut self) {
+    /// The part of the starting point below the resolution of f64.
+    pub fn starting_point_low(&self) -> ComplexNumber {
+        self.starting_point_low
+    }
+
+    /// Generates a new sample in a view of the focus radius and sees if this is better than the old one. It
+    /// distributes the computation over two phases.
+    pub fn try_improve(&mut self, focus_radius: f64) {
         if self.remaining_iteration == 0 {
             return;
         }
+        profile_scope!("improve start point");
         if let Some((num_array, test)) = self.precomputed_field.as_ref() {
             self.remaining_iteration -= 1;
             let focus = FocusPointWithScore::new(num_array);
-            if focus.score() > self.score {
-                self.score = focus.score();
-                self.starting_point =
-                    focus.get_absolute_focus_in_complex_number_pane(*test, START_FOCUS_RADIUS);
+            let point = focus.get_absolute_focus_in_complex_number_pane(
+                *test,
+                focus_radius,
+                ComplexNumber::ONE,
+            );
+            let score = focus.score() * self.coverage.novelty_factor(point);
+            if score > self.score {
+                self.score = score;
+                self.starting_point = point;
+                self.starting_point_low = ComplexNumber::default();
             }
             self.precomputed_field = None;
         } else {
-            let test = ComplexNumber::new(gen_range(-2.0, 1.0), gen_range(-1.0, 1.0));
-            let num_array = get_iteration_field(test, START_FOCUS_RADIUS);
-            self.precomputed_field = Some((num_array, test));
+            let test = ComplexNumber::new(
+                gen_range(SAMPLE_REAL.start, SAMPLE_REAL.end),
+                gen_range(SAMPLE_IMAG.start, SAMPLE_IMAG.end),
+            );
+            // The candidates are cheap, so their computation is never cancelled.
+            let cancel = CancellationToken::default();
+            self.precomputed_field = get_iteration_field(
+                Fractal::Mandelbrot,
+                &Viewport::window(test, focus_radius),
+                &CONFIG.iteration_settings(),
+                1.0,
+                &cancel,
+            )
+            .map(|num_array| (num_array, test));
+        }
+    }
+
+    /// The score of the current starting point.
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+
+    /// The amount of improvement attempts we still make.
+    pub fn remaining_iteration(&self) -> u8 {
+        self.remaining_iteration
+    }
+
+    /// Restores a previously saved search state. A half finished sample is not restored but redone.
+    pub fn restore(
+        starting_point: ComplexNumber,
+        score: f32,
+        remaining_iteration: u8,
+    ) -> StartPointForZoom {
+        StartPointForZoom {
+            starting_point,
+            starting_point_low: ComplexNumber::default(),
+            score,
+            remaining_iteration,
+            precomputed_field: None,
+            coverage: CoverageGrid::default(),
         }
     }
 
-    /// Resets the iteration scheme to generate a new point of interest. We generate a reasonable
-    /// starting point upfront that we do not run into Nirvana, if we do not find one during iteration.
-    pub fn reset_iteration(&mut self) {
+    /// Notes a finished dive at the point that reached the given depth in octaves below the start radius.
+    pub fn record_dive(&mut self, point: ComplexNumber, depth: f32) {
+        self.coverage.record(point, depth);
+    }
+
+    /// Replaces the search result with a fixed point, given with its low part, and stops any further search. A
+    /// fixed point has no score.
+    pub fn set_starting_point(&mut self, point: ComplexNumber, low: ComplexNumber) {
+        self.starting_point = point;
+        self.starting_point_low = low;
+        self.score = 0.0;
+        self.remaining_iteration = 0;
+        self.precomputed_field = None;
+    }
+
+    /// Resets the iteration scheme to generate a new point of interest, which has to beat the minimum score. We
+    /// generate a reasonable starting point upfront that we do not run into Nirvana, if we do not find one during
+    /// iteration.
+    pub fn reset_iteration(&mut self, start_min_score: f32) {
         self.remaining_iteration = NUM_OF_SAMPLES_FOR_FOCUS;
-        self.score = ITER_MINIMUM_SCORE;
+        self.score = start_min_score;
         self.starting_point = ComplexNumber::new(gen_range(-2.0, -1.0), gen_range(-0.1, 0.1));
+        self.starting_point_low = ComplexNumber::default();
         self.precomputed_field = None;
     }
 
     /// Generates a start estimate by running the loop itself. This should only be done at the beginning, as it does not distribute the load
     /// over several frames.
-    pub fn prepare_start() -> StartPointForZoom {
+    pub fn prepare_start(parameters: &FocusParameters) -> StartPointForZoom {
         let mut result = StartPointForZoom::default();
-        result.reset_iteration();
+        result.reset_iteration(parameters.start_min_score);
 
         for _ in 0..NUM_OF_SAMPLES_FOR_FOCUS * 2 {
-            result.try_improve();
+            result.try_improve(parameters.focus_radius);
         }
         result
     }
@@ -0,0 +1,474 @@
+//! User defined iteration formulas like `z = z^3 + c*z + c` or the burning ship `z = (|re z| + i|im z|)^2 + c`,
+//! set with `formula` in the configuration. The expression is parsed once and compiled into a tree of closures,
+//! which replaces z² + c for every pixel. The orbits of the Mandelbrot view start at zero with the pixel as c, the
+//! Julia views start at the pixel. The formulas are computed in double precision only.
+//!
+//! The expressions know `z`, `c`, the imaginary unit `i`, real numbers, `+ - * / ^`, `|x|` for the magnitude, the
+//! functions `re im abs conj exp log sqrt sin cos`, and multiplication by juxtaposition like `2z` or `i|im z|`. A
+//! function applies to the following factor, so `re z^2` is `(re z)^2`.
+
+use crate::math::{ComplexNumber, FractalFormula};
+use std::sync::Arc;
+
+/// The largest integer exponent computed by repeated multiplication, larger ones go through the logarithm.
+const MAX_INTEGER_POWER: f64 = 64.0;
+
+/// A compiled expression of z and c.
+type Compiled = Arc<dyn Fn(ComplexNumber, ComplexNumber) -> ComplexNumber + Send + Sync>;
+
+/// A parsed and compiled iteration formula.
+#[derive(Clone)]
+pub struct Formula {
+    /// The expression as the user wrote it.
+    source: String,
+    /// The step from z to the next z for the parameter c.
+    step: Compiled,
+}
+
+impl std::fmt::Debug for Formula {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "Formula({})", self.source)
+    }
+}
+
+impl Formula {
+    /// Parses the formula, the `z =` in front is optional.
+    pub fn parse(source: &str) -> Result<Formula, String> {
+        let tokens = tokenize(source)?;
+        let tokens = match tokens.as_slice() {
+            [Token::Name(name), Token::Equals, rest @ ..] if name == "z" => rest,
+            _ => &tokens,
+        };
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            in_magnitude: false,
+        };
+        let node = parser.sum()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected `{token}`"));
+        }
+        Ok(Formula {
+            source: source.trim().to_owned(),
+            step: compile(node),
+        })
+    }
+}
+
+impl FractalFormula for Formula {
+    fn step(&self, z: ComplexNumber, c: ComplexNumber) -> ComplexNumber {
+        (self.step)(z, c)
+    }
+}
+
+/// The parts an expression is made of.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A real number.
+    Number(f64),
+    /// A variable, the imaginary unit or a function.
+    Name(String),
+    /// An operator or bracket.
+    Symbol(char),
+    /// The assignment to z in front.
+    Equals,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(formatter, "{value}"),
+            Token::Name(name) => write!(formatter, "{name}"),
+            Token::Symbol(symbol) => write!(formatter, "{symbol}"),
+            Token::Equals => write!(formatter, "="),
+        }
+    }
+}
+
+/// Splits the source into tokens.
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&character) = chars.peek() {
+        if character.is_whitespace() {
+            chars.next();
+        } else if character.is_ascii_digit() || character == '.' {
+            let mut number = String::new();
+            while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                number.push(digit);
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .map_err(|_| format!("`{number}` is no number"))?;
+            tokens.push(Token::Number(value));
+        } else if character.is_ascii_alphabetic() {
+            let mut name = String::new();
+            while let Some(&letter) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                name.push(letter);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else if character == '=' {
+            tokens.push(Token::Equals);
+            chars.next();
+        } else if "+-*/^()|".contains(character) {
+            tokens.push(Token::Symbol(character));
+            chars.next();
+        } else {
+            return Err(format!("unexpected `{character}`"));
+        }
+    }
+    Ok(tokens)
+}
+
+/// The functions that can be applied to a factor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    /// The real part.
+    Re,
+    /// The imaginary part as real number.
+    Im,
+    /// The magnitude.
+    Abs,
+    /// The complex conjugate.
+    Conj,
+    /// The exponential function.
+    Exp,
+    /// The principal natural logarithm.
+    Log,
+    /// The principal square root.
+    Sqrt,
+    /// The sine.
+    Sin,
+    /// The cosine.
+    Cos,
+}
+
+impl Function {
+    /// Looks up a function by its name.
+    fn from_name(name: &str) -> Option<Function> {
+        Some(match name {
+            "re" => Function::Re,
+            "im" => Function::Im,
+            "abs" => Function::Abs,
+            "conj" => Function::Conj,
+            "exp" => Function::Exp,
+            "log" | "ln" => Function::Log,
+            "sqrt" => Function::Sqrt,
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            _ => return None,
+        })
+    }
+
+    /// Applies the function.
+    fn apply(self, x: ComplexNumber) -> ComplexNumber {
+        match self {
+            Function::Re => ComplexNumber::new(x.real, 0.0),
+            Function::Im => ComplexNumber::new(x.imag, 0.0),
+            Function::Abs => ComplexNumber::new(x.sq_mag().sqrt(), 0.0),
+            Function::Conj => ComplexNumber::new(x.real, -x.imag),
+            Function::Exp => exp(x),
+            Function::Log => log(x),
+            Function::Sqrt => exp(log(x) * ComplexNumber::new(0.5, 0.0)),
+            Function::Sin => {
+                ComplexNumber::new(x.real.sin() * x.imag.cosh(), x.real.cos() * x.imag.sinh())
+            }
+            Function::Cos => {
+                ComplexNumber::new(x.real.cos() * x.imag.cosh(), -x.real.sin() * x.imag.sinh())
+            }
+        }
+    }
+}
+
+/// The parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// The current orbit value.
+    Z,
+    /// The parameter.
+    C,
+    /// A constant.
+    Constant(ComplexNumber),
+    /// The negated operand.
+    Negate(Box<Node>),
+    /// The sum of both operands.
+    Add(Box<Node>, Box<Node>),
+    /// The difference of both operands.
+    Subtract(Box<Node>, Box<Node>),
+    /// The product of both operands.
+    Multiply(Box<Node>, Box<Node>),
+    /// The quotient of both operands.
+    Divide(Box<Node>, Box<Node>),
+    /// The base to the power of the exponent.
+    Power(Box<Node>, Box<Node>),
+    /// A function applied to the operand.
+    Call(Function, Box<Node>),
+}
+
+/// A recursive descent parser over the tokens.
+struct Parser<'a> {
+    /// The tokens of the expression.
+    tokens: &'a [Token],
+    /// The index of the next token.
+    position: usize,
+    /// Whether we are within `|…|`, where a bar closes instead of opening a new magnitude.
+    in_magnitude: bool,
+}
+
+impl Parser<'_> {
+    /// The next token without consuming it.
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// Consumes the next token if it is the symbol.
+    fn accept(&mut self, symbol: char) -> bool {
+        let found = self.peek() == Some(&Token::Symbol(symbol));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    /// Parses terms joined by `+` and `-`.
+    fn sum(&mut self) -> Result<Node, String> {
+        let mut node = self.product()?;
+        loop {
+            if self.accept('+') {
+                node = Node::Add(Box::new(node), Box::new(self.product()?));
+            } else if self.accept('-') {
+                node = Node::Subtract(Box::new(node), Box::new(self.product()?));
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    /// Parses factors joined by `*`, `/` or nothing at all.
+    fn product(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            if self.accept('*') {
+                node = Node::Multiply(Box::new(node), Box::new(self.unary()?));
+            } else if self.accept('/') {
+                node = Node::Divide(Box::new(node), Box::new(self.unary()?));
+            } else if self.starts_factor() {
+                node = Node::Multiply(Box::new(node), Box::new(self.power()?));
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    /// Whether the next token starts a factor multiplied by juxtaposition.
+    fn starts_factor(&self) -> bool {
+        match self.peek() {
+            Some(Token::Number(_) | Token::Name(_)) => true,
+            Some(Token::Symbol('(')) => true,
+            Some(Token::Symbol('|')) => !self.in_magnitude,
+            _ => false,
+        }
+    }
+
+    /// Parses a negation or a power.
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.accept('-') {
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    /// Parses a power, which binds to the right.
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.call()?;
+        if self.accept('^') {
+            return Ok(Node::Power(Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    /// Parses a function applied to a factor or an atom.
+    fn call(&mut self) -> Result<Node, String> {
+        if let Some(Token::Name(name)) = self.peek()
+            && let Some(function) = Function::from_name(name)
+        {
+            self.position += 1;
+            return Ok(Node::Call(function, Box::new(self.call()?)));
+        }
+        self.atom()
+    }
+
+    /// Parses a number, a variable, the imaginary unit, a bracket or a magnitude.
+    fn atom(&mut self) -> Result<Node, String> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or("unexpected end of the formula".to_owned())?;
+        self.position += 1;
+        match token {
+            Token::Number(value) => Ok(Node::Constant(ComplexNumber::new(value, 0.0))),
+            Token::Name(name) => match name.as_str() {
+                "z" => Ok(Node::Z),
+                "c" => Ok(Node::C),
+                "i" => Ok(Node::Constant(ComplexNumber::new(0.0, 1.0))),
+                _ => Err(format!("unknown name `{name}`")),
+            },
+            Token::Symbol('(') => {
+                let outer = std::mem::replace(&mut self.in_magnitude, false);
+                let node = self.sum()?;
+                self.in_magnitude = outer;
+                if !self.accept(')') {
+                    return Err("missing `)`".to_owned());
+                }
+                Ok(node)
+            }
+            Token::Symbol('|') => {
+                let outer = std::mem::replace(&mut self.in_magnitude, true);
+                let node = self.sum()?;
+                self.in_magnitude = outer;
+                if !self.accept('|') {
+                    return Err("missing closing `|`".to_owned());
+                }
+                Ok(Node::Call(Function::Abs, Box::new(node)))
+            }
+            token => Err(format!("unexpected `{token}`")),
+        }
+    }
+}
+
+/// Turns the expression into nested closures, so the tree is walked once instead of for every step.
+fn compile(node: Node) -> Compiled {
+    match node {
+        Node::Z => Arc::new(|z, _| z),
+        Node::C => Arc::new(|_, c| c),
+        Node::Constant(value) => Arc::new(move |_, _| value),
+        Node::Negate(operand) => {
+            let operand = compile(*operand);
+            Arc::new(move |z, c| ComplexNumber::default() - operand(z, c))
+        }
+        Node::Add(left, right) => {
+            let (left, right) = (compile(*left), compile(*right));
+            Arc::new(move |z, c| left(z, c) + right(z, c))
+        }
+        Node::Subtract(left, right) => {
+            let (left, right) = (compile(*left), compile(*right));
+            Arc::new(move |z, c| left(z, c) - right(z, c))
+        }
+        Node::Multiply(left, right) => {
+            let (left, right) = (compile(*left), compile(*right));
+            Arc::new(move |z, c| left(z, c) * right(z, c))
+        }
+        Node::Divide(left, right) => {
+            let (left, right) = (compile(*left), compile(*right));
+            Arc::new(move |z, c| left(z, c) / right(z, c))
+        }
+        Node::Power(base, exponent) => {
+            let base = compile(*base);
+            match *exponent {
+                // Integer powers like the common z^2 are multiplied out, which is faster and exact.
+                Node::Constant(value)
+                    if value.imag == 0.0
+                        && value.real.fract() == 0.0
+                        && value.real.abs() <= MAX_INTEGER_POWER =>
+                {
+                    let power = value.real as i32;
+                    Arc::new(move |z, c| integer_power(base(z, c), power))
+                }
+                exponent => {
+                    let exponent = compile(exponent);
+                    Arc::new(move |z, c| exp(log(base(z, c)) * exponent(z, c)))
+                }
+            }
+        }
+        Node::Call(function, operand) => {
+            let operand = compile(*operand);
+            Arc::new(move |z, c| function.apply(operand(z, c)))
+        }
+    }
+}
+
+/// Raises the number to the integer power by repeated squaring.
+fn integer_power(base: ComplexNumber, power: i32) -> ComplexNumber {
+    let mut result = ComplexNumber::ONE;
+    let mut square = base;
+    let mut remaining = power.unsigned_abs();
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            result = result * square;
+        }
+        square = square * square;
+        remaining >>= 1;
+    }
+    if power < 0 {
+        ComplexNumber::ONE / result
+    } else {
+        result
+    }
+}
+
+/// The complex exponential function.
+fn exp(x: ComplexNumber) -> ComplexNumber {
+    ComplexNumber::from_angle(x.imag) * ComplexNumber::new(x.real.exp(), 0.0)
+}
+
+/// The principal natural logarithm.
+fn log(x: ComplexNumber) -> ComplexNumber {
+    ComplexNumber::new(0.5 * x.sq_mag().ln(), x.imag.atan2(x.real))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates one step of the formula for z and c = 0.
+    fn evaluate(source: &str, z: ComplexNumber) -> ComplexNumber {
+        Formula::parse(source)
+            .expect("the formula parses")
+            .step(z, ComplexNumber::default())
+    }
+
+    /// Checks that the values agree up to the rounding of the logarithm powers go through.
+    fn assert_close(actual: ComplexNumber, expected: ComplexNumber) {
+        assert!(
+            (actual - expected).sq_mag() < 1e-20,
+            "{actual:?} is not {expected:?}"
+        );
+    }
+
+    #[test]
+    fn negation_binds_weaker_than_power() {
+        let z = ComplexNumber::new(1.0, 1.0);
+        assert_eq!(evaluate("-z^2", z), ComplexNumber::new(0.0, -2.0));
+        assert_eq!(evaluate("(-z)^2", z), ComplexNumber::new(0.0, 2.0));
+    }
+
+    #[test]
+    fn power_binds_to_the_right() {
+        let z = ComplexNumber::new(2.0, 0.0);
+        assert_close(evaluate("z^2^3", z), ComplexNumber::new(256.0, 0.0));
+        assert_eq!(evaluate("(z^2)^3", z), ComplexNumber::new(64.0, 0.0));
+        assert_eq!(evaluate("z^-1", z), ComplexNumber::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn bars_take_the_magnitude() {
+        assert_eq!(
+            evaluate("|z|", ComplexNumber::new(3.0, 4.0)),
+            ComplexNumber::new(5.0, 0.0)
+        );
+        assert_eq!(
+            evaluate("z = |re z| + i|im z|", ComplexNumber::new(-1.0, -2.0)),
+            ComplexNumber::new(1.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn malformed_formulas_are_rejected() {
+        assert!(Formula::parse("|z").is_err());
+        assert!(Formula::parse("(z").is_err());
+        assert!(Formula::parse("w^2 + c").is_err());
+        assert!(Formula::parse("z^").is_err());
+    }
+}
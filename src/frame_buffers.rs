@@ -0,0 +1,36 @@
+//! Double buffering between the colored frames and the display. A new frame is uploaded into the back texture while
+//! the front one stays presented, and the two only swap once the upload is complete, so neither the screen nor the
+//! post pipeline ever sample a half written texture. As the draw calls of the last frame read the other texture, the
+//! upload does not have to wait for them to finish either.
+
+use macroquad::texture::{Image, Texture2D};
+
+/// The texture on screen and the one the next frame goes into.
+pub struct FrameBuffers {
+    /// Both textures in the size of the window.
+    textures: [Texture2D; 2],
+    /// The index of the texture on screen.
+    front: usize,
+}
+
+impl FrameBuffers {
+    /// Two textures in the size of the image, both showing it.
+    pub fn new(image: &Image) -> FrameBuffers {
+        FrameBuffers {
+            textures: [Texture2D::from_image(image), Texture2D::from_image(image)],
+            front: 0,
+        }
+    }
+
+    /// Uploads the image into the back texture and brings it to the front.
+    pub fn present(&mut self, image: &Image) {
+        let back = 1 - self.front;
+        self.textures[back].update(image);
+        self.front = back;
+    }
+
+    /// The texture on screen.
+    pub fn front(&self) -> &Texture2D {
+        &self.textures[self.front]
+    }
+}
@@ -0,0 +1,69 @@
+//! Optional gamepad support. The left stick pans and the triggers zoom in manual mode, the buttons trigger
+//! commands.
+
+use crate::commands::Command;
+use crate::manual_control::ManualInput;
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+/// Stick deflections below this value are ignored.
+const DEAD_ZONE: f32 = 0.15;
+
+/// The gamepad state of all connected controllers.
+pub struct GamepadControls {
+    /// The gilrs context, None if the gamepad subsystem is not available.
+    gilrs: Option<Gilrs>,
+}
+
+impl GamepadControls {
+    /// Initializes the gamepad subsystem. Failures are reported and leave us without gamepad support.
+    pub fn new() -> GamepadControls {
+        GamepadControls {
+            gilrs: Gilrs::new()
+                .inspect_err(|err| eprintln!("Could not initialize gamepads: {err}"))
+                .ok(),
+        }
+    }
+
+    /// Processes the pending events, appends the triggered commands and returns the steering input.
+    pub fn poll(&mut self, commands: &mut Vec<Command>) -> ManualInput {
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return ManualInput::default();
+        };
+
+        while let Some(event) = gilrs.next_event() {
+            if let EventType::ButtonPressed(button, _) = event.event {
+                let command = match button {
+                    Button::Start => Some(Command::ToggleManual),
+                    Button::Select => Some(Command::TogglePause),
+                    Button::South => Some(Command::NextTarget),
+                    Button::West => Some(Command::NextPalette),
+                    Button::North => Some(Command::Screenshot),
+                    _ => None,
+                };
+                commands.extend(command);
+            }
+        }
+
+        gilrs
+            .gamepads()
+            .map(|(_, gamepad)| {
+                let trigger = |button| gamepad.button_data(button).map_or(0.0, |data| data.value());
+                ManualInput {
+                    pan_x: dead_zone(gamepad.value(Axis::LeftStickX)),
+                    pan_y: -dead_zone(gamepad.value(Axis::LeftStickY)),
+                    zoom: (trigger(Button::RightTrigger2) - trigger(Button::LeftTrigger2)) as f64,
+                    zoom_steps: 0.0,
+                }
+            })
+            .fold(ManualInput::default(), ManualInput::combine)
+    }
+}
+
+/// Removes small stick deflections and converts to double precision.
+fn dead_zone(value: f32) -> f64 {
+    if value.abs() < DEAD_ZONE {
+        0.0
+    } else {
+        value as f64
+    }
+}
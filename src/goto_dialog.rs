@@ -0,0 +1,148 @@
+//! The go-to dialog, a text field for typing or pasting a location as "re, im, radius". Enter flies there with the
+//! usual zoom out, pan and zoom in, escape closes the dialog without moving.
+
+use crate::math::ComplexNumber;
+use macroquad::prelude::*;
+
+/// The font size of the dialog.
+const FONT_SIZE: f32 = 24.0;
+
+/// The padding around the text in pixels.
+const PADDING: f32 = 12.0;
+
+/// The width of the dialog relative to the screen width.
+const DIALOG_WIDTH: f32 = 0.6;
+
+/// The result of the input of a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DialogOutcome {
+    /// The dialog stays open.
+    Editing,
+    /// The dialog was closed without a location.
+    Cancelled,
+    /// The entered location with its center and radius.
+    GoTo(ComplexNumber, f64),
+}
+
+/// The state of the open dialog.
+#[derive(Debug, Default)]
+pub struct GoToDialog {
+    /// The text entered so far.
+    text: String,
+    /// The reason the last entry was rejected.
+    error: Option<String>,
+}
+
+impl GoToDialog {
+    /// Opens an empty dialog.
+    pub fn new() -> GoToDialog {
+        // The key that opened the dialog must not end up in the text.
+        clear_input_queue();
+        GoToDialog::default()
+    }
+
+    /// Processes the keyboard input of this frame.
+    pub fn update(&mut self) -> DialogOutcome {
+        if is_key_pressed(KeyCode::Escape) {
+            return DialogOutcome::Cancelled;
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            match parse_location(&self.text) {
+                Ok((center, radius)) => return DialogOutcome::GoTo(center, radius),
+                Err(err) => self.error = Some(err),
+            }
+        }
+        edit_text(&mut self.text);
+        DialogOutcome::Editing
+    }
+
+    /// Draws the dialog in the middle of the screen.
+    pub fn draw(&self) {
+        let width = screen_width() * DIALOG_WIDTH;
+        let height = 3.0 * FONT_SIZE + 2.0 * PADDING;
+        let x = (screen_width() - width) * 0.5;
+        let y = (screen_height() - height) * 0.5;
+        draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.8));
+        draw_rectangle_lines(x, y, width, height, 2.0, WHITE);
+        draw_text(
+            "Go to re, im, radius",
+            x + PADDING,
+            y + PADDING + FONT_SIZE,
+            FONT_SIZE,
+            GRAY,
+        );
+        // The cursor blinks twice a second.
+        let cursor = if get_time().fract() < 0.5 { "_" } else { "" };
+        draw_text(
+            &format!("{}{cursor}", self.text),
+            x + PADDING,
+            y + PADDING + 2.0 * FONT_SIZE,
+            FONT_SIZE,
+            WHITE,
+        );
+        if let Some(error) = self.error.as_ref() {
+            draw_text(
+                error,
+                x + PADDING,
+                y + PADDING + 3.0 * FONT_SIZE,
+                FONT_SIZE,
+                RED,
+            );
+        }
+    }
+}
+
+/// Applies the typed characters, backspace and pasting from the clipboard to the text.
+pub fn edit_text(text: &mut String) {
+    if is_key_pressed(KeyCode::Backspace) {
+        text.pop();
+    }
+    let control = is_key_down(KeyCode::LeftControl)
+        || is_key_down(KeyCode::RightControl)
+        || is_key_down(KeyCode::LeftSuper)
+        || is_key_down(KeyCode::RightSuper);
+    if control && is_key_pressed(KeyCode::V) {
+        if let Some(pasted) = miniquad::window::clipboard_get() {
+            text.push_str(pasted.trim());
+        }
+        clear_input_queue();
+    }
+    // The queue hands out the last key first.
+    let mut typed = Vec::new();
+    while let Some(character) = get_char_pressed() {
+        typed.push(character);
+    }
+    text.extend(typed.into_iter().rev().filter(|c| !c.is_control()));
+}
+
+/// Reads a location given as real part, imaginary part and radius, separated by commas or spaces. A trailing i on
+/// the imaginary part is accepted.
+pub fn parse_location(text: &str) -> Result<(ComplexNumber, f64), String> {
+    let parts: Vec<&str> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .collect();
+    let [real, imag, radius] = parts[..] else {
+        return Err("expected re, im, radius".to_owned());
+    };
+    let number = |part: &str| {
+        part.parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| format!("{part} is not a number"))
+    };
+    let center = ComplexNumber::new(number(real)?, number(imag.trim_end_matches('i'))?);
+    check_location(center, number(radius)?)
+}
+
+/// Checks that a location from outside can be flown to: the center has to be finite and the radius finite and
+/// positive.
+pub fn check_location(center: ComplexNumber, radius: f64) -> Result<(ComplexNumber, f64), String> {
+    if !(center.real.is_finite() && center.imag.is_finite()) {
+        return Err("the center must be finite".to_owned());
+    }
+    if !(radius.is_finite() && radius > 0.0) {
+        return Err("the radius must be positive".to_owned());
+    }
+    Ok((center, radius))
+}
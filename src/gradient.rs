@@ -0,0 +1,157 @@
+//! User defined color gradients, which are stored as small text files in the palettes directory. Every line
+//! holds one stop as `position red green blue`, with the position in [0, 1) and the channels in 0..=255.
+
+use crate::color::{Color, color_to_rgba8};
+use crate::color_generation::mix_linear;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The directory user defined gradients are stored in.
+pub const PALETTE_DIRECTORY: &str = "palettes";
+
+/// The maximum amount of stops a gradient can have.
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+/// A color at a position within the cycle of a gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// The position within the cycle in [0, 1).
+    pub position: f32,
+    /// The sRGB color at the position.
+    pub color: Color,
+}
+
+/// A cyclic gradient with freely placed stops. It is stored inline, so the palette settings stay copyable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gradient {
+    /// The stops sorted by position, only the first `len` are used.
+    stops: [GradientStop; MAX_GRADIENT_STOPS],
+    /// The amount of stops in use.
+    len: usize,
+}
+
+impl Gradient {
+    /// Creates a gradient from the stops, surplus stops are dropped. Needs at least one stop.
+    pub fn new(stops: &[GradientStop]) -> Gradient {
+        let mut gradient = Gradient {
+            stops: [stops[0]; MAX_GRADIENT_STOPS],
+            len: 0,
+        };
+        for stop in stops.iter().take(MAX_GRADIENT_STOPS) {
+            gradient.insert(*stop);
+        }
+        gradient
+    }
+
+    /// The stops sorted by position.
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops[..self.len]
+    }
+
+    /// Adds a stop at its sorted place and returns its index. Returns None if the gradient is full.
+    pub fn insert(&mut self, stop: GradientStop) -> Option<usize> {
+        if self.len == MAX_GRADIENT_STOPS {
+            return None;
+        }
+        let index = self
+            .stops()
+            .partition_point(|s| s.position <= stop.position);
+        self.stops.copy_within(index..self.len, index + 1);
+        self.stops[index] = stop;
+        self.len += 1;
+        Some(index)
+    }
+
+    /// Removes the stop, the last one is always kept.
+    pub fn remove(&mut self, index: usize) {
+        if self.len > 1 && index < self.len {
+            self.stops.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+    }
+
+    /// Moves the stop to a new position and returns its new index.
+    pub fn move_stop(&mut self, index: usize, position: f32) -> usize {
+        let mut stop = self.stops[index];
+        stop.position = position.clamp(0.0, 0.999);
+        if self.len == 1 {
+            self.stops[0] = stop;
+            return 0;
+        }
+        self.remove(index);
+        self.insert(stop).unwrap_or(index)
+    }
+
+    /// Changes the color of the stop.
+    pub fn set_color(&mut self, index: usize, color: Color) {
+        self.stops[index].color = color;
+    }
+
+    /// Gets the color for a relative position in [0, 1), interpolated in linear light between the neighboring
+    /// stops and wrapping around from the last to the first one.
+    pub fn color_at(&self, rel_val: f32) -> Color {
+        let stops = self.stops();
+        let next = stops.partition_point(|s| s.position <= rel_val);
+        let (from, mut from_position) = if next == 0 {
+            (
+                stops[stops.len() - 1],
+                stops[stops.len() - 1].position - 1.0,
+            )
+        } else {
+            (stops[next - 1], stops[next - 1].position)
+        };
+        let (to, mut to_position) = if next == stops.len() {
+            (stops[0], stops[0].position + 1.0)
+        } else {
+            (stops[next], stops[next].position)
+        };
+        if stops.len() == 1 {
+            from_position = 0.0;
+            to_position = 1.0;
+        }
+        let t = if to_position > from_position {
+            (rel_val - from_position) / (to_position - from_position)
+        } else {
+            0.0
+        };
+        mix_linear(from.color, to.color, t)
+    }
+
+    /// Saves the gradient as gradient file.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut content = String::new();
+        for stop in self.stops() {
+            let [r, g, b, _] = color_to_rgba8(stop.color);
+            let _ = writeln!(content, "{:.4} {r} {g} {b}", stop.position);
+        }
+        std::fs::write(path, content)
+    }
+
+    /// Loads a gradient file.
+    pub fn load(path: &Path) -> Result<Gradient, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let stops = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let values: Vec<f32> = line
+                    .split_whitespace()
+                    .map(|value| value.parse::<f32>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid stop `{line}`"))?;
+                match values[..] {
+                    [position, r, g, b] => Ok(GradientStop {
+                        position: position.rem_euclid(1.0),
+                        color: Color::new(r / 255.0, g / 255.0, b / 255.0, 1.0),
+                    }),
+                    _ => Err(format!("expected `position red green blue`, got `{line}`")),
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if stops.is_empty() {
+            return Err("the gradient has no stops".to_owned());
+        }
+        Ok(Gradient::new(&stops))
+    }
+}
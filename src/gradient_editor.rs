@@ -0,0 +1,232 @@
+//! Overlay for designing gradients on top of the running zoom, the result is previewed live. Clicking on the bar
+//! adds a stop, stops can be dragged along the bar and removed with a right click. The sliders above the bar tune
+//! hue, saturation and value of the selected stop, enter saves the gradient into the palettes directory.
+
+use crate::color_generation::hsv_to_rgb_color;
+use crate::export::time_stamp;
+use crate::gradient::{Gradient, GradientStop, PALETTE_DIRECTORY};
+use macroquad::prelude::*;
+use std::path::Path;
+
+/// The horizontal margin of the gradient bar relative to the screen width.
+const BAR_MARGIN: f32 = 0.1;
+
+/// The height of the gradient bar in pixels.
+const BAR_HEIGHT: f32 = 32.0;
+
+/// The distance between the gradient bar and the lower screen border in pixels.
+const BAR_BOTTOM_OFFSET: f32 = 60.0;
+
+/// The half width of the stop markers below the bar in pixels.
+const MARKER_SIZE: f32 = 8.0;
+
+/// The width of the color sliders in pixels.
+const SLIDER_WIDTH: f32 = 240.0;
+
+/// The height of the color sliders in pixels.
+const SLIDER_HEIGHT: f32 = 14.0;
+
+/// The vertical distance between two sliders in pixels.
+const SLIDER_SPACING: f32 = 22.0;
+
+/// The labels of the hue, saturation and value sliders.
+const SLIDER_LABELS: [&str; 3] = ["H", "S", "V"];
+
+/// What the mouse is currently dragging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Drag {
+    /// The selected stop along the bar.
+    Stop,
+    /// The slider with the given index.
+    Slider(usize),
+}
+
+/// The state of the open editor.
+pub struct GradientEditor {
+    /// The gradient being edited.
+    gradient: Gradient,
+    /// The index of the selected stop.
+    selected: usize,
+    /// What the mouse drags, if anything.
+    drag: Option<Drag>,
+}
+
+impl GradientEditor {
+    /// Opens the editor on the gradient.
+    pub fn new(gradient: Gradient) -> GradientEditor {
+        GradientEditor {
+            gradient,
+            selected: 0,
+            drag: None,
+        }
+    }
+
+    /// The gradient in its current state.
+    pub fn gradient(&self) -> Gradient {
+        self.gradient
+    }
+
+    /// Processes the mouse and keyboard input of this frame.
+    pub fn update(&mut self) {
+        let mouse = Vec2::from(mouse_position());
+        let bar = bar_rect();
+        let position = ((mouse.x - bar.x) / bar.w).clamp(0.0, 0.999);
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(slider) = (0..SLIDER_LABELS.len()).find(|i| slider_rect(*i).contains(mouse))
+            {
+                self.drag = Some(Drag::Slider(slider));
+            } else if let Some(index) = self.stop_at(mouse) {
+                self.selected = index;
+                self.drag = Some(Drag::Stop);
+            } else if bar.contains(mouse) {
+                let color = self.gradient.color_at(position);
+                if let Some(index) = self.gradient.insert(GradientStop { position, color }) {
+                    self.selected = index;
+                    self.drag = Some(Drag::Stop);
+                }
+            }
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            self.drag = None;
+        }
+        if is_mouse_button_pressed(MouseButton::Right)
+            && let Some(index) = self.stop_at(mouse)
+        {
+            self.gradient.remove(index);
+            self.selected = self.selected.min(self.gradient.stops().len() - 1);
+        }
+
+        match self.drag {
+            Some(Drag::Stop) => self.selected = self.gradient.move_stop(self.selected, position),
+            Some(Drag::Slider(slider)) => {
+                let rect = slider_rect(slider);
+                let mut hsv = rgb_to_hsv(self.selected_color());
+                hsv[slider] = ((mouse.x - rect.x) / rect.w).clamp(0.0, 1.0);
+                let color = hsv_to_rgb_color(hsv[0].min(0.999), hsv[1], hsv[2]);
+                self.gradient.set_color(self.selected, color);
+            }
+            None => {}
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            let path = Path::new(PALETTE_DIRECTORY).join(format!("gradient_{}.txt", time_stamp()));
+            match std::fs::create_dir_all(PALETTE_DIRECTORY)
+                .and_then(|()| self.gradient.save(&path))
+            {
+                Ok(()) => println!("Saved gradient to {}", path.display()),
+                Err(err) => eprintln!("Could not save gradient: {err}"),
+            }
+        }
+    }
+
+    /// Draws the bar with its stops, the sliders of the selected stop and a short help.
+    pub fn draw(&self) {
+        let bar = bar_rect();
+        let top = slider_rect(0).y - SLIDER_SPACING;
+        draw_rectangle(
+            bar.x - 12.0,
+            top - 12.0,
+            bar.w + 24.0,
+            bar.bottom() + 2.0 * MARKER_SIZE - top + 24.0,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        draw_text(
+            "click: add stop   drag: move   right click: remove   enter: save   E: close",
+            bar.x,
+            top,
+            20.0,
+            WHITE,
+        );
+
+        for x in 0..bar.w as usize {
+            let color = self.gradient.color_at(x as f32 / bar.w);
+            draw_rectangle(bar.x + x as f32, bar.y, 1.0, bar.h, screen_color(color));
+        }
+        for (index, stop) in self.gradient.stops().iter().enumerate() {
+            let x = bar.x + stop.position * bar.w;
+            let tip = vec2(x, bar.bottom());
+            let left = vec2(x - MARKER_SIZE, bar.bottom() + 2.0 * MARKER_SIZE);
+            let right = vec2(x + MARKER_SIZE, bar.bottom() + 2.0 * MARKER_SIZE);
+            draw_triangle(tip, left, right, screen_color(stop.color));
+            let outline = if index == self.selected { WHITE } else { GRAY };
+            draw_triangle_lines(tip, left, right, 2.0, outline);
+        }
+
+        let hsv = rgb_to_hsv(self.selected_color());
+        for (slider, label) in SLIDER_LABELS.iter().enumerate() {
+            let rect = slider_rect(slider);
+            draw_text(label, rect.x - 18.0, rect.bottom(), 20.0, WHITE);
+            for x in (0..rect.w as usize).step_by(2) {
+                let mut sample = hsv;
+                sample[slider] = x as f32 / rect.w;
+                let color = hsv_to_rgb_color(sample[0].min(0.999), sample[1], sample[2]);
+                draw_rectangle(rect.x + x as f32, rect.y, 2.0, rect.h, screen_color(color));
+            }
+            let knob = rect.x + hsv[slider] * rect.w;
+            draw_rectangle_lines(knob - 2.0, rect.y - 2.0, 4.0, rect.h + 4.0, 2.0, WHITE);
+        }
+    }
+
+    /// The color of the selected stop.
+    fn selected_color(&self) -> crate::color::Color {
+        self.gradient.stops()[self.selected].color
+    }
+
+    /// Gets the index of the stop whose marker is under the mouse.
+    fn stop_at(&self, mouse: Vec2) -> Option<usize> {
+        let bar = bar_rect();
+        if mouse.y < bar.y || mouse.y > bar.bottom() + 2.0 * MARKER_SIZE {
+            return None;
+        }
+        self.gradient
+            .stops()
+            .iter()
+            .position(|stop| (bar.x + stop.position * bar.w - mouse.x).abs() <= MARKER_SIZE)
+    }
+}
+
+/// The screen area of the gradient bar.
+fn bar_rect() -> Rect {
+    let x = screen_width() * BAR_MARGIN;
+    Rect::new(
+        x,
+        screen_height() - BAR_BOTTOM_OFFSET - BAR_HEIGHT,
+        screen_width() - 2.0 * x,
+        BAR_HEIGHT,
+    )
+}
+
+/// The screen area of the slider with the given index.
+fn slider_rect(slider: usize) -> Rect {
+    let bar = bar_rect();
+    Rect::new(
+        bar.x + 18.0,
+        bar.y - (SLIDER_LABELS.len() - slider) as f32 * SLIDER_SPACING - 4.0,
+        SLIDER_WIDTH,
+        SLIDER_HEIGHT,
+    )
+}
+
+/// Converts an rgb color into hue, saturation and value, all in [0, 1].
+fn rgb_to_hsv(color: crate::color::Color) -> [f32; 3] {
+    let max = color.r.max(color.g).max(color.b);
+    let min = color.r.min(color.g).min(color.b);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == color.r {
+        ((color.g - color.b) / delta).rem_euclid(6.0) / 6.0
+    } else if max == color.g {
+        ((color.b - color.r) / delta + 2.0) / 6.0
+    } else {
+        ((color.r - color.g) / delta + 4.0) / 6.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    [hue, saturation, max]
+}
+
+/// Converts a gradient color into the color the screen is drawn with.
+fn screen_color(color: crate::color::Color) -> Color {
+    Color::new(color.r, color.g, color.b, color.a)
+}
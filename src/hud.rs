@@ -0,0 +1,86 @@
+//! The heads-up display in the upper right corner. It shows where we are, the arithmetic the view is computed in
+//! and the period of the hyperbolic component at or near the center of the view, which tells how many steps the
+//! orbits there need to repeat. Inside a named place its name is shown as well.
+
+use crate::config::CONFIG;
+use crate::locations::location_at;
+use crate::math::{ComplexNumber, Fractal};
+use crate::minibrot::{find_period, orbit_period};
+use crate::night::dimmed;
+use crate::precision::PrecisionTier;
+use macroquad::prelude::*;
+
+/// The font size of the display.
+const FONT_SIZE: f32 = 22.0;
+
+/// The distance of the display to the screen border and the padding around the text in pixels.
+const MARGIN: f32 = 12.0;
+
+/// The period found for the view center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PeriodReadout {
+    /// The center lies inside a component with this period.
+    Inside(usize),
+    /// The lowest period of the components within the view.
+    Near(usize),
+    /// No component was found.
+    Unknown,
+}
+
+impl PeriodReadout {
+    /// Determines the period at the center, the disc of the radius is searched if the center itself escapes.
+    fn detect(center: ComplexNumber, radius: f64) -> PeriodReadout {
+        if let Some(period) = orbit_period(center) {
+            PeriodReadout::Inside(period)
+        } else if let Some(period) = find_period(center, radius) {
+            PeriodReadout::Near(period)
+        } else {
+            PeriodReadout::Unknown
+        }
+    }
+}
+
+/// Draws the display for the view, the text dimmed to the brightness.
+pub fn draw_hud(fractal: Fractal, center: ComplexNumber, radius: f64, brightness: f32) {
+    let mut lines = vec![
+        format!("center {:+.12} {:+.12}i", center.real, center.imag),
+        format!("radius {radius:.3e}"),
+        format!(
+            "precision {}",
+            PrecisionTier::for_radius(radius, &CONFIG.iteration_settings()).name()
+        ),
+    ];
+    match fractal {
+        Fractal::Mandelbrot => lines.push(match PeriodReadout::detect(center, radius) {
+            PeriodReadout::Inside(period) => format!("period {period}"),
+            PeriodReadout::Near(period) => format!("near period {period}"),
+            PeriodReadout::Unknown => "period -".to_owned(),
+        }),
+        Fractal::Julia(c) => lines.push(format!("julia {:+.6} {:+.6}i", c.real, c.imag)),
+    }
+    if let Some(location) = location_at(fractal, center, radius) {
+        lines.push(location.name.to_owned());
+    }
+
+    let width = lines
+        .iter()
+        .map(|line| measure_text(line, None, FONT_SIZE as u16, 1.0).width)
+        .fold(0.0, f32::max);
+    let x = screen_width() - width - 2.0 * MARGIN;
+    draw_rectangle(
+        x - MARGIN,
+        MARGIN,
+        width + 2.0 * MARGIN,
+        lines.len() as f32 * FONT_SIZE + MARGIN,
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    );
+    for (index, line) in lines.iter().enumerate() {
+        draw_text(
+            line,
+            x,
+            MARGIN + (index + 1) as f32 * FONT_SIZE,
+            FONT_SIZE,
+            dimmed(WHITE, brightness),
+        );
+    }
+}
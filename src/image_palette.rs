@@ -0,0 +1,156 @@
+//! Derives a palette from the pixels of an image, so the fractal can be themed to match a photo or brand colors.
+//! Either the dominant colors are extracted with a median cut, or the middle scanline is used as is.
+
+use crate::color::Color;
+use crate::color_generation::mix_linear;
+
+/// The amount of entries in the color table of the palette.
+const PALETTE_SIZE: usize = 256;
+
+/// The amount of dominant colors extracted by the median cut.
+const DOMINANT_COLOR_COUNT: usize = 8;
+
+/// The maximum amount of pixels the median cut looks at, larger images are subsampled.
+const MAX_SAMPLES: usize = 65536;
+
+/// How the colors are taken from the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImagePaletteMode {
+    /// The dominant colors, ordered from dark to bright.
+    #[default]
+    Dominant,
+    /// The colors along the middle row from left to right.
+    Scanline,
+}
+
+impl ImagePaletteMode {
+    /// Looks up a mode by its name.
+    pub fn from_name(name: &str) -> Option<ImagePaletteMode> {
+        match name {
+            "dominant" => Some(ImagePaletteMode::Dominant),
+            "scanline" => Some(ImagePaletteMode::Scanline),
+            _ => None,
+        }
+    }
+}
+
+/// A palette cycle sampled from an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePalette {
+    /// The colors of one palette cycle.
+    colors: Vec<Color>,
+}
+
+impl ImagePalette {
+    /// Builds the palette from the RGBA8 pixels of an image, stored row by row.
+    pub fn from_rgba8(
+        width: usize,
+        height: usize,
+        pixels: &[u8],
+        mode: ImagePaletteMode,
+    ) -> Result<ImagePalette, String> {
+        if width == 0 || height == 0 {
+            return Err("the image is empty".to_owned());
+        }
+        let key_colors = match mode {
+            ImagePaletteMode::Dominant => dominant_colors(pixels),
+            ImagePaletteMode::Scanline => {
+                let row = height / 2 * width * 4;
+                pixels[row..row + width * 4]
+                    .chunks_exact(4)
+                    .map(|rgba| Color::from_rgba(rgba[0], rgba[1], rgba[2], rgba[3]))
+                    .collect()
+            }
+        };
+        Ok(ImagePalette::from_key_colors(&key_colors))
+    }
+
+    /// Gets the color for a relative position in [0, 1) within one palette cycle.
+    pub fn color_at(&self, rel_val: f32) -> Color {
+        self.colors[(rel_val * PALETTE_SIZE as f32) as usize % PALETTE_SIZE]
+    }
+
+    /// Spreads the key colors over the palette cycle. They are run through forth and back, so the cycle has no
+    /// seam.
+    fn from_key_colors(key_colors: &[Color]) -> ImagePalette {
+        let colors = (0..PALETTE_SIZE)
+            .map(|i| {
+                let position = 1.0 - (2.0 * i as f32 / PALETTE_SIZE as f32 - 1.0).abs();
+                let scaled = position * (key_colors.len() - 1) as f32;
+                let index = (scaled.floor() as usize).min(key_colors.len().saturating_sub(2));
+                let next = (index + 1).min(key_colors.len() - 1);
+                mix_linear(key_colors[index], key_colors[next], scaled - index as f32)
+            })
+            .collect();
+        ImagePalette { colors }
+    }
+}
+
+/// Extracts the dominant colors with a median cut: the box of pixels with the widest channel range is split at its
+/// median until we have enough boxes, then every box contributes its average color.
+fn dominant_colors(rgba: &[u8]) -> Vec<Color> {
+    let step = (rgba.len() / 4).div_ceil(MAX_SAMPLES).max(1);
+    let pixels: Vec<[u8; 3]> = rgba
+        .chunks_exact(4)
+        .step_by(step)
+        .filter(|rgba| rgba[3] > 0)
+        .map(|rgba| [rgba[0], rgba[1], rgba[2]])
+        .collect();
+    if pixels.is_empty() {
+        return vec![Color::new(0.0, 0.0, 0.0, 1.0)];
+    }
+
+    let mut boxes = vec![pixels];
+    while boxes.len() < DOMINANT_COLOR_COUNT {
+        let Some((index, channel, range)) = boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, pixels)| {
+                (0..3).map(move |channel| {
+                    let (min, max) = pixels.iter().fold((u8::MAX, 0), |(min, max), p| {
+                        (min.min(p[channel]), max.max(p[channel]))
+                    });
+                    (index, channel, max.saturating_sub(min))
+                })
+            })
+            .max_by_key(|(_, _, range)| *range)
+        else {
+            break;
+        };
+        if range == 0 {
+            break;
+        }
+        let mut pixels = boxes.swap_remove(index);
+        pixels.sort_unstable_by_key(|p| p[channel]);
+        let upper = pixels.split_off(pixels.len() / 2);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+
+    let mut colors: Vec<Color> = boxes
+        .iter()
+        .map(|pixels| {
+            let sum = pixels.iter().fold([0u64; 3], |sum, p| {
+                [
+                    sum[0] + p[0] as u64,
+                    sum[1] + p[1] as u64,
+                    sum[2] + p[2] as u64,
+                ]
+            });
+            let count = pixels.len() as u64;
+            Color::from_rgba(
+                (sum[0] / count) as u8,
+                (sum[1] / count) as u8,
+                (sum[2] / count) as u8,
+                255,
+            )
+        })
+        .collect();
+    colors.sort_by(|a, b| luminance(*a).total_cmp(&luminance(*b)));
+    colors
+}
+
+/// The perceived brightness of a color.
+fn luminance(color: Color) -> f32 {
+    0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
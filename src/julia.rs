@@ -0,0 +1,138 @@
+//! The side-by-side view of the Mandelbrot set and the Julia set belonging to the point in the center of the view.
+//! The left half shows the middle of the Mandelbrot view, the right half the complete Julia set. While the zoom
+//! approaches the border of the Mandelbrot set, the Julia set turns from a connected blob into dust.
+//!
+//! Also contains the morphing interludes, which move the Julia parameter along the border of a bulb between two
+//! dives.
+
+use crate::color::Color;
+use crate::color_generation::{ColorLookup, ColorSettings, generate_colors};
+use crate::math::{ComplexNumber, Viewport, get_julia_tile};
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+use std::f64::consts::TAU;
+
+/// The radius of the Julia view around the origin, which fits every Julia set of interest.
+const JULIA_RADIUS: f64 = 1.6;
+
+/// The half length of the cross marking the parameter in the Mandelbrot half, in pixels.
+const MARKER_SIZE: f32 = 10.0;
+
+/// How far inside the bulbs the morphing path runs relative to their border. Just inside the sets stay connected
+/// but show the shapes of the border.
+const MORPH_PATH_SCALE: f64 = 0.97;
+
+/// The fraction of a full turn around the bulb a morphing interlude covers.
+const MORPH_SWEEP: f64 = 0.5;
+
+/// The bulb of the Mandelbrot set the morphing path runs around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MorphBulb {
+    /// The main cardioid.
+    Cardioid,
+    /// The circle of period 2 left of the cardioid.
+    PeriodTwo,
+}
+
+/// The state of a morphing interlude.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JuliaMorph {
+    /// The bulb we run around.
+    bulb: MorphBulb,
+    /// The angle on the bulb where we started.
+    start_angle: f64,
+    /// The seconds played so far.
+    elapsed: f64,
+    /// The length of the interlude in seconds.
+    duration: f64,
+}
+
+impl JuliaMorph {
+    /// Starts an interlude of the given length on a random part of a random bulb.
+    pub fn random(duration: f64) -> JuliaMorph {
+        JuliaMorph {
+            bulb: if gen_range(0, 2) == 0 {
+                MorphBulb::Cardioid
+            } else {
+                MorphBulb::PeriodTwo
+            },
+            start_angle: gen_range(0.0, TAU),
+            elapsed: 0.0,
+            duration: duration.max(f64::EPSILON),
+        }
+    }
+
+    /// The current Julia parameter.
+    pub fn parameter(&self) -> ComplexNumber {
+        // Eases in and out, so the morph starts and ends at rest.
+        let progress = (self.elapsed / self.duration).clamp(0.0, 1.0);
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        let angle = self.start_angle + eased * MORPH_SWEEP * TAU;
+        let (sin, cos) = angle.sin_cos();
+        match self.bulb {
+            MorphBulb::Cardioid => {
+                // c = mu / 2 - mu² / 4 maps the unit circle onto the border of the cardioid.
+                let mu = ComplexNumber::new(cos * MORPH_PATH_SCALE, sin * MORPH_PATH_SCALE);
+                ComplexNumber::new(
+                    mu.real * 0.5 - (mu.real * mu.real - mu.imag * mu.imag) * 0.25,
+                    mu.imag * 0.5 - mu.real * mu.imag * 0.5,
+                )
+            }
+            MorphBulb::PeriodTwo => ComplexNumber::new(
+                -1.0 + 0.25 * MORPH_PATH_SCALE * cos,
+                0.25 * MORPH_PATH_SCALE * sin,
+            ),
+        }
+    }
+
+    /// Advances the interlude and returns true once it is over.
+    pub fn advance(&mut self, delta_time: f64) -> bool {
+        self.elapsed += delta_time;
+        self.elapsed >= self.duration
+    }
+}
+
+/// Computes the colors of the Julia set of c for the right half of the window.
+pub fn julia_colors(
+    c: ComplexNumber,
+    lookup: &ColorLookup,
+    color_settings: &ColorSettings,
+) -> Vec<Color> {
+    let viewport = Viewport {
+        center: ComplexNumber::default(),
+        center_low: ComplexNumber::default(),
+        radius: JULIA_RADIUS,
+        rotation: ComplexNumber::ONE,
+        width: WINDOW_WIDTH as u32 / 2,
+        height: WINDOW_HEIGHT as u32,
+    };
+    let field = get_julia_tile(&viewport, 0, 0, viewport.width, viewport.height, c);
+    generate_colors(&field, viewport.width as usize, lookup, color_settings)
+}
+
+/// Combines the middle half of the Mandelbrot colors with the Julia colors of [`julia_colors`] side by side.
+pub fn compose_split(mandelbrot_colors: &[Color], julia_colors: &[Color]) -> Vec<Color> {
+    let width = WINDOW_WIDTH as usize;
+    let half = width / 2;
+    let quarter = width / 4;
+    mandelbrot_colors
+        .chunks_exact(width)
+        .zip(julia_colors.chunks_exact(half))
+        .flat_map(|(mandelbrot_row, julia_row)| {
+            mandelbrot_row[quarter..quarter + half]
+                .iter()
+                .chain(julia_row)
+                .copied()
+        })
+        .collect()
+}
+
+/// Draws the divider between the halves and marks the parameter of the Julia set in the Mandelbrot half.
+pub fn draw_split_overlay() {
+    let (x, y) = (screen_width() * 0.25, screen_height() * 0.5);
+    draw_line(x - MARKER_SIZE, y, x + MARKER_SIZE, y, 2.0, WHITE);
+    draw_line(x, y - MARKER_SIZE, x, y + MARKER_SIZE, 2.0, WHITE);
+    let divider = screen_width() * 0.5;
+    draw_line(divider, 0.0, divider, screen_height(), 2.0, BLACK);
+}
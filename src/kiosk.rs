@@ -0,0 +1,141 @@
+//! Runs the explorer as the program a screensaver or lock screen host starts, like xscreensaver, xsecurelock, a
+//! Wayland idle daemon or a macOS screensaver wrapper. The window covers its output, the display is kept awake while
+//! it runs and the first input quits, so the host takes over again. For several outputs one instance per output is
+//! started, each placed on its output with `window-position`.
+
+use crate::screensaver::LocalInput;
+use macroquad::prelude::*;
+
+/// The time after the start in seconds in which input is ignored, since going fullscreen moves the mouse.
+const START_GRACE: f64 = 2.0;
+
+/// Quits on input and keeps the display awake.
+pub struct Kiosk {
+    /// The time we started.
+    start: f64,
+    /// Sees the input to our window.
+    local_input: LocalInput,
+    /// Keeps the display from sleeping till dropped, None if the platform refused.
+    _inhibitor: Option<SleepInhibitor>,
+}
+
+impl Kiosk {
+    /// Hides the mouse and keeps the display awake.
+    pub fn new() -> Kiosk {
+        show_mouse(false);
+        Kiosk {
+            start: get_time(),
+            local_input: LocalInput::new(),
+            _inhibitor: SleepInhibitor::new(),
+        }
+    }
+
+    /// Whether the user touched keyboard or mouse after the start.
+    pub fn input_seen(&mut self) -> bool {
+        let input = self.local_input.detect();
+        input && get_time() - self.start > START_GRACE
+    }
+}
+
+/// Keeps the display awake while it lives with `systemd-inhibit` on Linux and `caffeinate` on macOS. Both are
+/// bound to our process id, so they also end if we do not get to drop the inhibitor.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+struct SleepInhibitor(std::process::Child);
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+impl SleepInhibitor {
+    /// Starts the helper, None if it is missing.
+    fn new() -> Option<SleepInhibitor> {
+        let pid = std::process::id().to_string();
+        let mut command;
+        if cfg!(target_os = "macos") {
+            command = std::process::Command::new("caffeinate");
+            command.args(["-d", "-i", "-w", &pid]);
+        } else {
+            command = std::process::Command::new("systemd-inhibit");
+            command.args([
+                "--what=idle:sleep",
+                "--who=Mandelbrot Explorer",
+                "--why=Showing the zoom",
+                "--mode=block",
+                "tail",
+                "--pid",
+                &pid,
+                "-f",
+                "/dev/null",
+            ]);
+        }
+        command
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .inspect_err(|err| eprintln!("Could not keep the display awake: {err}"))
+            .ok()
+            .map(SleepInhibitor)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Keeps the display awake while it lives with the execution state of the thread.
+#[cfg(target_os = "windows")]
+struct SleepInhibitor;
+
+#[cfg(target_os = "windows")]
+mod execution_state {
+    /// ES_CONTINUOUS, keeps the state till it is changed again.
+    pub const CONTINUOUS: u32 = 0x8000_0000;
+    /// ES_DISPLAY_REQUIRED
+    pub const DISPLAY_REQUIRED: u32 = 0x0000_0002;
+    /// ES_SYSTEM_REQUIRED
+    pub const SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        pub fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl SleepInhibitor {
+    /// Requires display and system, None if the call failed.
+    fn new() -> Option<SleepInhibitor> {
+        use execution_state::*;
+        // SAFETY: Plain call without pointers.
+        let previous =
+            unsafe { SetThreadExecutionState(CONTINUOUS | DISPLAY_REQUIRED | SYSTEM_REQUIRED) };
+        if previous == 0 {
+            eprintln!("Could not keep the display awake.");
+            return None;
+        }
+        Some(SleepInhibitor)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        // SAFETY: Plain call without pointers.
+        unsafe {
+            execution_state::SetThreadExecutionState(execution_state::CONTINUOUS);
+        }
+    }
+}
+
+/// The display can not be kept awake on the remaining platforms.
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+struct SleepInhibitor;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+impl SleepInhibitor {
+    /// Always None.
+    fn new() -> Option<SleepInhibitor> {
+        None
+    }
+}
@@ -0,0 +1,33 @@
+//! The computation and coloring of the fractal, free of any window: the iteration in all precision tiers, the
+//! palettes, the colorings and the deterministic frame rendering of [`render`]. Everything a frame depends on is
+//! passed in, the explorer builds its window, controls and offline renders on top of it.
+
+pub mod color;
+pub mod color_generation;
+pub mod colormaps;
+pub mod double_double;
+pub mod fixed;
+pub mod formula;
+pub mod gradient;
+pub mod image_palette;
+pub mod math;
+pub mod normalization;
+pub mod palette_registry;
+pub mod perturbation;
+pub mod precision;
+pub mod render;
+
+/// Width of the window in stand-alone mode, the fields of the window views have this size.
+pub const WINDOW_WIDTH: i32 = 1280;
+/// Height of the window in stand-alone mode.
+pub const WINDOW_HEIGHT: i32 = 720;
+
+/// Opens a profiler scope with the given name till the end of the enclosing block. With the `profiler` feature the
+/// scope is recorded with puffin, without it expands to nothing.
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        #[cfg(feature = "profiler")]
+        puffin::profile_scope!($name);
+    };
+}
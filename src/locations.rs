@@ -0,0 +1,168 @@
+//! The named places of the Mandelbrot set. Each covers a disc of the parameter plane, so the view can be named
+//! while it is inside: the heads-up display shows the name of the place, a caption introduces it when a dive
+//! enters it, the console flies there with `goto <name>` and the demo tour visits a selection of them. Nested
+//! places like a minibrot within a valley take precedence over the place around them.
+
+use crate::math::{ComplexNumber, Fractal};
+use macroquad::prelude::*;
+
+/// The font size of the caption.
+const FONT_SIZE: f32 = 40.0;
+
+/// The distance of the caption to the lower screen border in pixels.
+const MARGIN: f32 = 48.0;
+
+/// How long the caption is shown once the view enters a place, in seconds.
+const CAPTION_DURATION: f64 = 8.0;
+
+/// The last part of the caption duration in which it fades out, in seconds.
+const CAPTION_FADE: f64 = 1.5;
+
+/// A named place of the Mandelbrot set.
+#[derive(Debug, Clone, Copy)]
+pub struct Location {
+    /// The name shown in the heads-up display and the caption.
+    pub name: &'static str,
+    /// The center of the view showing the place.
+    pub center: ComplexNumber,
+    /// The radius of the view showing the place.
+    pub radius: f64,
+    /// The radius of the disc around the center the place covers. Views inside it and not larger than it are named
+    /// after the place.
+    pub extent: f64,
+    /// Whether the demo tour visits the place.
+    pub tour: bool,
+}
+
+impl Location {
+    /// Whether the view lies within the place.
+    fn contains(&self, center: ComplexNumber, radius: f64) -> bool {
+        radius <= self.extent && (center - self.center).sq_mag() <= self.extent * self.extent
+    }
+}
+
+/// Creates a location, to keep the table short.
+const fn location(
+    name: &'static str,
+    (real, imag): (f64, f64),
+    radius: f64,
+    extent: f64,
+    tour: bool,
+) -> Location {
+    Location {
+        name,
+        center: ComplexNumber { real, imag },
+        radius,
+        extent,
+        tour,
+    }
+}
+
+/// The named places, the tour visits the marked ones in this order.
+pub const LOCATIONS: [Location; 14] = [
+    location("Seahorse Valley", (-0.7453, 0.1127), 6.5e-4, 0.05, true),
+    location("Elephant Valley", (0.2925, 0.0149), 5e-3, 0.05, true),
+    location("Misiurewicz point i", (0.0, 1.0), 1e-2, 0.02, true),
+    location(
+        "Period 3 minibrot",
+        (-1.754877666246693, 0.0),
+        3e-2,
+        0.03,
+        true,
+    ),
+    location(
+        "Misiurewicz point -0.1011 + 0.9563i",
+        (-0.10109636384562, 0.95628651080914),
+        5e-3,
+        1e-2,
+        true,
+    ),
+    location(
+        "Period 5 minibrot on the needle",
+        (-1.985424253054205, 0.0),
+        6e-5,
+        1e-4,
+        true,
+    ),
+    location("Period 2 bulb", (-1.0, 0.0), 0.3, 0.3, false),
+    location("Period 4 bulb", (-1.3107, 0.0), 0.07, 0.07, false),
+    location("Upper period 3 bulb", (-0.1226, 0.7449), 0.12, 0.12, false),
+    location("Lower period 3 bulb", (-0.1226, -0.7449), 0.12, 0.12, false),
+    location("Triple Spiral Valley", (-0.088, 0.654), 1e-2, 0.02, false),
+    location("Feigenbaum point", (-1.401155189, 0.0), 1e-4, 1e-3, false),
+    location("Tip of the needle", (-2.0, 0.0), 1e-3, 1e-2, false),
+    location(
+        "Lower Seahorse Valley",
+        (-0.7453, -0.1127),
+        6.5e-4,
+        0.05,
+        false,
+    ),
+];
+
+/// The innermost place containing the view of the Mandelbrot set, None for Julia sets.
+pub fn location_at(
+    fractal: Fractal,
+    center: ComplexNumber,
+    radius: f64,
+) -> Option<&'static Location> {
+    if fractal != Fractal::Mandelbrot {
+        return None;
+    }
+    LOCATIONS
+        .iter()
+        .filter(|location| location.contains(center, radius))
+        .min_by(|a, b| a.extent.total_cmp(&b.extent))
+}
+
+/// The place with the name, ignoring the case.
+pub fn find_location(name: &str) -> Option<&'static Location> {
+    LOCATIONS
+        .iter()
+        .find(|location| location.name.eq_ignore_ascii_case(name))
+}
+
+/// Introduces every place the view enters with a caption at the bottom of the screen.
+#[derive(Debug, Default)]
+pub struct LocationCaptions {
+    /// The name of the place the view was in during the last frame.
+    current: Option<&'static str>,
+    /// The caption shown with the time it appeared.
+    caption: Option<(&'static str, f64)>,
+}
+
+impl LocationCaptions {
+    /// Follows the view and shows the caption when it enters a place.
+    pub fn update(&mut self, fractal: Fractal, center: ComplexNumber, radius: f64) {
+        let name = location_at(fractal, center, radius).map(|location| location.name);
+        if name != self.current
+            && let Some(name) = name
+        {
+            self.caption = Some((name, get_time()));
+        }
+        self.current = name;
+    }
+
+    /// Draws the caption of the entered place.
+    pub fn draw(&self) {
+        let Some((name, shown_at)) = self.caption else {
+            return;
+        };
+        let remaining = CAPTION_DURATION - (get_time() - shown_at);
+        if remaining <= 0.0 {
+            return;
+        }
+        let alpha = (remaining / CAPTION_FADE).min(1.0) as f32;
+        let size = measure_text(name, None, FONT_SIZE as u16, 1.0);
+        let x = (screen_width() - size.width) * 0.5;
+        let y = screen_height() - MARGIN;
+        draw_text(
+            name,
+            x + 2.0,
+            y + 2.0,
+            FONT_SIZE,
+            Color::new(0.0, 0.0, 0.0, alpha),
+        );
+        draw_text(name, x, y, FONT_SIZE, Color::new(1.0, 1.0, 1.0, alpha));
+    }
+}
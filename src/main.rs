@@ -1,162 +1,1080 @@
 #![windows_subsystem = "windows"]
 
-mod color_generation;
+mod adaptive;
+mod analytics;
+mod best_of;
+mod bookmark;
+mod clock;
+mod color_script;
+mod commands;
+mod config;
+mod config_watch;
+mod console;
+mod dive_gif;
+mod errors;
+mod exp_map;
+mod export;
+mod field_cache;
+mod field_job;
 mod focus_system;
-mod math;
+mod frame_buffers;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod goto_dialog;
+mod gradient_editor;
+mod hud;
+mod julia;
+mod kiosk;
+mod locations;
+mod manual_control;
+mod minibrot;
+mod night;
+mod osc;
+mod overlays;
+mod pacing;
+mod post;
+mod poster;
+mod power;
+mod preferences;
+mod profiling;
+mod recording;
+#[cfg(feature = "remote")]
+mod remote;
+mod schedule;
+mod screensaver;
+mod session;
+#[cfg(feature = "settings-panel")]
+mod settings_panel;
+mod status;
+#[cfg(feature = "stream")]
+mod stream;
+mod telemetry;
+mod terminal;
+mod thread_pool;
+mod tour;
+mod trajectory;
+mod transition;
+#[cfg(all(feature = "tray", any(target_os = "windows", target_os = "macos")))]
+mod tray;
+mod verify;
+mod video;
+#[cfg(feature = "virtual-camera")]
+mod virtual_camera;
+mod wall;
+mod wallpaper;
+mod watermark;
+mod zoom_director;
 
-use crate::color_generation::generate_colors;
-use crate::focus_system::{FocusPointWithScore, StartPointForZoom};
-use crate::math::{ComplexNumber, get_iteration_field};
+use mandelbrot_explorer::{
+    WINDOW_HEIGHT, WINDOW_WIDTH, color, color_generation, colormaps, double_double, formula,
+    gradient, image_palette, math, normalization, precision, render,
+};
+
+use crate::adaptive::AdaptiveQuality;
+use crate::analytics::FocusAnalytics;
+use crate::best_of::BestOfGallery;
+use crate::bookmark::{load_bookmark, poll_dropped_files};
+use crate::clock::draw_clock;
+use crate::color_generation::{
+    ColorLookup, PaletteSettings, escape_tracking, generate_anaglyph_colors, generate_colors,
+    generate_escape_colors,
+};
+use crate::color_script::ColorScript;
+use crate::colormaps::Cubehelix;
+use crate::commands::{Command, keyboard_commands};
+use crate::config::CONFIG;
+use crate::config_watch::ConfigWatcher;
+use crate::console::Console;
+use crate::dive_gif::DiveGifRecorder;
+use crate::errors::{ErrorLog, catch_frame_panic, check_field, install_panic_log};
+use crate::export::{ViewMetadata, fill_image, save_screenshot};
+use crate::field_cache::{FieldCache, FieldKey};
+use crate::field_job::{FieldJob, StepClock};
+use crate::frame_buffers::FrameBuffers;
+use crate::goto_dialog::{DialogOutcome, GoToDialog};
+use crate::gradient::{Gradient, GradientStop};
+use crate::gradient_editor::GradientEditor;
+use crate::hud::draw_hud;
+use crate::julia::{compose_split, draw_split_overlay, julia_colors};
+use crate::kiosk::Kiosk;
+use crate::locations::LocationCaptions;
+use crate::manual_control::{
+    IdleReturn, ManualInput, TouchControls, julia_dive_click, keyboard_manual_input,
+};
+use crate::math::{
+    CancellationToken, ComplexNumber, Fractal, OrbitTracking, fractal_formula, get_iteration_field,
+    max_iter, set_fractal_formula, set_max_iter,
+};
+use crate::night::{NightMode, dimmed};
+use crate::normalization::{IterationRange, RangeSmoother};
+use crate::osc::OscControl;
+use crate::overlays::{draw_contours, draw_external_rays, draw_grid};
+use crate::pacing::FramePacer;
+use crate::post::{FrameInfo, PostPipeline};
+use crate::power::PowerMonitor;
+use crate::preferences::Preferences;
+use crate::profiling::profile_scope;
+use crate::recording::Recorder;
+use crate::screensaver::IdleLauncher;
+use crate::session::{Session, reseed_random};
+use crate::status::{Status, StatusStream};
+use crate::telemetry::{FrameTimings, TelemetryWriter};
+use crate::thread_pool::configure_thread_pool;
+use crate::tour::DemoTour;
+use crate::trajectory::TrajectoryRecorder;
+use crate::transition::draw_cover;
+use crate::verify::Verifier;
+use crate::wall::{VideoWall, WallRole, WallState};
+use crate::wallpaper::{WALLPAPER_FRAME_TIME, attach_to_desktop};
+use crate::watermark::Watermark;
+use crate::zoom_director::{ZoomDirector, ZoomState};
 use macroquad::prelude::*;
-use macroquad::rand::srand;
+use macroquad::rand::{gen_range, srand};
 use std::default::Default;
 
-/// Width of the window in stand-alone mode.
-const WINDOW_WIDTH: i32 = 1280;
-/// Height of the window in stand-alone mode.
-const WINDOW_HEIGHT: i32 = 720;
-
-/// The radius at which we start using the autofocus.
-const START_FOCUS_RADIUS: f64 = 0.05;
-
-/// The radius where we start with.
-const START_RADIUS: f64 = 1.5;
-
-/// The scaling factor we have for in scaling per second.
-const RADIUS_SCALING: f64 = 0.5;
-
-/// Zoom-out speed multiplier (how fast we zoom out during transition).
-const ZOOM_OUT_SPEED: f64 = 4.0;
+/// The title of the window, which is also used to find it for the wallpaper mode.
+const WINDOW_TITLE: &str = "Mandelbrot";
 
-/// Smooth time for panning between positions (in seconds).
-const PAN_SMOOTH_TIME: f64 = 0.25;
+/// The time in seconds between two automatic saves of the session.
+const SESSION_SAVE_INTERVAL: f64 = 60.0;
 
-/// The smooth time we use for the autofocus.
-const FOCUS_SMOOTH_TIME: f64 = 1.25;
-
-/// Threshold for considering the pan complete (in complex plane units).
-const PAN_COMPLETE_THRESHOLD: f64 = 0.01;
-
-/// Represents the current state of the zoom system.
-enum ZoomState {
-    /// The start zooming phase, where we do not follow a focus.
-    StartZooming,
-    /// Normal operation: zooming in and following focus.
-    ZoomingInAndFollowing,
-    /// Transitioning out: zooming back to BASE_RADIUS before jumping.
-    ZoomingOut,
-    /// Panning to new position at BASE_RADIUS before zooming in again.
-    Panning,
-}
+/// The time we sleep per frame outside the scheduled hours, to keep the power draw down.
+const SCHEDULE_SLEEP: std::time::Duration = std::time::Duration::from_millis(500);
 
 /// Sets the windows name and the required size.
 fn window_conf() -> Conf {
-    Conf {
-        window_title: "Mandelbrot".to_owned(),
+    let mut conf = Conf {
+        window_title: WINDOW_TITLE.to_owned(),
         window_width: WINDOW_WIDTH,
         window_height: WINDOW_HEIGHT,
-        fullscreen: true,
+        // A placed window goes fullscreen once it is on its output.
+        fullscreen: (CONFIG.fullscreen || CONFIG.kiosk)
+            && CONFIG.idle_start_minutes.is_none()
+            && !CONFIG.wallpaper
+            && CONFIG.window_position.is_none(),
         ..Default::default()
+    };
+    if let Some(vsync) = CONFIG.vsync {
+        conf.platform.swap_interval = Some(vsync.into());
+    }
+    if CONFIG.wallpaper {
+        conf.platform.swap_interval = Some(1);
     }
+    conf
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    let mut fullscreen = true;
+fn main() {
+    install_panic_log();
+    configure_thread_pool();
+    set_max_iter(CONFIG.max_iter);
+    if let Some(formula) = &CONFIG.formula {
+        set_fractal_formula(formula.clone());
+    }
+    match CONFIG.command.as_deref() {
+        None if CONFIG.terminal => {
+            attach_parent_console();
+            terminal::run_terminal();
+        }
+        None => macroquad::Window::from_config(window_conf(), run_explorer()),
+        Some("render") => {
+            attach_parent_console();
+            poster::render_poster_from_config();
+        }
+        Some("expmap") => {
+            attach_parent_console();
+            exp_map::render_exp_map_from_config();
+        }
+        Some("video") => {
+            attach_parent_console();
+            video::render_video_from_config();
+        }
+        Some("golden") => {
+            attach_parent_console();
+            if !render::check_golden(CONFIG.golden_update) {
+                std::process::exit(1);
+            }
+        }
+        Some(other) => {
+            attach_parent_console();
+            eprintln!(
+                "Unknown command `{other}`, available are `render`, `expmap`, `video` and `golden`."
+            );
+        }
+    }
+}
+
+/// Commands print to the console, which a windows subsystem application has to attach to explicitly.
+#[cfg(target_os = "windows")]
+fn attach_parent_console() {
+    /// Attaches to the console of the parent process.
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // SAFETY: Plain call without pointers, failure just leaves us without console.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Commands print to the console, which a windows subsystem application has to attach to explicitly.
+#[cfg(not(target_os = "windows"))]
+fn attach_parent_console() {}
+
+/// Runs the interactive explorer.
+async fn run_explorer() {
+    let mut fullscreen = CONFIG.fullscreen || CONFIG.kiosk;
+    let mut anaglyph = false;
+    let mut julia_split = CONFIG.julia_split;
+    let mut show_hud = CONFIG.hud;
+    let mut show_rays = CONFIG.rays_overlay;
+    let mut show_contours = CONFIG.contour_overlay;
+    let mut show_grid = CONFIG.grid_overlay;
+    let mut show_clock = CONFIG.clock;
+    let mut paused = false;
     srand(miniquad::date::now() as _);
-    show_mouse(false);
+    prevent_quit();
 
-    let mut center = ComplexNumber::new(-0.5, 0.0);
-    let mut radius = START_RADIUS;
-    let mut velocity = (0.0, 0.0);
-    let mut best_start_candidate = StartPointForZoom::prepare_start();
-    let mut zoom_state = ZoomState::Panning;
+    let resumed_session = if CONFIG.resume { Session::load() } else { None };
+    let (mut director, mut palette_settings) = match resumed_session {
+        Some(session) => {
+            srand(session.seed);
+            (
+                ZoomDirector::from_snapshot(session.director),
+                session.palette_settings,
+            )
+        }
+        None => {
+            let mut director = ZoomDirector::new();
+            director.set_zoom_speed(CONFIG.zoom_speed);
+            (
+                director,
+                PaletteSettings {
+                    palette: CONFIG.palette,
+                    cubehelix: CONFIG.cubehelix,
+                    ..Default::default()
+                },
+            )
+        }
+    };
+    // Gradients and image palettes are not part of the session, so the configured ones also apply when resuming.
+    palette_settings.image = CONFIG.palette_image.as_ref();
+    if CONFIG.gradient.is_some() {
+        palette_settings.gradient = CONFIG.gradient;
+    }
+    let color_settings = CONFIG.color_settings();
+    let mut color_lookup = ColorLookup::new(palette_settings, max_iter());
+    let mut gradient_editor: Option<GradientEditor> = None;
+    let mut goto_dialog: Option<GoToDialog> = None;
+    let mut console = Console::default();
+    #[cfg(feature = "settings-panel")]
+    let mut settings_panel = settings_panel::SettingsPanel::default();
+    let mut last_session_save = get_time();
+    if CONFIG.wallpaper {
+        fullscreen = false;
+        if !attach_to_desktop(WINDOW_TITLE) {
+            eprintln!("Could not attach the window to the desktop background.");
+        }
+    } else {
+        if let Some((x, y)) = CONFIG.window_position {
+            miniquad::window::set_window_position(x, y);
+            miniquad::window::set_fullscreen(fullscreen);
+        }
+        show_mouse(!fullscreen);
+    }
+    let mut kiosk = CONFIG.kiosk.then(Kiosk::new);
+    let mut idle_launcher = CONFIG.idle_start_minutes.and_then(|minutes| {
+        IdleLauncher::new(minutes)
+            .inspect_err(|err| eprintln!("Not waiting for the system to become idle: {err}"))
+            .ok()
+    });
+    // Lets the remote controls abandon the computation of a view they are about to leave.
+    let cancel = CancellationToken::default();
+    #[cfg(all(feature = "tray", any(target_os = "windows", target_os = "macos")))]
+    let tray_controls = tray::TrayControls::new();
+    #[cfg(feature = "remote")]
+    let remote_control =
+        remote::RemoteControl::start(&CONFIG.remote_bind, CONFIG.remote_port, cancel.clone());
+    #[cfg(feature = "stream")]
+    let mut frame_stream = CONFIG.stream_port.and_then(stream::FrameStream::start);
+    #[cfg(feature = "virtual-camera")]
+    let virtual_camera = CONFIG
+        .virtual_camera
+        .as_deref()
+        .map(virtual_camera::VirtualCamera::start);
+    let osc_control = CONFIG
+        .osc_port
+        .and_then(|port| OscControl::start(port, cancel.clone()));
+    let mut config_watcher = ConfigWatcher::new();
+    let mut demo_tour = CONFIG.demo_tour.then(DemoTour::default);
+    // The tour introduces its places with the captions.
+    let mut location_captions =
+        (CONFIG.location_captions || CONFIG.demo_tour).then(LocationCaptions::default);
+    // The reference iterates z² + c, which other formulas do not.
+    let mut verifier = (CONFIG.verify_pixels > 0 && fractal_formula().is_quadratic())
+        .then(|| Verifier::new(CONFIG.verify_pixels));
+    let mut touch_controls = TouchControls::default();
+    let mut idle_return = IdleReturn::default();
+    let mut recorder: Option<Recorder> = None;
+    let mut dive_gif_recorder = CONFIG.dive_gif.then(DiveGifRecorder::default);
+    let mut best_of = (CONFIG.best_of > 0).then(BestOfGallery::default);
+    let mut trajectory = TrajectoryRecorder::default();
+    #[cfg(feature = "gamepad")]
+    let mut gamepad_controls = gamepad::GamepadControls::new();
+
+    let mut power_monitor = PowerMonitor::new();
+    let mut frame_pacer = FramePacer::default();
+    let mut adaptive_quality = AdaptiveQuality::default();
+    #[cfg(feature = "profiler")]
+    let mut profiler = profiling::Profiler::new();
+    let mut error_log = ErrorLog::default();
+    let mut video_wall = VideoWall::from_config();
+    let mut telemetry = CONFIG.telemetry.as_ref().and_then(|path| {
+        TelemetryWriter::create(std::path::Path::new(path))
+            .inspect_err(|err| eprintln!("Could not create the telemetry file `{path}`: {err}"))
+            .ok()
+    });
+    let mut status_stream = CONFIG.status_json.then(StatusStream::new);
+    let mut analytics = CONFIG
+        .analytics
+        .as_ref()
+        .map(|path| FocusAnalytics::new(path.into(), get_time()));
+    let watermark = Watermark::from_config();
+    let mut night_mode = NightMode::new();
+    let mut step_clock = StepClock::new();
+    // The field computing ahead for the view expected in the next frame.
+    let mut prefetched: Option<(FieldKey, FieldJob)> = None;
+    // Commands carried into the next frame, at first those of the bookmark.
+    let mut deferred_commands = CONFIG.bookmark.as_ref().map_or_else(Vec::new, |path| {
+        load_bookmark(std::path::Path::new(path)).unwrap_or_else(|err| {
+            eprintln!("Could not open the bookmark `{path}`: {err}");
+            Vec::new()
+        })
+    });
+    let mut range_smoother = RangeSmoother::default();
+    let mut field_cache = FieldCache::new(CONFIG.field_cache);
+    let mut color_script = CONFIG.color_script.as_ref().and_then(|path| {
+        ColorScript::load(std::path::Path::new(path), CONFIG.color_script_per_pixel)
+            .inspect_err(|err| eprintln!("Could not load the color script `{path}`: {err}"))
+            .ok()
+    });
+    let mut num_array = get_iteration_field(
+        director.fractal(),
+        &director.viewport(),
+        &CONFIG.iteration_settings(),
+        power_monitor.render_scale(),
+        &CancellationToken::default(),
+    )
+    .and_then(|field| check_field(field).ok())
+    .unwrap_or_else(|| vec![0; (WINDOW_WIDTH * WINDOW_HEIGHT) as usize]);
+    // Only filled while the coloring needs more than the iteration counts.
+    let mut escape_array = Vec::new();
+    let mut coloring = CONFIG.coloring;
+    let mut shading = CONFIG.shading;
 
     let mut image = Image::gen_image_color(WINDOW_WIDTH as u16, WINDOW_HEIGHT as u16, BLANK);
-    let texture = Texture2D::from_image(&image);
+    let mut frame_buffers = FrameBuffers::new(&image);
+    let post_pipeline = PostPipeline::from_config(WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32);
+    let mut previous_radius = director.radius();
+    let mut saved_preferences = Preferences {
+        palette: palette_settings.palette,
+        coloring,
+        shading,
+        zoom_speed: director.zoom_speed(),
+        hud: show_hud,
+        fullscreen,
+    };
+
+    'main: loop {
+        #[cfg(feature = "profiler")]
+        profiler.new_frame();
+        let work_start = get_time();
+        let mut timings = FrameTimings {
+            total: get_frame_time() as f64,
+            ..Default::default()
+        };
+        power_monitor.poll();
+        // While typing the keys belong to the dialog or the console.
+        let typing = goto_dialog.is_some() || console.is_open();
+        let mut commands = if !typing {
+            keyboard_commands()
+        } else {
+            Vec::new()
+        };
+        if is_quit_requested() || kiosk.as_mut().is_some_and(Kiosk::input_seen) {
+            commands.push(Command::Quit);
+        }
+        #[cfg(all(feature = "tray", any(target_os = "windows", target_os = "macos")))]
+        tray_controls.poll(&mut commands);
+        #[cfg(feature = "remote")]
+        if let Some(remote_control) = remote_control.as_ref() {
+            remote_control.poll(&mut commands);
+        }
+        if let Some(osc_control) = osc_control.as_ref() {
+            osc_control.poll(&mut commands);
+        }
+        config_watcher.poll(&mut commands);
+        poll_dropped_files(&mut commands);
+        commands.append(&mut deferred_commands);
+        if let Some(tour) = demo_tour.as_mut() {
+            tour.poll(director.zoom_state(), &mut commands);
+        }
+        // Cancellations up to here belong to commands we already have.
+        cancel.reset();
+        #[allow(unused_mut)]
+        let mut manual_input = if !typing {
+            keyboard_manual_input()
+        } else {
+            ManualInput::default()
+        };
+        #[cfg(feature = "gamepad")]
+        {
+            manual_input = manual_input.combine(gamepad_controls.poll(&mut commands));
+        }
 
-    loop {
-        if is_key_pressed(KeyCode::Escape) {
-            break;
+        if CONFIG
+            .duration
+            .is_some_and(|duration| get_time() >= duration)
+        {
+            commands.push(Command::Quit);
         }
-        if is_key_pressed(KeyCode::F11) {
-            fullscreen = !fullscreen;
-            show_mouse(!fullscreen);
-            miniquad::window::set_fullscreen(fullscreen);
+        if let Some(schedule) = CONFIG.schedule
+            && !schedule.is_active_now()
+        {
+            if commands.contains(&Command::Quit) {
+                break 'main;
+            }
+            clear_background(BLACK);
+            std::thread::sleep(SCHEDULE_SLEEP);
+            next_frame().await;
+            continue;
         }
 
-        let delta_time = get_frame_time() as f64;
-        let num_array = get_iteration_field(center, radius);
+        if let Some(launcher) = idle_launcher.as_mut()
+            && !launcher.update()
+        {
+            if commands.contains(&Command::Quit) {
+                break 'main;
+            }
+            clear_background(BLACK);
+            next_frame().await;
+            continue;
+        }
 
-        // State machine logic
-        match zoom_state {
-            ZoomState::StartZooming => {
-                radius *= RADIUS_SCALING.powf(delta_time);
-                if radius <= START_FOCUS_RADIUS {
-                    radius = START_FOCUS_RADIUS;
-                    zoom_state = ZoomState::ZoomingInAndFollowing;
+        if goto_dialog.is_none() && is_key_pressed(KeyCode::GraveAccent) {
+            console.toggle();
+        }
+        console.update(&mut commands);
+        if let Some(dialog) = goto_dialog.as_mut() {
+            match dialog.update() {
+                DialogOutcome::Editing => {}
+                DialogOutcome::Cancelled => goto_dialog = None,
+                DialogOutcome::GoTo(center, radius) => {
+                    commands.push(Command::GoTo(center, ComplexNumber::default(), radius));
+                    goto_dialog = None;
                 }
             }
-            ZoomState::ZoomingInAndFollowing => {
-                // compute the target center we want to approach
-                let focus = FocusPointWithScore::new(&num_array);
-                let target_center =
-                    focus.get_absolute_focus_in_complex_number_pane(center, radius);
-
-                // smoothly move center towards target_center using the existing ComplexNumber smoothing
-                center.smooth_damp_to(target_center, &mut velocity, FOCUS_SMOOTH_TIME, delta_time);
-
-                // Check if we need to transition out
-                if radius < 1e-13 {
-                    velocity = (0.0, 0.0);
-                    // In zooming out we search our new point.
-                    best_start_candidate.reset_iteration();
-                    zoom_state = ZoomState::ZoomingOut
+        }
+        if !typing {
+            if is_key_pressed(KeyCode::G) {
+                goto_dialog = Some(GoToDialog::new());
+            }
+            #[cfg(feature = "settings-panel")]
+            if is_key_pressed(KeyCode::Tab) {
+                settings_panel.toggle();
+                show_mouse(settings_panel.is_open() || gradient_editor.is_some() || !fullscreen);
+            }
+            #[cfg(feature = "profiler")]
+            if is_key_pressed(KeyCode::F3) {
+                profiler.toggle();
+            }
+            if is_key_pressed(KeyCode::F11) {
+                fullscreen = !fullscreen;
+                show_mouse(!fullscreen);
+                miniquad::window::set_fullscreen(fullscreen);
+            }
+            if is_key_pressed(KeyCode::A) {
+                anaglyph = !anaglyph;
+            }
+            if is_key_pressed(KeyCode::J) {
+                julia_split = !julia_split;
+            }
+            if is_key_pressed(KeyCode::H) {
+                show_hud = !show_hud;
+            }
+            if is_key_pressed(KeyCode::X) {
+                show_rays = !show_rays;
+            }
+            if is_key_pressed(KeyCode::T) {
+                show_contours = !show_contours;
+            }
+            if is_key_pressed(KeyCode::K) {
+                show_grid = !show_grid;
+            }
+            if is_key_pressed(KeyCode::O) {
+                show_clock = !show_clock;
+            }
+            if is_key_pressed(KeyCode::E) {
+                gradient_editor = match gradient_editor {
+                    Some(_) => None,
+                    None => {
+                        let gradient = palette_settings.gradient.unwrap_or_else(|| {
+                            let stops: Vec<GradientStop> = (0..4)
+                                .map(|i| {
+                                    let position = i as f32 / 4.0;
+                                    let color = palette_settings.sample(position);
+                                    GradientStop { position, color }
+                                })
+                                .collect();
+                            Gradient::new(&stops)
+                        });
+                        Some(GradientEditor::new(gradient))
+                    }
+                };
+                show_mouse(gradient_editor.is_some() || !fullscreen);
+            }
+        }
+        if let Some(editor) = gradient_editor.as_mut() {
+            editor.update();
+            palette_settings.gradient = Some(editor.gradient());
+        }
+
+        if gradient_editor.is_none()
+            && director.zoom_state() == ZoomState::Manual
+            && director.fractal() == Fractal::Mandelbrot
+            && let Some(c) = julia_dive_click(&director.viewport(), julia_split)
+        {
+            commands.push(Command::JuliaDive(c));
+        }
+
+        #[cfg(feature = "settings-panel")]
+        settings_panel.update(
+            &settings_panel::PanelState {
+                palette: palette_settings.palette,
+                hue_offset: palette_settings.hue_offset,
+                coloring,
+                shading,
+                zoom_speed: director.zoom_speed(),
+                max_iter: max_iter(),
+                focus: director.focus_parameters(),
+                zoom_state: director.zoom_state(),
+                paused,
+                recording: recorder.is_some(),
+            },
+            &mut commands,
+        );
+
+        let mut take_screenshot = false;
+        let commanded = !commands.is_empty();
+        for command in commands {
+            match command {
+                Command::TogglePause => paused = !paused,
+                Command::SetPaused(value) => paused = value,
+                Command::ToggleManual => director.toggle_manual(),
+                Command::NextTarget => director.skip_to_next_target(),
+                Command::Screenshot => take_screenshot = true,
+                Command::ExportTrajectory => trajectory.export(),
+                Command::ToggleRecording | Command::SetRecording(_) => {
+                    let start = match command {
+                        Command::SetRecording(value) => value,
+                        _ => recorder.is_none(),
+                    };
+                    match recorder.take() {
+                        Some(running) if !start => running.stop(),
+                        Some(running) => recorder = Some(running),
+                        None if start => {
+                            recorder = Recorder::start(CONFIG.record_format)
+                                .inspect_err(|err| {
+                                    eprintln!("Could not start recording: {err}");
+                                    console.log(format!("Could not start recording: {err}"));
+                                })
+                                .ok()
+                        }
+                        None => {}
+                    }
+                }
+                Command::NextPalette => {
+                    palette_settings.palette = palette_settings.palette.next();
+                    palette_settings.image = None;
+                    palette_settings.gradient = None;
+                }
+                Command::SetPalette(value) => {
+                    palette_settings.palette = value;
+                    palette_settings.image = None;
+                    palette_settings.gradient = None;
                 }
-                radius *= RADIUS_SCALING.powf(delta_time);
-            }
-            ZoomState::ZoomingOut => {
-                best_start_candidate.try_improve();
-                radius *= RADIUS_SCALING.powf(-delta_time * ZOOM_OUT_SPEED);
-                // Check if we've reached START_RADIUS
-                if radius >= START_RADIUS {
-                    radius = START_RADIUS;
-                    zoom_state = ZoomState::Panning
+                Command::SetHueOffset(value) => palette_settings.hue_offset = value,
+                Command::NextColoring => coloring = coloring.next(),
+                Command::SetColoring(value) => coloring = value,
+                Command::NextShading => shading = shading.next(),
+                Command::SetShading(value) => shading = value,
+                Command::SetSeed(seed) => srand(seed),
+                Command::AdjustPalette(parameter, delta) => {
+                    palette_settings.adjust(parameter, delta)
                 }
+                Command::SetZoomSpeed(value) => director.set_zoom_speed(value),
+                Command::SetMaxIter(value) => set_max_iter(value),
+                Command::SetFocusParameter(parameter, value) => {
+                    director.set_focus_parameter(parameter, value)
+                }
+                Command::GoTo(center, center_low, radius) => {
+                    director.go_to(center, center_low, radius)
+                }
+                Command::JuliaDive(c) => director.start_julia_dive(c),
+                Command::Quit => break 'main,
             }
-            ZoomState::Panning => {
-                // Smooth damp center towards next_center
-                center.smooth_damp_to(
-                    best_start_candidate.starting_point(),
-                    &mut velocity,
-                    PAN_SMOOTH_TIME,
-                    delta_time,
-                );
+        }
 
-                let dist_sq = (center - best_start_candidate.starting_point()).sq_mag();
-                if dist_sq < PAN_COMPLETE_THRESHOLD * PAN_COMPLETE_THRESHOLD {
-                    center = best_start_candidate.starting_point();
-                    zoom_state = ZoomState::StartZooming;
+        if let Some(wall) = video_wall.as_mut()
+            && wall.role() == WallRole::Follower
+            && let Some(state) = wall.receive()
+        {
+            let (center, radius) = state.tile_view(CONFIG.wall_grid, CONFIG.wall_tile);
+            director.follow(state.fractal, center, radius, state.rotation);
+            // Image palettes and gradients come from our own config.
+            palette_settings = PaletteSettings {
+                image: palette_settings.image,
+                gradient: palette_settings.gradient,
+                ..state.palette_settings
+            };
+            coloring = state.coloring;
+            shading = state.shading;
+        }
+
+        let width = WINDOW_WIDTH as usize;
+        // The split view only shows the middle half of the Mandelbrot field.
+        let columns = if julia_split {
+            width / 4..width / 4 + width / 2
+        } else {
+            0..width
+        };
+        // The rays need the final orbit values, which the plain escape provides.
+        let tracking = color_script
+            .as_ref()
+            .and_then(ColorScript::tracking)
+            .or(escape_tracking(coloring, shading, &color_settings))
+            .or(show_rays.then(OrbitTracking::default));
+        if paused {
+            step_clock.restart();
+        } else {
+            let delta_time = step_clock.step();
+            let render_scale = power_monitor.render_scale() * adaptive_quality.scale();
+            timings.render_scale = render_scale;
+            let compute_start = get_time();
+            let key = FieldKey {
+                fractal: director.fractal(),
+                viewport: director.viewport(),
+                scale: render_scale,
+                tracking,
+                max_iter: max_iter(),
+            };
+            let cached = field_cache.get(&key);
+            // The field started at the end of the last frame serves if the view went where it was expected.
+            let job = match prefetched.take() {
+                Some((prefetched_key, job)) if cached.is_none() && key.matches(&prefetched_key) => {
+                    Some(job)
+                }
+                Some((_, job)) => {
+                    job.abandon(&cancel);
+                    None
+                }
+                None => None,
+            };
+            let computed = if cached.is_some() {
+                Ok(cached)
+            } else {
+                let listen = goto_dialog.is_none() && !console.is_open();
+                job.unwrap_or_else(|| FieldJob::spawn(key, &cancel, power_monitor.pool()))
+                    .finish(
+                        frame_buffers.front(),
+                        &cancel,
+                        &mut deferred_commands,
+                        listen,
+                    )
+                    .await
+                    .inspect(|computed| {
+                        if let Some(field) = computed {
+                            field_cache.insert(key, field);
+                        }
+                    })
+            };
+            timings.compute = get_time() - compute_start;
+            match computed {
+                // A cancelled field keeps the last frame, the next one starts from the new target.
+                Ok(None) => {}
+                // A failed one as well, so the display keeps running.
+                Err(err) => error_log.report(&err),
+                Ok(Some((iterations, escapes))) => {
+                    num_array = iterations;
+                    escape_array = escapes;
+                    if let Some(verifier) = verifier.as_mut() {
+                        verifier.check(
+                            director.fractal(),
+                            &director.viewport(),
+                            &num_array,
+                            columns.clone(),
+                        );
+                    }
+                    let previous_state = director.zoom_state();
+                    let focus_start = get_time();
+                    let focused = catch_frame_panic(|| {
+                        profile_scope!("focus");
+                        director.update(&num_array, delta_time);
+                    });
+                    // The director may be half updated, so the dive starts over.
+                    if let Err(err) = focused {
+                        error_log.report(&err);
+                        let zoom_speed = director.zoom_speed();
+                        let focus = director.focus_parameters();
+                        director = ZoomDirector::new();
+                        director.set_zoom_speed(zoom_speed);
+                        director.set_focus_parameters(focus);
+                    }
+                    timings.focus = get_time() - focus_start;
+                    if CONFIG.cubehelix_per_dive
+                        && previous_state != ZoomState::StartZooming
+                        && director.zoom_state() == ZoomState::StartZooming
+                    {
+                        palette_settings.cubehelix = Cubehelix::random(gen_range);
+                    }
                 }
             }
+            trajectory.record(
+                director.zoom_state(),
+                director.fractal(),
+                director.center(),
+                director.radius(),
+                get_time(),
+                CONFIG.dive_trajectories,
+            );
+            director.steer(manual_input, delta_time);
+            let touched = touch_controls.poll(&director.viewport());
+            if let Some((center, radius)) = touched {
+                director.set_manual_view(center, radius);
+            }
+            let active = !manual_input.is_idle() || touched.is_some() || commanded;
+            // Wall followers stay in manual mode, the director of the wall moves them.
+            if director.zoom_state() == ZoomState::Manual
+                && video_wall
+                    .as_ref()
+                    .is_none_or(|wall| wall.role() != WallRole::Follower)
+                && idle_return.update(active, delta_time)
+            {
+                director.resume_automatic();
+            }
+            // The next field computes while this frame is colored and uploaded. Commands or a new render scale
+            // may still move the view, then it is abandoned.
+            let next_key = FieldKey {
+                fractal: director.fractal(),
+                viewport: director.viewport(),
+                scale: power_monitor.render_scale() * adaptive_quality.scale(),
+                tracking,
+                max_iter: max_iter(),
+            };
+            if !field_cache.contains(&next_key) {
+                prefetched = Some((
+                    next_key,
+                    FieldJob::spawn(next_key, &cancel, power_monitor.pool()),
+                ));
+            }
+        }
+
+        if let Some(wall) = video_wall.as_ref()
+            && wall.role() == WallRole::Director
+        {
+            wall.send(&WallState {
+                time: get_time(),
+                fractal: director.fractal(),
+                center: director.center(),
+                radius: director.radius(),
+                rotation: director.rotation(),
+                palette_settings,
+                coloring,
+                shading,
+            });
+        }
+
+        let preferences = Preferences {
+            palette: palette_settings.palette,
+            coloring,
+            shading,
+            zoom_speed: director.zoom_speed(),
+            hud: show_hud,
+            fullscreen,
         };
+        // The wallpaper and kiosk modes force their own window, which must not become the preference.
+        if preferences != saved_preferences && !CONFIG.wallpaper && !CONFIG.kiosk {
+            saved_preferences = preferences;
+            if let Err(err) = preferences.save() {
+                eprintln!("Could not save the preferences: {err}");
+            }
+        }
+
+        if get_time() - last_session_save > SESSION_SAVE_INTERVAL {
+            last_session_save = get_time();
+            save_session(&director, palette_settings);
+        }
+
+        #[cfg(feature = "remote")]
+        if let Some(remote_control) = remote_control.as_ref() {
+            remote_control.publish(remote::RemoteStatus {
+                center: director.center(),
+                radius: director.radius(),
+                palette: palette_settings.palette,
+                paused,
+            });
+        }
+
+        let color_start = get_time();
+        color_lookup.update(palette_settings, max_iter());
+        // The lookup of this frame, the Julia half of the split screen keeps the plain one.
+        let stretched_lookup = CONFIG
+            .normalize_iterations
+            .then(|| {
+                range_smoother.update(
+                    IterationRange::of_field(&num_array, color_lookup.max_iter()),
+                    get_frame_time() as f64,
+                    CONFIG.normalize_smooth_time,
+                )
+            })
+            .flatten()
+            .map(|range| color_lookup.stretched(range));
+        let frame_lookup = stretched_lookup.as_ref().unwrap_or(&color_lookup);
+        let script_lookup = color_script
+            .as_mut()
+            .and_then(|script| script.lookup(frame_lookup));
+        let frame_lookup = script_lookup.as_ref().unwrap_or(frame_lookup);
+        let script_colors = color_script
+            .as_ref()
+            .filter(|_| !anaglyph && escape_array.len() == num_array.len())
+            .and_then(|script| script.colors(&escape_array));
+        let mut color_array = if let Some(colors) = script_colors {
+            colors
+        } else if anaglyph {
+            generate_anaglyph_colors(
+                &num_array,
+                WINDOW_WIDTH as usize,
+                frame_lookup,
+                &color_settings,
+            )
+        } else if tracking.is_some() && escape_array.len() == num_array.len() {
+            generate_escape_colors(
+                &escape_array,
+                WINDOW_WIDTH as usize,
+                frame_lookup,
+                coloring,
+                shading,
+                &color_settings,
+            )
+        } else {
+            generate_colors(
+                &num_array,
+                WINDOW_WIDTH as usize,
+                frame_lookup,
+                &color_settings,
+            )
+        };
+        if show_rays && escape_array.len() == color_array.len() {
+            draw_external_rays(&mut color_array, &escape_array, WINDOW_WIDTH as usize);
+        }
+        let color_array = if julia_split {
+            compose_split(
+                &color_array,
+                &julia_colors(director.center(), &color_lookup, &color_settings),
+            )
+        } else {
+            color_array
+        };
+
+        timings.color = get_time() - color_start;
+
+        if let Some(recorder) = dive_gif_recorder.as_mut()
+            && !paused
+        {
+            recorder.capture(director.zoom_state(), &color_array);
+        }
+
+        let upload_start = get_time();
+        {
+            profile_scope!("upload");
+            fill_image(&mut image, &color_array);
+            frame_buffers.present(&image);
+        }
+        timings.upload = get_time() - upload_start;
+
+        if let Some(recorder) = recorder.as_mut()
+            && !paused
+        {
+            recorder.push_frame(&image.bytes);
+        }
+
+        #[cfg(feature = "stream")]
+        if let Some(frame_stream) = frame_stream.as_mut() {
+            frame_stream.push(&image.bytes, get_time());
+        }
 
-        let color_array = generate_colors(&num_array);
+        #[cfg(feature = "virtual-camera")]
+        if let Some(camera) = virtual_camera.as_ref() {
+            camera.push_frame(&image.bytes);
+        }
+
+        if let Some(gallery) = best_of.as_mut()
+            && !paused
+        {
+            gallery.observe(&director, palette_settings.palette, &image, get_time());
+        }
 
-        image.update(&color_array);
-        texture.update(&image);
+        if take_screenshot {
+            let metadata = ViewMetadata {
+                fractal: director.fractal(),
+                viewport: director.viewport(),
+                palette: palette_settings.palette,
+            };
+            match save_screenshot(&image, &metadata) {
+                Ok(path) => println!("Saved screenshot to {}", path.display()),
+                Err(err) => eprintln!("Could not save screenshot: {err}"),
+            }
+        }
 
-        draw_texture_ex(
-            &texture,
-            0.0,
-            0.0,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(Vec2::new(screen_width(), screen_height())),
-                ..Default::default()
+        if let Some(captions) = location_captions.as_mut() {
+            captions.update(director.fractal(), director.center(), director.radius());
+        }
+
+        let presented = post_pipeline.apply(
+            frame_buffers.front(),
+            &FrameInfo {
+                previous_radius,
+                radius: director.radius(),
+                time: get_time(),
             },
         );
+        previous_radius = director.radius();
+        let brightness = night_mode.update(get_frame_time() as f64);
+        {
+            profile_scope!("draw");
+            draw_texture_ex(
+                &presented,
+                0.0,
+                0.0,
+                dimmed(WHITE, brightness),
+                DrawTextureParams {
+                    dest_size: Some(Vec2::new(screen_width(), screen_height())),
+                    ..Default::default()
+                },
+            );
+            if show_contours {
+                draw_contours(&num_array, width, columns.clone());
+            }
+            if let Some(verifier) = verifier.as_ref() {
+                verifier.draw(width, WINDOW_HEIGHT as usize, columns.clone());
+            }
+            if show_grid {
+                draw_grid(&director.viewport(), columns);
+            }
+            if julia_split {
+                draw_split_overlay();
+            }
+            if let Some(cover) = director.cover() {
+                draw_cover(cover);
+            }
+            if let Some(captions) = location_captions.as_ref() {
+                captions.draw();
+            }
+            if let Some(watermark) = watermark.as_ref() {
+                watermark.draw();
+            }
+            if show_clock {
+                draw_clock(brightness);
+            }
+            if show_hud {
+                draw_hud(
+                    director.fractal(),
+                    director.center(),
+                    director.radius(),
+                    brightness,
+                );
+            }
+            if let Some(editor) = gradient_editor.as_ref() {
+                editor.draw();
+            }
+            if let Some(dialog) = goto_dialog.as_ref() {
+                dialog.draw();
+            }
+            console.draw();
+            #[cfg(feature = "settings-panel")]
+            settings_panel.draw();
+            if let Some(recorder) = recorder.as_ref() {
+                recorder.draw_indicator();
+            }
+            #[cfg(feature = "profiler")]
+            profiler.draw();
+        }
+
+        adaptive_quality.update(get_time() - work_start);
+        timings.radius = director.radius();
+        if let Some(writer) = telemetry.as_mut()
+            && let Err(err) = writer.write(get_time(), &timings)
+        {
+            eprintln!("Could not write the telemetry, stopping it: {err}");
+            telemetry = None;
+        }
+        if let Some(stream) = status_stream.as_mut()
+            && let Err(err) = stream.emit(Status {
+                time: get_time(),
+                center: director.center(),
+                radius: director.radius(),
+                score: director.focus_score(),
+                fps: get_fps(),
+                state: director.zoom_state().name(),
+                paused,
+            })
+        {
+            eprintln!("Could not write the status, stopping it: {err}");
+            status_stream = None;
+        }
+        if let Some(collector) = analytics.as_mut()
+            && let Err(err) = collector.observe(&mut director, get_time())
+        {
+            eprintln!("Could not write the analytics, stopping them: {err}");
+            analytics = None;
+        }
+        // The slowest of the frame rate caps wins.
+        let mut frame_time = power_monitor.frame_time().unwrap_or(0.0);
+        if CONFIG.fps_cap > 0.0 {
+            frame_time = frame_time.max(1.0 / CONFIG.fps_cap);
+        }
+        if CONFIG.wallpaper {
+            frame_time = frame_time.max(WALLPAPER_FRAME_TIME);
+        }
+        frame_pacer.wait(frame_time);
 
         next_frame().await;
     }
+
+    if let Some(recorder) = recorder {
+        recorder.stop();
+    }
+    if let Some(gallery) = best_of {
+        gallery.finish();
+    }
+    if let Some(Err(err)) = telemetry.as_mut().map(TelemetryWriter::flush) {
+        eprintln!("Could not write the telemetry: {err}");
+    }
+    if let Some(Err(err)) = analytics.map(|collector| collector.write(get_time())) {
+        eprintln!("Could not write the analytics: {err}");
+    }
+    save_session(&director, palette_settings);
+}
+
+/// Saves the session and reports failures.
+fn save_session(director: &ZoomDirector, palette_settings: PaletteSettings) {
+    let session = Session {
+        director: director.snapshot(),
+        palette_settings,
+        seed: reseed_random(),
+    };
+    if let Err(err) = session.save() {
+        eprintln!("Could not save session: {err}");
+    }
 }
@@ -0,0 +1,160 @@
+//! Gathers the steering input for the manual exploration mode.
+
+use crate::config::CONFIG;
+use crate::math::{ComplexNumber, Viewport};
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+/// The steering input of one frame in manual mode.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ManualInput {
+    /// Horizontal pan speed in [-1, 1], positive moves the view to the right.
+    pub pan_x: f64,
+    /// Vertical pan speed in [-1, 1], positive moves the view down.
+    pub pan_y: f64,
+    /// Zoom speed in [-1, 1], positive zooms in.
+    pub zoom: f64,
+    /// Discrete zoom steps (mouse wheel notches), positive zooms in.
+    pub zoom_steps: f64,
+}
+
+impl ManualInput {
+    /// Combines two inputs, clamping the continuous values to their range.
+    #[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+    pub fn combine(self, other: ManualInput) -> ManualInput {
+        ManualInput {
+            pan_x: (self.pan_x + other.pan_x).clamp(-1.0, 1.0),
+            pan_y: (self.pan_y + other.pan_y).clamp(-1.0, 1.0),
+            zoom: (self.zoom + other.zoom).clamp(-1.0, 1.0),
+            zoom_steps: self.zoom_steps + other.zoom_steps,
+        }
+    }
+
+    /// Whether the input steers at all.
+    pub fn is_idle(self) -> bool {
+        self.pan_x == 0.0 && self.pan_y == 0.0 && self.zoom == 0.0 && self.zoom_steps == 0.0
+    }
+}
+
+/// Hands manual mode back to the automatic once the user left it alone for the configured time, so an explorer
+/// on display returns to its dives after a visitor played with it.
+#[derive(Debug, Default)]
+pub struct IdleReturn {
+    /// The seconds since the last input in manual mode.
+    idle_time: f64,
+}
+
+impl IdleReturn {
+    /// Advances the time without input, which any input resets. Returns true once it reaches the configured time.
+    pub fn update(&mut self, active: bool, delta_time: f64) -> bool {
+        if active || CONFIG.manual_idle_return <= 0.0 {
+            self.idle_time = 0.0;
+            return false;
+        }
+        self.idle_time += delta_time;
+        self.idle_time >= CONFIG.manual_idle_return
+    }
+}
+
+/// Reads the arrow keys, plus and minus and the mouse wheel.
+pub fn keyboard_manual_input() -> ManualInput {
+    let axis = |negative: &[KeyCode], positive: &[KeyCode]| {
+        let down = |keys: &[KeyCode]| keys.iter().any(|k| is_key_down(*k)) as i32 as f64;
+        down(positive) - down(negative)
+    };
+
+    ManualInput {
+        pan_x: axis(&[KeyCode::Left], &[KeyCode::Right]),
+        pan_y: axis(&[KeyCode::Up], &[KeyCode::Down]),
+        zoom: axis(
+            &[KeyCode::Minus, KeyCode::KpSubtract],
+            &[KeyCode::Equal, KeyCode::KpAdd],
+        ),
+        zoom_steps: mouse_wheel().1.signum() as f64,
+    }
+}
+
+/// Gets the parameter for a Julia dive, if the screen was clicked with shift held down. In the split screen only
+/// the Mandelbrot half on the left can be clicked, it shows the middle of the view.
+pub fn julia_dive_click(view: &Viewport, split: bool) -> Option<ComplexNumber> {
+    let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+    if !shift || !is_mouse_button_pressed(MouseButton::Left) {
+        return None;
+    }
+    let mut position = Vec2::from(mouse_position());
+    if split {
+        if position.x >= screen_width() * 0.5 {
+            return None;
+        }
+        position.x += screen_width() * 0.25;
+    }
+    Some(screen_to_complex(view, position))
+}
+
+/// Converts a position on the screen into the point of the complex number pane the window view shows there.
+pub fn screen_to_complex(view: &Viewport, screen_pos: Vec2) -> ComplexNumber {
+    screen_offset(view, screen_pos, view.radius) + view.center
+}
+
+/// The offset of a screen position from the screen center in the complex number pane for the given radius,
+/// turned by the rotation of the view.
+fn screen_offset(view: &Viewport, screen_pos: Vec2, radius: f64) -> ComplexNumber {
+    let aspect = view.width as f64 / view.height as f64;
+    ComplexNumber::new(
+        (screen_pos.x / screen_width() - 0.5) as f64 * 2.0 * aspect * radius,
+        (screen_pos.y / screen_height() - 0.5) as f64 * 2.0 * radius,
+    ) * view.rotation
+}
+
+/// Turns touch input into view changes: one finger drags the view, two fingers pinch zoom around their midpoint.
+#[derive(Default)]
+pub struct TouchControls {
+    /// The finger positions of the last frame, indexed by touch id.
+    last_positions: HashMap<u64, Vec2>,
+}
+
+impl TouchControls {
+    /// Evaluates the touches of this frame and returns the new center and radius, if a gesture moved the window
+    /// view.
+    pub fn poll(&mut self, view: &Viewport) -> Option<(ComplexNumber, f64)> {
+        let current: HashMap<u64, Vec2> = touches()
+            .into_iter()
+            .filter(|t| !matches!(t.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+            .map(|t| (t.id, t.position))
+            .collect();
+
+        // Only fingers that were down in both frames contribute to the gesture.
+        let mut pairs: Vec<(Vec2, Vec2)> = current
+            .iter()
+            .filter_map(|(id, pos)| Some((*self.last_positions.get(id)?, *pos)))
+            .collect();
+        pairs.truncate(2);
+        self.last_positions = current;
+
+        let (previous_anchor, current_anchor, zoom_factor) = match pairs.as_slice() {
+            [(previous, current)] => (*previous, *current, 1.0),
+            [(previous_a, current_a), (previous_b, current_b)] => {
+                let previous_distance = previous_a.distance(*previous_b);
+                let current_distance = current_a.distance(*current_b);
+                if previous_distance < 1.0 || current_distance < 1.0 {
+                    return None;
+                }
+                (
+                    (*previous_a + *previous_b) * 0.5,
+                    (*current_a + *current_b) * 0.5,
+                    (previous_distance / current_distance) as f64,
+                )
+            }
+            _ => return None,
+        };
+        if previous_anchor == current_anchor && zoom_factor == 1.0 {
+            return None;
+        }
+
+        // The point under the previous anchor has to end up under the current anchor with the new radius.
+        let grabbed_point = screen_to_complex(view, previous_anchor);
+        let new_radius = view.radius * zoom_factor;
+        let new_center = grabbed_point - screen_offset(view, current_anchor, new_radius);
+        Some((new_center, new_radius))
+    }
+}
@@ -1,25 +1,340 @@
 //! Contains the real mandelbrot caclulations.
 
+use crate::double_double::{DoubleDouble, try_iteration_field_double_double};
+use crate::fixed::try_iteration_field_fixed;
+use crate::formula::Formula;
+use crate::perturbation::try_iteration_field_perturbed;
+use crate::precision::PrecisionTier;
+use crate::profile_scope;
 use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
 use rayon::prelude::*;
-use std::ops::{AddAssign, Sub};
+use std::ops::{Add, AddAssign, Div, Mul, RangeInclusive, Sub};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// The iteration limit the explorer starts with.
+pub const DEFAULT_MAX_ITER: u16 = 100;
+
+/// The iteration limits that can be set at runtime. The counts are stored as u16, and beyond the upper end a frame
+/// takes seconds.
+pub const MAX_ITER_RANGE: RangeInclusive<u16> = 10..=10_000;
+
+/// The maximum amount of iterations we do for a complex number to check for divergence, see [`max_iter`].
+static MAX_ITER: AtomicU16 = AtomicU16::new(DEFAULT_MAX_ITER);
 
 /// The maximum amount of iterations we want to do for a complex number in Mandelbrot to check for divergence.
-pub const MAX_ITER: u16 = 100;
+/// Points that reach it count as inside the set.
+pub fn max_iter() -> u16 {
+    MAX_ITER.load(Ordering::Relaxed)
+}
+
+/// Changes the iteration limit of all following computations, clamped to [`MAX_ITER_RANGE`]. Fields computed with
+/// another limit have to be computed again.
+pub fn set_max_iter(limit: u16) {
+    MAX_ITER.store(
+        limit.clamp(*MAX_ITER_RANGE.start(), *MAX_ITER_RANGE.end()),
+        Ordering::Relaxed,
+    );
+}
+
+/// The formula set with [`set_fractal_formula`], z² + c if none was set.
+static FORMULA: OnceLock<Formula> = OnceLock::new();
+
+/// Everything besides the view that determines the iteration counts of a field: the formula, the iteration limit
+/// and the arithmetic beyond double precision.
+#[derive(Debug, Clone, Copy)]
+pub struct IterationSettings {
+    /// The formula that is iterated.
+    pub formula: &'static dyn FractalFormula,
+    /// The iteration limit, points reaching it count as inside the set.
+    pub max_iter: u16,
+    /// Whether views beyond double precision are computed as perturbation of a reference orbit.
+    pub perturbation: bool,
+    /// Whether the fixed point backend replaces double-double, see [`PrecisionTier::for_radius`].
+    pub fixed_point: bool,
+}
+
+impl Default for IterationSettings {
+    /// z² + c with the default limit and perturbation, as the explorer starts without a config.
+    fn default() -> Self {
+        IterationSettings {
+            formula: &MandelbrotFormula,
+            max_iter: DEFAULT_MAX_ITER,
+            perturbation: true,
+            fixed_point: false,
+        }
+    }
+}
+
+/// The squared escape radius for the smooth iteration count. The large radius keeps the fractional part free of
+/// visible bands.
+const SMOOTH_ESCAPE_SQ_RADIUS: f64 = 65536.0;
+
+/// The smallest fraction of the window resolution a field is computed in.
+const MIN_RENDER_SCALE: f32 = 0.1;
+
+/// The pixels iterated together in single precision, the loops over them map onto SIMD registers.
+const LANES: usize = 8;
+
+/// A value averaged along the orbit of escaping points, used by the orbit statistic colorings.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OrbitStatistic {
+    /// Nothing is accumulated.
+    #[default]
+    None,
+    /// The triangle inequality average: where |z²| lies between its lower and upper bound |z|² ∓ |c|.
+    TriangleInequality,
+    /// The stripe average: the sine of the orbit angle times the frequency, mapped to [0, 1].
+    Stripes(f64),
+}
+
+/// The shared state behind a [`CancellationToken`].
+#[derive(Debug, Default)]
+struct TokenState {
+    /// Whether the computation should be abandoned.
+    cancelled: AtomicBool,
+    /// The pixels the running computation finished, counted a row at a time.
+    done: AtomicUsize,
+    /// The pixels of the running computation.
+    total: AtomicUsize,
+}
+
+/// Signals a running field computation that its result is no longer needed, and lets it report how far it got.
+/// Clones share the signal, so another thread can abandon the computation of the main loop or show its progress.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<TokenState>);
+
+impl CancellationToken {
+    /// Requests to abandon the running computation.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the computation should be abandoned.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Clears the request before the next computation.
+    pub fn reset(&self) {
+        self.0.cancelled.store(false, Ordering::Relaxed);
+    }
+
+    /// Starts counting the progress of a computation of the given amount of pixels.
+    fn begin(&self, total: usize) {
+        self.0.done.store(0, Ordering::Relaxed);
+        self.0.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Counts finished pixels. The backends count whole rows, so the shared counter is not contended.
+    pub fn advance(&self, pixels: usize) {
+        self.0.done.fetch_add(pixels, Ordering::Relaxed);
+    }
+
+    /// The finished part of the running computation in [0, 1].
+    pub fn progress(&self) -> f32 {
+        let total = self.0.total.load(Ordering::Relaxed).max(1);
+        (self.0.done.load(Ordering::Relaxed) as f32 / total as f32).min(1.0)
+    }
+}
+
+/// What is tracked along the orbit besides the iteration count.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OrbitTracking {
+    /// The statistic averaged along the orbit.
+    pub statistic: OrbitStatistic,
+    /// Whether the derivative of the orbit with respect to c is tracked, as needed for distance estimation.
+    pub derivative: bool,
+    /// Whether the iteration at which the orbit comes closest to zero and that distance are tracked.
+    pub atom_domain: bool,
+}
+
+/// The fractal whose orbits are iterated.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Fractal {
+    /// The Mandelbrot set, the orbits start at zero and the pixel is the parameter.
+    #[default]
+    Mandelbrot,
+    /// The Julia set of the parameter, the orbits start at the pixel.
+    Julia(ComplexNumber),
+}
+
+impl Fractal {
+    /// Gets the amount of iterations till divergence for the point.
+    pub fn iterations(self, point: ComplexNumber) -> u16 {
+        self.escape(point, OrbitTracking::default()).iterations
+    }
+
+    /// Iterates the point with the active formula and iteration limit and tracks the requested values, see
+    /// [`FractalFormula::escape`].
+    pub fn escape(self, point: ComplexNumber, tracking: OrbitTracking) -> Escape {
+        fractal_formula().escape(self, point, tracking, max_iter())
+    }
+
+    /// Like [`Fractal::escape`] with the formula and the iteration limit of the settings.
+    pub fn escape_with(
+        self,
+        point: ComplexNumber,
+        tracking: OrbitTracking,
+        settings: &IterationSettings,
+    ) -> Escape {
+        settings
+            .formula
+            .escape(self, point, tracking, settings.max_iter)
+    }
+}
+
+/// An iteration formula z → f(z, c). The Mandelbrot view iterates it for every pixel as c, the Julia views from
+/// every pixel for their fixed c. A new fractal type implements this and is picked up by the field computation and
+/// the zoom director through [`fractal_formula`].
+pub trait FractalFormula: Send + Sync + std::fmt::Debug {
+    /// The start of the orbit of c in the Mandelbrot view, usually the critical point of the formula.
+    fn init(&self, _c: ComplexNumber) -> ComplexNumber {
+        ComplexNumber::default()
+    }
+
+    /// One step of the orbit.
+    fn step(&self, z: ComplexNumber, c: ComplexNumber) -> ComplexNumber;
+
+    /// The squared radius the orbits escape at.
+    fn bailout(&self) -> f64 {
+        4.0
+    }
+
+    /// Whether this is z² + c, which the single precision, double-double, fixed point and perturbation backends,
+    /// the orbit statistics, the minibrot dives and the verifier are written for. Other formulas are computed in
+    /// double precision only.
+    fn is_quadratic(&self) -> bool {
+        false
+    }
+
+    /// Iterates the pixel at the point till its orbit escapes or reaches the limit and tracks what the formula
+    /// supports of the requested values. By default nothing is tracked.
+    fn escape(
+        &self,
+        fractal: Fractal,
+        point: ComplexNumber,
+        _tracking: OrbitTracking,
+        limit: u16,
+    ) -> Escape {
+        let (mut z, c) = match fractal {
+            Fractal::Mandelbrot => (self.init(point), point),
+            Fractal::Julia(c) => (point, c),
+        };
+        let bailout = self.bailout();
+        let mut iterations = 0;
+        // Checked before each step like the built-in iteration, so the counts agree for z² + c.
+        while iterations < limit {
+            let inside = z.sq_mag() < bailout;
+            z = self.step(z, c);
+            if !inside {
+                break;
+            }
+            iterations += 1;
+        }
+        Escape {
+            iterations,
+            z,
+            ..Default::default()
+        }
+    }
+}
+
+/// The built-in formula z² + c of the Mandelbrot set and its Julia sets, with all orbit statistics.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MandelbrotFormula;
+
+impl FractalFormula for MandelbrotFormula {
+    fn step(&self, z: ComplexNumber, c: ComplexNumber) -> ComplexNumber {
+        z * z + c
+    }
+
+    fn is_quadratic(&self) -> bool {
+        true
+    }
+
+    fn escape(
+        &self,
+        fractal: Fractal,
+        point: ComplexNumber,
+        tracking: OrbitTracking,
+        limit: u16,
+    ) -> Escape {
+        match fractal {
+            Fractal::Mandelbrot => point.escape_with(tracking, limit),
+            Fractal::Julia(c) => point.julia_escape_with(c, tracking, limit),
+        }
+    }
+}
+
+/// The formula all views iterate, the one set with [`set_fractal_formula`] or z² + c.
+pub fn fractal_formula() -> &'static dyn FractalFormula {
+    match FORMULA.get() {
+        Some(formula) => formula,
+        None => &MandelbrotFormula,
+    }
+}
+
+/// Replaces z² + c for all following computations. Only the first formula set is taken.
+pub fn set_fractal_formula(formula: Formula) {
+    let _ = FORMULA.set(formula);
+}
+
+/// The result of iterating a point: how long it took to escape and where the orbit ended.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Escape {
+    /// The amount of iterations till divergence, [`max_iter`] for points in the set.
+    pub iterations: u16,
+    /// The last value of the orbit, just outside the escape radius for escaped points.
+    pub z: ComplexNumber,
+    /// The orbit statistic in [0, 1], smoothly blended over the last step. Zero if none was requested.
+    pub statistic: f32,
+    /// The derivative of the final orbit value with respect to c. Zero if it was not requested.
+    pub derivative: ComplexNumber,
+    /// The iteration at which the orbit came closest to zero, which identifies the atom domain. Zero if it was not
+    /// requested.
+    pub atom_domain: u16,
+    /// The closest distance of the orbit to zero, the orbit trap at the origin. Zero if it was not requested.
+    #[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+    pub trap_distance: f32,
+}
+
+impl Escape {
+    /// The continuous iteration count derived from the final orbit value, the limit for points in the set.
+    pub fn smooth_iterations(&self, limit: u16) -> f32 {
+        if self.iterations >= limit {
+            return limit as f32;
+        }
+        let log_mag = 0.5 * self.z.sq_mag().ln();
+        (self.iterations as f64 + 1.0 - log_mag.max(f64::MIN_POSITIVE).log2()) as f32
+    }
+}
 
 /// Complex number used in Mandelbrot in double precision.
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct ComplexNumber {
     pub real: f64,
     pub imag: f64,
 }
 
 impl ComplexNumber {
+    /// The multiplicative identity, which as rotation turns nothing.
+    pub const ONE: ComplexNumber = ComplexNumber {
+        real: 1.0,
+        imag: 0.0,
+    };
+
     /// Constructor.
     pub fn new(real: f64, imag: f64) -> ComplexNumber {
         ComplexNumber { real, imag }
     }
 
+    /// The number on the unit circle with the given angle in radians, multiplying with it rotates by the angle.
+    pub fn from_angle(angle: f64) -> ComplexNumber {
+        let (sin, cos) = angle.sin_cos();
+        ComplexNumber::new(cos, sin)
+    }
+
     /// Does the next step on a complex number and returns true if we still need to iterate.
     /// We change ourselves.
     fn next_step(&mut self, offset: ComplexNumber) -> bool {
@@ -34,12 +349,60 @@ impl ComplexNumber {
 
     /// Gets the amount of iterations we need till divergence.
     pub fn get_iteration_till_termination(&self) -> u16 {
+        self.escape().iterations
+    }
+
+    /// Iterates the point till divergence and reports the final orbit value along with the iterations.
+    pub fn escape(&self) -> Escape {
+        escape_orbit(ComplexNumber::default(), *self, max_iter())
+    }
+
+    /// Like [`ComplexNumber::escape`] with the given limit, but also tracks the requested values along the orbit. A
+    /// larger escape radius is used here, otherwise the blending between the last two averages shows bands.
+    pub fn escape_with(&self, tracking: OrbitTracking, limit: u16) -> Escape {
+        if tracking == OrbitTracking::default() {
+            return escape_orbit(ComplexNumber::default(), *self, limit);
+        }
+        // z' = 2 z z' + 1 with the derivative taken with respect to c.
+        track_orbit(
+            ComplexNumber::default(),
+            *self,
+            ComplexNumber::default(),
+            1.0,
+            tracking,
+            limit,
+        )
+    }
+
+    /// Like [`ComplexNumber::escape_with`] for the Julia set of c, the orbit starts at this point.
+    pub fn julia_escape_with(
+        &self,
+        c: ComplexNumber,
+        tracking: OrbitTracking,
+        limit: u16,
+    ) -> Escape {
+        if tracking == OrbitTracking::default() {
+            return escape_orbit(*self, c, limit);
+        }
+        // z' = 2 z z' with the derivative taken with respect to the start point.
+        track_orbit(*self, c, ComplexNumber::new(1.0, 0.0), 0.0, tracking, limit)
+    }
+
+    /// Gets the continuous iteration count till divergence, points in the set return [`max_iter`].
+    pub fn get_smooth_iteration(&self) -> f32 {
+        let limit = max_iter();
         let mut iter = 0;
         let mut scan = ComplexNumber::default();
-        while iter < MAX_ITER && scan.next_step(*self) {
+        while iter < limit {
+            scan.next_step(*self);
             iter += 1;
+            let sq_mag = scan.sq_mag();
+            if sq_mag > SMOOTH_ESCAPE_SQ_RADIUS {
+                let smooth = iter as f64 + 1.0 - (0.5 * sq_mag.ln()).log2();
+                return (smooth as f32).clamp(0.0, limit as f32);
+            }
         }
-        iter
+        limit as f32
     }
 
     /// Does a smooth damp with critical damped spring to a target complex number.
@@ -72,6 +435,120 @@ impl ComplexNumber {
     }
 }
 
+/// Iterates z² + c from the start point till it leaves the radius 2 or reaches the limit.
+fn escape_orbit(start: ComplexNumber, c: ComplexNumber, limit: u16) -> Escape {
+    let mut iterations = 0;
+    let mut z = start;
+    while iterations < limit && z.next_step(c) {
+        iterations += 1;
+    }
+    Escape {
+        iterations,
+        z,
+        ..Default::default()
+    }
+}
+
+/// Iterates z² + c from the start point till the large escape radius and tracks the requested values. The
+/// derivative follows z' = 2 z z' + offset from its start value.
+fn track_orbit(
+    mut z: ComplexNumber,
+    c: ComplexNumber,
+    mut derivative: ComplexNumber,
+    derivative_offset: f64,
+    tracking: OrbitTracking,
+    limit: u16,
+) -> Escape {
+    let statistic = tracking.statistic;
+    let c_mag = c.sq_mag().sqrt();
+    let (mut sum, mut previous_sum, mut count) = (0.0, 0.0, 0);
+    let (mut atom_domain, mut closest_sq_mag) = (0, f64::MAX);
+    let trap_distance = |closest_sq_mag: f64| {
+        if tracking.atom_domain {
+            closest_sq_mag.sqrt() as f32
+        } else {
+            0.0
+        }
+    };
+    let mut iterations = 0;
+    while iterations < limit {
+        let previous_sq_mag = z.sq_mag();
+        if tracking.derivative {
+            derivative = ComplexNumber::new(
+                2.0 * (z.real * derivative.real - z.imag * derivative.imag) + derivative_offset,
+                2.0 * (z.real * derivative.imag + z.imag * derivative.real),
+            );
+        }
+        z.next_step(c);
+        iterations += 1;
+        // The first step of the Mandelbrot set always lands on c, which carries no information.
+        if statistic != OrbitStatistic::None && iterations > 1 {
+            let term = match statistic {
+                OrbitStatistic::None => 0.0,
+                OrbitStatistic::TriangleInequality => {
+                    let low = (previous_sq_mag - c_mag).abs();
+                    let high = previous_sq_mag + c_mag;
+                    if high > low {
+                        (z.sq_mag().sqrt() - low) / (high - low)
+                    } else {
+                        0.0
+                    }
+                }
+                OrbitStatistic::Stripes(frequency) => {
+                    0.5 + 0.5 * (frequency * z.imag.atan2(z.real)).sin()
+                }
+            };
+            previous_sum = sum;
+            sum += term;
+            count += 1;
+        }
+        let sq_mag = z.sq_mag();
+        if tracking.atom_domain && sq_mag < closest_sq_mag {
+            closest_sq_mag = sq_mag;
+            atom_domain = iterations;
+        }
+        if sq_mag > SMOOTH_ESCAPE_SQ_RADIUS {
+            if count == 0 {
+                return Escape {
+                    iterations,
+                    z,
+                    statistic: 0.0,
+                    derivative,
+                    atom_domain,
+                    trap_distance: trap_distance(closest_sq_mag),
+                };
+            }
+            let average = sum / count as f64;
+            let previous_average = if count > 1 {
+                previous_sum / (count - 1) as f64
+            } else {
+                average
+            };
+            // The fractional part of the smooth iteration count decides how much of the last term we take.
+            let fraction = (sq_mag.ln() / SMOOTH_ESCAPE_SQ_RADIUS.ln())
+                .log2()
+                .clamp(0.0, 1.0);
+            let blended = average + (previous_average - average) * fraction;
+            return Escape {
+                iterations,
+                z,
+                statistic: blended.clamp(0.0, 1.0) as f32,
+                derivative,
+                atom_domain,
+                trap_distance: trap_distance(closest_sq_mag),
+            };
+        }
+    }
+    Escape {
+        iterations,
+        z,
+        statistic: 0.0,
+        derivative,
+        atom_domain,
+        trap_distance: trap_distance(closest_sq_mag),
+    }
+}
+
 impl AddAssign<ComplexNumber> for ComplexNumber {
     fn add_assign(&mut self, other: ComplexNumber) {
         self.real += other.real;
@@ -79,6 +556,17 @@ impl AddAssign<ComplexNumber> for ComplexNumber {
     }
 }
 
+impl Add for ComplexNumber {
+    type Output = ComplexNumber;
+
+    fn add(self, rhs: ComplexNumber) -> Self::Output {
+        ComplexNumber {
+            real: self.real + rhs.real,
+            imag: self.imag + rhs.imag,
+        }
+    }
+}
+
 impl Sub for ComplexNumber {
     type Output = ComplexNumber;
 
@@ -90,23 +578,481 @@ impl Sub for ComplexNumber {
     }
 }
 
-/// Generates an iteration field for the given complex number as a center and an extension given as a radius.
-/// The window half height corresponds to the radius.
-pub fn get_iteration_field(center: ComplexNumber, extension: f64) -> Vec<u16> {
-    let window_height = WINDOW_HEIGHT as f64;
-    let step_increment = extension / (window_height * 0.5);
+impl Mul for ComplexNumber {
+    type Output = ComplexNumber;
+
+    fn mul(self, rhs: ComplexNumber) -> Self::Output {
+        ComplexNumber {
+            real: self.real * rhs.real - self.imag * rhs.imag,
+            imag: self.real * rhs.imag + self.imag * rhs.real,
+        }
+    }
+}
+
+impl Div for ComplexNumber {
+    type Output = ComplexNumber;
+
+    fn div(self, rhs: ComplexNumber) -> Self::Output {
+        let sq_mag = rhs.sq_mag();
+        ComplexNumber {
+            real: (self.real * rhs.real + self.imag * rhs.imag) / sq_mag,
+            imag: (self.imag * rhs.real - self.real * rhs.imag) / sq_mag,
+        }
+    }
+}
+
+/// Describes how a grid of pixels maps onto the complex number pane. The half height of the grid corresponds
+/// to the radius.
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    /// The point in the center of the grid.
+    pub center: ComplexNumber,
+    /// The part of the center below the resolution of f64, which only the precision tiers beyond double precision
+    /// use. Zero for views double precision resolves.
+    pub center_low: ComplexNumber,
+    /// The extension from the center to the upper and lower border.
+    pub radius: f64,
+    /// The rotation of the grid around the center as number on the unit circle, [`ComplexNumber::ONE`] for none.
+    pub rotation: ComplexNumber,
+    /// The width of the grid in pixels.
+    pub width: u32,
+    /// The height of the grid in pixels.
+    pub height: u32,
+}
+
+impl Viewport {
+    /// The viewport covering the window with the given center and radius.
+    pub fn window(center: ComplexNumber, radius: f64) -> Viewport {
+        Viewport {
+            center,
+            center_low: ComplexNumber::default(),
+            radius,
+            rotation: ComplexNumber::ONE,
+            width: WINDOW_WIDTH as u32,
+            height: WINDOW_HEIGHT as u32,
+        }
+    }
+
+    /// The distance between two neighboring pixels in the complex number pane.
+    pub fn pixel_step(&self) -> f64 {
+        self.radius / (self.height as f64 * 0.5)
+    }
+
+    /// The offset of the given pixel from the center in the complex number pane, turned by the rotation.
+    pub fn pixel_offset(&self, x: i64, y: i64) -> ComplexNumber {
+        let step = self.pixel_step();
+        ComplexNumber::new(
+            (x - (self.width / 2) as i64) as f64 * step,
+            (y - (self.height / 2) as i64) as f64 * step,
+        ) * self.rotation
+    }
+
+    /// Gets the point in the complex number pane of the given pixel.
+    pub fn pixel_to_complex(&self, x: i64, y: i64) -> ComplexNumber {
+        let mut scan = self.pixel_offset(x, y);
+        scan += self.center;
+        scan
+    }
+
+    /// The viewport covering the tile with its upper left corner at the given pixel, with the same pixel step and
+    /// rotation. The center keeps its low part, so the tile resolves as deep as the viewport.
+    pub fn tile(&self, tile_x: u32, tile_y: u32, tile_width: u32, tile_height: u32) -> Viewport {
+        let offset = self.pixel_offset(
+            (tile_x + tile_width / 2) as i64,
+            (tile_y + tile_height / 2) as i64,
+        );
+        let real = DoubleDouble::two_sum(self.center.real, self.center_low.real)
+            + DoubleDouble::from_f64(offset.real);
+        let imag = DoubleDouble::two_sum(self.center.imag, self.center_low.imag)
+            + DoubleDouble::from_f64(offset.imag);
+        Viewport {
+            center: ComplexNumber::new(real.hi, imag.hi),
+            center_low: ComplexNumber::new(real.lo, imag.lo),
+            radius: self.pixel_step() * tile_height as f64 * 0.5,
+            rotation: self.rotation,
+            width: tile_width,
+            height: tile_height,
+        }
+    }
+}
+
+/// Computes the iterations for a rectangular tile of the viewport with its upper left corner at the given pixel.
+/// The result is stored row by row.
+pub fn get_iteration_tile(
+    viewport: &Viewport,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+    settings: &IterationSettings,
+) -> Vec<u16> {
+    get_fractal_iteration_tile(
+        viewport,
+        Fractal::Mandelbrot,
+        tile_x,
+        tile_y,
+        tile_width,
+        tile_height,
+        settings,
+    )
+}
+
+/// Computes the iterations in the Julia set of c for a tile, see [`get_iteration_tile`].
+pub fn get_julia_tile(
+    viewport: &Viewport,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+    c: ComplexNumber,
+) -> Vec<u16> {
+    map_tile(viewport, tile_x, tile_y, tile_width, tile_height, |point| {
+        Fractal::Julia(c).iterations(point)
+    })
+}
+
+/// Computes the continuous iteration counts for a tile, see [`get_iteration_tile`].
+pub fn get_smooth_iteration_tile(
+    viewport: &Viewport,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+) -> Vec<f32> {
+    map_tile(viewport, tile_x, tile_y, tile_width, tile_height, |point| {
+        point.get_smooth_iteration()
+    })
+}
+
+/// Computes the escape results for a tile, see [`get_fractal_escape_tile`].
+pub fn get_escape_tile(
+    viewport: &Viewport,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+    tracking: OrbitTracking,
+    settings: &IterationSettings,
+) -> Vec<Escape> {
+    get_fractal_escape_tile(
+        viewport,
+        Fractal::Mandelbrot,
+        tile_x,
+        tile_y,
+        tile_width,
+        tile_height,
+        tracking,
+        settings,
+    )
+}
+
+/// Computes the iterations in the given fractal for a tile, see [`get_iteration_tile`]. Views below the limit of
+/// double precision are computed in the deeper tiers like the window, see [`get_iteration_field`], with or without
+/// perturbation. Single precision is never used, the pixels of offline renders are usually finer than the ones of
+/// the window.
+pub fn get_fractal_iteration_tile(
+    viewport: &Viewport,
+    fractal: Fractal,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+    settings: &IterationSettings,
+) -> Vec<u16> {
+    let tier = PrecisionTier::for_radius(viewport.radius, settings);
+    if tier <= PrecisionTier::Double {
+        return map_tile(viewport, tile_x, tile_y, tile_width, tile_height, |point| {
+            fractal
+                .escape_with(point, OrbitTracking::default(), settings)
+                .iterations
+        });
+    }
+    // A token nobody else holds is never cancelled.
+    let cancel = CancellationToken::default();
+    let tile = viewport.tile(tile_x, tile_y, tile_width, tile_height);
+    let limit = settings.max_iter;
+    match tier {
+        _ if settings.perturbation => {
+            try_iteration_field_perturbed(&tile, fractal, tier, limit, &cancel)
+        }
+        PrecisionTier::DoubleDouble => {
+            try_iteration_field_double_double(&tile, fractal, limit, &cancel)
+        }
+        _ => try_iteration_field_fixed(&tile, fractal, limit, &cancel),
+    }
+    .unwrap_or_default()
+}
+
+/// Computes the escape results in the given fractal for a tile, see [`get_iteration_tile`]. Like
+/// [`get_escape_field`] views beyond double precision get the iterations of the deeper tiers with the orbit ending
+/// on the escape radius.
+#[expect(
+    clippy::too_many_arguments,
+    reason = "the tile is passed like in the other tile functions"
+)]
+pub fn get_fractal_escape_tile(
+    viewport: &Viewport,
+    fractal: Fractal,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+    tracking: OrbitTracking,
+    settings: &IterationSettings,
+) -> Vec<Escape> {
+    if PrecisionTier::for_radius(viewport.radius, settings) > PrecisionTier::Double {
+        return get_fractal_iteration_tile(
+            viewport,
+            fractal,
+            tile_x,
+            tile_y,
+            tile_width,
+            tile_height,
+            settings,
+        )
+        .into_iter()
+        .map(|iterations| Escape {
+            iterations,
+            z: ComplexNumber::new(2.0, 0.0),
+            ..Default::default()
+        })
+        .collect();
+    }
+    map_tile(viewport, tile_x, tile_y, tile_width, tile_height, |point| {
+        fractal.escape_with(point, tracking, settings)
+    })
+}
+
+/// Evaluates the function for every pixel of the tile in parallel.
+fn map_tile<T: Send>(
+    viewport: &Viewport,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+    function: impl Fn(ComplexNumber) -> T + Sync,
+) -> Vec<T> {
+    // A token nobody else holds is never cancelled.
+    let cancel = CancellationToken::default();
+    try_map_tile(
+        viewport,
+        tile_x,
+        tile_y,
+        tile_width,
+        tile_height,
+        &cancel,
+        function,
+    )
+    .unwrap_or_default()
+}
 
-    (0..WINDOW_WIDTH * WINDOW_HEIGHT)
+/// Evaluates the function for every pixel of the tile in parallel like [`map_tile`]. Returns None as soon as the
+/// token is cancelled, the remaining pixels are skipped.
+fn try_map_tile<T: Send>(
+    viewport: &Viewport,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+    cancel: &CancellationToken,
+    function: impl Fn(ComplexNumber) -> T + Sync,
+) -> Option<Vec<T>> {
+    (0..tile_width * tile_height)
         .into_par_iter()
-        .map(|x| {
-            let y_pos = x / WINDOW_WIDTH - WINDOW_HEIGHT / 2;
-            let x_pos = x % WINDOW_WIDTH - WINDOW_WIDTH / 2;
-            let mut scan =
-                ComplexNumber::new(x_pos as f64 * step_increment, y_pos as f64 * step_increment);
-            scan += center;
-            scan.get_iteration_till_termination()
+        .map(|idx| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            if idx % tile_width == 0 {
+                cancel.advance(tile_width as usize);
+            }
+            let x = (tile_x + idx % tile_width) as i64;
+            let y = (tile_y + idx / tile_width) as i64;
+            Some(function(viewport.pixel_to_complex(x, y)))
         })
-        .collect::<Vec<u16>>()
+        .collect()
+}
+
+/// Computes a field for the window viewport at the given fraction of its resolution and scales the result back up
+/// to the window size by repeating pixels, so everything working on the field keeps its size. A scale of 1 computes
+/// every pixel. The computation gets the viewport to fill row by row and reports its progress to the token.
+fn try_map_window<T: Send + Sync + Copy>(
+    window: &Viewport,
+    scale: f32,
+    cancel: &CancellationToken,
+    compute: impl FnOnce(&Viewport) -> Option<Vec<T>>,
+) -> Option<Vec<T>> {
+    profile_scope!("compute field");
+    let scale = scale.clamp(MIN_RENDER_SCALE, 1.0);
+    if scale >= 1.0 {
+        cancel.begin((window.width * window.height) as usize);
+        return compute(window);
+    }
+    let reduced = Viewport {
+        width: ((window.width as f32 * scale).round() as u32).max(1),
+        height: ((window.height as f32 * scale).round() as u32).max(1),
+        ..*window
+    };
+    cancel.begin((reduced.width * reduced.height) as usize);
+    let field = compute(&reduced)?;
+    Some(
+        (0..window.width * window.height)
+            .into_par_iter()
+            .map(|idx| {
+                let x = idx % window.width * reduced.width / window.width;
+                let y = idx / window.width * reduced.height / window.height;
+                field[(y * reduced.width + x) as usize]
+            })
+            .collect(),
+    )
+}
+
+/// Computes the iterations of the whole viewport in single precision up to the limit, a row at a time and [`LANES`]
+/// pixels at once. Returns None as soon as the token is cancelled.
+fn try_iteration_field_f32(
+    viewport: &Viewport,
+    fractal: Fractal,
+    limit: u16,
+    cancel: &CancellationToken,
+) -> Option<Vec<u16>> {
+    let width = viewport.width as usize;
+    let mut field = vec![0; width * viewport.height as usize];
+    field
+        .par_chunks_mut(width)
+        .enumerate()
+        .try_for_each(|(y, row)| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            cancel.advance(width);
+            for (chunk_index, chunk) in row.chunks_mut(LANES).enumerate() {
+                let mut real = [0.0; LANES];
+                let mut imag = [0.0; LANES];
+                for lane in 0..LANES {
+                    // Lanes beyond the end of the row repeat its last pixel.
+                    let x = (chunk_index * LANES + lane).min(width - 1);
+                    let point = viewport.pixel_to_complex(x as i64, y as i64);
+                    real[lane] = point.real as f32;
+                    imag[lane] = point.imag as f32;
+                }
+                let counts = match fractal {
+                    Fractal::Mandelbrot => {
+                        iterate_lanes([0.0; LANES], [0.0; LANES], real, imag, limit)
+                    }
+                    Fractal::Julia(c) => iterate_lanes(
+                        real,
+                        imag,
+                        [c.real as f32; LANES],
+                        [c.imag as f32; LANES],
+                        limit,
+                    ),
+                };
+                chunk.copy_from_slice(&counts[..chunk.len()]);
+            }
+            Some(())
+        })?;
+    Some(field)
+}
+
+/// Iterates z² + c for all lanes at once till every orbit left the radius 2 or the limit is reached, see
+/// [`escape_orbit`]. Escaped lanes keep being iterated, but their count stays, which keeps the loop free of
+/// branches.
+fn iterate_lanes(
+    mut z_real: [f32; LANES],
+    mut z_imag: [f32; LANES],
+    c_real: [f32; LANES],
+    c_imag: [f32; LANES],
+    limit: u16,
+) -> [u16; LANES] {
+    let mut counts = [0; LANES];
+    let mut alive = [true; LANES];
+    for _ in 0..limit {
+        for lane in 0..LANES {
+            let sq_real = z_real[lane] * z_real[lane];
+            let sq_imag = z_imag[lane] * z_imag[lane];
+            alive[lane] &= sq_real + sq_imag < 4.0;
+            counts[lane] += alive[lane] as u16;
+            z_imag[lane] = 2.0 * z_real[lane] * z_imag[lane] + c_imag[lane];
+            z_real[lane] = sq_real - sq_imag + c_real[lane];
+        }
+        if !alive.contains(&true) {
+            break;
+        }
+    }
+    counts
+}
+
+/// Generates an iteration field of the fractal for the viewport, usually [`Viewport::window`]. The scale reduces
+/// the resolution the field is computed in. The precision tier follows from the radius and the settings, see
+/// [`PrecisionTier`]. Returns None if the token got cancelled.
+pub fn get_iteration_field(
+    fractal: Fractal,
+    window: &Viewport,
+    settings: &IterationSettings,
+    scale: f32,
+    cancel: &CancellationToken,
+) -> Option<Vec<u16>> {
+    let tier = PrecisionTier::for_radius(window.radius, settings);
+    let limit = settings.max_iter;
+    try_map_window(window, scale, cancel, |viewport| match tier {
+        PrecisionTier::Single => try_iteration_field_f32(viewport, fractal, limit, cancel),
+        PrecisionTier::Double => try_map_tile(
+            viewport,
+            0,
+            0,
+            viewport.width,
+            viewport.height,
+            cancel,
+            |point| {
+                fractal
+                    .escape_with(point, OrbitTracking::default(), settings)
+                    .iterations
+            },
+        ),
+        _ if settings.perturbation => {
+            try_iteration_field_perturbed(viewport, fractal, tier, limit, cancel)
+        }
+        PrecisionTier::DoubleDouble => {
+            try_iteration_field_double_double(viewport, fractal, limit, cancel)
+        }
+        PrecisionTier::FixedPoint => try_iteration_field_fixed(viewport, fractal, limit, cancel),
+    })
+}
+
+/// Generates the escape results for the window, see [`get_iteration_field`]. They need double precision, views
+/// beyond it get the iterations of the deeper tiers with the orbit ending on the escape radius.
+pub fn get_escape_field(
+    fractal: Fractal,
+    window: &Viewport,
+    tracking: OrbitTracking,
+    settings: &IterationSettings,
+    scale: f32,
+    cancel: &CancellationToken,
+) -> Option<Vec<Escape>> {
+    if PrecisionTier::for_radius(window.radius, settings) > PrecisionTier::Double {
+        let field = get_iteration_field(fractal, window, settings, scale, cancel)?;
+        return Some(
+            field
+                .into_iter()
+                .map(|iterations| Escape {
+                    iterations,
+                    z: ComplexNumber::new(2.0, 0.0),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+    }
+    try_map_window(window, scale, cancel, |viewport| {
+        try_map_tile(
+            viewport,
+            0,
+            0,
+            viewport.width,
+            viewport.height,
+            cancel,
+            |point| fractal.escape_with(point, tracking, settings),
+        )
+    })
 }
 
 /// Generic smooth damping function that works on a critically damped spring.
@@ -0,0 +1,134 @@
+//! Finds the minibrots near the view, which are the most satisfying places to end a dive. The period of the
+//! lowest period component within the view is found by iterating a ball around the center, Newton's method then
+//! converges onto the nucleus of that component and its size is estimated from the derivatives of the orbit.
+//! Points inside a component have an attracting cycle, whose length is the period of the component.
+
+use crate::math::ComplexNumber;
+
+/// The highest period we look for.
+const MAX_PERIOD: usize = 4096;
+
+/// The maximum amount of Newton steps for the nucleus.
+const MAX_NEWTON_STEPS: usize = 64;
+
+/// Newton's method stops once a step is shorter than this fraction of the expected minibrot size.
+const NEWTON_TOLERANCE: f64 = 1e-6;
+
+/// The orbit settles on its attracting cycle within these iterations before we look for the cycle.
+const CYCLE_TRANSIENT: usize = 2000;
+
+/// Two orbit values closer than this count as the same point of the cycle.
+const CYCLE_TOLERANCE: f64 = 1e-10;
+
+/// A minibrot: its nucleus, which is the center of the main cardioid, and its approximate radius.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minibrot {
+    /// The point whose orbit returns exactly to zero after `period` steps.
+    pub nucleus: ComplexNumber,
+    /// The period of the orbits within the minibrot.
+    pub period: usize,
+    /// The approximate radius of the minibrot.
+    pub size: f64,
+}
+
+impl Minibrot {
+    /// Searches for the lowest period minibrot within the radius around the center.
+    pub fn find(center: ComplexNumber, radius: f64) -> Option<Minibrot> {
+        let period = find_period(center, radius)?;
+        let nucleus = find_nucleus(center, period, radius * NEWTON_TOLERANCE)?;
+        if (nucleus - center).sq_mag() > radius * radius {
+            return None;
+        }
+        let size = minibrot_size(nucleus, period)?;
+        Some(Minibrot {
+            nucleus,
+            period,
+            size,
+        })
+    }
+}
+
+/// Iterates the disc with the radius around the center in first order approximation and returns the first
+/// iteration at which it contains zero. That is the lowest period of the hyperbolic components in the disc.
+pub fn find_period(center: ComplexNumber, radius: f64) -> Option<usize> {
+    let one = ComplexNumber::new(1.0, 0.0);
+    let two = ComplexNumber::new(2.0, 0.0);
+    let mut z = ComplexNumber::default();
+    // The derivative with respect to c, which scales the disc along the orbit.
+    let mut derivative = ComplexNumber::default();
+    for period in 1..=MAX_PERIOD {
+        derivative = two * z * derivative + one;
+        z = z * z + center;
+        let sq_mag = z.sq_mag();
+        if sq_mag > 1e20 || !sq_mag.is_finite() {
+            return None;
+        }
+        if sq_mag < radius * radius * derivative.sq_mag() {
+            return Some(period);
+        }
+    }
+    None
+}
+
+/// Gets the length of the attracting cycle of the orbit of c, which is the period of the hyperbolic component c
+/// lies in. Returns None if the orbit escapes or has not settled within the iterations.
+pub fn orbit_period(c: ComplexNumber) -> Option<usize> {
+    let mut z = ComplexNumber::default();
+    for _ in 0..CYCLE_TRANSIENT {
+        z = z * z + c;
+        if z.sq_mag() > 4.0 {
+            return None;
+        }
+    }
+    let reference = z;
+    for period in 1..=MAX_PERIOD {
+        z = z * z + c;
+        if (z - reference).sq_mag() < CYCLE_TOLERANCE * CYCLE_TOLERANCE {
+            return Some(period);
+        }
+    }
+    None
+}
+
+/// Finds the point near the guess whose orbit returns to zero after the period with Newton's method.
+pub fn find_nucleus(guess: ComplexNumber, period: usize, tolerance: f64) -> Option<ComplexNumber> {
+    let one = ComplexNumber::new(1.0, 0.0);
+    let two = ComplexNumber::new(2.0, 0.0);
+    let mut c = guess;
+    for _ in 0..MAX_NEWTON_STEPS {
+        let mut z = ComplexNumber::default();
+        let mut derivative = ComplexNumber::default();
+        for _ in 0..period {
+            derivative = two * z * derivative + one;
+            z = z * z + c;
+        }
+        if derivative.sq_mag() == 0.0 {
+            return None;
+        }
+        let step = z / derivative;
+        c = c - step;
+        if !c.sq_mag().is_finite() {
+            return None;
+        }
+        if step.sq_mag() < tolerance * tolerance {
+            return Some(c);
+        }
+    }
+    None
+}
+
+/// Estimates the radius of the minibrot with the given nucleus from the derivatives of its orbit.
+pub fn minibrot_size(nucleus: ComplexNumber, period: usize) -> Option<f64> {
+    let one = ComplexNumber::new(1.0, 0.0);
+    let two = ComplexNumber::new(2.0, 0.0);
+    let mut z = ComplexNumber::default();
+    let mut l = one;
+    let mut b = one;
+    for _ in 1..period {
+        z = z * z + nucleus;
+        l = two * z * l;
+        b += one / l;
+    }
+    let size = 1.0 / (b * l * l).sq_mag().sqrt();
+    (size.is_finite() && size > 0.0).then_some(size)
+}
@@ -0,0 +1,50 @@
+//! The night mode: within the configured hours of the local time the fractal, the heads-up display and the clock
+//! dim to the configured brightness, so the display does not light up a bedroom or an office at night. The
+//! brightness changes gradually over a minute.
+
+use crate::config::CONFIG;
+use macroquad::prelude::*;
+
+/// The time the brightness takes from full to none in seconds.
+const FADE_TIME: f64 = 60.0;
+
+/// Follows the night hours with the brightness.
+#[derive(Debug)]
+pub struct NightMode {
+    /// The current brightness in [0, 1].
+    brightness: f32,
+}
+
+impl NightMode {
+    /// Starts at the brightness of the current time, so a start at night is dim right away.
+    pub fn new() -> NightMode {
+        NightMode {
+            brightness: target_brightness(),
+        }
+    }
+
+    /// Moves the brightness towards the one of the current time and returns it.
+    pub fn update(&mut self, delta_time: f64) -> f32 {
+        let step = (delta_time / FADE_TIME) as f32;
+        self.brightness += (target_brightness() - self.brightness).clamp(-step, step);
+        self.brightness
+    }
+}
+
+/// The brightness for the current time, full outside the night hours.
+fn target_brightness() -> f32 {
+    match CONFIG.night_hours {
+        Some(hours) if hours.is_active_now() => CONFIG.night_brightness.clamp(0.0, 1.0),
+        _ => 1.0,
+    }
+}
+
+/// The color that dims what is drawn with it to the brightness.
+pub fn dimmed(color: Color, brightness: f32) -> Color {
+    Color::new(
+        color.r * brightness,
+        color.g * brightness,
+        color.b * brightness,
+        color.a,
+    )
+}
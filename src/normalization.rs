@@ -0,0 +1,101 @@
+//! Normalizes the iteration counts of a frame before coloring. The populated range between two percentiles of the
+//! escaped pixels gets stretched over the whole palette, so the contrast stays as the distribution of the counts
+//! shifts with the depth instead of the frames drifting into a single band of hues.
+//!
+//! The percentiles jump by whole counts from frame to frame, the range used for coloring eases towards them, so the
+//! palette does not visibly pump.
+
+/// The share of the escaped pixels below the start of the stretched range.
+const LOW_PERCENTILE: f64 = 0.01;
+
+/// The share of the escaped pixels below the end of the stretched range.
+const HIGH_PERCENTILE: f64 = 0.99;
+
+/// The iteration counts that get stretched over the whole palette.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationRange {
+    /// The count mapped to the start of the palette.
+    pub low: f32,
+    /// The count mapped to the end of the palette.
+    pub high: f32,
+}
+
+impl IterationRange {
+    /// The range between the percentiles of the pixels of the field that escaped before the iteration limit, None
+    /// if no pixel escaped.
+    pub fn of_field(field: &[u16], max_iter: u16) -> Option<IterationRange> {
+        let mut histogram = vec![0usize; max_iter as usize];
+        for &iterations in field {
+            if iterations < max_iter {
+                histogram[iterations as usize] += 1;
+            }
+        }
+        let escaped: usize = histogram.iter().sum();
+        if escaped == 0 {
+            return None;
+        }
+        // The count at which the cumulated histogram passes the share of the escaped pixels.
+        let percentile = |share: f64| {
+            let rank = (share * escaped as f64) as usize;
+            let mut cumulated = 0;
+            histogram
+                .iter()
+                .position(|&count| {
+                    cumulated += count;
+                    cumulated > rank
+                })
+                .unwrap_or(max_iter as usize - 1) as f32
+        };
+        let low = percentile(LOW_PERCENTILE);
+        Some(IterationRange {
+            low,
+            high: percentile(HIGH_PERCENTILE).max(low + 1.0),
+        })
+    }
+
+    /// The range the given fraction of the way towards the other one.
+    fn lerp(self, other: IterationRange, fraction: f32) -> IterationRange {
+        IterationRange {
+            low: self.low + (other.low - self.low) * fraction,
+            high: self.high + (other.high - self.high) * fraction,
+        }
+    }
+
+    /// Maps an iteration count onto the position in [0, max_iter - 1] it gets colored with.
+    pub fn stretch(self, iterations: f32, max_iter: u16) -> f32 {
+        let last = (max_iter - 1) as f32;
+        ((iterations - self.low) / (self.high - self.low) * last).clamp(0.0, last)
+    }
+}
+
+/// Eases the range used for coloring towards the ranges of the frames.
+#[derive(Debug, Default)]
+pub struct RangeSmoother {
+    /// The range used for coloring, None before the first frame with escaped pixels.
+    range: Option<IterationRange>,
+}
+
+impl RangeSmoother {
+    /// Moves towards the range of the frame with the given smooth time and returns the range to color with. The
+    /// first range is taken as it is, frames without a range keep the last one.
+    pub fn update(
+        &mut self,
+        target: Option<IterationRange>,
+        delta_time: f64,
+        smooth_time: f64,
+    ) -> Option<IterationRange> {
+        let Some(target) = target else {
+            return self.range;
+        };
+        let fraction = if smooth_time > 0.0 {
+            1.0 - (-delta_time / smooth_time).exp()
+        } else {
+            1.0
+        };
+        self.range = Some(
+            self.range
+                .map_or(target, |range| range.lerp(target, fraction as f32)),
+        );
+        self.range
+    }
+}
@@ -0,0 +1,174 @@
+//! Receives OSC messages over UDP, so the explorer can be driven from VJ software and MIDI controllers.
+//!
+//! Supported addresses:
+//! - `/zoom/speed <speed>`: zoom speed as multiple of the default speed.
+//! - `/palette/hue <offset>`: shifts the position within the palette cycle, in [0, 1).
+//! - `/palette/next`, `/palette/name <name>`: switches the palette.
+//! - `/target/jump`: leaves the current dive.
+//! - `/target/goto <real> <imag> <radius>`: moves the view to the given location.
+//! - `/pause [<0|1>]`: toggles or sets the pause state.
+//! - `/screenshot`: saves the current frame.
+
+use crate::color_generation::Palette;
+use crate::commands::Command;
+use crate::goto_dialog::check_location;
+use crate::math::{CancellationToken, ComplexNumber};
+use std::net::UdpSocket;
+use std::sync::mpsc::{Receiver, channel};
+
+/// The maximum size of a datagram we accept.
+const MAX_PACKET_SIZE: usize = 4096;
+
+/// The header every OSC bundle starts with.
+const BUNDLE_HEADER: &[u8] = b"#bundle\0";
+
+/// The size of the bundle header including the time tag, which we ignore.
+const BUNDLE_PREFIX_SIZE: usize = 16;
+
+/// A decoded OSC argument.
+enum OscArgument {
+    /// Any numeric type, converted to double precision.
+    Number(f64),
+    /// A string argument.
+    Text(String),
+}
+
+/// The main loop side of the OSC receiver.
+pub struct OscControl {
+    /// The commands decoded from the received messages.
+    receiver: Receiver<Command>,
+}
+
+impl OscControl {
+    /// Starts listening on the given UDP port. Returns None, if the port can not be opened. Commands that move the
+    /// view cancel the running computation through the token.
+    pub fn start(port: u16, cancel: CancellationToken) -> Option<OscControl> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))
+            .inspect_err(|err| eprintln!("Could not open OSC port {port}: {err}"))
+            .ok()?;
+        let (sender, receiver) = channel();
+
+        std::thread::spawn(move || {
+            let mut buffer = [0; MAX_PACKET_SIZE];
+            while let Ok(size) = socket.recv(&mut buffer) {
+                let mut commands = Vec::new();
+                parse_packet(&buffer[..size], &mut commands);
+                if commands.iter().any(|command| command.retargets()) {
+                    cancel.cancel();
+                }
+                if commands.into_iter().any(|c| sender.send(c).is_err()) {
+                    break;
+                }
+            }
+        });
+
+        Some(OscControl { receiver })
+    }
+
+    /// Appends the commands received since the last call.
+    pub fn poll(&self, commands: &mut Vec<Command>) {
+        commands.extend(self.receiver.try_iter());
+    }
+}
+
+/// Decodes a packet, which is either a single message or a bundle of packets.
+fn parse_packet(packet: &[u8], commands: &mut Vec<Command>) {
+    if packet.starts_with(BUNDLE_HEADER) {
+        let mut rest = packet.get(BUNDLE_PREFIX_SIZE..).unwrap_or_default();
+        while let Some((size, tail)) = rest.split_first_chunk::<4>() {
+            let size = u32::from_be_bytes(*size) as usize;
+            let Some(element) = tail.get(..size) else {
+                break;
+            };
+            parse_packet(element, commands);
+            rest = &tail[size..];
+        }
+    } else if let Some((address, arguments)) = parse_message(packet)
+        && let Some(command) = message_command(address, &arguments)
+    {
+        commands.push(command);
+    }
+}
+
+/// Splits a message into its address and the decoded arguments.
+fn parse_message(packet: &[u8]) -> Option<(&str, Vec<OscArgument>)> {
+    let (address, rest) = read_string(packet)?;
+    let Some((type_tags, mut data)) = read_string(rest) else {
+        // Messages without type tag string carry no arguments.
+        return Some((address, Vec::new()));
+    };
+
+    let mut arguments = Vec::new();
+    for tag in type_tags.chars().skip(1) {
+        let argument = match tag {
+            'f' => {
+                let (value, tail) = data.split_first_chunk::<4>()?;
+                data = tail;
+                OscArgument::Number(f32::from_be_bytes(*value) as f64)
+            }
+            'i' => {
+                let (value, tail) = data.split_first_chunk::<4>()?;
+                data = tail;
+                OscArgument::Number(i32::from_be_bytes(*value) as f64)
+            }
+            'd' => {
+                let (value, tail) = data.split_first_chunk::<8>()?;
+                data = tail;
+                OscArgument::Number(f64::from_be_bytes(*value))
+            }
+            'h' => {
+                let (value, tail) = data.split_first_chunk::<8>()?;
+                data = tail;
+                OscArgument::Number(i64::from_be_bytes(*value) as f64)
+            }
+            's' => {
+                let (value, tail) = read_string(data)?;
+                data = tail;
+                OscArgument::Text(value.to_owned())
+            }
+            'T' => OscArgument::Number(1.0),
+            'F' => OscArgument::Number(0.0),
+            // We can not skip unknown types, as we do not know their size.
+            _ => break,
+        };
+        arguments.push(argument);
+    }
+    Some((address, arguments))
+}
+
+/// Reads a null terminated string, which is padded to a multiple of four bytes, and returns the remaining data.
+fn read_string(data: &[u8]) -> Option<(&str, &[u8])> {
+    let end = data.iter().position(|b| *b == 0)?;
+    let text = std::str::from_utf8(&data[..end]).ok()?;
+    let padded_end = (end + 4) & !3;
+    Some((text, data.get(padded_end..).unwrap_or_default()))
+}
+
+/// Maps a message onto the command it triggers.
+fn message_command(address: &str, arguments: &[OscArgument]) -> Option<Command> {
+    // NaN or infinite values would end up in the center, radius or zoom speed of the view.
+    let number = |index: usize| match arguments.get(index) {
+        Some(OscArgument::Number(value)) if value.is_finite() => Some(*value),
+        _ => None,
+    };
+
+    match address {
+        "/zoom/speed" => Some(Command::SetZoomSpeed(number(0)?)),
+        "/palette/hue" => Some(Command::SetHueOffset(number(0)?.rem_euclid(1.0) as f32)),
+        "/palette/next" => Some(Command::NextPalette),
+        "/palette/name" => match arguments.first() {
+            Some(OscArgument::Text(name)) => Palette::from_name(name).map(Command::SetPalette),
+            _ => None,
+        },
+        "/target/jump" => Some(Command::NextTarget),
+        "/target/goto" => check_location(ComplexNumber::new(number(0)?, number(1)?), number(2)?)
+            .ok()
+            .map(|(center, radius)| Command::GoTo(center, ComplexNumber::default(), radius)),
+        "/pause" => Some(match number(0) {
+            Some(value) => Command::SetPaused(value != 0.0),
+            None => Command::TogglePause,
+        }),
+        "/screenshot" => Some(Command::Screenshot),
+        _ => None,
+    }
+}
@@ -0,0 +1,323 @@
+//! Overlays that show the mathematical structure on top of the colors. The external rays and equipotentials are
+//! read off the escape results: within an iteration band the angle of the final orbit value runs around the set
+//! once per ray family, and the smooth iteration count is constant along the equipotentials.
+//!
+//! The contour overlay turns the view into a topographic map by tracing the lines between the iteration counts with
+//! marching squares.
+//!
+//! The grid overlay draws the axes and a labeled coordinate grid, whose spacing follows the zoom and which turns
+//! with the view.
+
+use crate::math::{ComplexNumber, Escape, Viewport, max_iter};
+use macroquad::prelude::*;
+use rayon::prelude::*;
+use std::f64::consts::TAU;
+use std::ops::Range;
+
+/// The amount of external rays drawn per iteration band. Every band further out doubles the rays of the one
+/// within, so the rays fan out like the branches of a tree towards the set.
+const RAY_COUNT: f64 = 8.0;
+
+/// The amount of equipotential lines per unit of the smooth iteration count.
+const EQUIPOTENTIAL_DENSITY: f32 = 1.0;
+
+/// The color of the overlay lines.
+const LINE_COLOR: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+
+/// How much of the line color is mixed into the pixels on a line.
+const LINE_OPACITY: f32 = 0.6;
+
+/// The iteration counts between two contour lines.
+const CONTOUR_STEP: u16 = 5;
+
+/// The color of the contour lines.
+const CONTOUR_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.7);
+
+/// The width of the contour lines in pixels.
+const CONTOUR_WIDTH: f32 = 1.0;
+
+/// The grid spacing is chosen to get about this many lines over the height of the view.
+const GRID_LINES: f64 = 6.0;
+
+/// The color of the grid lines.
+const GRID_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.35);
+
+/// The color of the real and imaginary axes.
+const AXIS_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.8);
+
+/// The font size of the grid labels.
+const LABEL_SIZE: f32 = 16.0;
+
+/// Where on the screen an overlay like the clock or the watermark is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenPosition {
+    /// The upper left corner.
+    TopLeft,
+    /// The upper right corner, where the heads-up display also goes.
+    TopRight,
+    /// The lower left corner.
+    BottomLeft,
+    /// The lower right corner.
+    BottomRight,
+    /// The middle of the screen.
+    Center,
+}
+
+impl ScreenPosition {
+    /// Looks up a position by its name.
+    pub fn from_name(name: &str) -> Option<ScreenPosition> {
+        match name {
+            "top-left" => Some(ScreenPosition::TopLeft),
+            "top-right" => Some(ScreenPosition::TopRight),
+            "bottom-left" => Some(ScreenPosition::BottomLeft),
+            "bottom-right" => Some(ScreenPosition::BottomRight),
+            "center" => Some(ScreenPosition::Center),
+            _ => None,
+        }
+    }
+
+    /// The upper left corner of a box of the given size at this position, the given distance away from the
+    /// screen border.
+    pub fn place(self, width: f32, height: f32, border: f32) -> (f32, f32) {
+        let right = screen_width() - border - width;
+        let bottom = screen_height() - border - height;
+        match self {
+            ScreenPosition::TopLeft => (border, border),
+            ScreenPosition::TopRight => (right, border),
+            ScreenPosition::BottomLeft => (border, bottom),
+            ScreenPosition::BottomRight => (right, bottom),
+            ScreenPosition::Center => (
+                (screen_width() - width) * 0.5,
+                (screen_height() - height) * 0.5,
+            ),
+        }
+    }
+}
+
+/// A line piece in field coordinates.
+type Segment = (Vec2, Vec2);
+
+/// Draws the external rays and equipotentials into the colors of the escape field.
+pub fn draw_external_rays(colors: &mut [crate::color::Color], field: &[Escape], width: usize) {
+    colors.par_iter_mut().enumerate().for_each(|(idx, color)| {
+        if on_ray_or_equipotential(field, width, idx) {
+            *color = crate::color::Color::new(
+                color.r + (LINE_COLOR.r - color.r) * LINE_OPACITY,
+                color.g + (LINE_COLOR.g - color.g) * LINE_OPACITY,
+                color.b + (LINE_COLOR.b - color.b) * LINE_OPACITY,
+                color.a,
+            );
+        }
+    });
+}
+
+/// Checks whether a ray or an equipotential passes between the pixel and its right or lower neighbor.
+fn on_ray_or_equipotential(field: &[Escape], width: usize, idx: usize) -> bool {
+    let limit = max_iter();
+    let escape = &field[idx];
+    if escape.iterations >= limit {
+        return false;
+    }
+    let ray_sector = |escape: &Escape| {
+        let angle = escape.z.imag.atan2(escape.z.real).rem_euclid(TAU) / TAU;
+        (angle * RAY_COUNT) as u32
+    };
+    let level = |escape: &Escape| (escape.smooth_iterations(limit) * EQUIPOTENTIAL_DENSITY).floor();
+
+    let x = idx % width;
+    let neighbors = [
+        (x + 1 < width).then_some(idx + 1),
+        (idx + width < field.len()).then_some(idx + width),
+    ];
+    neighbors.into_iter().flatten().any(|neighbor| {
+        let other = &field[neighbor];
+        if other.iterations >= limit {
+            return false;
+        }
+        // The angle only continues within the same band, across bands it jumps.
+        (other.iterations == escape.iterations && ray_sector(other) != ray_sector(escape))
+            || level(other) != level(escape)
+    })
+}
+
+/// Draws the contour lines of the iteration field. Only the given columns of the field are shown, starting at the
+/// left border of the screen like the colors do in the split view.
+pub fn draw_contours(field: &[u16], width: usize, columns: Range<usize>) {
+    let height = field.len() / width;
+    if height < 2 || columns.len() < 2 {
+        return;
+    }
+    let segments: Vec<Segment> = (0..height - 1)
+        .into_par_iter()
+        .flat_map_iter(|y| {
+            let mut segments = Vec::new();
+            for x in columns.start..columns.end - 1 {
+                cell_segments(field, width, x, y, &mut segments);
+            }
+            segments
+        })
+        .collect();
+
+    let scale = Vec2::new(
+        screen_width() / width as f32,
+        screen_height() / height as f32,
+    );
+    let offset = Vec2::new(columns.start as f32 - 0.5, -0.5);
+    for (start, end) in segments {
+        let start = (start - offset) * scale;
+        let end = (end - offset) * scale;
+        draw_line(start.x, start.y, end.x, end.y, CONTOUR_WIDTH, CONTOUR_COLOR);
+    }
+}
+
+/// Adds the contour pieces within the square between the pixel centers at (x, y) and (x + 1, y + 1).
+fn cell_segments(field: &[u16], width: usize, x: usize, y: usize, segments: &mut Vec<Segment>) {
+    let idx = y * width + x;
+    // The corners in the order we walk around the square.
+    let corners = [
+        (Vec2::new(x as f32, y as f32), field[idx]),
+        (Vec2::new((x + 1) as f32, y as f32), field[idx + 1]),
+        (
+            Vec2::new((x + 1) as f32, (y + 1) as f32),
+            field[idx + width + 1],
+        ),
+        (Vec2::new(x as f32, (y + 1) as f32), field[idx + width]),
+    ];
+    let lowest = corners.iter().map(|corner| corner.1).min().unwrap_or(0);
+    let highest = corners.iter().map(|corner| corner.1).max().unwrap_or(0);
+    // Every multiple of the step in (lowest, highest] is a level the cell crosses.
+    let mut level = (lowest / CONTOUR_STEP + 1) * CONTOUR_STEP;
+    while level <= highest {
+        let threshold = level as f32 - 0.5;
+        let crossings: Vec<Vec2> = (0..4)
+            .filter_map(|edge| {
+                let (from, from_value) = corners[edge];
+                let (to, to_value) = corners[(edge + 1) % 4];
+                ((from_value >= level) != (to_value >= level)).then(|| {
+                    let fraction =
+                        (threshold - from_value as f32) / (to_value as f32 - from_value as f32);
+                    from.lerp(to, fraction)
+                })
+            })
+            .collect();
+        // Saddles cross all four edges, we pair neighboring edges then.
+        for pair in crossings.chunks_exact(2) {
+            segments.push((pair[0], pair[1]));
+        }
+        level += CONTOUR_STEP;
+    }
+}
+
+/// Draws the axes and a labeled coordinate grid for the window view, turned along with it. As with the contours
+/// only the given columns of the window are shown, starting at the left border of the screen.
+pub fn draw_grid(view: &Viewport, columns: Range<usize>) {
+    let (width, height) = (view.width as f64, view.height as f64);
+    let pixel_step = view.pixel_step();
+    let scale = Vec2::new(
+        screen_width() / width as f32,
+        screen_height() / height as f32,
+    );
+    let to_screen = |point: ComplexNumber| {
+        // Undoing the rotation gives the offset in window pixels.
+        let offset = (point - view.center) / view.rotation;
+        Vec2::new(
+            (offset.real / pixel_step + width * 0.5 - columns.start as f64) as f32,
+            (offset.imag / pixel_step + height * 0.5) as f32,
+        ) * scale
+    };
+    let visible = Rect::new(
+        0.0,
+        0.0,
+        (columns.end - columns.start) as f32 * scale.x,
+        screen_height(),
+    );
+
+    // The bounding box of the shown part in the complex number pane.
+    let corners = [
+        (columns.start, 0),
+        (columns.end, 0),
+        (columns.start, view.height as usize),
+        (columns.end, view.height as usize),
+    ]
+    .map(|(x, y)| view.pixel_to_complex(x as i64, y as i64));
+    let lower = corners.into_iter().fold(corners[0], |lower, corner| {
+        ComplexNumber::new(lower.real.min(corner.real), lower.imag.min(corner.imag))
+    });
+    let upper = corners.into_iter().fold(corners[0], |upper, corner| {
+        ComplexNumber::new(upper.real.max(corner.real), upper.imag.max(corner.imag))
+    });
+    let spacing = grid_spacing(2.0 * view.radius / GRID_LINES);
+    let decimals = (-spacing.log10().floor()).max(0.0) as usize;
+
+    for k in (lower.real / spacing).ceil() as i64..=(upper.real / spacing).floor() as i64 {
+        let real = k as f64 * spacing;
+        let Some((start, end)) = clip_line(
+            to_screen(ComplexNumber::new(real, lower.imag)),
+            to_screen(ComplexNumber::new(real, upper.imag)),
+            visible,
+        ) else {
+            continue;
+        };
+        let color = if k == 0 { AXIS_COLOR } else { GRID_COLOR };
+        draw_line(start.x, start.y, end.x, end.y, 1.0, color);
+        draw_text(
+            &format!("{real:.decimals$}"),
+            start.x + 3.0,
+            start.y + LABEL_SIZE,
+            LABEL_SIZE,
+            color,
+        );
+    }
+    for k in (lower.imag / spacing).ceil() as i64..=(upper.imag / spacing).floor() as i64 {
+        let imag = k as f64 * spacing;
+        let Some((start, end)) = clip_line(
+            to_screen(ComplexNumber::new(lower.real, imag)),
+            to_screen(ComplexNumber::new(upper.real, imag)),
+            visible,
+        ) else {
+            continue;
+        };
+        let color = if k == 0 { AXIS_COLOR } else { GRID_COLOR };
+        draw_line(start.x, start.y, end.x, end.y, 1.0, color);
+        draw_text(
+            &format!("{imag:+.decimals$}i"),
+            start.x + 3.0,
+            start.y - 3.0,
+            LABEL_SIZE,
+            color,
+        );
+    }
+}
+
+/// Clips the line to the rectangle with the Liang-Barsky algorithm, None if it misses the rectangle.
+fn clip_line(start: Vec2, end: Vec2, rect: Rect) -> Option<(Vec2, Vec2)> {
+    let delta = end - start;
+    let (mut enter, mut exit) = (0.0f32, 1.0f32);
+    for (direction, distance) in [
+        (-delta.x, start.x - rect.left()),
+        (delta.x, rect.right() - start.x),
+        (-delta.y, start.y - rect.top()),
+        (delta.y, rect.bottom() - start.y),
+    ] {
+        if direction == 0.0 {
+            if distance < 0.0 {
+                return None;
+            }
+        } else if direction < 0.0 {
+            enter = enter.max(distance / direction);
+        } else {
+            exit = exit.min(distance / direction);
+        }
+    }
+    (enter <= exit).then(|| (start + delta * enter, start + delta * exit))
+}
+
+/// Rounds the spacing to the next lower of 1, 2 or 5 times a power of ten, which gives readable labels.
+fn grid_spacing(target: f64) -> f64 {
+    let power = 10f64.powf(target.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|factor| factor * power)
+        .find(|&spacing| spacing <= target)
+        .unwrap_or(power)
+}
@@ -0,0 +1,42 @@
+//! Caps the frame rate. The frames are paced against a fixed schedule, so the rate does not drift with the time
+//! the frames take, and the last stretch before a deadline is waited out by yielding instead of sleeping, which
+//! would overshoot by up to a scheduler tick.
+
+use std::time::{Duration, Instant};
+
+/// The part of the wait we do not trust the sleep with.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Keeps the schedule of the frames.
+#[derive(Debug, Default)]
+pub struct FramePacer {
+    /// The time the last frame was released, None while uncapped.
+    previous: Option<Instant>,
+}
+
+impl FramePacer {
+    /// Waits till the frame is due, given the shortest time a frame may take in seconds. 0 does not wait.
+    pub fn wait(&mut self, frame_time: f64) {
+        if frame_time <= 0.0 {
+            self.previous = None;
+            return;
+        }
+        let frame_time = Duration::from_secs_f64(frame_time);
+        let now = Instant::now();
+        let target = self
+            .previous
+            .map(|previous| previous + frame_time)
+            // When we fell behind by more than a frame we start over instead of rushing to catch up.
+            .filter(|target| now.saturating_duration_since(*target) <= frame_time)
+            .unwrap_or(now);
+
+        let remaining = target.saturating_duration_since(now);
+        if remaining > SPIN_MARGIN {
+            std::thread::sleep(remaining - SPIN_MARGIN);
+        }
+        while Instant::now() < target {
+            std::thread::yield_now();
+        }
+        self.previous = Some(target);
+    }
+}
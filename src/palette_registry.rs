@@ -0,0 +1,149 @@
+//! The registry of the palettes, through which the config, the console and the other controls address them by
+//! name. The built-in palettes register first, then every gradient file in the palettes directory under its file
+//! name without extension, so a saved gradient like `palettes/sunset.txt` can be picked as `sunset` like a built-in
+//! one. New palettes only need to implement [`PaletteFunction`] and register in [`PaletteRegistry::with_builtins`].
+
+use crate::color::Color;
+use crate::color_generation::{PaletteSettings, cyclic_gradient, hsv_to_rgb_color};
+use crate::colormaps::{CIVIDIS, INFERNO, MAGMA, PLASMA, TURBO, VIRIDIS, colormap_at};
+use crate::gradient::{Gradient, PALETTE_DIRECTORY};
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// The extension of the gradient files loaded as palettes.
+const GRADIENT_EXTENSION: &str = "txt";
+
+/// The color stops of the fire palette, traversed cyclically.
+const FIRE_STOPS: [(f32, f32, f32); 4] = [
+    (0.35, 0.02, 0.0),
+    (0.9, 0.3, 0.0),
+    (1.0, 0.85, 0.2),
+    (1.0, 1.0, 0.85),
+];
+
+/// The color stops of the ocean palette, traversed cyclically.
+const OCEAN_STOPS: [(f32, f32, f32); 4] = [
+    (0.0, 0.05, 0.3),
+    (0.0, 0.4, 0.6),
+    (0.3, 0.8, 0.8),
+    (0.9, 1.0, 1.0),
+];
+
+/// All registered palettes, built on first use.
+pub static PALETTES: LazyLock<PaletteRegistry> = LazyLock::new(|| {
+    let mut registry = PaletteRegistry::with_builtins();
+    registry.load_directory(Path::new(PALETTE_DIRECTORY));
+    registry
+});
+
+/// Maps a relative position in [0, 1) within one palette cycle to a color.
+pub trait PaletteFunction: Send + Sync {
+    /// Gets the color at the position, the settings carry the tunable parameters of the built-in palettes.
+    fn color_at(&self, rel_val: f32, settings: &PaletteSettings) -> Color;
+}
+
+impl<F: Fn(f32, &PaletteSettings) -> Color + Send + Sync> PaletteFunction for F {
+    fn color_at(&self, rel_val: f32, settings: &PaletteSettings) -> Color {
+        self(rel_val, settings)
+    }
+}
+
+impl PaletteFunction for Gradient {
+    fn color_at(&self, rel_val: f32, _settings: &PaletteSettings) -> Color {
+        Gradient::color_at(self, rel_val)
+    }
+}
+
+/// The palettes in cycling order with their names.
+pub struct PaletteRegistry {
+    /// The palettes by name, the first one is the default.
+    entries: Vec<(String, Box<dyn PaletteFunction>)>,
+}
+
+impl PaletteRegistry {
+    /// A registry with the built-in palettes.
+    pub fn with_builtins() -> PaletteRegistry {
+        let mut registry = PaletteRegistry {
+            entries: Vec::new(),
+        };
+        registry.register("rainbow", |rel_val, settings: &PaletteSettings| {
+            hsv_to_rgb_color(rel_val, settings.saturation, settings.value)
+        });
+        registry.register("fire", |rel_val, _: &PaletteSettings| {
+            cyclic_gradient(&FIRE_STOPS, rel_val)
+        });
+        registry.register("ocean", |rel_val, _: &PaletteSettings| {
+            cyclic_gradient(&OCEAN_STOPS, rel_val)
+        });
+        for (name, colormap) in [
+            ("viridis", &VIRIDIS),
+            ("magma", &MAGMA),
+            ("inferno", &INFERNO),
+            ("plasma", &PLASMA),
+            ("cividis", &CIVIDIS),
+            ("turbo", &TURBO),
+        ] {
+            registry.register(name, move |rel_val, _: &PaletteSettings| {
+                colormap_at(colormap, rel_val)
+            });
+        }
+        registry.register("cubehelix", |rel_val, settings: &PaletteSettings| {
+            settings.cubehelix.color_at(rel_val)
+        });
+        registry
+    }
+
+    /// Adds the palette under the name. A name that is already taken is reported and the palette dropped.
+    pub fn register(&mut self, name: &str, palette: impl PaletteFunction + 'static) {
+        if self.find(name).is_some() {
+            eprintln!("Ignoring palette {name}: the name is already taken");
+            return;
+        }
+        self.entries.push((name.to_owned(), Box::new(palette)));
+    }
+
+    /// Registers the gradient files of the directory in the order of their names. A missing directory is fine,
+    /// broken files are reported.
+    pub fn load_directory(&mut self, directory: &Path) {
+        let Ok(entries) = std::fs::read_dir(directory) else {
+            return;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == GRADIENT_EXTENSION)
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            match Gradient::load(&path) {
+                Ok(gradient) => self.register(name, gradient),
+                Err(err) => eprintln!("Ignoring palette {}: {err}", path.display()),
+            }
+        }
+    }
+
+    /// The amount of registered palettes.
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The index of the palette with the name.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|(entry, _)| entry == name)
+    }
+
+    /// The name of the palette at the index.
+    pub fn name(&self, index: usize) -> &str {
+        &self.entries[index].0
+    }
+
+    /// The color of the palette at the index for a relative position in [0, 1) within one cycle.
+    pub fn color_at(&self, index: usize, rel_val: f32, settings: &PaletteSettings) -> Color {
+        self.entries[index].1.color_at(rel_val, settings)
+    }
+}
@@ -0,0 +1,401 @@
+//! Perturbation for the deep precision tiers. Only the orbit of the view center, the reference, is iterated in the
+//! slow arithmetic of the tier. Every pixel follows as difference to it in f64, which only has to resolve the tiny
+//! distance to the reference instead of the whole coordinate.
+//!
+//! The reference orbit is cached and reused by the following frames while the center stays close to the reference,
+//! so during a dive it is only iterated again every few frames.
+//!
+//! The first iterations of all pixels are skipped with a series approximation. While the differences are small, the
+//! difference after n iterations is close to a cubic polynomial in the difference of the pixel, whose coefficients
+//! follow from the reference orbit alone. The pixels start iterating where the polynomial stops being accurate for
+//! the whole view.
+//!
+//! Where the orbit of a pixel comes much closer to zero than the reference, its difference holds the whole value and
+//! its precision is gone, which shows as flat blobs. These glitches are detected with Pauldelbrot's criterion and
+//! iterated again against a secondary reference inside them, the pixels left over in the arithmetic of the tier.
+
+use crate::double_double::{DoubleDouble, PreciseComplex};
+use crate::fixed::FixedComplex;
+use crate::math::{CancellationToken, ComplexNumber, Fractal, Viewport};
+use crate::precision::PrecisionTier;
+use crate::profile_scope;
+use rayon::prelude::*;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The reference is reused while it is at most this many radii away from the view center. Further out the
+/// differences of the pixels grow and with them their rounding errors.
+const REUSE_RADII: f64 = 0.5;
+
+/// The series approximation holds while the cubic term stays below this fraction of the linear one for every pixel,
+/// which keeps the neglected higher terms far below it.
+const SERIES_TOLERANCE: f64 = 1e-9;
+
+/// A pixel glitched once its orbit comes closer to zero than this fraction of the reference, compared as squares.
+const GLITCH_TOLERANCE: f64 = 1e-6;
+
+/// The most secondary references computed for the glitches of a field.
+const MAX_SECONDARY_REFERENCES: usize = 8;
+
+/// The last reference orbit, shared by all fields since the iteration fields are computed by free functions.
+static REFERENCE_CACHE: Mutex<Option<Arc<ReferenceOrbit>>> = Mutex::new(None);
+
+/// The orbit of a reference point in double precision, iterated in the arithmetic of a precision tier.
+#[derive(Debug)]
+struct ReferenceOrbit {
+    /// The fractal the orbit belongs to.
+    fractal: Fractal,
+    /// The arithmetic the orbit was iterated in.
+    tier: PrecisionTier,
+    /// The reference point, the center of the view the orbit was computed for.
+    center: ComplexNumber,
+    /// The part of the reference point below the resolution of f64.
+    center_low: ComplexNumber,
+    /// The orbit values rounded to f64, starting with the start value. It ends with the first value outside the
+    /// radius 2 or after the iteration limit of values.
+    orbit: Vec<ComplexNumber>,
+    /// The iteration limit the orbit was iterated with.
+    max_iter: u16,
+    /// The coefficients of the linear, quadratic and cubic term of the series approximation for every orbit value.
+    series: Vec<[ComplexNumber; 3]>,
+}
+
+impl ReferenceOrbit {
+    /// Iterates the orbit of the center of the viewport up to the limit.
+    fn compute(
+        viewport: &Viewport,
+        fractal: Fractal,
+        tier: PrecisionTier,
+        limit: u16,
+    ) -> ReferenceOrbit {
+        profile_scope!("reference orbit");
+        let (x, y) = (viewport.width / 2, viewport.height / 2);
+        let orbit = if tier == PrecisionTier::FixedPoint {
+            let point = FixedComplex::pixel(viewport, x, y);
+            let (mut z, c) = match fractal {
+                Fractal::Mandelbrot => (FixedComplex::default(), point),
+                Fractal::Julia(c) => (point, FixedComplex::from_complex(c)),
+            };
+            record_orbit(limit, || {
+                let value = z.to_complex();
+                z = z.square_add(c);
+                value
+            })
+        } else {
+            let point = PreciseComplex::pixel(viewport, x, y);
+            let (mut z, c) = match fractal {
+                Fractal::Mandelbrot => (PreciseComplex::default(), point),
+                Fractal::Julia(c) => (point, PreciseComplex::from_complex(c)),
+            };
+            record_orbit(limit, || {
+                let value = z.to_complex();
+                z = z.square_add(c);
+                value
+            })
+        };
+        ReferenceOrbit {
+            fractal,
+            tier,
+            center: viewport.center,
+            center_low: viewport.center_low,
+            max_iter: limit,
+            series: series_coefficients(&orbit, fractal),
+            orbit,
+        }
+    }
+
+    /// The last iteration the series approximation is valid for if the differences to the reference point are at
+    /// most the reach. None of the pixels escaped till there.
+    fn skipped_iterations(&self, reach: f64) -> usize {
+        self.series
+            .iter()
+            .zip(&self.orbit)
+            .take_while(|([a, b, c], reference)| {
+                let linear = a.sq_mag().sqrt() * reach;
+                let quadratic = b.sq_mag().sqrt() * reach.powi(2);
+                let cubic = c.sq_mag().sqrt() * reach.powi(3);
+                cubic <= SERIES_TOLERANCE * linear
+                    && reference.sq_mag().sqrt() + linear + quadratic + cubic < 2.0
+            })
+            .count()
+            .saturating_sub(1)
+    }
+
+    /// The center of the viewport relative to the reference point.
+    fn offset_to(&self, viewport: &Viewport) -> ComplexNumber {
+        let difference = |center: f64, center_low: f64, reference: f64, reference_low: f64| {
+            (DoubleDouble::two_sum(center, center_low)
+                - DoubleDouble::two_sum(reference, reference_low))
+            .hi
+        };
+        ComplexNumber::new(
+            difference(
+                viewport.center.real,
+                viewport.center_low.real,
+                self.center.real,
+                self.center_low.real,
+            ),
+            difference(
+                viewport.center.imag,
+                viewport.center_low.imag,
+                self.center.imag,
+                self.center_low.imag,
+            ),
+        )
+    }
+
+    /// Whether the orbit can serve as reference for the viewport with the limit.
+    fn serves(
+        &self,
+        viewport: &Viewport,
+        fractal: Fractal,
+        tier: PrecisionTier,
+        limit: u16,
+    ) -> bool {
+        self.fractal == fractal
+            && self.tier == tier
+            && self.max_iter == limit
+            && self.offset_to(viewport).sq_mag() <= (REUSE_RADII * viewport.radius).powi(2)
+    }
+
+    /// The iterations of the point at the given difference to the reference point, counted like
+    /// [`ComplexNumber::get_iteration_till_termination`]. The iteration starts after the skipped ones with the
+    /// difference of the series approximation. None if the point glitched or outlives the reference orbit.
+    fn iterations(&self, difference: ComplexNumber, skipped: usize) -> Option<u16> {
+        let delta_c = match self.fractal {
+            Fractal::Mandelbrot => difference,
+            Fractal::Julia(_) => ComplexNumber::default(),
+        };
+        let [a, b, c] = self.series[skipped];
+        let mut delta = ((c * difference + b) * difference + a) * difference;
+        for iterations in skipped as u16..self.max_iter {
+            let reference = *self.orbit.get(iterations as usize)?;
+            let z = reference + delta;
+            if z.sq_mag() >= 4.0 {
+                return Some(iterations);
+            }
+            if z.sq_mag() < GLITCH_TOLERANCE * reference.sq_mag() {
+                return None;
+            }
+            // (Z + δ)² + c + δc - (Z² + c)
+            delta = ComplexNumber::new(2.0, 0.0) * reference * delta + delta * delta + delta_c;
+        }
+        Some(self.max_iter)
+    }
+}
+
+/// Collects the values of the step function till one leaves the radius 2 or the limit of values is reached.
+fn record_orbit(limit: u16, mut step: impl FnMut() -> ComplexNumber) -> Vec<ComplexNumber> {
+    let mut orbit = Vec::with_capacity(limit as usize);
+    while orbit.len() < limit as usize {
+        let value = step();
+        orbit.push(value);
+        if value.sq_mag() >= 4.0 {
+            break;
+        }
+    }
+    orbit
+}
+
+/// The coefficients of the series approximation for the orbit. The difference of the orbits after n iterations is
+/// A_n·d + B_n·d² + C_n·d³ for the difference d of the pixel, which enters as start value for Julia sets and as
+/// parameter for the Mandelbrot set.
+fn series_coefficients(orbit: &[ComplexNumber], fractal: Fractal) -> Vec<[ComplexNumber; 3]> {
+    let one = ComplexNumber::new(1.0, 0.0);
+    let (start, parameter) = match fractal {
+        Fractal::Mandelbrot => (ComplexNumber::default(), one),
+        Fractal::Julia(_) => (one, ComplexNumber::default()),
+    };
+    let mut coefficients = [start, ComplexNumber::default(), ComplexNumber::default()];
+    let mut series = Vec::with_capacity(orbit.len());
+    for &reference in orbit {
+        series.push(coefficients);
+        let [a, b, c] = coefficients;
+        let twice = ComplexNumber::new(2.0, 0.0) * reference;
+        // Inserted into the difference of the orbits, 2·Z·δ + δ² + δc, sorted by powers.
+        coefficients = [
+            twice * a + parameter,
+            twice * b + a * a,
+            twice * c + ComplexNumber::new(2.0, 0.0) * a * b,
+        ];
+    }
+    series
+}
+
+/// The cached reference orbit if it serves the viewport, otherwise a new one, which replaces it in the cache.
+fn reference_for(
+    viewport: &Viewport,
+    fractal: Fractal,
+    tier: PrecisionTier,
+    limit: u16,
+) -> Arc<ReferenceOrbit> {
+    let mut cache = REFERENCE_CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(reference) = cache
+        .as_ref()
+        .filter(|reference| reference.serves(viewport, fractal, tier, limit))
+    {
+        return reference.clone();
+    }
+    let reference = Arc::new(ReferenceOrbit::compute(viewport, fractal, tier, limit));
+    *cache = Some(reference.clone());
+    reference
+}
+
+/// Iterates a single pixel in the arithmetic of the tier, for the pixels no reference could compute.
+fn precise_iterations(
+    viewport: &Viewport,
+    fractal: Fractal,
+    tier: PrecisionTier,
+    limit: u16,
+    x: u32,
+    y: u32,
+) -> u16 {
+    if tier == PrecisionTier::FixedPoint {
+        let point = FixedComplex::pixel(viewport, x, y);
+        match fractal {
+            Fractal::Mandelbrot => FixedComplex::default().escape_iterations(point, limit),
+            Fractal::Julia(c) => point.escape_iterations(FixedComplex::from_complex(c), limit),
+        }
+    } else {
+        let point = PreciseComplex::pixel(viewport, x, y);
+        match fractal {
+            Fractal::Mandelbrot => {
+                PreciseComplex::iterations(PreciseComplex::default(), point, limit)
+            }
+            Fractal::Julia(c) => {
+                PreciseComplex::iterations(point, PreciseComplex::from_complex(c), limit)
+            }
+        }
+    }
+}
+
+/// The viewport moved to the given pixel, whose position is rounded to double-double.
+fn centered_on_pixel(viewport: &Viewport, x: u32, y: u32) -> Viewport {
+    let point = PreciseComplex::pixel(viewport, x, y);
+    Viewport {
+        center: ComplexNumber::new(point.real.hi, point.imag.hi),
+        center_low: ComplexNumber::new(point.real.lo, point.imag.lo),
+        ..*viewport
+    }
+}
+
+/// Computes the iterations of the whole viewport as perturbation of the reference orbit, which is iterated in the
+/// arithmetic of the tier, up to the limit. The glitched pixels get secondary references. Returns None as soon as
+/// the token is cancelled.
+pub fn try_iteration_field_perturbed(
+    viewport: &Viewport,
+    fractal: Fractal,
+    tier: PrecisionTier,
+    limit: u16,
+    cancel: &CancellationToken,
+) -> Option<Vec<u16>> {
+    let width = viewport.width as usize;
+    let step = viewport.pixel_step();
+    let half_diagonal = step * (viewport.width as f64).hypot(viewport.height as f64) * 0.5;
+    let mut field = vec![0; width * viewport.height as usize];
+    // The pixels without a result yet, at first all of them.
+    let mut pending: Vec<usize> = (0..field.len()).collect();
+    let mut reference = reference_for(viewport, fractal, tier, limit);
+    for secondary in 0..=MAX_SECONDARY_REFERENCES {
+        if secondary > 0 {
+            // A pixel in the middle of the glitches, which usually lies inside the largest blob.
+            let Some(&index) = pending.get(pending.len() / 2) else {
+                break;
+            };
+            let (x, y) = ((index % width) as u32, (index / width) as u32);
+            let moved = centered_on_pixel(viewport, x, y);
+            reference = Arc::new(ReferenceOrbit::compute(&moved, fractal, tier, limit));
+        }
+        let shift = reference.offset_to(viewport);
+        let skipped = reference.skipped_iterations(shift.sq_mag().sqrt() + half_diagonal);
+        let results = pending
+            .par_iter()
+            .map(|&index| {
+                if cancel.is_cancelled() {
+                    return None;
+                }
+                if index % width == 0 {
+                    cancel.advance(width);
+                }
+                let offset = viewport.pixel_offset((index % width) as i64, (index / width) as i64);
+                let difference = offset + shift;
+                Some(reference.iterations(difference, skipped))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        pending = pending
+            .into_iter()
+            .zip(results)
+            .filter_map(|(index, result)| match result {
+                Some(iterations) => {
+                    field[index] = iterations;
+                    None
+                }
+                None => Some(index),
+            })
+            .collect();
+    }
+
+    let precise = pending
+        .par_iter()
+        .map(|&index| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let (x, y) = ((index % width) as u32, (index / width) as u32);
+            Some(precise_iterations(viewport, fractal, tier, limit, x, y))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    for (index, iterations) in pending.into_iter().zip(precise) {
+        field[index] = iterations;
+    }
+    Some(field)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::double_double::try_iteration_field_double_double;
+
+    #[test]
+    fn perturbation_matches_direct_iteration() {
+        // A view in the seahorse valley, where most pixels escape late and the orbits pass close to zero.
+        let viewport = Viewport {
+            width: 64,
+            height: 64,
+            ..Viewport::window(
+                ComplexNumber::new(-0.743643887037151, 0.131825904205330),
+                1e-10,
+            )
+        };
+        let limit = 2000;
+        let cancel = CancellationToken::default();
+        let perturbed = try_iteration_field_perturbed(
+            &viewport,
+            Fractal::Mandelbrot,
+            PrecisionTier::DoubleDouble,
+            limit,
+            &cancel,
+        )
+        .expect("the computation is not cancelled");
+        let direct =
+            try_iteration_field_double_double(&viewport, Fractal::Mandelbrot, limit, &cancel)
+                .expect("the computation is not cancelled");
+        let escaped = direct
+            .iter()
+            .filter(|&&iterations| iterations < limit)
+            .count();
+        assert!(escaped > direct.len() / 2, "the view shows mostly the set");
+        // Pixels on the edge of chaos may escape an iteration apart, as the rounding differs.
+        let differing = perturbed
+            .iter()
+            .zip(&direct)
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(
+            differing <= direct.len() / 200,
+            "{differing} of {} pixels differ",
+            direct.len()
+        );
+    }
+}
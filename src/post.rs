@@ -0,0 +1,483 @@
+//! Post-processing of the final texture before it is presented. The configured effects run in order, each one
+//! renders its input through a shader into its own render target, which becomes the input of the next. The bloom
+//! extracts the bright parts of the image, blurs them at half resolution with a separable gaussian and adds them
+//! back, which lets the filaments glow. The motion blur smears the image towards the zoom center by the distance it
+//! moved within the frame, which hides the strobing of fast zooms. Screenshots and recordings are taken from the
+//! unprocessed image.
+
+use crate::config::CONFIG;
+use macroquad::prelude::*;
+
+/// Brightness above which a pixel contributes to the bloom, if not configured otherwise.
+pub const DEFAULT_BLOOM_THRESHOLD: f32 = 0.7;
+
+/// Strength of the blurred glow added back to the image, if not configured otherwise.
+pub const DEFAULT_BLOOM_INTENSITY: f32 = 0.8;
+
+/// Darkening of the corners by the vignette, if not configured otherwise.
+pub const DEFAULT_VIGNETTE_STRENGTH: f32 = 0.5;
+
+/// Amplitude of the film grain, if not configured otherwise.
+pub const DEFAULT_GRAIN_STRENGTH: f32 = 0.06;
+
+/// Offset of the red and blue channels at the corners in pixels, if not configured otherwise.
+pub const DEFAULT_CHROMATIC_ABERRATION: f32 = 3.0;
+
+/// Weight of the unsharp mask, if not configured otherwise.
+pub const DEFAULT_SHARPEN_STRENGTH: f32 = 0.6;
+
+/// The bloom is computed at the image resolution divided by this factor, which widens the blur for free.
+const BLOOM_DOWNSCALE: u32 = 2;
+
+/// The amount of samples along the zoom direction the motion blur averages.
+const MOTION_BLUR_SAMPLES: usize = 12;
+
+/// The motion blur never smears further than this fraction of the distance to the center.
+const MAX_MOTION_BLUR: f32 = 0.15;
+
+/// The vertex shader shared by all passes, it only passes the texture coordinates on.
+const VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+varying mediump vec2 uv;
+uniform mat4 Model;
+uniform mat4 Projection;
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+}
+"#;
+
+/// Keeps the part of the color above the threshold, measured by luminance.
+const BRIGHT_PASS_SHADER: &str = r#"#version 100
+precision mediump float;
+varying vec2 uv;
+uniform sampler2D Texture;
+uniform float strength;
+void main() {
+    vec3 color = texture2D(Texture, uv).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    float weight = max(luminance - strength, 0.0) / max(1.0 - strength, 0.001);
+    gl_FragColor = vec4(color * weight, 1.0);
+}
+"#;
+
+/// One direction of the separable 9 tap gaussian blur, the texel is the step along the direction.
+const BLUR_SHADER: &str = r#"#version 100
+precision mediump float;
+varying vec2 uv;
+uniform sampler2D Texture;
+uniform vec2 texel;
+void main() {
+    vec3 sum = texture2D(Texture, uv).rgb * 0.2270270;
+    sum += texture2D(Texture, uv + texel * 1.3846154).rgb * 0.3162162;
+    sum += texture2D(Texture, uv - texel * 1.3846154).rgb * 0.3162162;
+    sum += texture2D(Texture, uv + texel * 3.2307692).rgb * 0.0702703;
+    sum += texture2D(Texture, uv - texel * 3.2307692).rgb * 0.0702703;
+    gl_FragColor = vec4(sum, 1.0);
+}
+"#;
+
+/// Adds the blurred glow onto the image.
+const COMPOSITE_SHADER: &str = r#"#version 100
+precision mediump float;
+varying vec2 uv;
+uniform sampler2D Texture;
+uniform sampler2D Bloom;
+uniform float strength;
+void main() {
+    vec3 color = texture2D(Texture, uv).rgb + texture2D(Bloom, uv).rgb * strength;
+    gl_FragColor = vec4(min(color, vec3(1.0)), 1.0);
+}
+"#;
+
+/// Averages the samples along the line to the image center, the strength is the relative length of the line.
+const MOTION_BLUR_SHADER: &str = r#"#version 100
+precision mediump float;
+varying vec2 uv;
+uniform sampler2D Texture;
+uniform float strength;
+void main() {
+    vec2 offset = uv - vec2(0.5);
+    vec3 sum = vec3(0.0);
+    for (int i = 0; i < SAMPLES; i++) {
+        float scale = 1.0 - strength * float(i) / float(SAMPLES - 1);
+        sum += texture2D(Texture, vec2(0.5) + offset * scale).rgb;
+    }
+    gl_FragColor = vec4(sum / float(SAMPLES), 1.0);
+}
+"#;
+
+/// Darkens the image towards the corners.
+const VIGNETTE_SHADER: &str = r#"#version 100
+precision mediump float;
+varying vec2 uv;
+uniform sampler2D Texture;
+uniform float strength;
+void main() {
+    float falloff = smoothstep(0.8, 0.2, length(uv - vec2(0.5)));
+    vec3 color = texture2D(Texture, uv).rgb * mix(1.0 - strength, 1.0, falloff);
+    gl_FragColor = vec4(color, 1.0);
+}
+"#;
+
+/// Adds noise that changes every frame.
+const GRAIN_SHADER: &str = r#"#version 100
+precision mediump float;
+varying vec2 uv;
+uniform sampler2D Texture;
+uniform float strength;
+uniform float time;
+void main() {
+    float noise = fract(sin(dot(uv + fract(time), vec2(12.9898, 78.233))) * 43758.5453);
+    vec3 color = texture2D(Texture, uv).rgb + (noise - 0.5) * strength;
+    gl_FragColor = vec4(clamp(color, 0.0, 1.0), 1.0);
+}
+"#;
+
+/// Shifts the red and blue channels apart, increasingly towards the corners like a cheap lens.
+const CHROMATIC_ABERRATION_SHADER: &str = r#"#version 100
+precision mediump float;
+varying vec2 uv;
+uniform sampler2D Texture;
+uniform float strength;
+uniform vec2 texel;
+void main() {
+    vec2 shift = (uv - vec2(0.5)) * 2.0 * strength * texel;
+    float red = texture2D(Texture, uv + shift).r;
+    float green = texture2D(Texture, uv).g;
+    float blue = texture2D(Texture, uv - shift).b;
+    gl_FragColor = vec4(red, green, blue, 1.0);
+}
+"#;
+
+/// An unsharp mask with the four direct neighbors.
+const SHARPEN_SHADER: &str = r#"#version 100
+precision mediump float;
+varying vec2 uv;
+uniform sampler2D Texture;
+uniform float strength;
+uniform vec2 texel;
+void main() {
+    vec3 color = texture2D(Texture, uv).rgb;
+    vec3 neighbors = texture2D(Texture, uv + vec2(texel.x, 0.0)).rgb
+        + texture2D(Texture, uv - vec2(texel.x, 0.0)).rgb
+        + texture2D(Texture, uv + vec2(0.0, texel.y)).rgb
+        + texture2D(Texture, uv - vec2(0.0, texel.y)).rgb;
+    vec3 sharpened = color + (color - neighbors * 0.25) * strength * 4.0;
+    gl_FragColor = vec4(clamp(sharpened, 0.0, 1.0), 1.0);
+}
+"#;
+
+/// The available effects, they can be combined in any order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostEffectKind {
+    /// Smears the image along the zoom direction.
+    MotionBlur,
+    /// Lets the bright parts glow.
+    Bloom,
+    /// Shifts the color channels apart towards the corners.
+    ChromaticAberration,
+    /// Enhances the edges.
+    Sharpen,
+    /// Darkens the corners.
+    Vignette,
+    /// Adds animated noise.
+    Grain,
+}
+
+impl PostEffectKind {
+    /// All effects in their recommended order.
+    pub const ALL: [PostEffectKind; 6] = [
+        PostEffectKind::MotionBlur,
+        PostEffectKind::Bloom,
+        PostEffectKind::ChromaticAberration,
+        PostEffectKind::Sharpen,
+        PostEffectKind::Vignette,
+        PostEffectKind::Grain,
+    ];
+
+    /// The name used in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            PostEffectKind::MotionBlur => "motion-blur",
+            PostEffectKind::Bloom => "bloom",
+            PostEffectKind::ChromaticAberration => "chromatic-aberration",
+            PostEffectKind::Sharpen => "sharpen",
+            PostEffectKind::Vignette => "vignette",
+            PostEffectKind::Grain => "grain",
+        }
+    }
+
+    /// Looks up an effect by its name.
+    pub fn from_name(name: &str) -> Option<PostEffectKind> {
+        PostEffectKind::ALL.into_iter().find(|e| e.name() == name)
+    }
+}
+
+/// What the effects know about the current frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameInfo {
+    /// The view radius of the last presented frame.
+    pub previous_radius: f64,
+    /// The view radius of this frame.
+    pub radius: f64,
+    /// The time since start in seconds.
+    pub time: f64,
+}
+
+/// A post-processing stage.
+pub trait PostEffect {
+    /// Applies the effect to the input and returns the result, which stays valid until the next call.
+    fn apply(&self, input: &Texture2D, frame: &FrameInfo) -> Texture2D;
+}
+
+/// The configured effects in their order.
+pub struct PostPipeline {
+    /// The effects, applied from first to last.
+    effects: Vec<Box<dyn PostEffect>>,
+}
+
+impl PostPipeline {
+    /// Sets up the effects from the config for images of the given size. Effects whose shaders do not compile on
+    /// this platform are reported and left out.
+    pub fn from_config(width: u32, height: u32) -> PostPipeline {
+        let effects = CONFIG
+            .post_effects
+            .iter()
+            .filter_map(|kind| {
+                create_effect(*kind, width, height)
+                    .inspect_err(|err| eprintln!("Could not set up the {}: {err}", kind.name()))
+                    .ok()
+            })
+            .collect();
+        PostPipeline { effects }
+    }
+
+    /// Runs the texture through all effects and returns the result.
+    pub fn apply(&self, texture: &Texture2D, frame: &FrameInfo) -> Texture2D {
+        let output = self
+            .effects
+            .iter()
+            .fold(texture.clone(), |input, effect| effect.apply(&input, frame));
+        set_default_camera();
+        output
+    }
+}
+
+/// Creates the effect with its configured parameters.
+fn create_effect(
+    kind: PostEffectKind,
+    width: u32,
+    height: u32,
+) -> Result<Box<dyn PostEffect>, String> {
+    let single_pass = |fragment: &str, strength: f32| -> Result<Box<dyn PostEffect>, String> {
+        Ok(Box::new(ShaderEffect::new(
+            fragment, width, height, strength,
+        )?))
+    };
+    match kind {
+        PostEffectKind::MotionBlur => Ok(Box::new(MotionBlur::new(
+            width,
+            height,
+            CONFIG.motion_blur_strength,
+        )?)),
+        PostEffectKind::Bloom => Ok(Box::new(Bloom::new(
+            width,
+            height,
+            CONFIG.bloom_threshold,
+            CONFIG.bloom_intensity,
+        )?)),
+        PostEffectKind::ChromaticAberration => {
+            single_pass(CHROMATIC_ABERRATION_SHADER, CONFIG.chromatic_aberration)
+        }
+        PostEffectKind::Sharpen => single_pass(SHARPEN_SHADER, CONFIG.sharpen_strength),
+        PostEffectKind::Vignette => single_pass(VIGNETTE_SHADER, CONFIG.vignette_strength),
+        PostEffectKind::Grain => single_pass(GRAIN_SHADER, CONFIG.grain_strength),
+    }
+}
+
+/// An effect that is a single shader pass at full resolution.
+struct ShaderEffect {
+    /// Holds the result.
+    target: RenderTarget,
+    /// The shader of the effect.
+    material: Material,
+    /// The strength passed to the shader.
+    strength: f32,
+}
+
+impl ShaderEffect {
+    /// Compiles the shader and creates the target for images of the given size.
+    fn new(fragment: &str, width: u32, height: u32, strength: f32) -> Result<ShaderEffect, String> {
+        Ok(ShaderEffect {
+            target: linear_target(width, height),
+            material: material(fragment, Vec::new())?,
+            strength,
+        })
+    }
+}
+
+impl PostEffect for ShaderEffect {
+    fn apply(&self, input: &Texture2D, frame: &FrameInfo) -> Texture2D {
+        let size = input.size();
+        self.material.set_uniform("strength", self.strength);
+        self.material
+            .set_uniform("texel", vec2(1.0 / size.x, 1.0 / size.y));
+        self.material.set_uniform("time", frame.time as f32);
+        draw_pass(input, &self.target, &self.material);
+        self.target.texture.clone()
+    }
+}
+
+/// The render targets and materials of the bloom pass.
+struct Bloom {
+    /// Holds the bright pass and the result of the vertical blur.
+    bright: RenderTarget,
+    /// Holds the result of the horizontal blur.
+    blurred: RenderTarget,
+    /// Holds the image with the glow added.
+    output: RenderTarget,
+    /// Extracts the bright parts.
+    bright_pass: Material,
+    /// Blurs along one direction.
+    blur: Material,
+    /// Adds the glow onto the image.
+    composite: Material,
+    /// Brightness above which a pixel contributes.
+    threshold: f32,
+    /// Strength of the glow.
+    intensity: f32,
+}
+
+impl Bloom {
+    /// Creates the bloom for images of the given size.
+    fn new(width: u32, height: u32, threshold: f32, intensity: f32) -> Result<Bloom, String> {
+        let (bloom_width, bloom_height) = (
+            (width / BLOOM_DOWNSCALE).max(1),
+            (height / BLOOM_DOWNSCALE).max(1),
+        );
+        Ok(Bloom {
+            bright: linear_target(bloom_width, bloom_height),
+            blurred: linear_target(bloom_width, bloom_height),
+            output: linear_target(width, height),
+            bright_pass: material(BRIGHT_PASS_SHADER, Vec::new())?,
+            blur: material(BLUR_SHADER, Vec::new())?,
+            composite: material(COMPOSITE_SHADER, vec!["Bloom".to_owned()])?,
+            threshold,
+            intensity,
+        })
+    }
+}
+
+impl PostEffect for Bloom {
+    fn apply(&self, input: &Texture2D, _frame: &FrameInfo) -> Texture2D {
+        let size = self.bright.texture.size();
+        let texel = vec2(1.0 / size.x, 1.0 / size.y);
+
+        self.bright_pass.set_uniform("strength", self.threshold);
+        draw_pass(input, &self.bright, &self.bright_pass);
+        self.blur.set_uniform("texel", vec2(texel.x, 0.0));
+        draw_pass(&self.bright.texture, &self.blurred, &self.blur);
+        self.blur.set_uniform("texel", vec2(0.0, texel.y));
+        draw_pass(&self.blurred.texture, &self.bright, &self.blur);
+
+        self.composite.set_uniform("strength", self.intensity);
+        self.composite
+            .set_texture("Bloom", self.bright.texture.clone());
+        draw_pass(input, &self.output, &self.composite);
+        self.output.texture.clone()
+    }
+}
+
+/// Smears the image along the zoom direction.
+struct MotionBlur {
+    /// Holds the blurred image.
+    target: RenderTarget,
+    /// Averages along the zoom direction.
+    material: Material,
+    /// Scales the blur relative to the actual motion within the frame.
+    strength: f32,
+}
+
+impl MotionBlur {
+    /// Creates the motion blur for images of the given size.
+    fn new(width: u32, height: u32, strength: f32) -> Result<MotionBlur, String> {
+        let fragment = MOTION_BLUR_SHADER.replace("SAMPLES", &MOTION_BLUR_SAMPLES.to_string());
+        Ok(MotionBlur {
+            target: linear_target(width, height),
+            material: material(&fragment, Vec::new())?,
+            strength,
+        })
+    }
+}
+
+impl PostEffect for MotionBlur {
+    /// Blurs by the zoom between the radius of the last frame and the current one. Returns the input itself if
+    /// there is no motion to blur.
+    fn apply(&self, input: &Texture2D, frame: &FrameInfo) -> Texture2D {
+        // A point at the border moved by this fraction of its distance to the center within the frame.
+        let ratio = frame.radius / frame.previous_radius;
+        let motion = (1.0 - ratio.min(1.0 / ratio)) as f32;
+        let blur = (motion * self.strength).min(MAX_MOTION_BLUR);
+        if !blur.is_finite() || blur <= 0.0 {
+            return input.clone();
+        }
+        self.material.set_uniform("strength", blur);
+        draw_pass(input, &self.target, &self.material);
+        self.target.texture.clone()
+    }
+}
+
+/// Creates a render target that is sampled with linear filtering.
+fn linear_target(width: u32, height: u32) -> RenderTarget {
+    let target = render_target(width, height);
+    target.texture.set_filter(FilterMode::Linear);
+    target
+}
+
+/// Compiles the fragment shader together with the shared vertex shader. All shaders share the same uniforms, the
+/// ones a shader does not declare are ignored.
+fn material(fragment: &str, textures: Vec<String>) -> Result<Material, String> {
+    load_material(
+        ShaderSource::Glsl {
+            vertex: VERTEX_SHADER,
+            fragment,
+        },
+        MaterialParams {
+            uniforms: vec![
+                UniformDesc::new("strength", UniformType::Float1),
+                UniformDesc::new("texel", UniformType::Float2),
+                UniformDesc::new("time", UniformType::Float1),
+            ],
+            textures,
+            ..Default::default()
+        },
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Draws the texture through the material into the whole target. The camera keeps the first image row at the
+/// start of the texture, so all targets share the orientation of the uploaded image.
+fn draw_pass(texture: &Texture2D, target: &RenderTarget, material: &Material) {
+    let size = target.texture.size();
+    set_camera(&Camera2D {
+        zoom: vec2(2.0 / size.x, 2.0 / size.y),
+        target: size * 0.5,
+        render_target: Some(target.clone()),
+        ..Default::default()
+    });
+    gl_use_material(material);
+    draw_texture_ex(
+        texture,
+        0.0,
+        0.0,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(size),
+            ..Default::default()
+        },
+    );
+    gl_use_default_material();
+}
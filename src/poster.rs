@@ -0,0 +1,333 @@
+//! Offline rendering of high resolution stills, which bypasses the interactive loop:
+//! `mandelbrot-explorer render --center -0.75,0.1 --radius 0.01 --size 7680x4320 --out poster.png`
+//!
+//! The image is computed in tiles, so memory stays bounded even for gigapixel renders. Either the tiles are
+//! stitched into a single PNG strip by strip, or they are written as Deep Zoom tile pyramid for web viewers
+//! like OpenSeadragon. The tiles are computed in the precision tier the radius needs, like the window, so stills
+//! reach as deep as the dives.
+//!
+//! With `--raw` the smooth iteration values are written instead of colors, so they can be graded in external
+//! tools without banding. Files ending in `.exr` get 32-bit floats, all others become 16-bit grayscale PNGs.
+//! The smooth values are only computed in double precision.
+
+use crate::color::color_to_rgba8;
+use crate::color_generation::{
+    ColorLookup, PaletteSettings, escape_tracking, generate_colors, generate_escape_colors,
+};
+use crate::config::CONFIG;
+use crate::export::{ViewMetadata, save_png};
+use crate::math::{
+    ComplexNumber, Fractal, Viewport, fractal_formula, get_escape_tile, get_iteration_tile,
+    get_smooth_iteration_tile, max_iter,
+};
+use crate::precision::{DEEPEST_RADIUS, DOUBLE_RADIUS_LIMIT};
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The file the still is written to without `--out`.
+const DEFAULT_OUT: &str = "poster.png";
+
+/// The edge length of the tiles the image is computed in.
+const TILE_SIZE: u32 = 512;
+
+/// The edge length of the tiles in the Deep Zoom pyramid.
+const PYRAMID_TILE_SIZE: u32 = 256;
+
+/// Renders the still described by the config and writes it as PNG or tile pyramid.
+pub fn render_poster_from_config() {
+    let radius = match render_radius_from_config(DEEPEST_RADIUS) {
+        Ok(radius) => radius,
+        Err(err) => {
+            eprintln!("Could not render: {err}");
+            return;
+        }
+    };
+    let (width, height) = CONFIG.render_size;
+    let viewport = Viewport {
+        center: CONFIG.render_center,
+        center_low: ComplexNumber::default(),
+        radius,
+        rotation: ComplexNumber::from_angle(CONFIG.rotation.to_radians()),
+        width,
+        height,
+    };
+    let lookup = ColorLookup::new(palette_settings_from_config(), max_iter());
+
+    let out = Path::new(CONFIG.render_out.as_deref().unwrap_or(DEFAULT_OUT));
+    let result = if CONFIG.render_raw {
+        write_smooth_values(out, &viewport)
+    } else if CONFIG.render_pyramid {
+        write_deep_zoom(&out.with_extension("dzi"), &viewport, &lookup)
+    } else {
+        let metadata = ViewMetadata {
+            fractal: Fractal::Mandelbrot,
+            viewport,
+            palette: CONFIG.palette,
+        };
+        write_stitched_png(out, &viewport, &lookup, &metadata)
+    };
+    match result {
+        Ok(()) => println!("Saved {width}x{height} render to {}", out.display()),
+        Err(err) => eprintln!("Could not save render: {err}"),
+    }
+}
+
+/// The radius of offline renders set in the config. It has to be positive and no smaller than the deepest radius
+/// the renderer resolves, custom formulas only resolve down to the limit of double precision.
+pub fn render_radius_from_config(deepest: f64) -> Result<f64, String> {
+    let deepest = if fractal_formula().is_quadratic() {
+        deepest
+    } else {
+        deepest.max(DOUBLE_RADIUS_LIMIT)
+    };
+    let radius = CONFIG.render_radius;
+    if radius.is_finite() && radius >= deepest {
+        Ok(radius)
+    } else {
+        Err(format!(
+            "the radius {radius} is out of range, expected a radius of at least {deepest:e}"
+        ))
+    }
+}
+
+/// The palette set in the config.
+pub fn palette_settings_from_config() -> PaletteSettings {
+    PaletteSettings {
+        palette: CONFIG.palette,
+        cubehelix: CONFIG.cubehelix,
+        image: CONFIG.palette_image.as_ref(),
+        gradient: CONFIG.gradient,
+        ..Default::default()
+    }
+}
+
+/// Renders a tile of the viewport into RGBA8 rows.
+fn render_tile_rgba(
+    viewport: &Viewport,
+    lookup: &ColorLookup,
+    tile_x: u32,
+    tile_y: u32,
+    tile_width: u32,
+    tile_height: u32,
+) -> Vec<u8> {
+    let settings = CONFIG.iteration_settings();
+    let color_settings = CONFIG.color_settings();
+    let tracking = escape_tracking(CONFIG.coloring, CONFIG.shading, &color_settings);
+    let colors = if let Some(tracking) = tracking {
+        let field = get_escape_tile(
+            viewport,
+            tile_x,
+            tile_y,
+            tile_width,
+            tile_height,
+            tracking,
+            &settings,
+        );
+        generate_escape_colors(
+            &field,
+            tile_width as usize,
+            lookup,
+            CONFIG.coloring,
+            CONFIG.shading,
+            &color_settings,
+        )
+    } else {
+        let field =
+            get_iteration_tile(viewport, tile_x, tile_y, tile_width, tile_height, &settings);
+        generate_colors(&field, tile_width as usize, lookup, &color_settings)
+    };
+    colors.into_iter().flat_map(color_to_rgba8).collect()
+}
+
+/// Renders one strip of tiles after the other and streams it into a single PNG file.
+fn write_stitched_png(
+    path: &Path,
+    viewport: &Viewport,
+    lookup: &ColorLookup,
+    metadata: &ViewMetadata,
+) -> std::io::Result<()> {
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        viewport.width,
+        viewport.height,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    metadata.write_to(&mut encoder)?;
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+
+    let strip_count = viewport.height.div_ceil(TILE_SIZE);
+    let row_bytes = viewport.width as usize * 4;
+    for strip in 0..strip_count {
+        let tile_y = strip * TILE_SIZE;
+        let strip_height = TILE_SIZE.min(viewport.height - tile_y);
+        let tiles: Vec<(u32, Vec<u8>)> = (0..viewport.width.div_ceil(TILE_SIZE))
+            .into_par_iter()
+            .map(|column| {
+                let tile_x = column * TILE_SIZE;
+                let tile_width = TILE_SIZE.min(viewport.width - tile_x);
+                let rgba =
+                    render_tile_rgba(viewport, lookup, tile_x, tile_y, tile_width, strip_height);
+                (tile_width, rgba)
+            })
+            .collect();
+
+        // Stitch the tiles of the strip together row by row.
+        let mut rows = vec![0u8; row_bytes * strip_height as usize];
+        let mut x_offset = 0;
+        for (tile_width, rgba) in tiles {
+            let tile_row_bytes = tile_width as usize * 4;
+            for (row, tile_row) in rgba.chunks_exact(tile_row_bytes).enumerate() {
+                let start = row * row_bytes + x_offset;
+                rows[start..start + tile_row_bytes].copy_from_slice(tile_row);
+            }
+            x_offset += tile_row_bytes;
+        }
+        stream.write_all(&rows)?;
+        report_progress(strip as usize + 1, strip_count as usize);
+    }
+    stream.finish()?;
+    Ok(())
+}
+
+/// Writes the smooth iteration values as EXR or 16-bit PNG depending on the file extension.
+fn write_smooth_values(path: &Path, viewport: &Viewport) -> std::io::Result<()> {
+    let strip_count = viewport.height.div_ceil(TILE_SIZE);
+    let strips = (0..strip_count).map(|strip| {
+        let tile_y = strip * TILE_SIZE;
+        let strip_height = TILE_SIZE.min(viewport.height - tile_y);
+        let values = get_smooth_iteration_tile(viewport, 0, tile_y, viewport.width, strip_height);
+        report_progress(strip as usize + 1, strip_count as usize);
+        values
+    });
+
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exr"))
+    {
+        use exr::prelude::*;
+        let values: Vec<f32> = strips.flatten().collect();
+        let channel = AnyChannel::new("Y", FlatSamples::F32(values));
+        let layer = Layer::new(
+            (viewport.width as usize, viewport.height as usize),
+            LayerAttributes::named("iterations"),
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(SmallVec::from_vec(vec![channel])),
+        );
+        Image::from_layer(layer)
+            .write()
+            .to_file(path)
+            .map_err(std::io::Error::other)
+    } else {
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            viewport.width,
+            viewport.height,
+        );
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        let mut writer = encoder.write_header()?;
+        let mut stream = writer.stream_writer()?;
+        for values in strips {
+            let bytes: Vec<u8> = values
+                .into_iter()
+                .flat_map(|value| {
+                    ((value / max_iter() as f32 * u16::MAX as f32).round() as u16).to_be_bytes()
+                })
+                .collect();
+            stream.write_all(&bytes)?;
+        }
+        stream.finish()?;
+        Ok(())
+    }
+}
+
+/// Writes a Deep Zoom pyramid. Every level is rendered directly at its resolution instead of downsampling the
+/// level above, so no level ever has to be kept in memory.
+fn write_deep_zoom(
+    dzi_path: &Path,
+    viewport: &Viewport,
+    lookup: &ColorLookup,
+) -> std::io::Result<()> {
+    let stem = dzi_path.file_stem().unwrap_or_default().to_string_lossy();
+    let tiles_directory = dzi_path.with_file_name(format!("{stem}_files"));
+    let max_level = viewport
+        .width
+        .max(viewport.height)
+        .next_power_of_two()
+        .ilog2();
+
+    let levels: Vec<(u32, Viewport)> = (0..=max_level)
+        .map(|level| {
+            let scale = 1u32 << (max_level - level);
+            let level_viewport = Viewport {
+                width: viewport.width.div_ceil(scale),
+                height: viewport.height.div_ceil(scale),
+                ..*viewport
+            };
+            (level, level_viewport)
+        })
+        .collect();
+    let tile_count: usize = levels
+        .iter()
+        .map(|(_, v)| {
+            (v.width.div_ceil(PYRAMID_TILE_SIZE) * v.height.div_ceil(PYRAMID_TILE_SIZE)) as usize
+        })
+        .sum();
+    let finished = AtomicUsize::new(0);
+
+    for (level, level_viewport) in &levels {
+        let level_directory = tiles_directory.join(level.to_string());
+        std::fs::create_dir_all(&level_directory)?;
+        let columns = level_viewport.width.div_ceil(PYRAMID_TILE_SIZE);
+        let rows = level_viewport.height.div_ceil(PYRAMID_TILE_SIZE);
+
+        (0..columns * rows).into_par_iter().try_for_each(|tile| {
+            let (column, row) = (tile % columns, tile / columns);
+            let (tile_x, tile_y) = (column * PYRAMID_TILE_SIZE, row * PYRAMID_TILE_SIZE);
+            let tile_width = PYRAMID_TILE_SIZE.min(level_viewport.width - tile_x);
+            let tile_height = PYRAMID_TILE_SIZE.min(level_viewport.height - tile_y);
+            let rgba = render_tile_rgba(
+                level_viewport,
+                lookup,
+                tile_x,
+                tile_y,
+                tile_width,
+                tile_height,
+            );
+            save_png(
+                &level_directory.join(format!("{column}_{row}.png")),
+                tile_width,
+                tile_height,
+                &rgba,
+                None,
+            )?;
+            report_progress(finished.fetch_add(1, Ordering::Relaxed) + 1, tile_count);
+            Ok::<(), std::io::Error>(())
+        })?;
+    }
+
+    std::fs::write(
+        dzi_path,
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" Format=\"png\" Overlap=\"0\" TileSize=\"{PYRAMID_TILE_SIZE}\">\n\
+             \x20 <Size Width=\"{}\" Height=\"{}\"/>\n\
+             </Image>\n",
+            viewport.width, viewport.height
+        ),
+    )
+}
+
+/// Prints the progress on the console.
+pub fn report_progress(done: usize, total: usize) {
+    print!("\rRendering {done}/{total} ({}%)", done * 100 / total);
+    if done == total {
+        println!();
+    }
+    let _ = std::io::stdout().flush();
+}
@@ -0,0 +1,146 @@
+//! Throttles the explorer while a laptop runs on battery. The fields are then computed at a lower resolution, the
+//! frame rate is capped and the computation runs on fewer threads, so the zoom keeps going without draining the
+//! battery. The power source is checked every few seconds.
+
+use crate::config::CONFIG;
+use macroquad::prelude::*;
+use std::sync::Arc;
+
+/// The time between two checks of the power source in seconds.
+const POWER_CHECK_INTERVAL: f64 = 10.0;
+
+/// Follows the power source and provides the throttled settings.
+pub struct PowerMonitor {
+    /// Whether we run on battery.
+    on_battery: bool,
+    /// The time of the last check.
+    last_check: f64,
+    /// The smaller thread pool the computation runs on while on battery, None if throttling is off.
+    battery_pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl PowerMonitor {
+    /// Creates the monitor and checks the power source right away.
+    pub fn new() -> PowerMonitor {
+        let battery_pool = CONFIG
+            .battery_saver
+            .then(build_battery_pool)
+            .flatten()
+            .map(Arc::new);
+        let mut monitor = PowerMonitor {
+            on_battery: false,
+            last_check: f64::NEG_INFINITY,
+            battery_pool,
+        };
+        monitor.poll();
+        monitor
+    }
+
+    /// Checks the power source if it is due.
+    pub fn poll(&mut self) {
+        if !CONFIG.battery_saver || get_time() - self.last_check < POWER_CHECK_INTERVAL {
+            return;
+        }
+        self.last_check = get_time();
+        let on_battery = on_battery_power();
+        if on_battery != self.on_battery {
+            println!(
+                "{}",
+                if on_battery {
+                    "Running on battery, reducing the quality."
+                } else {
+                    "Running on external power, restoring the quality."
+                }
+            );
+            self.on_battery = on_battery;
+        }
+    }
+
+    /// The fraction of the window resolution the fields are computed in.
+    pub fn render_scale(&self) -> f32 {
+        if self.on_battery {
+            CONFIG.battery_render_scale
+        } else {
+            1.0
+        }
+    }
+
+    /// The shortest time a frame may take in seconds, None if the frame rate is not capped.
+    pub fn frame_time(&self) -> Option<f64> {
+        (self.on_battery && CONFIG.battery_fps > 0.0).then(|| 1.0 / CONFIG.battery_fps)
+    }
+
+    /// The smaller thread pool the computation runs on while on battery, None for the global one.
+    pub fn pool(&self) -> Option<Arc<rayon::ThreadPool>> {
+        self.battery_pool.clone().filter(|_| self.on_battery)
+    }
+}
+
+/// Builds the thread pool for battery operation, by default with half the threads of the global pool.
+fn build_battery_pool() -> Option<rayon::ThreadPool> {
+    let threads = match CONFIG.battery_threads {
+        0 => (rayon::current_num_threads() / 2).max(1),
+        threads => threads,
+    };
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .inspect_err(|err| {
+            eprintln!("Could not create the thread pool for battery operation: {err}")
+        })
+        .ok()
+}
+
+/// Whether a battery is discharging.
+#[cfg(target_os = "linux")]
+fn on_battery_power() -> bool {
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    supplies.flatten().any(|supply| {
+        let read =
+            |name: &str| std::fs::read_to_string(supply.path().join(name)).unwrap_or_default();
+        read("type").trim() == "Battery" && read("status").trim() == "Discharging"
+    })
+}
+
+/// Whether the AC line is offline.
+#[cfg(target_os = "windows")]
+fn on_battery_power() -> bool {
+    /// The power status as filled in by the system.
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    let mut status = SystemPowerStatus::default();
+    // SAFETY: The struct matches SYSTEM_POWER_STATUS and outlives the call.
+    let success = unsafe { GetSystemPowerStatus(&mut status) };
+    success != 0 && status.ac_line_status == 0
+}
+
+/// Whether pmset reports the battery as power source.
+#[cfg(target_os = "macos")]
+fn on_battery_power() -> bool {
+    std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+}
+
+/// Other platforms are assumed to run on external power.
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn on_battery_power() -> bool {
+    false
+}
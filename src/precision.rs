@@ -0,0 +1,59 @@
+//! Picks the arithmetic the fields are computed in from the radius of the view, so the zoom keeps going without the
+//! user configuring anything. Each tier takes over where the pixel step of the view approaches the resolution of
+//! the previous one near the set, where the coordinates have a magnitude of about 2.
+
+use crate::math::IterationSettings;
+
+/// Above this radius single precision resolves the pixels.
+const SINGLE_RADIUS_LIMIT: f64 = 1e-3;
+
+/// Above this radius double precision resolves the pixels.
+pub const DOUBLE_RADIUS_LIMIT: f64 = 1e-13;
+
+/// Above this radius double-double arithmetic resolves the pixels.
+const DOUBLE_DOUBLE_RADIUS_LIMIT: f64 = 1e-28;
+
+/// The smallest radius the deepest tier, the 128-bit fixed point, resolves. The dives end here.
+pub const DEEPEST_RADIUS: f64 = 1e-33;
+
+/// The arithmetic a field is computed in, from the fastest to the most precise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PrecisionTier {
+    /// f32, eight pixels at a time.
+    Single,
+    /// f64.
+    Double,
+    /// Double-double with about 106 bits of mantissa.
+    DoubleDouble,
+    /// 128-bit fixed point with 120 fractional bits.
+    FixedPoint,
+}
+
+impl PrecisionTier {
+    /// The fastest tier resolving the pixels of a view with the given radius. With `fixed_point` set the fixed point
+    /// backend replaces double-double, double precision is kept. Formulas other than z² + c only exist in double
+    /// precision.
+    pub fn for_radius(radius: f64, settings: &IterationSettings) -> PrecisionTier {
+        if !settings.formula.is_quadratic() {
+            PrecisionTier::Double
+        } else if radius > SINGLE_RADIUS_LIMIT {
+            PrecisionTier::Single
+        } else if radius > DOUBLE_RADIUS_LIMIT {
+            PrecisionTier::Double
+        } else if radius > DOUBLE_DOUBLE_RADIUS_LIMIT && !settings.fixed_point {
+            PrecisionTier::DoubleDouble
+        } else {
+            PrecisionTier::FixedPoint
+        }
+    }
+
+    /// The name shown in the heads-up display.
+    pub fn name(self) -> &'static str {
+        match self {
+            PrecisionTier::Single => "f32",
+            PrecisionTier::Double => "f64",
+            PrecisionTier::DoubleDouble => "double-double",
+            PrecisionTier::FixedPoint => "fixed 128",
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Remembers the settings changed in the running explorer across restarts. The preferences file in the per-user
+//! config directory uses the keys of the config file and is read before it, so the config file and the command line
+//! still have the last word.
+
+use crate::color_generation::{Coloring, Palette, Shading};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// The directory within the per-user config directory.
+const APP_DIRECTORY: &str = "mandelbrot-explorer";
+
+/// The name of the preferences file.
+const PREFERENCES_FILE_NAME: &str = "preferences.cfg";
+
+/// The settings we remember.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Preferences {
+    /// The palette in use.
+    pub palette: Palette,
+    /// The exterior coloring in use.
+    pub coloring: Coloring,
+    /// The shading in use.
+    pub shading: Shading,
+    /// The zoom speed as multiple of the default speed.
+    pub zoom_speed: f64,
+    /// Whether the heads-up display is shown.
+    pub hud: bool,
+    /// Whether the window covers the screen.
+    pub fullscreen: bool,
+}
+
+impl Preferences {
+    /// Writes the preferences file, creating the directory if needed.
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = preferences_path().ok_or(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no config directory",
+        ))?;
+        let entries = [
+            ("palette", self.palette.name().to_owned()),
+            ("coloring", self.coloring.name().to_owned()),
+            ("shading", self.shading.name().to_owned()),
+            ("zoom-speed", self.zoom_speed.to_string()),
+            ("hud", self.hud.to_string()),
+            ("fullscreen", self.fullscreen.to_string()),
+        ];
+        let mut content = String::new();
+        for (key, value) in entries {
+            let _ = writeln!(content, "{key} = {value}");
+        }
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        std::fs::write(path, content)
+    }
+}
+
+/// The location of the preferences file: the roaming app data on Windows, the application support directory on
+/// macOS and the XDG config directory elsewhere. None if the environment does not tell.
+pub fn preferences_path() -> Option<PathBuf> {
+    let env = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+    let base = if cfg!(target_os = "windows") {
+        PathBuf::from(env("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(env("HOME")?).join("Library/Application Support")
+    } else {
+        env("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(env("HOME")?).join(".config")))?
+    };
+    Some(base.join(APP_DIRECTORY).join(PREFERENCES_FILE_NAME))
+}
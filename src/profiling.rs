@@ -0,0 +1,156 @@
+//! Profiler scopes around the stages of a frame. With the `profiler` feature the scopes are recorded with puffin,
+//! served for `puffin_viewer` on the default puffin port and drawn as flame graph of the last frame with F3.
+//! Without the feature [`profile_scope`] expands to nothing.
+
+pub(crate) use mandelbrot_explorer::profile_scope;
+
+#[cfg(feature = "profiler")]
+pub use flame::Profiler;
+
+#[cfg(feature = "profiler")]
+mod flame {
+    use macroquad::prelude::*;
+    use puffin::{GlobalFrameView, Reader, ScopeCollection, Stream};
+
+    /// The height of a bar in the flame graph in pixels.
+    const BAR_HEIGHT: f32 = 18.0;
+
+    /// The font size of the scope names.
+    const FONT_SIZE: f32 = 16.0;
+
+    /// The distance of the graph to the screen border in pixels.
+    const MARGIN: f32 = 12.0;
+
+    /// The colors the nesting levels cycle through.
+    const LEVEL_COLORS: [Color; 3] = [
+        Color::new(0.85, 0.45, 0.15, 0.85),
+        Color::new(0.85, 0.65, 0.15, 0.85),
+        Color::new(0.75, 0.3, 0.3, 0.85),
+    ];
+
+    /// Records the scopes and shows them.
+    pub struct Profiler {
+        /// Serves the scopes to external viewers, None if the port was taken.
+        _server: Option<puffin_http::Server>,
+        /// The recorded frames for the flame graph.
+        frame_view: GlobalFrameView,
+        /// Whether the flame graph is shown.
+        shown: bool,
+    }
+
+    impl Profiler {
+        /// Starts recording and serving the scopes.
+        pub fn new() -> Profiler {
+            puffin::set_scopes_on(true);
+            let address = format!("127.0.0.1:{}", puffin_http::DEFAULT_PORT);
+            let server = puffin_http::Server::new(&address)
+                .inspect_err(|err| eprintln!("Could not serve the profiler scopes: {err}"))
+                .ok();
+            Profiler {
+                _server: server,
+                frame_view: GlobalFrameView::default(),
+                shown: false,
+            }
+        }
+
+        /// Finishes the recorded frame and starts the next one.
+        pub fn new_frame(&self) {
+            puffin::GlobalProfiler::lock().new_frame();
+        }
+
+        /// Shows or hides the flame graph.
+        pub fn toggle(&mut self) {
+            self.shown = !self.shown;
+        }
+
+        /// Draws the flame graph of the last finished frame at the bottom of the screen, one block of rows per
+        /// thread.
+        pub fn draw(&self) {
+            if !self.shown {
+                return;
+            }
+            let view = self.frame_view.lock();
+            let Some(frame) = view.latest_frame() else {
+                return;
+            };
+            let Ok(frame) = frame.unpacked() else {
+                return;
+            };
+            let (start_ns, end_ns) = frame.range_ns();
+            let graph = FlameGraph {
+                scopes: view.scope_collection(),
+                start_ns,
+                ns_per_pixel: (end_ns - start_ns).max(1) as f32 / (screen_width() - 2.0 * MARGIN),
+            };
+
+            let rows: usize = frame
+                .thread_streams
+                .values()
+                .map(|info| info.depth + 1)
+                .sum();
+            let mut y = screen_height() - MARGIN - rows as f32 * BAR_HEIGHT;
+            draw_rectangle(
+                0.0,
+                y - FONT_SIZE - MARGIN,
+                screen_width(),
+                screen_height() - y + FONT_SIZE + MARGIN,
+                Color::new(0.0, 0.0, 0.0, 0.7),
+            );
+            draw_text(
+                &format!(
+                    "frame {} {:.2} ms",
+                    frame.frame_index(),
+                    (end_ns - start_ns) as f64 / 1e6
+                ),
+                MARGIN,
+                y - MARGIN * 0.5,
+                FONT_SIZE,
+                WHITE,
+            );
+            for (thread, info) in &frame.thread_streams {
+                draw_text(&thread.name, MARGIN, y + FONT_SIZE * 0.8, FONT_SIZE, GRAY);
+                y += BAR_HEIGHT;
+                graph.draw_level(&info.stream, Reader::from_start(&info.stream), y, 0);
+                y += info.depth as f32 * BAR_HEIGHT;
+            }
+        }
+    }
+
+    /// Places the scopes of a frame on screen.
+    struct FlameGraph<'a> {
+        /// The names of the scopes.
+        scopes: &'a ScopeCollection,
+        /// The start of the frame.
+        start_ns: i64,
+        /// The time per pixel.
+        ns_per_pixel: f32,
+    }
+
+    impl FlameGraph<'_> {
+        /// Draws the scopes read by the reader as bars in the row at y and their children below.
+        fn draw_level(&self, stream: &Stream, reader: Reader<'_>, y: f32, level: usize) {
+            for scope in reader.map_while(Result::ok) {
+                let x = MARGIN + (scope.record.start_ns - self.start_ns) as f32 / self.ns_per_pixel;
+                let width = (scope.record.duration_ns as f32 / self.ns_per_pixel).max(1.0);
+                draw_rectangle(
+                    x,
+                    y,
+                    width,
+                    BAR_HEIGHT - 1.0,
+                    LEVEL_COLORS[level % LEVEL_COLORS.len()],
+                );
+                let name = self
+                    .scopes
+                    .fetch_by_id(&scope.id)
+                    .map_or("?", |details| details.name().as_ref());
+                let label = format!("{name} {:.2} ms", scope.record.duration_ns as f64 / 1e6);
+                if measure_text(&label, None, FONT_SIZE as u16, 1.0).width < width - 4.0 {
+                    draw_text(&label, x + 2.0, y + FONT_SIZE * 0.8, FONT_SIZE, BLACK);
+                }
+                if let Ok(children) = Reader::with_offset(stream, scope.child_begin_position) {
+                    self.draw_level(stream, children, y + BAR_HEIGHT, level + 1);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Records the rendered frames. A writer thread encodes the frames, so the render loop never waits for the disk.
+//! If the writer falls behind, frames are dropped and counted instead of stalling the animation.
+
+use crate::export::{save_png, time_stamp};
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use macroquad::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+use std::thread::JoinHandle;
+
+/// The directory recordings are stored in.
+const RECORDING_DIRECTORY: &str = "recordings";
+
+/// The amount of frames that may wait for the writer.
+const QUEUE_SIZE: usize = 8;
+
+/// The nominal frame rate written into the y4m header.
+const Y4M_FRAME_RATE: u32 = 30;
+
+/// The file formats we can record into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingFormat {
+    /// One numbered PNG file per frame.
+    PngSequence,
+    /// A single uncompressed YUV4MPEG2 video, which can be fed into ffmpeg.
+    Y4m,
+}
+
+impl RecordingFormat {
+    /// Looks up a format by its config name.
+    pub fn from_name(name: &str) -> Option<RecordingFormat> {
+        match name {
+            "png" => Some(RecordingFormat::PngSequence),
+            "y4m" => Some(RecordingFormat::Y4m),
+            _ => None,
+        }
+    }
+}
+
+/// A running recording.
+pub struct Recorder {
+    /// Hands the RGBA8 frames over to the writer thread.
+    sender: SyncSender<Vec<u8>>,
+    /// The writer thread, which ends once the sender is dropped.
+    writer: JoinHandle<std::io::Result<()>>,
+    /// The frames handed over to the writer.
+    recorded_frames: usize,
+    /// The frames dropped because the writer was busy.
+    dropped_frames: usize,
+    /// Where the recording is stored.
+    path: PathBuf,
+}
+
+impl Recorder {
+    /// Starts a new recording in the given format.
+    pub fn start(format: RecordingFormat) -> std::io::Result<Recorder> {
+        std::fs::create_dir_all(RECORDING_DIRECTORY)?;
+        let base = Path::new(RECORDING_DIRECTORY).join(format!("recording_{}", time_stamp()));
+        let (sender, receiver) = sync_channel(QUEUE_SIZE);
+
+        let (path, writer) = match format {
+            RecordingFormat::PngSequence => {
+                std::fs::create_dir_all(&base)?;
+                let directory = base.clone();
+                (
+                    base,
+                    std::thread::spawn(move || write_png_sequence(&directory, receiver)),
+                )
+            }
+            RecordingFormat::Y4m => {
+                let path = base.with_extension("y4m");
+                let file = BufWriter::new(File::create(&path)?);
+                (path, std::thread::spawn(move || write_y4m(file, receiver)))
+            }
+        };
+
+        Ok(Recorder {
+            sender,
+            writer,
+            recorded_frames: 0,
+            dropped_frames: 0,
+            path,
+        })
+    }
+
+    /// Hands a frame over to the writer, or drops it if the writer is busy.
+    pub fn push_frame(&mut self, rgba: &[u8]) {
+        match self.sender.try_send(rgba.to_vec()) {
+            Ok(()) => self.recorded_frames += 1,
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.dropped_frames += 1
+            }
+        }
+    }
+
+    /// Finishes the recording and waits for the writer to flush the remaining frames.
+    pub fn stop(self) {
+        drop(self.sender);
+        match self.writer.join() {
+            Ok(Ok(())) => println!(
+                "Saved recording with {} frames ({} dropped) to {}",
+                self.recorded_frames,
+                self.dropped_frames,
+                self.path.display()
+            ),
+            Ok(Err(err)) => eprintln!("Recording failed: {err}"),
+            Err(_) => eprintln!("Recording thread panicked"),
+        }
+    }
+
+    /// Draws a blinking record symbol with the frame count in the upper left corner.
+    pub fn draw_indicator(&self) {
+        if get_time().fract() < 0.5 {
+            draw_circle(24.0, 24.0, 10.0, RED);
+        }
+        let mut text = format!("REC {}", self.recorded_frames);
+        if self.dropped_frames > 0 {
+            text += &format!(" ({} dropped)", self.dropped_frames);
+        }
+        draw_text(&text, 42.0, 31.0, 24.0, WHITE);
+    }
+}
+
+/// Writes every received frame as numbered PNG file.
+fn write_png_sequence(directory: &Path, receiver: Receiver<Vec<u8>>) -> std::io::Result<()> {
+    for (index, rgba) in receiver.into_iter().enumerate() {
+        let path = directory.join(format!("frame_{index:06}.png"));
+        save_png(
+            &path,
+            WINDOW_WIDTH as u32,
+            WINDOW_HEIGHT as u32,
+            &rgba,
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes all received frames into a YUV4MPEG2 stream with full chroma resolution.
+fn write_y4m(mut file: BufWriter<File>, receiver: Receiver<Vec<u8>>) -> std::io::Result<()> {
+    writeln!(
+        file,
+        "YUV4MPEG2 W{WINDOW_WIDTH} H{WINDOW_HEIGHT} F{Y4M_FRAME_RATE}:1 Ip A1:1 C444"
+    )?;
+    let pixel_count = (WINDOW_WIDTH * WINDOW_HEIGHT) as usize;
+    let mut planes = vec![0u8; pixel_count * 3];
+
+    for rgba in receiver {
+        for (idx, pixel) in rgba.chunks_exact(4).enumerate() {
+            let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+            // BT.601 conversion into the limited video range.
+            planes[idx] = (16.0 + 0.257 * r + 0.504 * g + 0.098 * b) as u8;
+            planes[idx + pixel_count] = (128.0 - 0.148 * r - 0.291 * g + 0.439 * b) as u8;
+            planes[idx + 2 * pixel_count] = (128.0 + 0.439 * r - 0.368 * g - 0.071 * b) as u8;
+        }
+        file.write_all(b"FRAME\n")?;
+        file.write_all(&planes)?;
+    }
+    file.flush()
+}
@@ -0,0 +1,173 @@
+//! Optional HTTP remote control. A background thread answers simple GET or POST requests, translates them into
+//! commands for the main loop and reports the latest published status. All answers are JSON.
+//!
+//! There is no authentication, so the server only listens on the local machine unless `remote-bind` opens it to
+//! the network.
+//!
+//! Endpoints:
+//! - `/status`: current center, radius, palette and pause state.
+//! - `/target?re=<real>&im=<imag>&radius=<radius>`: moves the view to the given location.
+//! - `/pause`, `/resume`: stops and resumes the animation.
+//! - `/next`: leaves the current dive.
+//! - `/palette?name=<name>`, `/palette/next`: switches the palette.
+//! - `/screenshot`: saves the current frame.
+
+use crate::color_generation::Palette;
+use crate::commands::Command;
+use crate::goto_dialog::check_location;
+use crate::math::{CancellationToken, ComplexNumber};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The time we wait for a client to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The state of the explorer as reported by `/status`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoteStatus {
+    /// The center of the view.
+    pub center: ComplexNumber,
+    /// The radius of the view.
+    pub radius: f64,
+    /// The active palette.
+    pub palette: Palette,
+    /// Whether the animation is paused.
+    pub paused: bool,
+}
+
+/// The main loop side of the remote control.
+pub struct RemoteControl {
+    /// The commands received from clients.
+    receiver: Receiver<Command>,
+    /// The status shared with the server thread.
+    status: Arc<Mutex<RemoteStatus>>,
+}
+
+impl RemoteControl {
+    /// Starts the server thread on the given address and port. Returns None, if the port can not be opened.
+    /// Commands that move the view cancel the running computation through the token.
+    pub fn start(address: &str, port: u16, cancel: CancellationToken) -> Option<RemoteControl> {
+        let listener = TcpListener::bind((address, port))
+            .inspect_err(|err| {
+                eprintln!("Could not start remote control on {address} port {port}: {err}")
+            })
+            .ok()?;
+        let (sender, receiver) = channel();
+        let status = Arc::new(Mutex::new(RemoteStatus::default()));
+        let thread_status = status.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle_connection(stream, &sender, &thread_status, &cancel);
+            }
+        });
+
+        Some(RemoteControl { receiver, status })
+    }
+
+    /// Appends the commands received since the last call.
+    pub fn poll(&self, commands: &mut Vec<Command>) {
+        commands.extend(self.receiver.try_iter());
+    }
+
+    /// Makes the current status available to clients.
+    pub fn publish(&self, status: RemoteStatus) {
+        if let Ok(mut shared) = self.status.lock() {
+            *shared = status;
+        }
+    }
+}
+
+/// Reads a single request, executes it and writes the answer.
+fn handle_connection(
+    stream: TcpStream,
+    sender: &Sender<Command>,
+    status: &Mutex<RemoteStatus>,
+    cancel: &CancellationToken,
+) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let mut request_line = String::new();
+    if BufReader::new(&stream)
+        .read_line(&mut request_line)
+        .is_err()
+    {
+        return;
+    }
+    // The request line has the form `METHOD /path?query HTTP/1.1`.
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let response = match parse_command(path, query) {
+        Ok(Some(command)) => {
+            if command.retargets() {
+                cancel.cancel();
+            }
+            let _ = sender.send(command);
+            Ok("{\"ok\":true}".to_owned())
+        }
+        Ok(None) => Ok(status_json(&status.lock().map(|s| *s).unwrap_or_default())),
+        Err(message) => Err(message),
+    };
+
+    let (code, body) = match response {
+        Ok(body) => ("200 OK", body),
+        Err(message) => ("400 Bad Request", format!("{{\"error\":\"{message}\"}}")),
+    };
+    let _ = write!(
+        &stream,
+        "HTTP/1.1 {code}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+/// Translates a request into a command. Returns Ok(None) for the status request.
+fn parse_command(path: &str, query: &str) -> Result<Option<Command>, String> {
+    let parameter = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+            .ok_or(format!("missing parameter {name}"))
+    };
+    let number = |name: &str| {
+        parameter(name)?
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or(format!("parameter {name} is no number"))
+    };
+
+    match path.trim_end_matches('/') {
+        "" | "/status" => Ok(None),
+        "/target" => check_location(
+            ComplexNumber::new(number("re")?, number("im")?),
+            number("radius")?,
+        )
+        .map(|(center, radius)| Some(Command::GoTo(center, ComplexNumber::default(), radius))),
+        "/pause" => Ok(Some(Command::SetPaused(true))),
+        "/resume" => Ok(Some(Command::SetPaused(false))),
+        "/next" => Ok(Some(Command::NextTarget)),
+        "/palette" => Palette::from_name(parameter("name")?)
+            .map(|palette| Some(Command::SetPalette(palette)))
+            .ok_or("unknown palette".to_owned()),
+        "/palette/next" => Ok(Some(Command::NextPalette)),
+        "/screenshot" => Ok(Some(Command::Screenshot)),
+        _ => Err("unknown endpoint".to_owned()),
+    }
+}
+
+/// Formats the status as JSON object.
+fn status_json(status: &RemoteStatus) -> String {
+    format!(
+        "{{\"real\":{},\"imag\":{},\"radius\":{},\"palette\":\"{}\",\"paused\":{}}}",
+        status.center.real,
+        status.center.imag,
+        status.radius,
+        status.palette.name(),
+        status.paused
+    )
+}
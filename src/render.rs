@@ -0,0 +1,321 @@
+//! Deterministic offscreen rendering of a single frame, independent of any window. [`render_frame`] turns a fully
+//! specified view into RGBA8 pixels, which makes it the reference for golden image checks: the test of this module
+//! and `mandelbrot-explorer golden` render a fixed set of views and compare their hashes with the ones stored in
+//! `golden-hashes.txt`, `golden --update` stores the current hashes after an intended change.
+//!
+//! Nothing besides the [`RenderParams`] goes into a frame, so the check does not depend on the config. The hashes
+//! depend on the floating point behavior of the platform, they are only comparable between builds for the same
+//! target.
+
+use crate::color::color_to_rgba8;
+use crate::color_generation::{
+    ColorLookup, ColorSettings, Coloring, Palette, PaletteSettings, Shading, escape_tracking,
+    generate_colors, generate_escape_colors,
+};
+use crate::math::{
+    ComplexNumber, Fractal, IterationSettings, Viewport, get_fractal_escape_tile,
+    get_fractal_iteration_tile,
+};
+use std::fmt::Write;
+
+/// The file the reference hashes are stored in.
+const GOLDEN_FILE_NAME: &str = "golden-hashes.txt";
+
+/// The width of the golden frames, small enough for the check to take seconds.
+const GOLDEN_WIDTH: u32 = 320;
+
+/// The height of the golden frames.
+const GOLDEN_HEIGHT: u32 = 180;
+
+/// Everything that determines a frame.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderParams {
+    /// The fractal to render.
+    pub fractal: Fractal,
+    /// The center of the view.
+    pub center: ComplexNumber,
+    /// The radius of the view, which corresponds to half the height.
+    pub radius: f64,
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+    /// The palette and its parameters.
+    pub palette_settings: PaletteSettings,
+    /// How the points outside the set are colored.
+    pub coloring: Coloring,
+    /// How the colors are lit.
+    pub shading: Shading,
+    /// The stripe frequency, the light and the dithering.
+    pub color_settings: ColorSettings,
+    /// The formula, the iteration limit and the arithmetic beyond double precision.
+    pub iteration: IterationSettings,
+}
+
+/// A rendered frame as RGBA8 pixels, row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaBuffer {
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+    /// Four bytes per pixel.
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaBuffer {
+    /// A 64-bit FNV-1a hash of the size and the pixels. Unlike the std hasher it is stable across Rust versions.
+    pub fn hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        self.width
+            .to_le_bytes()
+            .iter()
+            .chain(self.height.to_le_bytes().iter())
+            .chain(self.pixels.iter())
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(PRIME)
+            })
+    }
+}
+
+/// Renders the frame described by the parameters.
+pub fn render_frame(params: &RenderParams) -> RgbaBuffer {
+    let viewport = Viewport {
+        center: params.center,
+        center_low: ComplexNumber::default(),
+        radius: params.radius,
+        rotation: ComplexNumber::ONE,
+        width: params.width,
+        height: params.height,
+    };
+    let lookup = ColorLookup::new(params.palette_settings, params.iteration.max_iter);
+    let width = params.width as usize;
+    let color_settings = &params.color_settings;
+    let tracking = escape_tracking(params.coloring, params.shading, color_settings);
+    let colors = if let Some(tracking) = tracking {
+        let field = get_fractal_escape_tile(
+            &viewport,
+            params.fractal,
+            0,
+            0,
+            params.width,
+            params.height,
+            tracking,
+            &params.iteration,
+        );
+        generate_escape_colors(
+            &field,
+            width,
+            &lookup,
+            params.coloring,
+            params.shading,
+            color_settings,
+        )
+    } else {
+        let field = get_fractal_iteration_tile(
+            &viewport,
+            params.fractal,
+            0,
+            0,
+            params.width,
+            params.height,
+            &params.iteration,
+        );
+        generate_colors(&field, width, &lookup, color_settings)
+    };
+    RgbaBuffer {
+        width: params.width,
+        height: params.height,
+        pixels: colors.into_iter().flat_map(color_to_rgba8).collect(),
+    }
+}
+
+/// The views of the golden check with their names. Together they cover both fractals, every coloring and shading,
+/// a few palettes, and the precision tiers beyond double precision with and without perturbation.
+fn golden_cases() -> Vec<(&'static str, RenderParams)> {
+    let base = RenderParams {
+        fractal: Fractal::Mandelbrot,
+        center: ComplexNumber::new(-0.5, 0.0),
+        radius: 1.5,
+        width: GOLDEN_WIDTH,
+        height: GOLDEN_HEIGHT,
+        palette_settings: PaletteSettings::default(),
+        coloring: Coloring::Iteration,
+        shading: Shading::Flat,
+        color_settings: ColorSettings::default(),
+        iteration: IterationSettings::default(),
+    };
+    let seahorse = RenderParams {
+        center: ComplexNumber::new(-0.7453, 0.1127),
+        radius: 6.5e-4,
+        ..base
+    };
+    let with_palette = |params: RenderParams, palette: Palette| RenderParams {
+        palette_settings: PaletteSettings {
+            palette,
+            ..params.palette_settings
+        },
+        ..params
+    };
+    let mut cases = vec![
+        ("overview", base),
+        (
+            "overview-fire",
+            with_palette(base, Palette::from_name("fire").unwrap_or_default()),
+        ),
+        ("seahorse", seahorse),
+        (
+            "seahorse-viridis",
+            with_palette(seahorse, Palette::from_name("viridis").unwrap_or_default()),
+        ),
+        (
+            "seahorse-binary",
+            RenderParams {
+                coloring: Coloring::BinaryDecomposition,
+                ..seahorse
+            },
+        ),
+        (
+            "seahorse-triangle",
+            RenderParams {
+                coloring: Coloring::TriangleInequality,
+                ..seahorse
+            },
+        ),
+        (
+            "seahorse-stripes",
+            RenderParams {
+                coloring: Coloring::Stripes,
+                ..seahorse
+            },
+        ),
+        (
+            "overview-atom-domain",
+            RenderParams {
+                coloring: Coloring::AtomDomain,
+                ..base
+            },
+        ),
+        (
+            "seahorse-slope",
+            RenderParams {
+                shading: Shading::Slope,
+                ..seahorse
+            },
+        ),
+        (
+            "seahorse-relief",
+            RenderParams {
+                shading: Shading::Relief,
+                ..seahorse
+            },
+        ),
+        (
+            "julia",
+            RenderParams {
+                fractal: Fractal::Julia(ComplexNumber::new(-0.8, 0.156)),
+                center: ComplexNumber::default(),
+                ..base
+            },
+        ),
+    ];
+    // The dendrite around c = i, which is exact in f64 and shows detail at any depth. The radii lie in the range of
+    // double-double, close to its limit and in the range of fixed point.
+    for (name, direct_name, radius) in [
+        ("dendrite-1e-16", "dendrite-1e-16-direct", 1e-16),
+        ("dendrite-1e-25", "dendrite-1e-25-direct", 1e-25),
+        ("dendrite-1e-31", "dendrite-1e-31-direct", 1e-31),
+    ] {
+        let dendrite = RenderParams {
+            center: ComplexNumber::new(0.0, 1.0),
+            radius,
+            ..base
+        };
+        cases.push((name, dendrite));
+        cases.push((
+            direct_name,
+            RenderParams {
+                iteration: IterationSettings {
+                    perturbation: false,
+                    ..dendrite.iteration
+                },
+                ..dendrite
+            },
+        ));
+    }
+    cases
+}
+
+/// Renders the golden views and returns a line with the name and the hash per view.
+fn golden_hashes() -> String {
+    let mut current = String::new();
+    for (name, params) in golden_cases() {
+        let _ = writeln!(current, "{name} {:016x}", render_frame(&params).hash());
+    }
+    current
+}
+
+/// Renders the golden views and compares their hashes with the ones stored in the working directory, or stores
+/// them if `update` is set. Returns whether all hashes matched.
+pub fn check_golden(update: bool) -> bool {
+    let current = golden_hashes();
+    if update {
+        return match std::fs::write(GOLDEN_FILE_NAME, &current) {
+            Ok(()) => {
+                println!("Stored the golden hashes in {GOLDEN_FILE_NAME}.");
+                true
+            }
+            Err(err) => {
+                eprintln!("Could not store the golden hashes: {err}");
+                false
+            }
+        };
+    }
+
+    let stored = match std::fs::read_to_string(GOLDEN_FILE_NAME) {
+        Ok(stored) => stored,
+        Err(err) => {
+            eprintln!("Could not read {GOLDEN_FILE_NAME}: {err}");
+            return false;
+        }
+    };
+    compare_golden(&current, &stored)
+}
+
+/// Prints for every current hash whether it matches the stored one of its view. Returns whether all matched.
+fn compare_golden(current: &str, stored: &str) -> bool {
+    let mut passed = true;
+    for line in current.lines() {
+        let name = line.split_whitespace().next().unwrap_or_default();
+        let expected = stored
+            .lines()
+            .find(|stored| stored.split_whitespace().next() == Some(name));
+        match expected {
+            Some(expected) if expected.trim() == line => println!("ok       {name}"),
+            Some(_) => {
+                println!("changed  {name}");
+                passed = false;
+            }
+            None => {
+                println!("missing  {name}");
+                passed = false;
+            }
+        }
+    }
+    passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden_hashes_match() {
+        let stored = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/golden-hashes.txt"));
+        assert!(
+            compare_golden(&golden_hashes(), stored),
+            "the golden frames changed, run `golden --update` after an intended change"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Limits when the explorer runs, for displays in kiosks and offices: `--duration 30m` quits after the given time,
+//! `--schedule 22:00-07:00` only shows the zoom within these hours of the local time and a black screen outside.
+
+/// The minutes of a day.
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// A daily time window, which may wrap around midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    /// The first minute of the day in the window.
+    start: u32,
+    /// The first minute of the day after the window.
+    end: u32,
+}
+
+impl Schedule {
+    /// Reads a window given as `HH:MM-HH:MM`.
+    pub fn parse(value: &str) -> Result<Schedule, String> {
+        let (start, end) = value
+            .split_once('-')
+            .ok_or("expected a time window like 22:00-07:00".to_owned())?;
+        Ok(Schedule {
+            start: parse_time_of_day(start.trim())?,
+            end: parse_time_of_day(end.trim())?,
+        })
+    }
+
+    /// Whether the minute of the day lies in the window. Equal start and end cover the whole day.
+    pub fn contains(&self, minute: u32) -> bool {
+        if self.start <= self.end {
+            self.start == self.end || (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// Whether the window contains the current local time.
+    pub fn is_active_now(&self) -> bool {
+        self.contains(local_minute_of_day())
+    }
+}
+
+/// Reads a time of day given as `HH:MM` into the minute of the day.
+fn parse_time_of_day(value: &str) -> Result<u32, String> {
+    let (hours, minutes) = value
+        .split_once(':')
+        .ok_or(format!("expected HH:MM instead of `{value}`"))?;
+    let hours: u32 = hours
+        .parse()
+        .map_err(|_| format!("can not parse the hours of `{value}`"))?;
+    let minutes: u32 = minutes
+        .parse()
+        .map_err(|_| format!("can not parse the minutes of `{value}`"))?;
+    if hours > 24 || minutes > 59 || (hours == 24 && minutes > 0) {
+        return Err(format!("`{value}` is no time of day"));
+    }
+    Ok((hours * 60 + minutes) % MINUTES_PER_DAY)
+}
+
+/// Reads a duration in seconds given as number with unit, like `90s`, `30m`, `2h` or `1h30m`. A plain number
+/// counts minutes.
+pub fn parse_duration(value: &str) -> Result<f64, String> {
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    for character in value.trim().chars() {
+        if character.is_ascii_digit() || character == '.' {
+            number.push(character);
+            continue;
+        }
+        let unit = match character {
+            's' => 1.0,
+            'm' => 60.0,
+            'h' => 3600.0,
+            'd' => 86400.0,
+            _ => return Err(format!("unknown unit `{character}`, use s, m, h or d")),
+        };
+        let amount: f64 = std::mem::take(&mut number)
+            .parse()
+            .map_err(|_| format!("expected a number before `{character}`"))?;
+        seconds += amount * unit;
+    }
+    if !number.is_empty() {
+        seconds += number
+            .parse::<f64>()
+            .map_err(|_| format!("can not parse `{number}`"))?
+            * 60.0;
+    }
+    if seconds <= 0.0 {
+        return Err("the duration must be positive".to_owned());
+    }
+    Ok(seconds)
+}
+
+/// A point in local time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalTime {
+    /// The year.
+    pub year: u32,
+    /// The month, from 1 to 12.
+    pub month: u32,
+    /// The day of the month, from 1.
+    pub day: u32,
+    /// The day of the week, from 0 for Sunday to 6.
+    pub weekday: u32,
+    /// The hour, from 0 to 23.
+    pub hour: u32,
+    /// The minute.
+    pub minute: u32,
+    /// The second.
+    pub second: u32,
+}
+
+/// The minute of the day in local time.
+fn local_minute_of_day() -> u32 {
+    let time = local_time();
+    time.hour * 60 + time.minute
+}
+
+/// The current local time.
+#[cfg(target_os = "windows")]
+pub fn local_time() -> LocalTime {
+    /// The calendar time as filled in by the system.
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemTime {
+        year: u16,
+        month: u16,
+        day_of_week: u16,
+        day: u16,
+        hour: u16,
+        minute: u16,
+        second: u16,
+        milliseconds: u16,
+    }
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetLocalTime(time: *mut SystemTime);
+    }
+
+    let mut time = SystemTime::default();
+    // SAFETY: The struct matches SYSTEMTIME and outlives the call.
+    unsafe { GetLocalTime(&mut time) };
+    LocalTime {
+        year: time.year as u32,
+        month: time.month as u32,
+        day: time.day as u32,
+        weekday: time.day_of_week as u32,
+        hour: time.hour as u32,
+        minute: time.minute as u32,
+        second: time.second as u32,
+    }
+}
+
+/// The current local time, UTC if the platform can not tell the time zone.
+#[cfg(not(target_os = "windows"))]
+pub fn local_time() -> LocalTime {
+    /// The broken down time of the C library. The trailing offset and zone name exist on all unix systems we
+    /// build for.
+    #[repr(C)]
+    struct Tm {
+        sec: i32,
+        min: i32,
+        hour: i32,
+        mday: i32,
+        mon: i32,
+        year: i32,
+        wday: i32,
+        yday: i32,
+        isdst: i32,
+        gmtoff: i64,
+        zone: *const u8,
+    }
+
+    unsafe extern "C" {
+        fn localtime_r(time: *const i64, result: *mut Tm) -> *mut Tm;
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let mut tm = Tm {
+        sec: 0,
+        min: 0,
+        hour: 0,
+        mday: 0,
+        mon: 0,
+        year: 0,
+        wday: 0,
+        yday: 0,
+        isdst: 0,
+        gmtoff: 0,
+        zone: std::ptr::null(),
+    };
+    // SAFETY: Both pointers are valid for the call, the result is only read after it succeeded.
+    if unsafe { localtime_r(&now, &mut tm) }.is_null() {
+        return utc_time(now);
+    }
+    LocalTime {
+        year: (tm.year + 1900) as u32,
+        month: (tm.mon + 1) as u32,
+        day: tm.mday as u32,
+        weekday: tm.wday as u32,
+        hour: tm.hour as u32,
+        minute: tm.min as u32,
+        second: tm.sec as u32,
+    }
+}
+
+/// The UTC time of the seconds since the unix epoch, with the civil calendar computed after Howard Hinnant.
+#[cfg(not(target_os = "windows"))]
+fn utc_time(seconds: i64) -> LocalTime {
+    let days = seconds.div_euclid(86400);
+    let second_of_day = seconds.rem_euclid(86400);
+    // Days since 0000-03-01, in eras of 400 years, so the leap day ends each year.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    LocalTime {
+        year: year as u32,
+        month: month as u32,
+        day: (day_of_year - (153 * month_index + 2) / 5 + 1) as u32,
+        // The epoch was a Thursday.
+        weekday: (days + 4).rem_euclid(7) as u32,
+        hour: (second_of_day / 3600) as u32,
+        minute: (second_of_day / 60 % 60) as u32,
+        second: (second_of_day % 60) as u32,
+    }
+}
@@ -0,0 +1,205 @@
+//! Implements the start-on-idle behavior, which turns the app into a drop-in screensaver. The window waits
+//! small and idle until the system has seen no user input for the configured time, then goes fullscreen and
+//! returns to waiting on the first input.
+//!
+//! miniquad can neither hide nor minimize its window, so the waiting window stays visible on the desktop. The idle
+//! time has to come from the operating system: input to our own window alone would miss somebody typing in
+//! another program, so without a working query the idle mode is refused.
+
+use macroquad::prelude::*;
+
+/// Why the idle mode is refused on this system.
+#[cfg(target_os = "linux")]
+const IDLE_QUERY_MISSING: &str = "idle-start needs `xprintidle` on X11 or the idle monitor of GNOME on Wayland \
+     to see the input to other programs";
+
+/// Why the idle mode is refused on this system.
+#[cfg(not(target_os = "linux"))]
+const IDLE_QUERY_MISSING: &str = "idle-start can not query the system idle time on this platform";
+
+/// The size of the window while we wait.
+const WAITING_WINDOW_SIZE: (u32, u32) = (320, 180);
+
+/// The time we sleep per frame while waiting, to keep the power draw down.
+const WAITING_SLEEP: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Mouse movement in pixels that we still consider as jitter and not as user input.
+const MOUSE_JITTER: f32 = 2.0;
+
+/// Keeps track of whether we are waiting or showing the zoom.
+pub struct IdleLauncher {
+    /// The idle time in seconds after which we start.
+    idle_threshold: f64,
+    /// Whether the zoom is currently running in fullscreen.
+    running: bool,
+    /// Sees the input to our own window, which ends the fullscreen zoom.
+    local_input: LocalInput,
+}
+
+impl IdleLauncher {
+    /// Creates the launcher in waiting state. Fails if the system idle time can not be queried.
+    pub fn new(idle_minutes: f64) -> Result<IdleLauncher, String> {
+        if system_idle_seconds().is_none() {
+            return Err(IDLE_QUERY_MISSING.to_owned());
+        }
+        enter_waiting_window();
+        Ok(IdleLauncher {
+            idle_threshold: idle_minutes * 60.0,
+            running: false,
+            local_input: LocalInput::new(),
+        })
+    }
+
+    /// Updates the state and returns true, if the zoom should be rendered this frame.
+    pub fn update(&mut self) -> bool {
+        let input_seen = self.local_input.detect();
+        if self.running {
+            if input_seen {
+                self.running = false;
+                enter_waiting_window();
+            }
+        } else {
+            // A query failing on the way keeps us waiting.
+            let idle_time = system_idle_seconds().unwrap_or(0.0);
+            if idle_time >= self.idle_threshold {
+                self.running = true;
+                miniquad::window::set_fullscreen(true);
+                show_mouse(false);
+            } else {
+                std::thread::sleep(WAITING_SLEEP);
+            }
+        }
+        self.running
+    }
+}
+
+/// Detects user input to our own window.
+pub struct LocalInput {
+    /// The last mouse position, to detect mouse movement.
+    last_mouse: Vec2,
+}
+
+impl LocalInput {
+    /// Starts detecting from the current mouse position.
+    pub fn new() -> LocalInput {
+        LocalInput {
+            last_mouse: mouse_position().into(),
+        }
+    }
+
+    /// Checks for keys, mouse buttons or mouse movement since the last call.
+    pub fn detect(&mut self) -> bool {
+        let mouse: Vec2 = mouse_position().into();
+        let moved = mouse.distance(self.last_mouse) > MOUSE_JITTER;
+        self.last_mouse = mouse;
+
+        moved
+            || !get_keys_pressed().is_empty()
+            || is_mouse_button_pressed(MouseButton::Left)
+            || is_mouse_button_pressed(MouseButton::Right)
+            || is_mouse_button_pressed(MouseButton::Middle)
+    }
+}
+
+/// Shrinks the window to its waiting size.
+fn enter_waiting_window() {
+    miniquad::window::set_fullscreen(false);
+    miniquad::window::set_window_size(WAITING_WINDOW_SIZE.0, WAITING_WINDOW_SIZE.1);
+    show_mouse(true);
+}
+
+/// Asks the operating system for the seconds since the last user input in any application.
+#[cfg(target_os = "windows")]
+fn system_idle_seconds() -> Option<f64> {
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    let mut info = LastInputInfo {
+        cb_size: size_of::<LastInputInfo>() as u32,
+        dw_time: 0,
+    };
+    // SAFETY: The struct has the layout of LASTINPUTINFO and the size field is initialized.
+    unsafe {
+        if GetLastInputInfo(&mut info) == 0 {
+            return None;
+        }
+        Some(GetTickCount().wrapping_sub(info.dw_time) as f64 / 1000.0)
+    }
+}
+
+/// Asks the operating system for the seconds since the last user input in any application.
+#[cfg(target_os = "macos")]
+fn system_idle_seconds() -> Option<f64> {
+    /// kCGEventSourceStateCombinedSessionState
+    const COMBINED_SESSION_STATE: i32 = 0;
+    /// kCGAnyInputEventType
+    const ANY_INPUT_EVENT_TYPE: u32 = !0;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    unsafe extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+    }
+
+    // SAFETY: Plain query without pointers involved.
+    Some(unsafe {
+        CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT_TYPE)
+    })
+}
+
+/// Asks `xprintidle` on X11 or the idle monitor of GNOME on Wayland for the seconds since the last user input.
+#[cfg(target_os = "linux")]
+fn system_idle_seconds() -> Option<f64> {
+    let query = |program: &str, args: &[&str]| {
+        std::process::Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    // Prints the milliseconds.
+    let x11 = || query("xprintidle", &[]).and_then(|text| text.trim().parse::<f64>().ok());
+    // Prints the milliseconds as `(uint64 1234,)`.
+    let gnome = || {
+        let text = query(
+            "gdbus",
+            &[
+                "call",
+                "--session",
+                "--dest",
+                "org.gnome.Mutter.IdleMonitor",
+                "--object-path",
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                "--method",
+                "org.gnome.Mutter.IdleMonitor.GetIdletime",
+            ],
+        )?;
+        let digits: String = text
+            .split_once("uint64")?
+            .1
+            .trim_start()
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        digits.parse::<f64>().ok()
+    };
+    x11().or_else(gnome).map(|ms| ms / 1000.0)
+}
+
+/// There is no portable idle query on the remaining platforms.
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn system_idle_seconds() -> Option<f64> {
+    None
+}
@@ -0,0 +1,138 @@
+//! Persists the runtime state, so long unattended runs can continue after a restart with `--resume`.
+//! The session file uses the same `key = value` format as the config file.
+
+use crate::color_generation::{Palette, PaletteSettings};
+use crate::colormaps::Cubehelix;
+use crate::math::{ComplexNumber, Fractal};
+use crate::zoom_director::{DirectorSnapshot, ZoomState};
+use macroquad::rand::{rand, srand};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// The name of the session file in the working directory.
+const SESSION_FILE_NAME: &str = "mandelbrot.session";
+
+/// Everything needed to continue where we stopped.
+pub struct Session {
+    /// The state of the zoom director.
+    pub director: DirectorSnapshot,
+    /// The palette settings in use.
+    pub palette_settings: PaletteSettings,
+    /// The seed the random generator continues with.
+    pub seed: u64,
+}
+
+impl Session {
+    /// Writes the session file.
+    pub fn save(&self) -> std::io::Result<()> {
+        let d = &self.director;
+        let c = &self.palette_settings.cubehelix;
+        let mut content = String::new();
+        let julia = match d.fractal {
+            Fractal::Mandelbrot => "none".to_owned(),
+            Fractal::Julia(c) => format!("{},{}", c.real, c.imag),
+        };
+        let entries: [(&str, String); 26] = [
+            ("center-real", d.center.real.to_string()),
+            ("center-imag", d.center.imag.to_string()),
+            ("center-low-real", d.center_low.real.to_string()),
+            ("center-low-imag", d.center_low.imag.to_string()),
+            ("radius", d.radius.to_string()),
+            ("rotation", d.rotation.to_string()),
+            ("velocity-real", d.velocity.0.to_string()),
+            ("velocity-imag", d.velocity.1.to_string()),
+            ("zoom-state", d.zoom_state.name().to_owned()),
+            ("dive-start-radius", d.dive_start_radius.to_string()),
+            ("zoom-speed", d.zoom_speed.to_string()),
+            ("start-point-real", d.start_point.real.to_string()),
+            ("start-point-imag", d.start_point.imag.to_string()),
+            ("start-score", d.start_score.to_string()),
+            ("start-remaining", d.start_remaining_iteration.to_string()),
+            ("julia", julia),
+            ("palette", self.palette_settings.palette.name().to_owned()),
+            ("hue-offset", self.palette_settings.hue_offset.to_string()),
+            ("hue-cycles", self.palette_settings.hue_cycles.to_string()),
+            ("saturation", self.palette_settings.saturation.to_string()),
+            ("value", self.palette_settings.value.to_string()),
+            ("cubehelix-start", c.start.to_string()),
+            ("cubehelix-rotations", c.rotations.to_string()),
+            ("cubehelix-hue", c.hue.to_string()),
+            ("cubehelix-gamma", c.gamma.to_string()),
+            ("seed", self.seed.to_string()),
+        ];
+        for (key, value) in entries {
+            let _ = writeln!(content, "{key} = {value}");
+        }
+        std::fs::write(SESSION_FILE_NAME, content)
+    }
+
+    /// Reads the session file. Returns None, if it is missing or incomplete.
+    pub fn load() -> Option<Session> {
+        let content = std::fs::read_to_string(SESSION_FILE_NAME).ok()?;
+        let values: HashMap<&str, &str> = content
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect();
+        let get = |key: &str| values.get(key).copied();
+        let number = |key: &str| get(key)?.parse::<f64>().ok();
+        let defaults = PaletteSettings::default();
+        // Palette parameters added in later versions fall back to their defaults.
+        let float = |key: &str, default: f32| number(key).map_or(default, |v| v as f32);
+
+        Some(Session {
+            director: DirectorSnapshot {
+                center: ComplexNumber::new(number("center-real")?, number("center-imag")?),
+                // Sessions saved before the deep precision tiers have no low part.
+                center_low: ComplexNumber::new(
+                    number("center-low-real").unwrap_or_default(),
+                    number("center-low-imag").unwrap_or_default(),
+                ),
+                radius: number("radius")?,
+                // Sessions saved before the rotation were not turned.
+                rotation: number("rotation").unwrap_or_default(),
+                velocity: (number("velocity-real")?, number("velocity-imag")?),
+                zoom_state: ZoomState::from_name(get("zoom-state")?)?,
+                dive_start_radius: number("dive-start-radius")?,
+                zoom_speed: number("zoom-speed")?,
+                start_point: ComplexNumber::new(
+                    number("start-point-real")?,
+                    number("start-point-imag")?,
+                ),
+                start_score: number("start-score")? as f32,
+                start_remaining_iteration: get("start-remaining")?.parse().ok()?,
+                // Sessions without a Julia parameter were saved in the Mandelbrot set.
+                fractal: get("julia")
+                    .and_then(|value| value.split_once(','))
+                    .and_then(|(real, imag)| Some((real.parse().ok()?, imag.parse().ok()?)))
+                    .map_or(Fractal::Mandelbrot, |(real, imag)| {
+                        Fractal::Julia(ComplexNumber::new(real, imag))
+                    }),
+            },
+            palette_settings: PaletteSettings {
+                palette: Palette::from_name(get("palette")?)?,
+                hue_offset: number("hue-offset")? as f32,
+                hue_cycles: float("hue-cycles", defaults.hue_cycles),
+                saturation: float("saturation", defaults.saturation),
+                value: float("value", defaults.value),
+                cubehelix: Cubehelix {
+                    start: float("cubehelix-start", defaults.cubehelix.start),
+                    rotations: float("cubehelix-rotations", defaults.cubehelix.rotations),
+                    hue: float("cubehelix-hue", defaults.cubehelix.hue),
+                    gamma: float("cubehelix-gamma", defaults.cubehelix.gamma),
+                },
+                image: None,
+                gradient: None,
+            },
+            seed: get("seed")?.parse().ok()?,
+        })
+    }
+}
+
+/// Draws a new seed from the random generator and reseeds it with that value. The generator state can not be
+/// read out, but this way it is fully described by the returned seed.
+pub fn reseed_random() -> u64 {
+    let seed = ((rand() as u64) << 32) | rand() as u64;
+    srand(seed);
+    seed
+}
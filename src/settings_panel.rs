@@ -0,0 +1,268 @@
+//! The settings window, opened with tab, for everybody who would rather click than edit the config file. Like the
+//! other control surfaces it only produces commands, so every change takes effect in the same frame.
+//!
+//! Besides the colors and the zoom speed it has sliders for the iteration limit and the parameters of the
+//! autofocus, which start with the configured values. The autofocus picks up the changes with its next scan, start
+//! point search or dive.
+
+use crate::color_generation::{Coloring, Palette, Shading};
+use crate::commands::Command;
+use crate::focus_system::{FocusParameter, FocusParameters, max_depth_limit};
+use crate::math::MAX_ITER_RANGE;
+use crate::precision::DEEPEST_RADIUS;
+use crate::transition::START_RADIUS;
+use crate::zoom_director::ZoomState;
+use egui_macroquad::egui;
+
+/// The highest zoom speed offered by the slider, as multiple of the default speed.
+const MAX_ZOOM_SPEED: f64 = 10.0;
+
+/// The highest start score offered by the slider. The scores grow with the square of the iteration limit.
+const MAX_START_MIN_SCORE: f32 = 10_000.0;
+
+/// The highest hysteresis offered by the slider, as fraction of the tracked score.
+const MAX_HYSTERESIS: f32 = 2.0;
+
+/// The most focus candidates offered by the slider.
+const MAX_CANDIDATES: usize = 10;
+
+/// What the window shows of the running explorer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanelState {
+    /// The palette in use.
+    pub palette: Palette,
+    /// The position within the palette cycle.
+    pub hue_offset: f32,
+    /// The exterior coloring in use.
+    pub coloring: Coloring,
+    /// The shading in use.
+    pub shading: Shading,
+    /// The zoom speed as multiple of the default speed.
+    pub zoom_speed: f64,
+    /// The maximum amount of iterations per point.
+    pub max_iter: u16,
+    /// The parameters of the autofocus.
+    pub focus: FocusParameters,
+    /// The state of the dive.
+    pub zoom_state: ZoomState,
+    /// Whether the animation is stopped.
+    pub paused: bool,
+    /// Whether frames are being recorded.
+    pub recording: bool,
+}
+
+/// The settings window.
+#[derive(Debug, Default)]
+pub struct SettingsPanel {
+    /// Whether the window is shown.
+    open: bool,
+}
+
+impl SettingsPanel {
+    /// Whether the window is shown.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows or hides the window.
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Lays out the window for this frame and adds the commands of the changed settings.
+    pub fn update(&mut self, state: &PanelState, commands: &mut Vec<Command>) {
+        if !self.open {
+            return;
+        }
+        let mut open = self.open;
+        egui_macroquad::ui(|context| {
+            egui::Window::new("Settings")
+                .open(&mut open)
+                .resizable(false)
+                .show(context, |ui| settings_ui(ui, state, commands));
+        });
+        self.open = open;
+    }
+
+    /// Draws the window, must come after everything it is placed on.
+    pub fn draw(&self) {
+        if self.open {
+            egui_macroquad::draw();
+        }
+    }
+}
+
+/// The rows with the sliders of the autofocus parameters.
+fn focus_ui(ui: &mut egui::Ui, focus: &FocusParameters, commands: &mut Vec<Command>) {
+    let mut set = |parameter, value| commands.push(Command::SetFocusParameter(parameter, value));
+
+    ui.label("Start min score");
+    let mut start_min_score = focus.start_min_score;
+    if ui
+        .add(egui::Slider::new(&mut start_min_score, 0.0..=MAX_START_MIN_SCORE).logarithmic(true))
+        .changed()
+    {
+        set(FocusParameter::StartMinScore, start_min_score as f64);
+    }
+    ui.end_row();
+
+    ui.label("Focus radius");
+    let mut focus_radius = focus.focus_radius;
+    if ui
+        .add(egui::Slider::new(&mut focus_radius, DEEPEST_RADIUS..=START_RADIUS).logarithmic(true))
+        .changed()
+    {
+        set(FocusParameter::FocusRadius, focus_radius);
+    }
+    ui.end_row();
+
+    ui.label("Depth limit");
+    let mut depth_limit = focus.depth_limit;
+    if ui
+        .add(
+            egui::Slider::new(
+                &mut depth_limit,
+                DEEPEST_RADIUS..=max_depth_limit(focus.focus_radius),
+            )
+            .logarithmic(true),
+        )
+        .changed()
+    {
+        set(FocusParameter::DepthLimit, depth_limit);
+    }
+    ui.end_row();
+
+    ui.label("Focus hysteresis");
+    let mut hysteresis = focus.hysteresis;
+    if ui
+        .add(egui::Slider::new(&mut hysteresis, 0.0..=MAX_HYSTERESIS))
+        .changed()
+    {
+        set(FocusParameter::Hysteresis, hysteresis as f64);
+    }
+    ui.end_row();
+
+    ui.label("Focus candidates");
+    let mut candidates = focus.candidates;
+    if ui
+        .add(egui::Slider::new(&mut candidates, 1..=MAX_CANDIDATES))
+        .changed()
+    {
+        set(FocusParameter::Candidates, candidates as f64);
+    }
+    ui.end_row();
+}
+
+/// The content of the window.
+fn settings_ui(ui: &mut egui::Ui, state: &PanelState, commands: &mut Vec<Command>) {
+    egui::Grid::new("settings").num_columns(2).show(ui, |ui| {
+        ui.label("Palette");
+        let mut palette = state.palette;
+        egui::ComboBox::from_id_salt("palette")
+            .selected_text(palette.name())
+            .show_ui(ui, |ui| {
+                for option in Palette::all() {
+                    ui.selectable_value(&mut palette, option, option.name());
+                }
+            });
+        if palette != state.palette {
+            commands.push(Command::SetPalette(palette));
+        }
+        ui.end_row();
+
+        ui.label("Hue offset");
+        let mut hue_offset = state.hue_offset;
+        if ui
+            .add(egui::Slider::new(&mut hue_offset, 0.0..=0.999))
+            .changed()
+        {
+            commands.push(Command::SetHueOffset(hue_offset));
+        }
+        ui.end_row();
+
+        ui.label("Coloring");
+        let mut coloring = state.coloring;
+        egui::ComboBox::from_id_salt("coloring")
+            .selected_text(coloring.name())
+            .show_ui(ui, |ui| {
+                for option in Coloring::ALL {
+                    ui.selectable_value(&mut coloring, option, option.name());
+                }
+            });
+        if coloring != state.coloring {
+            commands.push(Command::SetColoring(coloring));
+        }
+        ui.end_row();
+
+        ui.label("Shading");
+        let mut shading = state.shading;
+        egui::ComboBox::from_id_salt("shading")
+            .selected_text(shading.name())
+            .show_ui(ui, |ui| {
+                for option in Shading::ALL {
+                    ui.selectable_value(&mut shading, option, option.name());
+                }
+            });
+        if shading != state.shading {
+            commands.push(Command::SetShading(shading));
+        }
+        ui.end_row();
+
+        ui.label("Zoom speed");
+        let mut zoom_speed = state.zoom_speed;
+        if ui
+            .add(egui::Slider::new(&mut zoom_speed, 0.0..=MAX_ZOOM_SPEED).logarithmic(true))
+            .changed()
+        {
+            commands.push(Command::SetZoomSpeed(zoom_speed));
+        }
+        ui.end_row();
+
+        ui.label("Iteration limit");
+        let mut max_iter = state.max_iter;
+        if ui
+            .add(egui::Slider::new(&mut max_iter, MAX_ITER_RANGE).logarithmic(true))
+            .changed()
+        {
+            commands.push(Command::SetMaxIter(max_iter));
+        }
+        ui.end_row();
+
+        focus_ui(ui, &state.focus, commands);
+
+        ui.label("Dive");
+        ui.label(state.zoom_state.name());
+        ui.end_row();
+    });
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        if ui
+            .button(if state.paused { "Resume" } else { "Pause" })
+            .clicked()
+        {
+            commands.push(Command::SetPaused(!state.paused));
+        }
+        if ui.button("Next target").clicked() {
+            commands.push(Command::NextTarget);
+        }
+        if ui.button("Manual").clicked() {
+            commands.push(Command::ToggleManual);
+        }
+    });
+    ui.horizontal(|ui| {
+        if ui
+            .button(if state.recording {
+                "Stop recording"
+            } else {
+                "Start recording"
+            })
+            .clicked()
+        {
+            commands.push(Command::SetRecording(!state.recording));
+        }
+        if ui.button("Screenshot").clicked() {
+            commands.push(Command::Screenshot);
+        }
+    });
+}
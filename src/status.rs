@@ -0,0 +1,75 @@
+//! The status stream enabled with `--status-json`. Once per second a JSON object with the view, the score of the
+//! autofocus target, the frame rate and the state of the zoom goes to stdout as a single line, so dashboards,
+//! streaming overlays or scripts can follow the explorer by reading its output.
+
+use crate::math::ComplexNumber;
+use std::io::Write;
+
+/// The time between two status lines in seconds.
+const STATUS_INTERVAL: f64 = 1.0;
+
+/// What a status line reports.
+#[derive(Debug, Clone, Copy)]
+pub struct Status {
+    /// The time since the start in seconds.
+    pub time: f64,
+    /// The center of the view.
+    pub center: ComplexNumber,
+    /// The radius of the view.
+    pub radius: f64,
+    /// The score of the autofocus target, None before the autofocus picked one.
+    pub score: Option<f32>,
+    /// The frames per second.
+    pub fps: i32,
+    /// The name of the zoom state.
+    pub state: &'static str,
+    /// Whether the animation is paused.
+    pub paused: bool,
+}
+
+impl Status {
+    /// Formats the status as JSON object.
+    fn to_json(self) -> String {
+        let score = self
+            .score
+            .map_or_else(|| "null".to_owned(), |score| score.to_string());
+        format!(
+            "{{\"time\":{:.3},\"real\":{},\"imag\":{},\"radius\":{:e},\"score\":{score},\"fps\":{},\
+             \"state\":\"{}\",\"paused\":{}}}",
+            self.time,
+            self.center.real,
+            self.center.imag,
+            self.radius,
+            self.fps,
+            self.state,
+            self.paused
+        )
+    }
+}
+
+/// Writes a status line per interval to stdout.
+#[derive(Debug)]
+pub struct StatusStream {
+    /// The time of the last line.
+    last_emit: f64,
+}
+
+impl StatusStream {
+    /// Creates the stream, the first status goes out right away.
+    pub fn new() -> StatusStream {
+        StatusStream {
+            last_emit: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Writes the status if the interval passed. Fails if stdout is gone, like a closed pipe.
+    pub fn emit(&mut self, status: Status) -> std::io::Result<()> {
+        if status.time - self.last_emit < STATUS_INTERVAL {
+            return Ok(());
+        }
+        self.last_emit = status.time;
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", status.to_json())?;
+        stdout.flush()
+    }
+}
@@ -0,0 +1,202 @@
+//! Optional MJPEG stream of the frames over HTTP. With `stream-port` set, `http://<host>:<port>/` streams the zoom to
+//! browsers, OBS media or browser sources and other displays that can not run the explorer, `/frame.jpg` serves
+//! the next frame as single image.
+//!
+//! An encoder thread compresses the frames, frames arriving while it is busy are dropped, so the render loop never
+//! waits for the network. Without viewers nothing is encoded at all.
+
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use jpeg_encoder::{ColorType, Encoder};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{SyncSender, sync_channel};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// The quality of the JPEG frames between 1 and 100.
+const JPEG_QUALITY: u8 = 80;
+
+/// The most frames per second sent to the viewers.
+const MAX_FRAME_RATE: f64 = 30.0;
+
+/// The time we wait for a client to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The time a single frame request waits for the next frame.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Separates the frames in the multipart response.
+const BOUNDARY: &str = "frame";
+
+/// The latest encoded frame shared with the viewers.
+#[derive(Default)]
+struct SharedFrame {
+    /// The number of the frame, which counts up with every encoded one, and its JPEG data.
+    frame: Mutex<(u64, Arc<Vec<u8>>)>,
+    /// Wakes the viewers up when a new frame is encoded.
+    new_frame: Condvar,
+    /// The connected viewers.
+    viewers: AtomicUsize,
+}
+
+impl SharedFrame {
+    /// The latest frame, None before the first one.
+    fn latest(&self) -> Option<(u64, Arc<Vec<u8>>)> {
+        let frame = self.frame.lock().ok()?;
+        (frame.0 > 0).then(|| frame.clone())
+    }
+
+    /// Waits for a frame newer than the given number, None on a timeout.
+    fn wait_after(&self, number: u64, timeout: Duration) -> Option<(u64, Arc<Vec<u8>>)> {
+        let guard = self.frame.lock().ok()?;
+        let (guard, _) = self
+            .new_frame
+            .wait_timeout_while(guard, timeout, |(latest, _)| *latest <= number)
+            .ok()?;
+        (guard.0 > number).then(|| guard.clone())
+    }
+}
+
+/// The main loop side of the stream.
+pub struct FrameStream {
+    /// Hands the RGBA8 frames over to the encoder thread.
+    sender: SyncSender<Vec<u8>>,
+    /// The frame shared with the viewer threads.
+    shared: Arc<SharedFrame>,
+    /// The time the last frame was handed over.
+    last_push: f64,
+}
+
+impl FrameStream {
+    /// Starts the encoder and the server thread on the given port. Returns None, if the port can not be opened.
+    pub fn start(port: u16) -> Option<FrameStream> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .inspect_err(|err| eprintln!("Could not start the stream on port {port}: {err}"))
+            .ok()?;
+        let shared = Arc::new(SharedFrame::default());
+        let (sender, receiver) = sync_channel::<Vec<u8>>(1);
+
+        let encoder_shared = shared.clone();
+        std::thread::spawn(move || {
+            for rgba in receiver {
+                let mut jpeg = Vec::new();
+                let encoded = Encoder::new(&mut jpeg, JPEG_QUALITY).encode(
+                    &rgba,
+                    WINDOW_WIDTH as u16,
+                    WINDOW_HEIGHT as u16,
+                    ColorType::Rgba,
+                );
+                if let Err(err) = encoded {
+                    eprintln!("Could not encode the stream frame: {err}");
+                    continue;
+                }
+                if let Ok(mut frame) = encoder_shared.frame.lock() {
+                    *frame = (frame.0 + 1, Arc::new(jpeg));
+                }
+                encoder_shared.new_frame.notify_all();
+            }
+        });
+
+        let server_shared = shared.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = server_shared.clone();
+                // Every viewer holds its connection open, so each gets its own thread.
+                std::thread::spawn(move || serve(stream, &shared));
+            }
+        });
+
+        println!("Streaming the frames on http://localhost:{port}/");
+        Some(FrameStream {
+            sender,
+            shared,
+            last_push: 0.0,
+        })
+    }
+
+    /// Hands the frame over to the encoder, unless nobody watches, the frame rate is reached or the encoder is
+    /// still busy with the last one.
+    pub fn push(&mut self, rgba: &[u8], time: f64) {
+        if self.shared.viewers.load(Ordering::Relaxed) == 0
+            || time - self.last_push < 1.0 / MAX_FRAME_RATE
+        {
+            return;
+        }
+        if self.sender.try_send(rgba.to_vec()).is_ok() {
+            self.last_push = time;
+        }
+    }
+}
+
+/// Reads the request of a viewer and answers with the stream or a single frame.
+fn serve(stream: TcpStream, shared: &SharedFrame) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let mut request_line = String::new();
+    if BufReader::new(&stream)
+        .read_line(&mut request_line)
+        .is_err()
+    {
+        return;
+    }
+    // The request line has the form `GET /path HTTP/1.1`.
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = target.split_once('?').map_or(target, |(path, _)| path);
+
+    shared.viewers.fetch_add(1, Ordering::Relaxed);
+    let _ = match path {
+        "/" | "/stream" => write_stream(&stream, shared),
+        "/frame.jpg" => write_frame(&stream, shared),
+        _ => write!(
+            &stream,
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ),
+    };
+    shared.viewers.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Sends every new frame as part of a multipart response, until the viewer disconnects.
+fn write_stream(mut stream: &TcpStream, shared: &SharedFrame) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary={BOUNDARY}\r\n\
+         Cache-Control: no-cache\r\nConnection: close\r\n\r\n"
+    )?;
+    // The latest frame may be old, as nothing is encoded without viewers.
+    let mut number = shared.latest().map_or(0, |(number, _)| number);
+    loop {
+        let Some((latest, jpeg)) = shared.wait_after(number, FRAME_TIMEOUT) else {
+            continue;
+        };
+        number = latest;
+        write!(
+            stream,
+            "--{BOUNDARY}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+            jpeg.len()
+        )?;
+        stream.write_all(&jpeg)?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+    }
+}
+
+/// Sends the next frame as single JPEG, or the latest one if no new frame comes in time.
+fn write_frame(mut stream: &TcpStream, shared: &SharedFrame) -> std::io::Result<()> {
+    let latest = shared.latest();
+    let number = latest.as_ref().map_or(0, |(number, _)| *number);
+    match shared.wait_after(number, FRAME_TIMEOUT).or(latest) {
+        Some((_, jpeg)) => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\
+                 Cache-Control: no-cache\r\nConnection: close\r\n\r\n",
+                jpeg.len()
+            )?;
+            stream.write_all(&jpeg)
+        }
+        None => write!(
+            stream,
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ),
+    }
+}
@@ -0,0 +1,109 @@
+//! Writes the timings of every frame to a file for offline analysis: `--telemetry frames.csv` writes CSV, a
+//! `.json` or `.jsonl` file gets one JSON object per line. Comparing the files of two versions or machines shows
+//! performance regressions in numbers.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The columns of the CSV file.
+const CSV_HEADER: &str =
+    "frame,time,compute_ms,focus_ms,color_ms,upload_ms,total_ms,render_scale,radius";
+
+/// The timings of a frame in seconds.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTimings {
+    /// The computation of the iteration or escape field.
+    pub compute: f64,
+    /// The update of the zoom director, which runs the focus scoring.
+    pub focus: f64,
+    /// The coloring of the field including the overlays drawn into it.
+    pub color: f64,
+    /// The upload of the colors into the texture.
+    pub upload: f64,
+    /// The whole frame as measured by the window.
+    pub total: f64,
+    /// The fraction of the window resolution the field was computed in.
+    pub render_scale: f32,
+    /// The radius of the view.
+    pub radius: f64,
+}
+
+/// The format of the telemetry file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TelemetryFormat {
+    /// Comma separated values with a header line.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+/// The open telemetry file.
+pub struct TelemetryWriter {
+    /// The buffered file.
+    writer: BufWriter<File>,
+    /// The format of the file.
+    format: TelemetryFormat,
+    /// The number of the next frame.
+    frame: u64,
+}
+
+impl TelemetryWriter {
+    /// Creates the file, its extension selects the format.
+    pub fn create(path: &Path) -> std::io::Result<TelemetryWriter> {
+        let format = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("jsonl") => {
+                TelemetryFormat::JsonLines
+            }
+            _ => TelemetryFormat::Csv,
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        if format == TelemetryFormat::Csv {
+            writeln!(writer, "{CSV_HEADER}")?;
+        }
+        Ok(TelemetryWriter {
+            writer,
+            format,
+            frame: 0,
+        })
+    }
+
+    /// Appends the timings of a frame taken at the given time.
+    pub fn write(&mut self, time: f64, timings: &FrameTimings) -> std::io::Result<()> {
+        let ms = |seconds: f64| seconds * 1000.0;
+        match self.format {
+            TelemetryFormat::Csv => writeln!(
+                self.writer,
+                "{},{time:.4},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:e}",
+                self.frame,
+                ms(timings.compute),
+                ms(timings.focus),
+                ms(timings.color),
+                ms(timings.upload),
+                ms(timings.total),
+                timings.render_scale,
+                timings.radius,
+            )?,
+            TelemetryFormat::JsonLines => writeln!(
+                self.writer,
+                "{{\"frame\":{},\"time\":{time:.4},\"compute_ms\":{:.3},\"focus_ms\":{:.3},\"color_ms\":{:.3},\
+                 \"upload_ms\":{:.3},\"total_ms\":{:.3},\"render_scale\":{:.3},\"radius\":{:e}}}",
+                self.frame,
+                ms(timings.compute),
+                ms(timings.focus),
+                ms(timings.color),
+                ms(timings.upload),
+                ms(timings.total),
+                timings.render_scale,
+                timings.radius,
+            )?,
+        }
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Writes the buffered lines to the file.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
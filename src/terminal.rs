@@ -0,0 +1,314 @@
+//! Renders the endless zoom into the terminal instead of a window, as headless fallback over SSH:
+//! `mandelbrot-explorer --terminal`. The same director drives the dives, the fields come from the same backends,
+//! computed at about the resolution of the terminal. By default every character cell shows two pixels as upper
+//! half block with the 256 ANSI colors, the upper one as foreground, the lower one as background. Terminals with
+//! image support get real pixels with `terminal-graphics` set to `sixel` or `kitty`, assuming cells of
+//! [`CELL_SIZE`] pixels.
+//!
+//! The size comes from `terminal-size`, else from the `COLUMNS` and `LINES` variables, else 80x24. `Ctrl+C` ends it.
+
+use crate::color::Color;
+use crate::color::color_to_rgba8;
+use crate::color_generation::{ColorLookup, generate_colors};
+use crate::config::CONFIG;
+use crate::math::{CancellationToken, get_iteration_field, max_iter};
+use crate::poster::palette_settings_from_config;
+use crate::zoom_director::ZoomDirector;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use macroquad::rand::srand;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// The size assumed when the terminal size is unknown, in characters.
+const DEFAULT_SIZE: (u32, u32) = (80, 24);
+
+/// The shortest time between two frames, so fast fields do not flood the terminal.
+const FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// The longest step of the state machine in seconds, like the window does.
+const MAX_STEP: f64 = 1.0;
+
+/// The brightness levels of the 6x6x6 color cube of the 256 ANSI colors.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The size of a character cell in pixels assumed for the image protocols, common for fonts of 12 to 14 points.
+const CELL_SIZE: (u32, u32) = (8, 16);
+
+/// The levels per channel of the Sixel palette, which has to fit into the 256 color registers.
+const SIXEL_LEVELS: u32 = 6;
+
+/// The largest payload of a Kitty graphics command in bytes.
+const KITTY_CHUNK: usize = 4096;
+
+/// How the frames are drawn into the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalGraphics {
+    /// Two pixels per character as half block in the 256 ANSI colors, which works in about every terminal.
+    HalfBlocks,
+    /// Pixels as Sixel image, supported by xterm, foot, mlterm, WezTerm and Windows Terminal among others.
+    Sixel,
+    /// Pixels in true color with the Kitty graphics protocol, supported by kitty, WezTerm, Ghostty and Konsole.
+    Kitty,
+}
+
+impl TerminalGraphics {
+    /// Looks up the kind by its config name.
+    pub fn from_name(name: &str) -> Option<TerminalGraphics> {
+        match name {
+            "ansi" => Some(TerminalGraphics::HalfBlocks),
+            "sixel" => Some(TerminalGraphics::Sixel),
+            "kitty" => Some(TerminalGraphics::Kitty),
+            _ => None,
+        }
+    }
+
+    /// The pixels per character cell.
+    fn cell_size(self) -> (u32, u32) {
+        match self {
+            TerminalGraphics::HalfBlocks => (1, 2),
+            TerminalGraphics::Sixel | TerminalGraphics::Kitty => CELL_SIZE,
+        }
+    }
+
+    /// Encodes the frame, to be drawn at the top left corner of the terminal.
+    fn encode(self, colors: &[Color], width: usize) -> String {
+        match self {
+            TerminalGraphics::HalfBlocks => half_blocks(colors, width),
+            TerminalGraphics::Sixel => sixel(colors, width),
+            TerminalGraphics::Kitty => kitty(colors, width),
+        }
+    }
+}
+
+/// Runs the zoom in the terminal until the process is ended.
+pub fn run_terminal() {
+    srand(macroquad::miniquad::date::now() as _);
+    let graphics = CONFIG.terminal_graphics;
+    let (width, height) = pixel_size(terminal_size(), graphics.cell_size());
+    // The field is computed at about the resolution shown, the director still gets it in the window size.
+    let scale = (width as f32 / WINDOW_WIDTH as f32).max(height as f32 / WINDOW_HEIGHT as f32);
+    let lookup = ColorLookup::new(palette_settings_from_config(), max_iter());
+    let cancel = CancellationToken::default();
+    let mut director = ZoomDirector::new();
+    director.set_zoom_speed(CONFIG.zoom_speed);
+
+    let mut stdout = std::io::stdout();
+    // Clears the screen once, every frame then overwrites the last one from the top left.
+    let _ = write!(stdout, "\x1b[2J");
+    let mut last_step = Instant::now();
+    loop {
+        let frame_start = Instant::now();
+        let Some(field) = get_iteration_field(
+            director.fractal(),
+            &director.viewport(),
+            &CONFIG.iteration_settings(),
+            scale,
+            &cancel,
+        ) else {
+            return;
+        };
+        let delta_time = last_step.elapsed().as_secs_f64().min(MAX_STEP);
+        last_step = Instant::now();
+        director.update(&field, delta_time);
+
+        let samples = sample_field(&field, width, height);
+        let colors = generate_colors(&samples, width as usize, &lookup, &CONFIG.color_settings());
+        if stdout
+            .write_all(graphics.encode(&colors, width as usize).as_bytes())
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            return;
+        }
+        std::thread::sleep(FRAME_TIME.saturating_sub(frame_start.elapsed()));
+    }
+}
+
+/// The size of the terminal in characters.
+fn terminal_size() -> (u32, u32) {
+    let variable = |name: &str| std::env::var(name).ok()?.parse::<u32>().ok();
+    CONFIG.terminal_size.unwrap_or_else(|| {
+        (
+            variable("COLUMNS").unwrap_or(DEFAULT_SIZE.0),
+            variable("LINES").unwrap_or(DEFAULT_SIZE.1),
+        )
+    })
+}
+
+/// The largest pixel size with the aspect ratio of the window that fits into the terminal with cells of the given
+/// size. The last line stays free, so the output does not scroll.
+fn pixel_size((columns, lines): (u32, u32), (cell_width, cell_height): (u32, u32)) -> (u32, u32) {
+    let max_width = columns * cell_width;
+    let max_height = lines.saturating_sub(1).max(1) * cell_height;
+    let height = (max_width * WINDOW_HEIGHT as u32 / WINDOW_WIDTH as u32).min(max_height) / 2 * 2;
+    let width = (height * WINDOW_WIDTH as u32 / WINDOW_HEIGHT as u32).min(max_width);
+    (width.max(1), height.max(2))
+}
+
+/// Picks the iterations at the pixel centers of the smaller size from the field of the window.
+fn sample_field(field: &[u16], width: u32, height: u32) -> Vec<u16> {
+    let (window_width, window_height) = (WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32);
+    (0..width * height)
+        .map(|idx| {
+            let x = ((idx % width) * 2 + 1) * window_width / (width * 2);
+            let y = ((idx / width) * 2 + 1) * window_height / (height * 2);
+            field[(y * window_width + x) as usize]
+        })
+        .collect()
+}
+
+/// Formats the colors as lines of half blocks, starting at the top left corner of the terminal.
+fn half_blocks(colors: &[Color], width: usize) -> String {
+    let mut out = String::from("\x1b[H");
+    for rows in colors.chunks_exact(width * 2) {
+        let (upper, lower) = rows.split_at(width);
+        let mut last = None;
+        for (top, bottom) in upper.iter().zip(lower) {
+            let pair = (ansi_color(*top), ansi_color(*bottom));
+            // Neighboring cells often share their colors, which only need to be set once.
+            if last != Some(pair) {
+                let _ = write!(out, "\x1b[38;5;{};48;5;{}m", pair.0, pair.1);
+                last = Some(pair);
+            }
+            out.push('▀');
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+/// Formats the colors as Sixel image. The colors are reduced to a cube of [`SIXEL_LEVELS`] per channel, every band
+/// of six rows is written color by color with runs of equal columns compressed.
+fn sixel(colors: &[Color], width: usize) -> String {
+    let max_level = SIXEL_LEVELS - 1;
+    let indices: Vec<u32> = colors
+        .iter()
+        .map(|color| {
+            let level = |channel: f32| (channel.clamp(0.0, 1.0) * max_level as f32).round() as u32;
+            (level(color.r) * SIXEL_LEVELS + level(color.g)) * SIXEL_LEVELS + level(color.b)
+        })
+        .collect();
+    let height = colors.len() / width;
+    let mut out = format!("\x1b[H\x1bPq\"1;1;{width};{height}");
+    for index in 0..SIXEL_LEVELS.pow(3) {
+        let percent = |level: u32| level * 100 / max_level;
+        let _ = write!(
+            out,
+            "#{index};2;{};{};{}",
+            percent(index / (SIXEL_LEVELS * SIXEL_LEVELS)),
+            percent(index / SIXEL_LEVELS % SIXEL_LEVELS),
+            percent(index % SIXEL_LEVELS)
+        );
+    }
+    let mut bits = vec![0u8; SIXEL_LEVELS.pow(3) as usize * width];
+    for band in indices.chunks(width * 6) {
+        bits.fill(0);
+        let mut used = vec![false; SIXEL_LEVELS.pow(3) as usize];
+        for (row, line) in band.chunks_exact(width).enumerate() {
+            for (x, index) in line.iter().enumerate() {
+                bits[*index as usize * width + x] |= 1 << row;
+                used[*index as usize] = true;
+            }
+        }
+        for index in (0..used.len()).filter(|index| used[*index]) {
+            let _ = write!(out, "#{index}");
+            let columns = &bits[index * width..(index + 1) * width];
+            let mut start = 0;
+            while start < width {
+                let run = columns[start..]
+                    .iter()
+                    .take_while(|sixel| **sixel == columns[start])
+                    .count();
+                let sixel = (columns[start] + 63) as char;
+                if run > 3 {
+                    let _ = write!(out, "!{run}{sixel}");
+                } else {
+                    out.extend(std::iter::repeat_n(sixel, run));
+                }
+                start += run;
+            }
+            // Returns to the start of the band for the next color.
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Formats the colors as true color image of the Kitty graphics protocol. Every frame replaces the image and the
+/// placement of the last one.
+fn kitty(colors: &[Color], width: usize) -> String {
+    let rgb: Vec<u8> = colors
+        .iter()
+        .flat_map(|color| {
+            let [r, g, b, _] = color_to_rgba8(*color);
+            [r, g, b]
+        })
+        .collect();
+    let payload = base64(&rgb);
+    let height = colors.len() / width;
+    let mut out = String::from("\x1b[H");
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        if index == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=24,s={width},v={height},i=1,p=1,q=2,m={more};"
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={more};");
+        }
+        // Base64 is plain ASCII.
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\x1b\\");
+    }
+    out
+}
+
+/// Encodes the bytes as standard Base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let value = group.iter().enumerate().fold(0u32, |value, (index, byte)| {
+            value | (*byte as u32) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= group.len() {
+                out.push(ALPHABET[(value >> (18 - 6 * index) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// The closest of the 256 ANSI colors, either from the color cube or from the gray ramp.
+fn ansi_color(color: Color) -> u8 {
+    let channels = [color.r, color.g, color.b].map(|channel| (channel * 255.0) as i32);
+    let level = |value: i32| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&index| (CUBE_LEVELS[index] as i32 - value).abs())
+            .unwrap_or_default()
+    };
+    let cube = channels.map(level);
+    let cube_error: i32 = (0..3)
+        .map(|channel| (CUBE_LEVELS[cube[channel]] as i32 - channels[channel]).pow(2))
+        .sum();
+    // The gray ramp has 24 steps from 8 to 238.
+    let mean = channels.iter().sum::<i32>() / 3;
+    let gray = ((mean - 8 + 5) / 10).clamp(0, 23);
+    let gray_error: i32 = channels
+        .iter()
+        .map(|channel| (8 + gray * 10 - channel).pow(2))
+        .sum();
+    if gray_error < cube_error {
+        232 + gray as u8
+    } else {
+        16 + (36 * cube[0] + 6 * cube[1] + cube[2]) as u8
+    }
+}
@@ -0,0 +1,53 @@
+//! Sizes the rayon thread pool and optionally lowers the priority of the computation. When the explorer runs as an
+//! ambient display on a work machine, it can be capped to a few cores and give way to everything else.
+
+use crate::config::CONFIG;
+
+/// Sets up the global thread pool from the config, must run before the first parallel computation.
+pub fn configure_thread_pool() {
+    let mut builder = rayon::ThreadPoolBuilder::new().num_threads(CONFIG.threads);
+    if CONFIG.background {
+        lower_thread_priority();
+        builder = builder.start_handler(|_| lower_thread_priority());
+    }
+    if let Err(err) = builder.build_global() {
+        eprintln!("Could not configure the thread pool: {err}");
+    }
+}
+
+/// Moves the calling thread into the background mode, which also lowers its disk and memory priority.
+#[cfg(target_os = "windows")]
+fn lower_thread_priority() {
+    /// THREAD_MODE_BACKGROUND_BEGIN
+    const THREAD_MODE_BACKGROUND_BEGIN: i32 = 0x0001_0000;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetCurrentThread() -> isize;
+        fn SetThreadPriority(thread: isize, priority: i32) -> i32;
+    }
+
+    // SAFETY: The pseudo handle of the current thread is always valid and needs no closing.
+    let success = unsafe { SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN) };
+    if success == 0 {
+        eprintln!("Could not lower the thread priority.");
+    }
+}
+
+/// Raises the nice value of the calling thread. On macOS the nice value belongs to the whole process.
+#[cfg(not(target_os = "windows"))]
+fn lower_thread_priority() {
+    /// PRIO_PROCESS, which addresses the calling thread on Linux.
+    const PRIO_PROCESS: i32 = 0;
+    /// The nice value of the background computation, 19 is the lowest priority.
+    const BACKGROUND_NICE: i32 = 10;
+
+    unsafe extern "C" {
+        fn setpriority(which: i32, who: u32, priority: i32) -> i32;
+    }
+
+    // SAFETY: Plain call without pointers, the id 0 addresses the caller.
+    if unsafe { setpriority(PRIO_PROCESS, 0, BACKGROUND_NICE) } != 0 {
+        eprintln!("Could not lower the thread priority.");
+    }
+}
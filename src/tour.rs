@@ -0,0 +1,41 @@
+//! The demo tour, which alternates the random dives with visits to famous places of the Mandelbrot set, the ones
+//! of [`LOCATIONS`] marked for the tour. Each visit is introduced with the caption naming the place, afterwards the
+//! autofocus takes over as in every dive.
+
+use crate::commands::Command;
+use crate::locations::{LOCATIONS, Location};
+use crate::math::ComplexNumber;
+use crate::zoom_director::ZoomState;
+
+/// The state of the tour.
+#[derive(Debug, Default)]
+pub struct DemoTour {
+    /// The index of the next landmark among the places of the tour.
+    next: usize,
+    /// Whether the next dive visits a landmark instead of a random place.
+    landmark_turn: bool,
+    /// The zoom state of the last frame, to notice when a dive ends.
+    previous_state: Option<ZoomState>,
+}
+
+impl DemoTour {
+    /// Follows the dives and adds the command flying to the next landmark whenever it is its turn.
+    pub fn poll(&mut self, zoom_state: ZoomState, commands: &mut Vec<Command>) {
+        let entered = self.previous_state != Some(zoom_state);
+        self.previous_state = Some(zoom_state);
+        if !entered || zoom_state != ZoomState::ZoomingOut {
+            return;
+        }
+        self.landmark_turn = !self.landmark_turn;
+        let landmarks: Vec<&Location> = LOCATIONS.iter().filter(|location| location.tour).collect();
+        if self.landmark_turn && !landmarks.is_empty() {
+            let landmark = landmarks[self.next % landmarks.len()];
+            self.next = (self.next + 1) % landmarks.len();
+            commands.push(Command::GoTo(
+                landmark.center,
+                ComplexNumber::default(),
+                landmark.radius,
+            ));
+        }
+    }
+}
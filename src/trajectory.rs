@@ -0,0 +1,252 @@
+//! Records the path of the view during the dives through the Mandelbrot set and exports it as CSV and as SVG plot,
+//! for analyzing and sharing where the explorer went. The plot shows the path over the silhouette of the set with
+//! circles marking the view every few halvings of the radius, and below it the depth over the time of the dive.
+
+use crate::export::{GALLERY_DIRECTORY, time_stamp};
+use crate::math::{ComplexNumber, Fractal, max_iter};
+use crate::zoom_director::ZoomState;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The time between two samples of the path in seconds.
+const SAMPLE_INTERVAL: f64 = 0.1;
+
+/// The part of the complex number pane the plot shows, as left, top, right and bottom.
+const PLOT_AREA: (f64, f64, f64, f64) = (-2.5, -1.25, 1.0, 1.25);
+
+/// The pixels of the plot per unit of the complex number pane.
+const PLOT_SCALE: f64 = 240.0;
+
+/// The height of the depth chart below the plot in pixels.
+const CHART_HEIGHT: f64 = 160.0;
+
+/// The distance between the set and the chart and around the image in pixels.
+const MARGIN: f64 = 20.0;
+
+/// The units of the complex number pane per point of the silhouette.
+const SILHOUETTE_STEP: f64 = 0.005;
+
+/// The view is marked with a circle every this many halvings of the radius.
+const CIRCLE_HALVINGS: f64 = 2.0;
+
+/// A point on the path.
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// The time since the start of the dive in seconds.
+    time: f64,
+    /// The center of the view.
+    center: ComplexNumber,
+    /// The radius of the view.
+    radius: f64,
+}
+
+/// Collects the path of the current or the last dive.
+#[derive(Debug, Default)]
+pub struct TrajectoryRecorder {
+    /// The path so far.
+    samples: Vec<Sample>,
+    /// The time the dive started.
+    start: f64,
+    /// Whether the last sample was taken during a dive.
+    diving: bool,
+}
+
+impl TrajectoryRecorder {
+    /// Takes a sample while we dive through the Mandelbrot set. Once the dive is over, the path is written if
+    /// requested. It is kept till the next dive starts.
+    pub fn record(
+        &mut self,
+        zoom_state: ZoomState,
+        fractal: Fractal,
+        center: ComplexNumber,
+        radius: f64,
+        time: f64,
+        export_finished: bool,
+    ) {
+        let diving = matches!(
+            zoom_state,
+            ZoomState::StartZooming | ZoomState::ZoomingInAndFollowing
+        );
+        let diving = diving && fractal == Fractal::Mandelbrot;
+        if diving && !self.diving {
+            self.samples.clear();
+            self.start = time;
+        }
+        if !diving && self.diving && export_finished {
+            self.export();
+        }
+        self.diving = diving;
+        let due = self
+            .samples
+            .last()
+            .is_none_or(|last| time - self.start - last.time >= SAMPLE_INTERVAL);
+        if diving && due {
+            self.samples.push(Sample {
+                time: time - self.start,
+                center,
+                radius,
+            });
+        }
+    }
+
+    /// Writes the path of the current or the last dive into the gallery directory, as done on demand and at the
+    /// end of the dives.
+    pub fn export(&self) {
+        if self.samples.is_empty() {
+            eprintln!("There is no dive path to export yet");
+            return;
+        }
+        let samples = self.samples.clone();
+        // The silhouette takes a moment, so we do not stall the render loop.
+        std::thread::spawn(move || match write_trajectory(&samples) {
+            Ok(stem) => println!("Saved dive path to {stem}.csv and {stem}.svg"),
+            Err(err) => eprintln!("Could not save the dive path: {err}"),
+        });
+    }
+}
+
+/// Writes the path as CSV and SVG and returns the common path without extension.
+fn write_trajectory(samples: &[Sample]) -> std::io::Result<String> {
+    std::fs::create_dir_all(GALLERY_DIRECTORY)?;
+    let stem = Path::new(GALLERY_DIRECTORY)
+        .join(format!("trajectory_{}", time_stamp()))
+        .display()
+        .to_string();
+    let mut csv = "time,real,imag,radius\n".to_owned();
+    for sample in samples {
+        let _ = writeln!(
+            csv,
+            "{:.3},{},{},{:e}",
+            sample.time, sample.center.real, sample.center.imag, sample.radius
+        );
+    }
+    std::fs::write(format!("{stem}.csv"), csv)?;
+    std::fs::write(format!("{stem}.svg"), plot(samples))?;
+    Ok(stem)
+}
+
+/// Draws the path over the silhouette of the set and the depth chart below it as SVG document.
+fn plot(samples: &[Sample]) -> String {
+    let (left, top, right, bottom) = PLOT_AREA;
+    let plot_width = (right - left) * PLOT_SCALE;
+    let plot_height = (bottom - top) * PLOT_SCALE;
+    let width = plot_width + 2.0 * MARGIN;
+    let height = plot_height + CHART_HEIGHT + 3.0 * MARGIN;
+    let to_plot = |point: ComplexNumber| {
+        (
+            MARGIN + (point.real - left) * PLOT_SCALE,
+            MARGIN + (point.imag - top) * PLOT_SCALE,
+        )
+    };
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" font-family="sans-serif" font-size="12">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+    // The silhouette as one horizontal run per row of points inside the set.
+    let _ = write!(svg, r##"<path fill="#d0d0d0" d=""##);
+    let rows = ((bottom - top) / SILHOUETTE_STEP) as usize;
+    let columns = ((right - left) / SILHOUETTE_STEP) as usize;
+    for row in 0..rows {
+        let imag = top + (row as f64 + 0.5) * SILHOUETTE_STEP;
+        let mut run_start = None;
+        for column in 0..=columns {
+            let real = left + (column as f64 + 0.5) * SILHOUETTE_STEP;
+            let inside = column < columns
+                && ComplexNumber::new(real, imag).get_iteration_till_termination() == max_iter();
+            match (inside, run_start) {
+                (true, None) => run_start = Some(column),
+                (false, Some(start)) => {
+                    let (x, y) = to_plot(ComplexNumber::new(
+                        left + start as f64 * SILHOUETTE_STEP,
+                        imag - 0.5 * SILHOUETTE_STEP,
+                    ));
+                    let run = (column - start) as f64 * SILHOUETTE_STEP * PLOT_SCALE;
+                    let step = SILHOUETTE_STEP * PLOT_SCALE;
+                    let _ = write!(svg, "M{x:.1} {y:.1}h{run:.1}v{step:.1}h{:.1}z", -run);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    let _ = writeln!(svg, r#""/>"#);
+
+    // The views every few halvings of the radius, while they are large enough to see.
+    let mut next_circle = samples[0].radius;
+    for sample in samples {
+        if sample.radius <= next_circle {
+            let (x, y) = to_plot(sample.center);
+            let _ = writeln!(
+                svg,
+                r##"<circle cx="{x:.1}" cy="{y:.1}" r="{:.2}" fill="none" stroke="#4a90d9" stroke-width="0.8"/>"##,
+                sample.radius * PLOT_SCALE
+            );
+            next_circle = sample.radius * (-CIRCLE_HALVINGS).exp2();
+        }
+    }
+
+    // The path of the center with the end of the dive marked.
+    let points = polyline(samples.iter().map(|sample| to_plot(sample.center)));
+    let _ = writeln!(
+        svg,
+        r##"<polyline points="{points}" fill="none" stroke="#d93a2b" stroke-width="1.5"/>"##
+    );
+    let last = samples[samples.len() - 1];
+    let (x, y) = to_plot(last.center);
+    let _ = writeln!(
+        svg,
+        r##"<circle cx="{x:.1}" cy="{y:.1}" r="3" fill="#d93a2b"/>"##
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{MARGIN}" y="{:.1}">end {:+.12} {:+.12}i, radius {:.3e}</text>"#,
+        MARGIN + plot_height - 4.0,
+        last.center.real,
+        last.center.imag,
+        last.radius
+    );
+
+    // The depth in halvings of the radius over the time of the dive.
+    let chart_top = plot_height + 2.0 * MARGIN;
+    let duration = last.time.max(SAMPLE_INTERVAL);
+    let first_radius = samples[0].radius;
+    let deepest = samples
+        .iter()
+        .map(|sample| (first_radius / sample.radius).log2())
+        .fold(1.0, f64::max);
+    let _ = writeln!(
+        svg,
+        r##"<rect x="{MARGIN}" y="{chart_top:.1}" width="{plot_width:.1}" height="{CHART_HEIGHT}" fill="none" stroke="#808080"/>"##
+    );
+    let points = polyline(samples.iter().map(|sample| {
+        let depth = (first_radius / sample.radius).log2();
+        (
+            MARGIN + sample.time / duration * plot_width,
+            chart_top + depth / deepest * CHART_HEIGHT,
+        )
+    }));
+    let _ = writeln!(
+        svg,
+        r##"<polyline points="{points}" fill="none" stroke="#2b8a3e" stroke-width="1.5"/>"##
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}">depth over {duration:.1} s, {deepest:.1} halvings of the radius</text>"#,
+        MARGIN + 4.0,
+        chart_top + 14.0
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Formats the points for the points attribute of a polyline.
+fn polyline(points: impl Iterator<Item = (f64, f64)>) -> String {
+    points
+        .map(|(x, y)| format!("{x:.2},{y:.2}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
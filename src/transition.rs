@@ -0,0 +1,264 @@
+//! The transitions that carry the camera from the end of one dive to the start of the next. Each one has two
+//! phases: leaving the old dive, which the director shows as zooming out state, and arriving at the start point of
+//! the next dive, which it shows as panning state. Between them the director switches back to the Mandelbrot set
+//! and may play a Julia interlude. Mixing them adds some variety to long sessions.
+
+use crate::math::ComplexNumber;
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+
+/// The radius that shows the whole Mandelbrot set, where the dives start.
+pub const START_RADIUS: f64 = 1.5;
+
+/// The zoom out speed in doublings of the radius per second.
+const ZOOM_OUT_SPEED: f64 = 4.0;
+
+/// Smooth time for panning between positions (in seconds).
+const PAN_SMOOTH_TIME: f64 = 0.25;
+
+/// Threshold for considering the pan complete (in complex plane units).
+const PAN_COMPLETE_THRESHOLD: f64 = 0.01;
+
+/// The radius the fly-through travels at.
+const FLY_RADIUS: f64 = 0.1;
+
+/// The speed of the fly-through in radii per second.
+const FLY_SPEED: f64 = 2.0;
+
+/// The shortest and the longest fly-through in seconds.
+const FLY_TIME_RANGE: (f64, f64) = (2.0, 8.0);
+
+/// The time the screen takes to go dark and to come back in seconds.
+const BLACKOUT_TIME: f64 = 1.0;
+
+/// The segments of the circle the iris opens.
+const IRIS_SEGMENTS: usize = 64;
+
+/// The kinds of transition, selectable with `transition` in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// Zooms out to the whole set and pans over to the next start point.
+    ZoomOut,
+    /// Zooms out a bit and flies over in a straight line.
+    FlyThrough,
+    /// Fades to black, jumps and fades back in.
+    Fade,
+    /// Closes a circle around the center, jumps and opens it again.
+    Iris,
+}
+
+impl TransitionKind {
+    /// All kinds of transition.
+    pub const ALL: [TransitionKind; 4] = [
+        TransitionKind::ZoomOut,
+        TransitionKind::FlyThrough,
+        TransitionKind::Fade,
+        TransitionKind::Iris,
+    ];
+
+    /// The name used in the configuration.
+    pub fn name(self) -> &'static str {
+        match self {
+            TransitionKind::ZoomOut => "zoom-out",
+            TransitionKind::FlyThrough => "fly-through",
+            TransitionKind::Fade => "fade",
+            TransitionKind::Iris => "iris",
+        }
+    }
+
+    /// Looks up a kind by its name.
+    pub fn from_name(name: &str) -> Option<TransitionKind> {
+        TransitionKind::ALL.into_iter().find(|k| k.name() == name)
+    }
+
+    /// Picks one of the kinds at random.
+    pub fn random() -> TransitionKind {
+        TransitionKind::ALL[gen_range(0, TransitionKind::ALL.len())]
+    }
+
+    /// Creates a transition of this kind.
+    pub fn create(self) -> Box<dyn Transition> {
+        match self {
+            TransitionKind::ZoomOut => Box::new(ZoomOutAndPan),
+            TransitionKind::FlyThrough => Box::new(FlyThrough::default()),
+            TransitionKind::Fade => Box::new(Blackout::new(false)),
+            TransitionKind::Iris => Box::new(Blackout::new(true)),
+        }
+    }
+}
+
+/// The part of the view a transition moves.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    /// The center of the view.
+    pub center: ComplexNumber,
+    /// The radius of the view.
+    pub radius: f64,
+    /// The velocity of the center used for smooth damping.
+    pub velocity: (f64, f64),
+}
+
+/// What hides the fractal during a transition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cover {
+    /// Black with the given opacity over the whole screen.
+    Fade(f32),
+    /// Black outside a circle, the circle reaches the screen corners at 1 and is closed at 0.
+    Iris(f32),
+}
+
+/// Carries the camera from one dive to the next. The director is shared with the render threads, hence the bounds.
+pub trait Transition: Send + Sync {
+    /// Moves away from the dive that ended. Returns true when done.
+    fn leave(&mut self, camera: &mut Camera, delta_time: f64) -> bool;
+
+    /// Brings the camera to the start point of the next dive. Returns true when it is there.
+    fn arrive(&mut self, camera: &mut Camera, target: ComplexNumber, delta_time: f64) -> bool;
+
+    /// What hides the fractal right now, None for transitions that only move the camera.
+    fn cover(&self) -> Option<Cover> {
+        None
+    }
+}
+
+/// Zooms out to the whole set and pans over, the original transition.
+#[derive(Debug, Clone, Copy)]
+pub struct ZoomOutAndPan;
+
+impl Transition for ZoomOutAndPan {
+    fn leave(&mut self, camera: &mut Camera, delta_time: f64) -> bool {
+        zoom_out_to(camera, START_RADIUS, delta_time)
+    }
+
+    fn arrive(&mut self, camera: &mut Camera, target: ComplexNumber, delta_time: f64) -> bool {
+        camera
+            .center
+            .smooth_damp_to(target, &mut camera.velocity, PAN_SMOOTH_TIME, delta_time);
+        (camera.center - target).sq_mag() < PAN_COMPLETE_THRESHOLD * PAN_COMPLETE_THRESHOLD
+    }
+}
+
+/// Zooms out to [`FLY_RADIUS`] and flies over in a straight line, easing in and out. The start zooming phase then
+/// continues from that depth, zooming out again for locations with a larger radius.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlyThrough {
+    /// Where the flight started and how long it takes, once it started.
+    flight: Option<(ComplexNumber, f64)>,
+    /// The seconds flown.
+    elapsed: f64,
+}
+
+impl Transition for FlyThrough {
+    fn leave(&mut self, camera: &mut Camera, delta_time: f64) -> bool {
+        zoom_out_to(camera, FLY_RADIUS, delta_time)
+    }
+
+    fn arrive(&mut self, camera: &mut Camera, target: ComplexNumber, delta_time: f64) -> bool {
+        let (from, duration) = *self.flight.get_or_insert_with(|| {
+            let distance = (target - camera.center).sq_mag().sqrt();
+            let duration = distance / (camera.radius * FLY_SPEED);
+            (
+                camera.center,
+                duration.clamp(FLY_TIME_RANGE.0, FLY_TIME_RANGE.1),
+            )
+        });
+        self.elapsed += delta_time;
+        let progress = (self.elapsed / duration).min(1.0);
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        camera.center = ComplexNumber::new(
+            from.real + (target.real - from.real) * eased,
+            from.imag + (target.imag - from.imag) * eased,
+        );
+        camera.velocity = (0.0, 0.0);
+        progress >= 1.0
+    }
+}
+
+/// Darkens the screen, jumps to the start point at the start radius and lights it up again, either as fade or
+/// as iris.
+#[derive(Debug, Clone, Copy)]
+pub struct Blackout {
+    /// Whether a closing circle darkens the screen instead of a fade.
+    iris: bool,
+    /// How dark the screen is in [0, 1].
+    darkness: f32,
+    /// Whether the camera already jumped to the start point.
+    jumped: bool,
+}
+
+impl Blackout {
+    /// Creates a fade or an iris.
+    pub fn new(iris: bool) -> Blackout {
+        Blackout {
+            iris,
+            darkness: 0.0,
+            jumped: false,
+        }
+    }
+}
+
+impl Transition for Blackout {
+    fn leave(&mut self, _camera: &mut Camera, delta_time: f64) -> bool {
+        self.darkness = (self.darkness + (delta_time / BLACKOUT_TIME) as f32).min(1.0);
+        self.darkness >= 1.0
+    }
+
+    fn arrive(&mut self, camera: &mut Camera, target: ComplexNumber, delta_time: f64) -> bool {
+        if !self.jumped {
+            self.jumped = true;
+            *camera = Camera {
+                center: target,
+                radius: START_RADIUS,
+                velocity: (0.0, 0.0),
+            };
+            return false;
+        }
+        self.darkness = (self.darkness - (delta_time / BLACKOUT_TIME) as f32).max(0.0);
+        self.darkness <= 0.0
+    }
+
+    fn cover(&self) -> Option<Cover> {
+        Some(if self.iris {
+            Cover::Iris(1.0 - self.darkness)
+        } else {
+            Cover::Fade(self.darkness)
+        })
+    }
+}
+
+/// Zooms the camera out till it reaches the radius. Returns true once it has, views that are already wider stay.
+fn zoom_out_to(camera: &mut Camera, radius: f64, delta_time: f64) -> bool {
+    if camera.radius >= radius {
+        return true;
+    }
+    camera.radius = (camera.radius * (delta_time * ZOOM_OUT_SPEED).exp2()).min(radius);
+    camera.radius >= radius
+}
+
+/// Draws the cover over the whole screen.
+pub fn draw_cover(cover: Cover) {
+    let (width, height) = (screen_width(), screen_height());
+    match cover {
+        Cover::Fade(opacity) => {
+            draw_rectangle(0.0, 0.0, width, height, Color::new(0.0, 0.0, 0.0, opacity))
+        }
+        Cover::Iris(openness) => {
+            // A ring of triangles from the circle to beyond the corners.
+            let center = vec2(width, height) * 0.5;
+            let outer = center.length() + 1.0;
+            let inner = outer * openness.clamp(0.0, 1.0);
+            for segment in 0..IRIS_SEGMENTS {
+                let angle =
+                    |index: usize| index as f32 / IRIS_SEGMENTS as f32 * std::f32::consts::TAU;
+                let (start, end) = (
+                    Vec2::from_angle(angle(segment)),
+                    Vec2::from_angle(angle(segment + 1)),
+                );
+                let (inner_start, inner_end) = (center + start * inner, center + end * inner);
+                let (outer_start, outer_end) = (center + start * outer, center + end * outer);
+                draw_triangle(inner_start, outer_start, outer_end, BLACK);
+                draw_triangle(inner_start, outer_end, inner_end, BLACK);
+            }
+        }
+    }
+}
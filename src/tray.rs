@@ -0,0 +1,84 @@
+//! Optional system tray icon, which offers the most important commands without focusing the window.
+
+use crate::commands::Command;
+use crate::math::{ComplexNumber, max_iter};
+use tray_icon::menu::{Menu, MenuEvent, MenuId, MenuItem};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+/// The edge length of the generated tray icon in pixels.
+const ICON_SIZE: u32 = 32;
+
+/// The tray icon together with the mapping from its menu entries to commands.
+pub struct TrayControls {
+    /// The icon has to be kept alive to stay visible.
+    _tray_icon: Option<TrayIcon>,
+    /// The ids of the menu entries and the commands they trigger.
+    entries: Vec<(MenuId, Command)>,
+}
+
+impl TrayControls {
+    /// Creates the tray icon and its menu. Failures are reported and leave us without tray icon.
+    pub fn new() -> TrayControls {
+        let menu = Menu::new();
+        let entries = [
+            ("Pause / Resume", Command::TogglePause),
+            ("Next target", Command::NextTarget),
+            ("Screenshot", Command::Screenshot),
+            ("Next palette", Command::NextPalette),
+            ("Quit", Command::Quit),
+        ]
+        .into_iter()
+        .filter_map(|(label, command)| {
+            let item = MenuItem::new(label, true, None);
+            menu.append(&item).ok()?;
+            Some((item.id().clone(), command))
+        })
+        .collect();
+
+        let mut builder = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("Mandelbrot Explorer");
+        if let Some(icon) = create_icon() {
+            builder = builder.with_icon(icon);
+        }
+        let tray_icon = builder
+            .build()
+            .inspect_err(|err| eprintln!("Could not create tray icon: {err}"))
+            .ok();
+
+        TrayControls {
+            _tray_icon: tray_icon,
+            entries,
+        }
+    }
+
+    /// Appends the commands selected in the tray menu since the last call.
+    pub fn poll(&self, commands: &mut Vec<Command>) {
+        while let Ok(event) = MenuEvent::receiver().try_recv() {
+            if let Some((_, command)) = self.entries.iter().find(|(id, _)| *id == event.id) {
+                commands.push(*command);
+            }
+        }
+    }
+}
+
+/// Renders a tiny Mandelbrot set as icon, None if the platform rejects it.
+fn create_icon() -> Option<Icon> {
+    let rgba = (0..ICON_SIZE * ICON_SIZE)
+        .flat_map(|idx| {
+            let x = (idx % ICON_SIZE) as f64 / ICON_SIZE as f64;
+            let y = (idx / ICON_SIZE) as f64 / ICON_SIZE as f64;
+            let iter =
+                ComplexNumber::new(x * 3.0 - 2.25, y * 3.0 - 1.5).get_iteration_till_termination();
+            if iter == max_iter() {
+                [0, 0, 0, 255]
+            } else {
+                let brightness = (255 * iter as u32 / max_iter() as u32 * 4).min(255) as u8;
+                [brightness, brightness / 2, 255 - brightness, 255]
+            }
+        })
+        .collect();
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE)
+        .inspect_err(|err| eprintln!("Could not create the tray icon image: {err}"))
+        .ok()
+}
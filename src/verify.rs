@@ -0,0 +1,148 @@
+//! The verification mode, which checks the iteration field against a slow reference. Every frame a few random
+//! pixels are iterated again in double-double arithmetic with about 106 bits of mantissa, including the position
+//! of the pixel itself. Pixels where the field disagrees are marked on screen and counted on the console.
+//!
+//! Disagreements show where the f64 computation runs out of precision, and any faster computation added later
+//! has to keep up with them.
+
+use crate::double_double::PreciseComplex;
+use crate::math::{Fractal, Viewport, max_iter};
+use macroquad::prelude::*;
+use std::ops::Range;
+
+/// The edge length of the markers of disagreeing pixels on screen.
+const MARKER_SIZE: f32 = 6.0;
+
+/// The color of the markers.
+const MARKER_COLOR: Color = Color::new(1.0, 0.1, 0.1, 0.9);
+
+/// How long a disagreement stays marked in seconds.
+const MARKER_LIFETIME: f64 = 2.0;
+
+/// The time between two reports on the console in seconds.
+const REPORT_INTERVAL: f64 = 5.0;
+
+/// A pixel where the field and the reference disagree.
+#[derive(Debug, Clone, Copy)]
+struct Disagreement {
+    /// The column of the pixel.
+    x: u32,
+    /// The row of the pixel.
+    y: u32,
+    /// The time it was found.
+    found_at: f64,
+}
+
+/// The state of the verification mode.
+#[derive(Debug)]
+pub struct Verifier {
+    /// The amount of pixels checked per frame.
+    samples: usize,
+    /// The state of the random generator picking the pixels. A private generator keeps the sequence of the
+    /// targets unchanged by the verification.
+    random_state: u64,
+    /// The disagreements still marked.
+    disagreements: Vec<Disagreement>,
+    /// The pixels checked since the last report.
+    checked: usize,
+    /// The disagreeing pixels since the last report.
+    failed: usize,
+    /// The largest difference in iterations since the last report.
+    worst: u16,
+    /// The time of the last report.
+    last_report: f64,
+}
+
+impl Verifier {
+    /// Creates the verifier checking the given amount of pixels per frame.
+    pub fn new(samples: usize) -> Verifier {
+        Verifier {
+            samples,
+            random_state: 0x9e37_79b9_7f4a_7c15,
+            disagreements: Vec::new(),
+            checked: 0,
+            failed: 0,
+            worst: 0,
+            last_report: get_time(),
+        }
+    }
+
+    /// Checks random pixels of the field computed for the view against the reference. Only the given columns
+    /// are sampled, the others are not shown.
+    pub fn check(
+        &mut self,
+        fractal: Fractal,
+        viewport: &Viewport,
+        field: &[u16],
+        columns: Range<usize>,
+    ) {
+        let now = get_time();
+        for _ in 0..self.samples {
+            let x = columns.start as u32 + self.next_random(columns.len() as u32);
+            let y = self.next_random(viewport.height);
+            let Some(&computed) = field.get((y * viewport.width + x) as usize) else {
+                continue;
+            };
+            let pixel = PreciseComplex::pixel(viewport, x, y);
+            let reference = match fractal {
+                Fractal::Mandelbrot => {
+                    PreciseComplex::iterations(PreciseComplex::default(), pixel, max_iter())
+                }
+                Fractal::Julia(c) => {
+                    PreciseComplex::iterations(pixel, PreciseComplex::from_complex(c), max_iter())
+                }
+            };
+            self.checked += 1;
+            if computed != reference {
+                self.failed += 1;
+                self.worst = self.worst.max(computed.abs_diff(reference));
+                self.disagreements.push(Disagreement {
+                    x,
+                    y,
+                    found_at: now,
+                });
+            }
+        }
+        self.disagreements
+            .retain(|disagreement| now - disagreement.found_at < MARKER_LIFETIME);
+
+        if now - self.last_report >= REPORT_INTERVAL {
+            if self.checked > 0 {
+                eprintln!(
+                    "Verification at radius {:.3e}: {} of {} pixels disagree, off by up to {} iterations.",
+                    viewport.radius, self.failed, self.checked, self.worst
+                );
+            }
+            self.checked = 0;
+            self.failed = 0;
+            self.worst = 0;
+            self.last_report = now;
+        }
+    }
+
+    /// Marks the disagreeing pixels, placed like the columns of the field the check got.
+    pub fn draw(&self, width: usize, height: usize, columns: Range<usize>) {
+        let scale_x = screen_width() / width as f32;
+        let scale_y = screen_height() / height as f32;
+        for disagreement in &self.disagreements {
+            let x = (disagreement.x as f32 - columns.start as f32 + 0.5) * scale_x;
+            let y = (disagreement.y as f32 + 0.5) * scale_y;
+            draw_rectangle_lines(
+                x - MARKER_SIZE * 0.5,
+                y - MARKER_SIZE * 0.5,
+                MARKER_SIZE,
+                MARKER_SIZE,
+                2.0,
+                MARKER_COLOR,
+            );
+        }
+    }
+
+    /// The next random number below the bound, from a xorshift generator.
+    fn next_random(&mut self, bound: u32) -> u32 {
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 7;
+        self.random_state ^= self.random_state << 17;
+        (self.random_state % bound.max(1) as u64) as u32
+    }
+}
@@ -0,0 +1,156 @@
+//! Offline zoom videos from keyframes, which bypasses the interactive loop:
+//! `mandelbrot-explorer video --center -0.743643887,0.131825904 --radius 1e-9 --size 1920x1080 --out dive`
+//!
+//! Only keyframes are rendered, spaced by a constant zoom factor of at most two from the start radius down to the
+//! target radius. The frames in between are composited from the two keyframes around them: the outer keyframe
+//! magnified fills the frame, the inner one shrunk covers the middle with its finer detail. Every keyframe thus
+//! serves `--video-frames-per-keyframe` frames, which makes long and smooth zoom videos a matter of minutes.
+//!
+//! The frames are written as numbered PNG files into the directory given by `--out`, `dive` by default, ready for
+//! a video encoder. The keyframes are computed in the precision tier of their radius like the window, so the
+//! video reaches as deep as the dives.
+
+use crate::config::CONFIG;
+use crate::export::save_png;
+use crate::math::Fractal;
+use crate::poster::{palette_settings_from_config, render_radius_from_config, report_progress};
+use crate::precision::DEEPEST_RADIUS;
+use crate::render::{RenderParams, RgbaBuffer, render_frame};
+use crate::transition::START_RADIUS;
+use rayon::prelude::*;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The largest zoom factor between two keyframes. The outer keyframe is magnified by up to this factor in the
+/// frames before the next one, so larger factors get blurry.
+const MAX_KEYFRAME_ZOOM: f64 = 2.0;
+
+/// The directory the frames are written to without `--out`.
+const DEFAULT_OUT: &str = "dive";
+
+/// Renders the video described by the config into a directory of frames.
+pub fn render_video_from_config() {
+    let target_radius = match render_radius_from_config(DEEPEST_RADIUS) {
+        Ok(radius) => radius,
+        Err(err) => {
+            eprintln!("Could not save the video: {err}");
+            return;
+        }
+    };
+    let directory = Path::new(CONFIG.render_out.as_deref().unwrap_or(DEFAULT_OUT));
+    match write_video(directory, target_radius) {
+        Ok(count) => println!("Saved {count} frames to {}", directory.display()),
+        Err(err) => eprintln!("Could not save the video: {err}"),
+    }
+}
+
+/// Renders the keyframes one after the other and writes the frames between each pair. Returns the amount of
+/// frames written.
+fn write_video(directory: &Path, target_radius: f64) -> std::io::Result<usize> {
+    std::fs::create_dir_all(directory)?;
+    let start_radius = START_RADIUS.max(target_radius);
+    let intervals = ((start_radius / target_radius).ln() / MAX_KEYFRAME_ZOOM.ln())
+        .ceil()
+        .max(1.0) as usize;
+    // Slightly less than the largest factor, so the keyframes end exactly at the target.
+    let zoom = (start_radius / target_radius).powf(1.0 / intervals as f64);
+    let frames_per_keyframe = CONFIG.video_frames_per_keyframe.max(1);
+    let count = intervals * frames_per_keyframe + 1;
+    let finished = AtomicUsize::new(0);
+
+    let params = |radius| {
+        let (width, height) = CONFIG.render_size;
+        RenderParams {
+            fractal: Fractal::Mandelbrot,
+            center: CONFIG.render_center,
+            radius,
+            width,
+            height,
+            palette_settings: palette_settings_from_config(),
+            coloring: CONFIG.coloring,
+            shading: CONFIG.shading,
+            color_settings: CONFIG.color_settings(),
+            iteration: CONFIG.iteration_settings(),
+        }
+    };
+    let write_frame = |index: usize, frame: &RgbaBuffer| {
+        save_png(
+            &directory.join(format!("frame_{index:06}.png")),
+            frame.width,
+            frame.height,
+            &frame.pixels,
+            None,
+        )?;
+        report_progress(finished.fetch_add(1, Ordering::Relaxed) + 1, count);
+        Ok::<(), std::io::Error>(())
+    };
+
+    let mut outer = render_frame(&params(start_radius));
+    for interval in 0..intervals {
+        let outer_radius = start_radius / zoom.powi(interval as i32);
+        let inner = render_frame(&params(outer_radius / zoom));
+        (0..frames_per_keyframe)
+            .into_par_iter()
+            .try_for_each(|step| {
+                let radius = outer_radius / zoom.powf(step as f64 / frames_per_keyframe as f64);
+                let frame = composite(&outer, &inner, radius / outer_radius, zoom);
+                write_frame(interval * frames_per_keyframe + step, &frame)
+            })?;
+        outer = inner;
+    }
+    write_frame(count - 1, &outer)?;
+    Ok(count)
+}
+
+/// Composites the frame at the given fraction of the outer keyframe radius from the two keyframes, the inner one
+/// has the radius of the outer one divided by the zoom.
+fn composite(outer: &RgbaBuffer, inner: &RgbaBuffer, scale: f64, zoom: f64) -> RgbaBuffer {
+    let (width, height) = (outer.width as usize, outer.height as usize);
+    let (center_x, center_y) = ((width / 2) as f64, (height / 2) as f64);
+    let mut pixels = vec![0u8; width * height * 4];
+    pixels
+        .par_chunks_exact_mut(width * 4)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let (dx, dy) = (x as f64 - center_x, y as f64 - center_y);
+                // The inner keyframe shows the middle in finer detail wherever it reaches.
+                let (inner_x, inner_y) =
+                    (center_x + dx * scale * zoom, center_y + dy * scale * zoom);
+                let color = if inner_x <= (width - 1) as f64
+                    && inner_y <= (height - 1) as f64
+                    && inner_x >= 0.0
+                    && inner_y >= 0.0
+                {
+                    sample(inner, inner_x, inner_y)
+                } else {
+                    sample(outer, center_x + dx * scale, center_y + dy * scale)
+                };
+                pixel.copy_from_slice(&color);
+            }
+        });
+    RgbaBuffer {
+        width: outer.width,
+        height: outer.height,
+        pixels,
+    }
+}
+
+/// Interpolates the color of the frame at the fractional pixel position bilinearly.
+fn sample(frame: &RgbaBuffer, x: f64, y: f64) -> [u8; 4] {
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let (x, y) = (
+        x.clamp(0.0, (width - 1) as f64),
+        y.clamp(0.0, (height - 1) as f64),
+    );
+    let (left, top) = (x.floor() as usize, y.floor() as usize);
+    let (right, bottom) = ((left + 1).min(width - 1), (top + 1).min(height - 1));
+    let (fx, fy) = (x - left as f64, y - top as f64);
+    let at =
+        |x: usize, y: usize, channel: usize| frame.pixels[(y * width + x) * 4 + channel] as f64;
+    std::array::from_fn(|channel| {
+        let upper = at(left, top, channel) * (1.0 - fx) + at(right, top, channel) * fx;
+        let lower = at(left, bottom, channel) * (1.0 - fx) + at(right, bottom, channel) * fx;
+        (upper * (1.0 - fy) + lower * fy).round() as u8
+    })
+}
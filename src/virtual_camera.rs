@@ -0,0 +1,56 @@
+//! Optional output of the frames as raw video, so streaming and AV pipelines can take the explorer in without screen
+//! capture. With the `virtual-camera` feature, `virtual-camera` names a file, typically a named pipe, that receives
+//! every frame as raw RGBA8 in the window size. ffmpeg turns it into a virtual webcam through v4l2loopback on Linux:
+//!
+//! ```text
+//! mkfifo /tmp/explorer.rgba
+//! ffmpeg -f rawvideo -pix_fmt rgba -video_size 1280x720 -framerate 60 -i /tmp/explorer.rgba \
+//!        -pix_fmt yuyv422 -f v4l2 /dev/video10
+//! ```
+//!
+//! A writer thread feeds the file, so a slow consumer drops frames instead of stalling the render loop. NDI needs
+//! the proprietary SDK, which is not linked, but the same pipe can feed an NDI sender.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+
+/// The amount of frames that may wait for the writer.
+const QUEUE_SIZE: usize = 2;
+
+/// Hands the frames to the writer thread of the output file.
+pub struct VirtualCamera {
+    /// Hands the RGBA8 frames over to the writer thread, which ends once it is dropped.
+    sender: SyncSender<Vec<u8>>,
+}
+
+impl VirtualCamera {
+    /// Starts the writer for the file. Opening a named pipe waits for its reader, so it happens on the writer thread.
+    pub fn start(path: &str) -> VirtualCamera {
+        let path = PathBuf::from(path);
+        let (sender, receiver) = sync_channel(QUEUE_SIZE);
+        std::thread::spawn(move || {
+            if let Err(err) = write_frames(&path, receiver) {
+                eprintln!("The virtual camera {} stopped: {err}", path.display());
+            }
+        });
+        VirtualCamera { sender }
+    }
+
+    /// Hands a frame over to the writer, or drops it if the consumer is behind.
+    pub fn push_frame(&self, rgba: &[u8]) {
+        // A full queue drops the frame, a stopped writer has reported why.
+        let _ = self.sender.try_send(rgba.to_vec());
+    }
+}
+
+/// Writes every received frame into the file, until the consumer goes away.
+fn write_frames(path: &Path, receiver: Receiver<Vec<u8>>) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    for rgba in receiver {
+        file.write_all(&rgba)?;
+        file.flush()?;
+    }
+    Ok(())
+}
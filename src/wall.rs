@@ -0,0 +1,234 @@
+//! Drives a tiled video wall from several machines. The director instance runs the zoom as usual and sends the view,
+//! the palette and its clock over UDP every frame. Follower instances do not zoom on their own, they render their
+//! tile of the wall from the latest state they received. The wall spans the view of the director in height, the
+//! tiles are laid out left to right and top to bottom, each with the aspect of the window.
+//!
+//! The states use the `key = value` lines of the session file.
+
+use crate::color_generation::{Coloring, Palette, PaletteSettings, Shading};
+use crate::colormaps::Cubehelix;
+use crate::config::CONFIG;
+use crate::math::{ComplexNumber, Fractal};
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::net::UdpSocket;
+
+/// The maximum size of a state datagram.
+const MAX_PACKET_SIZE: usize = 2048;
+
+/// A state that is older than the last one by more than this many seconds means the director restarted, so its
+/// clock begins anew.
+const RESTART_GAP: f64 = 5.0;
+
+/// The part an instance plays in the wall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallRole {
+    /// Runs the zoom and sends its state.
+    Director,
+    /// Renders its tile of the state it receives.
+    Follower,
+}
+
+impl WallRole {
+    /// Looks up a role by its name.
+    pub fn from_name(name: &str) -> Option<WallRole> {
+        match name {
+            "director" => Some(WallRole::Director),
+            "follower" => Some(WallRole::Follower),
+            _ => None,
+        }
+    }
+}
+
+/// What the director shares with the followers.
+#[derive(Debug, Clone, Copy)]
+pub struct WallState {
+    /// The clock of the director in seconds, which orders the states.
+    pub time: f64,
+    /// The fractal we show.
+    pub fractal: Fractal,
+    /// The center of the view of the director.
+    pub center: ComplexNumber,
+    /// The radius of the view of the director, which is half the height of the wall.
+    pub radius: f64,
+    /// The angle the view of the director is turned by in radians, the wall turns as a whole.
+    pub rotation: f64,
+    /// The palette settings without image palette and gradient, which every instance loads from its own config.
+    pub palette_settings: PaletteSettings,
+    /// The exterior coloring.
+    pub coloring: Coloring,
+    /// The shading.
+    pub shading: Shading,
+}
+
+impl WallState {
+    /// Writes the state as `key = value` lines.
+    fn encode(&self) -> String {
+        let p = &self.palette_settings;
+        let julia = match self.fractal {
+            Fractal::Mandelbrot => "none".to_owned(),
+            Fractal::Julia(c) => format!("{},{}", c.real, c.imag),
+        };
+        let entries: [(&str, String); 17] = [
+            ("time", self.time.to_string()),
+            ("julia", julia),
+            ("center-real", self.center.real.to_string()),
+            ("center-imag", self.center.imag.to_string()),
+            ("radius", self.radius.to_string()),
+            ("rotation", self.rotation.to_string()),
+            ("palette", p.palette.name().to_owned()),
+            ("hue-offset", p.hue_offset.to_string()),
+            ("hue-cycles", p.hue_cycles.to_string()),
+            ("saturation", p.saturation.to_string()),
+            ("value", p.value.to_string()),
+            ("cubehelix-start", p.cubehelix.start.to_string()),
+            ("cubehelix-rotations", p.cubehelix.rotations.to_string()),
+            ("cubehelix-hue", p.cubehelix.hue.to_string()),
+            ("cubehelix-gamma", p.cubehelix.gamma.to_string()),
+            ("coloring", self.coloring.name().to_owned()),
+            ("shading", self.shading.name().to_owned()),
+        ];
+        let mut content = String::new();
+        for (key, value) in entries {
+            let _ = writeln!(content, "{key} = {value}");
+        }
+        content
+    }
+
+    /// Reads a state written by [`WallState::encode`], None if it is incomplete.
+    fn decode(content: &str) -> Option<WallState> {
+        let values: HashMap<&str, &str> = content
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect();
+        let get = |key: &str| values.get(key).copied();
+        let number = |key: &str| get(key)?.parse::<f64>().ok();
+        let float = |key: &str| number(key).map(|v| v as f32);
+        let fractal = match get("julia")? {
+            "none" => Fractal::Mandelbrot,
+            julia => {
+                let (real, imag) = julia.split_once(',')?;
+                Fractal::Julia(ComplexNumber::new(real.parse().ok()?, imag.parse().ok()?))
+            }
+        };
+        Some(WallState {
+            time: number("time")?,
+            fractal,
+            center: ComplexNumber::new(number("center-real")?, number("center-imag")?),
+            radius: number("radius")?,
+            // Directors of older versions do not turn the view.
+            rotation: number("rotation").unwrap_or_default(),
+            palette_settings: PaletteSettings {
+                palette: Palette::from_name(get("palette")?)?,
+                hue_offset: float("hue-offset")?,
+                hue_cycles: float("hue-cycles")?,
+                saturation: float("saturation")?,
+                value: float("value")?,
+                cubehelix: Cubehelix {
+                    start: float("cubehelix-start")?,
+                    rotations: float("cubehelix-rotations")?,
+                    hue: float("cubehelix-hue")?,
+                    gamma: float("cubehelix-gamma")?,
+                },
+                image: None,
+                gradient: None,
+            },
+            coloring: Coloring::from_name(get("coloring")?)?,
+            shading: Shading::from_name(get("shading")?)?,
+        })
+    }
+
+    /// The center and radius of the tile in the given column and row of a wall with the given columns and rows. The
+    /// tiles turn along with the view, their rotation is the one of the state.
+    pub fn tile_view(&self, grid: (u32, u32), tile: (u32, u32)) -> (ComplexNumber, f64) {
+        let (columns, rows) = (grid.0.max(1) as f64, grid.1.max(1) as f64);
+        let (width, height) = (WINDOW_WIDTH as f64, WINDOW_HEIGHT as f64);
+        // The pixel step of the whole wall, whose height is twice the radius.
+        let step = 2.0 * self.radius / (rows * height);
+        let offset = ComplexNumber::new(
+            ((tile.0 as f64 + 0.5) * width - columns * width * 0.5) * step,
+            ((tile.1 as f64 + 0.5) * height - rows * height * 0.5) * step,
+        );
+        (
+            offset * ComplexNumber::from_angle(self.rotation) + self.center,
+            self.radius / rows,
+        )
+    }
+}
+
+/// The network side of the wall.
+pub struct VideoWall {
+    /// The part we play.
+    role: WallRole,
+    /// Sends as director, receives without blocking as follower.
+    socket: UdpSocket,
+    /// The addresses the director sends to.
+    targets: Vec<String>,
+    /// The time of the last state applied as follower.
+    last_time: f64,
+}
+
+impl VideoWall {
+    /// Opens the socket for the configured role, None if no role is configured or the socket can not be opened.
+    pub fn from_config() -> Option<VideoWall> {
+        let role = CONFIG.wall_role?;
+        let address = match role {
+            WallRole::Director => "0.0.0.0:0".to_owned(),
+            WallRole::Follower => format!("0.0.0.0:{}", CONFIG.wall_port),
+        };
+        let socket = UdpSocket::bind(&address)
+            .and_then(|socket| {
+                socket.set_broadcast(true)?;
+                socket.set_nonblocking(true)?;
+                Ok(socket)
+            })
+            .inspect_err(|err| eprintln!("Could not open the video wall socket {address}: {err}"))
+            .ok()?;
+        let targets = CONFIG
+            .wall_targets
+            .split(',')
+            .map(|target| target.trim().to_owned())
+            .filter(|target| !target.is_empty())
+            .collect();
+        Some(VideoWall {
+            role,
+            socket,
+            targets,
+            last_time: f64::NEG_INFINITY,
+        })
+    }
+
+    /// The part we play.
+    pub fn role(&self) -> WallRole {
+        self.role
+    }
+
+    /// Sends the state to the followers. Failed sends are dropped, the next frame sends a new state anyway.
+    pub fn send(&self, state: &WallState) {
+        let packet = state.encode();
+        for target in &self.targets {
+            let _ = self.socket.send_to(packet.as_bytes(), target.as_str());
+        }
+    }
+
+    /// The newest state received since the last call, None if there is none.
+    pub fn receive(&mut self) -> Option<WallState> {
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        let mut newest: Option<WallState> = None;
+        while let Ok(size) = self.socket.recv(&mut buffer) {
+            let Some(state) = std::str::from_utf8(&buffer[..size])
+                .ok()
+                .and_then(WallState::decode)
+            else {
+                continue;
+            };
+            if state.time > self.last_time || state.time < self.last_time - RESTART_GAP {
+                self.last_time = state.time;
+                newest = Some(state);
+            }
+        }
+        newest
+    }
+}
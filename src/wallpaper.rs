@@ -0,0 +1,328 @@
+//! Live wallpaper mode, which places the render window behind the desktop icons. On Windows the window gets
+//! re-parented into the WorkerW window behind the icons, on X11 it is marked as desktop window, which window
+//! managers keep below everything else. We do not get native handles from miniquad, so the window is looked up
+//! by its title.
+
+/// The frame rate we render with as wallpaper, which keeps the power draw moderate.
+const WALLPAPER_FPS: f64 = 20.0;
+
+/// The minimum time a frame should take in wallpaper mode.
+pub const WALLPAPER_FRAME_TIME: f64 = 1.0 / WALLPAPER_FPS;
+
+/// Attaches the window with the given title to the desktop background. Returns false if this failed or is not
+/// supported on this platform.
+pub fn attach_to_desktop(window_title: &str) -> bool {
+    platform::attach_to_desktop(window_title)
+}
+
+#[cfg(all(target_os = "windows", target_pointer_width = "64"))]
+mod platform {
+    use std::ffi::c_void;
+    use std::ptr::null_mut;
+
+    type Hwnd = *mut c_void;
+
+    /// Undocumented message that makes Progman spawn the WorkerW window behind the desktop icons.
+    const SPAWN_WORKER_MESSAGE: u32 = 0x052C;
+    const SMTO_NORMAL: u32 = 0x0000;
+    const GWL_STYLE: i32 = -16;
+    const WS_OVERLAPPEDWINDOW: isize = 0x00CF_0000;
+    const WS_POPUP: isize = 0x8000_0000;
+    const WS_CHILD: isize = 0x4000_0000;
+    const WS_VISIBLE: isize = 0x1000_0000;
+    const SM_CXSCREEN: i32 = 0;
+    const SM_CYSCREEN: i32 = 1;
+    const SWP_NOZORDER: u32 = 0x0004;
+    const SWP_FRAMECHANGED: u32 = 0x0020;
+    const SWP_SHOWWINDOW: u32 = 0x0040;
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn FindWindowW(class_name: *const u16, window_name: *const u16) -> Hwnd;
+        fn FindWindowExW(
+            parent: Hwnd,
+            child_after: Hwnd,
+            class_name: *const u16,
+            window_name: *const u16,
+        ) -> Hwnd;
+        fn SendMessageTimeoutW(
+            hwnd: Hwnd,
+            msg: u32,
+            w_param: usize,
+            l_param: isize,
+            flags: u32,
+            timeout: u32,
+            result: *mut usize,
+        ) -> isize;
+        fn EnumWindows(callback: extern "system" fn(Hwnd, isize) -> i32, l_param: isize) -> i32;
+        fn SetParent(child: Hwnd, new_parent: Hwnd) -> Hwnd;
+        fn GetWindowLongPtrW(hwnd: Hwnd, index: i32) -> isize;
+        fn SetWindowLongPtrW(hwnd: Hwnd, index: i32, new_long: isize) -> isize;
+        fn SetWindowPos(
+            hwnd: Hwnd,
+            insert_after: Hwnd,
+            x: i32,
+            y: i32,
+            cx: i32,
+            cy: i32,
+            flags: u32,
+        ) -> i32;
+        fn GetSystemMetrics(index: i32) -> i32;
+    }
+
+    /// Converts a string into a null terminated wide string.
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Called for every top level window, stores the WorkerW following the window that hosts the icons.
+    extern "system" fn find_worker(hwnd: Hwnd, l_param: isize) -> i32 {
+        // SAFETY: l_param is the pointer to the result handle handed over in attach_to_desktop.
+        unsafe {
+            let shell_view = FindWindowExW(
+                hwnd,
+                null_mut(),
+                wide("SHELLDLL_DefView").as_ptr(),
+                std::ptr::null(),
+            );
+            if !shell_view.is_null() {
+                *(l_param as *mut Hwnd) =
+                    FindWindowExW(null_mut(), hwnd, wide("WorkerW").as_ptr(), std::ptr::null());
+            }
+        }
+        1
+    }
+
+    pub fn attach_to_desktop(window_title: &str) -> bool {
+        // SAFETY: All calls get valid null terminated strings and check the returned handles.
+        unsafe {
+            let own_window = FindWindowW(std::ptr::null(), wide(window_title).as_ptr());
+            let progman = FindWindowW(wide("Progman").as_ptr(), std::ptr::null());
+            if own_window.is_null() || progman.is_null() {
+                return false;
+            }
+
+            let mut result = 0;
+            SendMessageTimeoutW(
+                progman,
+                SPAWN_WORKER_MESSAGE,
+                0,
+                0,
+                SMTO_NORMAL,
+                1000,
+                &mut result,
+            );
+
+            let mut worker: Hwnd = null_mut();
+            EnumWindows(find_worker, &mut worker as *mut Hwnd as isize);
+            if worker.is_null() {
+                // Newer Windows versions host the WorkerW inside Progman.
+                worker = FindWindowExW(
+                    progman,
+                    null_mut(),
+                    wide("WorkerW").as_ptr(),
+                    std::ptr::null(),
+                );
+            }
+            if worker.is_null() {
+                return false;
+            }
+
+            let style = GetWindowLongPtrW(own_window, GWL_STYLE);
+            SetWindowLongPtrW(
+                own_window,
+                GWL_STYLE,
+                (style & !WS_OVERLAPPEDWINDOW & !WS_POPUP) | WS_CHILD | WS_VISIBLE,
+            );
+            SetParent(own_window, worker);
+            SetWindowPos(
+                own_window,
+                null_mut(),
+                0,
+                0,
+                GetSystemMetrics(SM_CXSCREEN),
+                GetSystemMetrics(SM_CYSCREEN),
+                SWP_NOZORDER | SWP_FRAMECHANGED | SWP_SHOWWINDOW,
+            ) != 0
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::ffi::{CStr, CString, c_char, c_int, c_uchar, c_uint, c_ulong, c_void};
+    use std::ptr::null_mut;
+
+    type Display = c_void;
+    type Window = c_ulong;
+    type Atom = c_ulong;
+
+    const RTLD_NOW: c_int = 2;
+    const XA_ATOM: Atom = 4;
+    const PROP_MODE_REPLACE: c_int = 0;
+
+    unsafe extern "C" {
+        fn dlopen(filename: *const c_char, flag: c_int) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    }
+
+    /// The Xlib functions we need, loaded at runtime like miniquad does to avoid a link time dependency.
+    struct Xlib {
+        open_display: unsafe extern "C" fn(*const c_char) -> *mut Display,
+        close_display: unsafe extern "C" fn(*mut Display) -> c_int,
+        default_root_window: unsafe extern "C" fn(*mut Display) -> Window,
+        query_tree: unsafe extern "C" fn(
+            *mut Display,
+            Window,
+            *mut Window,
+            *mut Window,
+            *mut *mut Window,
+            *mut c_uint,
+        ) -> c_int,
+        fetch_name: unsafe extern "C" fn(*mut Display, Window, *mut *mut c_char) -> c_int,
+        free: unsafe extern "C" fn(*mut c_void) -> c_int,
+        intern_atom: unsafe extern "C" fn(*mut Display, *const c_char, c_int) -> Atom,
+        change_property: unsafe extern "C" fn(
+            *mut Display,
+            Window,
+            Atom,
+            Atom,
+            c_int,
+            c_int,
+            *const c_uchar,
+            c_int,
+        ) -> c_int,
+        unmap_window: unsafe extern "C" fn(*mut Display, Window) -> c_int,
+        map_window: unsafe extern "C" fn(*mut Display, Window) -> c_int,
+        lower_window: unsafe extern "C" fn(*mut Display, Window) -> c_int,
+        flush: unsafe extern "C" fn(*mut Display) -> c_int,
+    }
+
+    /// Resolves a symbol from a loaded library as function pointer of type `T`.
+    ///
+    /// # Safety
+    /// `T` has to be a function pointer type matching the symbol.
+    unsafe fn symbol<T>(handle: *mut c_void, name: &CStr) -> Option<T> {
+        unsafe {
+            let ptr = dlsym(handle, name.as_ptr());
+            (!ptr.is_null()).then(|| std::mem::transmute_copy::<*mut c_void, T>(&ptr))
+        }
+    }
+
+    impl Xlib {
+        /// Loads the library and resolves all symbols.
+        fn load() -> Option<Xlib> {
+            // SAFETY: The symbols are transmuted into the signatures documented by Xlib.
+            unsafe {
+                let handle = dlopen(c"libX11.so.6".as_ptr(), RTLD_NOW);
+                if handle.is_null() {
+                    return None;
+                }
+                Some(Xlib {
+                    open_display: symbol(handle, c"XOpenDisplay")?,
+                    close_display: symbol(handle, c"XCloseDisplay")?,
+                    default_root_window: symbol(handle, c"XDefaultRootWindow")?,
+                    query_tree: symbol(handle, c"XQueryTree")?,
+                    fetch_name: symbol(handle, c"XFetchName")?,
+                    free: symbol(handle, c"XFree")?,
+                    intern_atom: symbol(handle, c"XInternAtom")?,
+                    change_property: symbol(handle, c"XChangeProperty")?,
+                    unmap_window: symbol(handle, c"XUnmapWindow")?,
+                    map_window: symbol(handle, c"XMapWindow")?,
+                    lower_window: symbol(handle, c"XLowerWindow")?,
+                    flush: symbol(handle, c"XFlush")?,
+                })
+            }
+        }
+
+        /// Searches the window tree below `window` for a window with the given name.
+        ///
+        /// # Safety
+        /// The display has to be open.
+        unsafe fn find_window(
+            &self,
+            display: *mut Display,
+            window: Window,
+            title: &CStr,
+        ) -> Option<Window> {
+            unsafe {
+                let mut name: *mut c_char = null_mut();
+                if (self.fetch_name)(display, window, &mut name) != 0 && !name.is_null() {
+                    let matches = CStr::from_ptr(name) == title;
+                    (self.free)(name as *mut c_void);
+                    if matches {
+                        return Some(window);
+                    }
+                }
+
+                let (mut root, mut parent) = (0, 0);
+                let mut children: *mut Window = null_mut();
+                let mut count = 0;
+                if (self.query_tree)(
+                    display,
+                    window,
+                    &mut root,
+                    &mut parent,
+                    &mut children,
+                    &mut count,
+                ) == 0
+                    || children.is_null()
+                {
+                    return None;
+                }
+                let found = std::slice::from_raw_parts(children, count as usize)
+                    .iter()
+                    .find_map(|child| self.find_window(display, *child, title));
+                (self.free)(children as *mut c_void);
+                found
+            }
+        }
+    }
+
+    pub fn attach_to_desktop(window_title: &str) -> bool {
+        let (Some(xlib), Ok(title)) = (Xlib::load(), CString::new(window_title)) else {
+            return false;
+        };
+        // SAFETY: The display is checked for null and closed at the end, all pointers handed over are valid.
+        unsafe {
+            let display = (xlib.open_display)(std::ptr::null());
+            if display.is_null() {
+                return false;
+            }
+            let root = (xlib.default_root_window)(display);
+            let found = xlib.find_window(display, root, &title);
+            if let Some(window) = found {
+                let window_type = (xlib.intern_atom)(display, c"_NET_WM_WINDOW_TYPE".as_ptr(), 0);
+                let desktop_type =
+                    (xlib.intern_atom)(display, c"_NET_WM_WINDOW_TYPE_DESKTOP".as_ptr(), 0);
+                // Window managers only evaluate the type on mapping, so we map the window again.
+                (xlib.unmap_window)(display, window);
+                (xlib.change_property)(
+                    display,
+                    window,
+                    window_type,
+                    XA_ATOM,
+                    32,
+                    PROP_MODE_REPLACE,
+                    &desktop_type as *const Atom as *const c_uchar,
+                    1,
+                );
+                (xlib.map_window)(display, window);
+                (xlib.lower_window)(display, window);
+                (xlib.flush)(display);
+            }
+            (xlib.close_display)(display);
+            found.is_some()
+        }
+    }
+}
+
+#[cfg(not(any(
+    all(target_os = "windows", target_pointer_width = "64"),
+    target_os = "linux"
+)))]
+mod platform {
+    pub fn attach_to_desktop(_window_title: &str) -> bool {
+        false
+    }
+}
@@ -0,0 +1,84 @@
+//! Branding for public displays and streams: a text and a PNG logo drawn over the output with the configured
+//! position and opacity. With both configured the text goes below the logo.
+
+use crate::config::CONFIG;
+use macroquad::prelude::*;
+
+/// The font size of the text.
+const TEXT_SIZE: f32 = 32.0;
+
+/// The distance of the watermark to the screen border in pixels.
+const BORDER: f32 = 24.0;
+
+/// The space between logo and text in pixels.
+const GAP: f32 = 8.0;
+
+/// The configured text and logo.
+pub struct Watermark {
+    /// The text, None if only a logo is shown.
+    text: Option<String>,
+    /// The logo, None if only a text is shown.
+    logo: Option<Texture2D>,
+}
+
+impl Watermark {
+    /// Loads the configured logo. None if neither text nor logo is configured or the logo can not be loaded and
+    /// there is no text.
+    pub fn from_config() -> Option<Watermark> {
+        let logo = CONFIG.watermark_image.as_ref().and_then(|path| {
+            std::fs::read(path)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| {
+                    Image::from_file_with_format(&bytes, Some(ImageFormat::Png))
+                        .map_err(|err| err.to_string())
+                })
+                .map(|image| Texture2D::from_image(&image))
+                .inspect_err(|err| eprintln!("Could not load the watermark image `{path}`: {err}"))
+                .ok()
+        });
+        let text = CONFIG
+            .watermark_text
+            .clone()
+            .filter(|text| !text.is_empty());
+        (logo.is_some() || text.is_some()).then_some(Watermark { text, logo })
+    }
+
+    /// Draws the watermark over the screen.
+    pub fn draw(&self) {
+        let opacity = CONFIG.watermark_opacity.clamp(0.0, 1.0);
+        let logo_size = self.logo.as_ref().map_or(Vec2::ZERO, |logo| logo.size());
+        let text_extent = self
+            .text
+            .as_ref()
+            .map(|text| measure_text(text, None, TEXT_SIZE as u16, 1.0));
+        let text_height = text_extent.map_or(0.0, |extent| extent.height);
+        let gap = if self.logo.is_some() && self.text.is_some() {
+            GAP
+        } else {
+            0.0
+        };
+        let width = logo_size
+            .x
+            .max(text_extent.map_or(0.0, |extent| extent.width));
+        let height = logo_size.y + gap + text_height;
+        let (left, top) = CONFIG.watermark_position.place(width, height, BORDER);
+
+        if let Some(logo) = self.logo.as_ref() {
+            draw_texture(
+                logo,
+                left + (width - logo_size.x) * 0.5,
+                top,
+                Color::new(1.0, 1.0, 1.0, opacity),
+            );
+        }
+        if let (Some(text), Some(extent)) = (self.text.as_ref(), text_extent) {
+            draw_text(
+                text,
+                left + (width - extent.width) * 0.5,
+                top + logo_size.y + gap + extent.offset_y,
+                TEXT_SIZE,
+                Color::new(1.0, 1.0, 1.0, opacity),
+            );
+        }
+    }
+}
@@ -0,0 +1,716 @@
+//! The zoom director contains the state machine that decides where the camera is and where it goes next.
+
+use crate::config::CONFIG;
+use crate::double_double::DoubleDouble;
+use crate::focus_system::{
+    FocusParameter, FocusParameters, FocusScan, Retargets, StartPointForZoom,
+};
+use crate::julia::JuliaMorph;
+use crate::manual_control::ManualInput;
+use crate::math::{ComplexNumber, Fractal, Viewport, fractal_formula};
+use crate::minibrot::Minibrot;
+use crate::precision::DEEPEST_RADIUS;
+use crate::transition::{Camera, Cover, START_RADIUS, Transition, TransitionKind};
+use macroquad::rand::gen_range;
+
+/// The radius at which we start using the autofocus, unless configured otherwise.
+pub const DEFAULT_FOCUS_RADIUS: f64 = 0.05;
+
+/// The scaling factor we have for in scaling per second.
+const RADIUS_SCALING: f64 = 0.5;
+
+/// The smooth time we use for the autofocus.
+const FOCUS_SMOOTH_TIME: f64 = 1.25;
+
+/// The maximum zoom speed multiplier that can be set.
+const MAX_ZOOM_SPEED: f64 = 10.0;
+
+/// The pan speed in manual mode in radii per second.
+const MANUAL_PAN_SPEED: f64 = 1.0;
+
+/// The zoom speed in manual mode in halvings of the radius per second.
+const MANUAL_ZOOM_SPEED: f64 = 1.5;
+
+/// The zoom factor of a single mouse wheel notch.
+const MANUAL_ZOOM_STEP: f64 = 0.8;
+
+/// The smallest minibrot a dive ends on. The nucleus is found in double precision, which resolves it down to here.
+const MINIBROT_LIMIT_RADIUS: f64 = 1e-13;
+
+/// Below this radius the minibrot dives look for the minibrot to end on. Higher up the lowest period components
+/// are mostly bulbs on the main cardioid.
+const MINIBROT_SEARCH_RADIUS: f64 = 1e-4;
+
+/// A minibrot dive ends with the minibrot radius being this fraction of the view radius.
+const MINIBROT_FRAMING: f64 = 0.4;
+
+/// How long we look at the minibrot at the end of the dive in seconds.
+const MINIBROT_HOLD_TIME: f64 = 4.0;
+
+/// The time the zoom takes to get back to full speed when the automatic takes over from manual mode in seconds.
+const RESUME_EASE_TIME: f64 = 2.0;
+
+/// Represents the current state of the zoom system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoomState {
+    /// The start zooming phase, where we do not follow a focus. It zooms towards the start radius of the dive, in or
+    /// out depending on where the transition ended.
+    StartZooming,
+    /// Normal operation: zooming in and following focus.
+    ZoomingInAndFollowing,
+    /// The first phase of the transition, leaving the dive that ended, usually by zooming out.
+    ZoomingOut,
+    /// The second phase of the transition, getting to the start point of the next dive, usually by panning.
+    Panning,
+    /// Morphing the Julia set along the border of a bulb between two dives.
+    Interlude,
+    /// Manual exploration, the user steers and the automatic is off.
+    Manual,
+}
+
+impl ZoomState {
+    /// All states of the zoom system.
+    pub const ALL: [ZoomState; 6] = [
+        ZoomState::StartZooming,
+        ZoomState::ZoomingInAndFollowing,
+        ZoomState::ZoomingOut,
+        ZoomState::Panning,
+        ZoomState::Interlude,
+        ZoomState::Manual,
+    ];
+
+    /// The name of the state, used for persisting it.
+    pub fn name(self) -> &'static str {
+        match self {
+            ZoomState::StartZooming => "start-zooming",
+            ZoomState::ZoomingInAndFollowing => "zooming-in",
+            ZoomState::ZoomingOut => "zooming-out",
+            ZoomState::Panning => "panning",
+            ZoomState::Interlude => "interlude",
+            ZoomState::Manual => "manual",
+        }
+    }
+
+    /// Looks up a state by its name.
+    pub fn from_name(name: &str) -> Option<ZoomState> {
+        ZoomState::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// A copy of the complete director state, used to persist sessions.
+#[derive(Debug, Clone, Copy)]
+pub struct DirectorSnapshot {
+    /// The center of the view.
+    pub center: ComplexNumber,
+    /// The part of the center below the resolution of f64.
+    pub center_low: ComplexNumber,
+    /// The radius of the view.
+    pub radius: f64,
+    /// The angle the view is turned by in radians.
+    pub rotation: f64,
+    /// The velocity of the center used for smooth damping.
+    pub velocity: (f64, f64),
+    /// The state we are in.
+    pub zoom_state: ZoomState,
+    /// The radius at which the start zooming phase ends.
+    pub dive_start_radius: f64,
+    /// Multiplier for the zoom in speed.
+    pub zoom_speed: f64,
+    /// The best point found so far for the next dive.
+    pub start_point: ComplexNumber,
+    /// The score of the start point.
+    pub start_score: f32,
+    /// The remaining search steps for the start point.
+    pub start_remaining_iteration: u8,
+    /// The fractal we are diving through.
+    pub fractal: Fractal,
+}
+
+/// Why a dive ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiveEnd {
+    /// The radius reached the configured depth limit.
+    DepthLimit,
+    /// The dive took the configured maximum duration.
+    TimeLimit,
+    /// The dive looked at the minibrot it ended on long enough.
+    Minibrot,
+    /// The user skipped to the next target or left manual mode that way.
+    Skipped,
+    /// The user went to a location.
+    GoTo,
+}
+
+impl DiveEnd {
+    /// All reasons a dive ends for.
+    pub const ALL: [DiveEnd; 5] = [
+        DiveEnd::DepthLimit,
+        DiveEnd::TimeLimit,
+        DiveEnd::Minibrot,
+        DiveEnd::Skipped,
+        DiveEnd::GoTo,
+    ];
+
+    /// The name of the reason used in reports.
+    pub fn name(self) -> &'static str {
+        match self {
+            DiveEnd::DepthLimit => "depth limit",
+            DiveEnd::TimeLimit => "time limit",
+            DiveEnd::Minibrot => "minibrot",
+            DiveEnd::Skipped => "skipped",
+            DiveEnd::GoTo => "go to",
+        }
+    }
+}
+
+/// What happened during a dive that ended.
+#[derive(Debug, Clone, Copy)]
+pub struct DiveSummary {
+    /// Why the dive ended.
+    pub end: DiveEnd,
+    /// The seconds the dive zoomed in.
+    pub duration: f64,
+    /// The depth reached in halvings of the radius below the start radius.
+    pub depth: f32,
+    /// The score of the start point found by the search, None for dives that did not start at a searched point.
+    pub start_score: Option<f32>,
+    /// What the autofocus did with its target.
+    pub retargets: Retargets,
+}
+
+/// Drives the camera through the endless sequence of dives.
+pub struct ZoomDirector {
+    /// The center of the view in the complex number pane.
+    center: ComplexNumber,
+    /// The part of the center below the resolution of f64, which deep views need. The center only moves by offsets
+    /// in the size of the view, which are added in double-double arithmetic.
+    center_low: ComplexNumber,
+    /// The radius of the view, which corresponds to half the window height.
+    radius: f64,
+    /// The angle the view is turned by around the center in radians.
+    rotation: f64,
+    /// The velocity of the center used for smooth damping.
+    velocity: (f64, f64),
+    /// The search for the next point to dive into.
+    best_start_candidate: StartPointForZoom,
+    /// The search for the point to follow during the dive.
+    focus_scan: FocusScan,
+    /// The state we are currently in.
+    zoom_state: ZoomState,
+    /// The radius at which the start zooming phase ends and the autofocus takes over.
+    dive_start_radius: f64,
+    /// Multiplier for the zoom in speed.
+    zoom_speed: f64,
+    /// The parameters of the autofocus.
+    focus: FocusParameters,
+    /// The fractal we are diving through, Julia dives return to the Mandelbrot set when they end.
+    fractal: Fractal,
+    /// The transition to the next dive, while in the zooming out and panning states.
+    transition: Box<dyn Transition>,
+    /// The morphing interlude, while one is played.
+    interlude: Option<JuliaMorph>,
+    /// Whether the current dive looks for a minibrot to end on.
+    minibrot_dive: bool,
+    /// The minibrot the current dive ends on, once found.
+    minibrot: Option<Minibrot>,
+    /// The seconds we have been looking at the minibrot.
+    minibrot_hold: f64,
+    /// The seconds the current dive has been zooming in.
+    dive_time: f64,
+    /// The score of the start point of the current dive, None if it was not searched.
+    dive_start_score: Option<f32>,
+    /// The retargets of the current dive before the last reset of the focus scan.
+    dive_retargets: Retargets,
+    /// The summary of the last dive that ended, until it is taken.
+    finished_dive: Option<DiveSummary>,
+    /// The fraction of the zoom speed the dive zooms with, which eases back to 1 after manual mode.
+    zoom_ease: f64,
+}
+
+impl ZoomDirector {
+    /// Creates the director and already searches for the first point to dive into.
+    pub fn new() -> ZoomDirector {
+        let focus = FocusParameters::from_config();
+        ZoomDirector {
+            center: ComplexNumber::new(-0.5, 0.0),
+            center_low: ComplexNumber::default(),
+            radius: START_RADIUS,
+            rotation: CONFIG.rotation.to_radians(),
+            velocity: (0.0, 0.0),
+            best_start_candidate: StartPointForZoom::prepare_start(&focus),
+            focus_scan: FocusScan::default(),
+            zoom_state: ZoomState::Panning,
+            dive_start_radius: focus.focus_radius,
+            zoom_speed: 1.0,
+            focus,
+            fractal: Fractal::Mandelbrot,
+            transition: TransitionKind::ZoomOut.create(),
+            interlude: None,
+            minibrot_dive: false,
+            minibrot: None,
+            minibrot_hold: 0.0,
+            dive_time: 0.0,
+            dive_start_score: None,
+            dive_retargets: Retargets::default(),
+            finished_dive: None,
+            zoom_ease: 1.0,
+        }
+    }
+
+    /// Recreates a director from a saved state.
+    pub fn from_snapshot(snapshot: DirectorSnapshot) -> ZoomDirector {
+        ZoomDirector {
+            center: snapshot.center,
+            center_low: snapshot.center_low,
+            radius: snapshot.radius,
+            rotation: snapshot.rotation,
+            velocity: snapshot.velocity,
+            best_start_candidate: StartPointForZoom::restore(
+                snapshot.start_point,
+                snapshot.start_score,
+                snapshot.start_remaining_iteration,
+            ),
+            focus_scan: FocusScan::default(),
+            zoom_state: snapshot.zoom_state,
+            dive_start_radius: snapshot.dive_start_radius,
+            zoom_speed: snapshot.zoom_speed,
+            focus: FocusParameters::from_config(),
+            fractal: snapshot.fractal,
+            // Only the zoom out can continue from wherever a saved transition was.
+            transition: TransitionKind::ZoomOut.create(),
+            // The interlude is short, so a resumed one simply starts over on a new path.
+            interlude: (snapshot.zoom_state == ZoomState::Interlude)
+                .then(|| JuliaMorph::random(CONFIG.julia_interlude_duration)),
+            // A resumed dive continues as a normal one.
+            minibrot_dive: false,
+            minibrot: None,
+            minibrot_hold: 0.0,
+            dive_time: 0.0,
+            dive_start_score: None,
+            dive_retargets: Retargets::default(),
+            finished_dive: None,
+            zoom_ease: 1.0,
+        }
+    }
+
+    /// Captures the complete state of the director.
+    pub fn snapshot(&self) -> DirectorSnapshot {
+        DirectorSnapshot {
+            center: self.center,
+            center_low: self.center_low,
+            radius: self.radius,
+            rotation: self.rotation,
+            velocity: self.velocity,
+            zoom_state: self.zoom_state,
+            dive_start_radius: self.dive_start_radius,
+            zoom_speed: self.zoom_speed,
+            start_point: self.best_start_candidate.starting_point(),
+            start_score: self.best_start_candidate.score(),
+            start_remaining_iteration: self.best_start_candidate.remaining_iteration(),
+            fractal: self.fractal,
+        }
+    }
+
+    /// The state we are currently in.
+    pub fn zoom_state(&self) -> ZoomState {
+        self.zoom_state
+    }
+
+    /// The current center of the view.
+    pub fn center(&self) -> ComplexNumber {
+        self.center
+    }
+
+    /// The current radius of the view.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// The current angle the view is turned by in radians.
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    /// The view of the window including the low part of the center and the rotation.
+    pub fn viewport(&self) -> Viewport {
+        Viewport {
+            center_low: self.center_low,
+            rotation: ComplexNumber::from_angle(self.rotation),
+            ..Viewport::window(self.center, self.radius)
+        }
+    }
+
+    /// Places the center, dropping the low part.
+    fn set_center(&mut self, center: ComplexNumber) {
+        self.center = center;
+        self.center_low = ComplexNumber::default();
+    }
+
+    /// Moves the center by the offset without losing the low part.
+    fn move_center(&mut self, offset: ComplexNumber) {
+        let real = DoubleDouble::two_sum(self.center.real, self.center_low.real)
+            + DoubleDouble::from_f64(offset.real);
+        let imag = DoubleDouble::two_sum(self.center.imag, self.center_low.imag)
+            + DoubleDouble::from_f64(offset.imag);
+        self.center = ComplexNumber::new(real.hi, imag.hi);
+        self.center_low = ComplexNumber::new(real.lo, imag.lo);
+    }
+
+    /// The score of the spot the autofocus follows, None while it has not picked one yet.
+    pub fn focus_score(&self) -> Option<f32> {
+        self.focus_scan.target_score()
+    }
+
+    /// What hides the fractal during the transition to the next dive, if anything.
+    pub fn cover(&self) -> Option<Cover> {
+        match self.zoom_state {
+            ZoomState::ZoomingOut | ZoomState::Panning => self.transition.cover(),
+            _ => None,
+        }
+    }
+
+    /// The part of the view the transitions move.
+    fn camera(&self) -> Camera {
+        Camera {
+            center: self.center,
+            radius: self.radius,
+            velocity: self.velocity,
+        }
+    }
+
+    /// Takes over the view moved by a transition. The low part of the center stays while the center does not move.
+    fn set_camera(&mut self, camera: Camera) {
+        if camera.center != self.center {
+            self.set_center(camera.center);
+        }
+        self.radius = camera.radius;
+        self.velocity = camera.velocity;
+    }
+
+    /// The fractal we are diving through.
+    pub fn fractal(&self) -> Fractal {
+        self.fractal
+    }
+
+    /// Takes the summary of the last dive that ended, None if none ended since the last call.
+    pub fn take_finished_dive(&mut self) -> Option<DiveSummary> {
+        self.finished_dive.take()
+    }
+
+    /// Advances the state machine. The iteration field has to be the one computed for the current view.
+    pub fn update(&mut self, num_array: &[u16], delta_time: f64) {
+        if matches!(
+            self.zoom_state,
+            ZoomState::StartZooming | ZoomState::ZoomingInAndFollowing
+        ) {
+            self.rotation = (self.rotation + CONFIG.rotation_speed.to_radians() * delta_time)
+                .rem_euclid(std::f64::consts::TAU);
+            self.dive_time += delta_time;
+            self.zoom_ease = (self.zoom_ease + delta_time / RESUME_EASE_TIME).min(1.0);
+        }
+        match self.zoom_state {
+            ZoomState::StartZooming => {
+                let scaling = RADIUS_SCALING.powf(delta_time * self.zoom_speed * self.zoom_ease);
+                // Transitions like the fly-through end closer than a large start radius of a location, which is
+                // then reached by zooming out at the same speed.
+                let arrived = if self.radius < self.dive_start_radius {
+                    self.radius /= scaling;
+                    self.radius >= self.dive_start_radius
+                } else {
+                    self.radius *= scaling;
+                    self.radius <= self.dive_start_radius
+                };
+                if arrived {
+                    self.radius = self.dive_start_radius;
+                    self.dive_start_radius = self.focus.focus_radius;
+                    self.zoom_state = ZoomState::ZoomingInAndFollowing;
+                }
+            }
+            ZoomState::ZoomingInAndFollowing => {
+                if self.minibrot_dive
+                    && self.minibrot.is_none()
+                    && self.fractal == Fractal::Mandelbrot
+                    && self.radius < MINIBROT_SEARCH_RADIUS
+                {
+                    self.minibrot = Minibrot::find(self.center, self.radius).filter(|minibrot| {
+                        minibrot.size < self.radius * MINIBROT_FRAMING
+                            && minibrot.size > MINIBROT_LIMIT_RADIUS * MINIBROT_FRAMING
+                    });
+                }
+
+                // The target relative to the center, absolute positions would lose the low part of the center.
+                let target_offset = match self.minibrot {
+                    Some(minibrot) => minibrot.nucleus - self.center,
+                    None => self.focus_scan.update(
+                        num_array,
+                        ComplexNumber::default(),
+                        self.radius,
+                        ComplexNumber::from_angle(self.rotation),
+                        &self.focus,
+                    ),
+                };
+
+                // smoothly move center towards the target using the existing ComplexNumber smoothing
+                let mut offset = ComplexNumber::default();
+                offset.smooth_damp_to(
+                    target_offset,
+                    &mut self.velocity,
+                    FOCUS_SMOOTH_TIME,
+                    delta_time,
+                );
+                self.move_center(offset);
+                self.focus_scan.shift(offset);
+
+                if let Some(minibrot) = self.minibrot {
+                    let final_radius = minibrot.size / MINIBROT_FRAMING;
+                    if self.radius <= final_radius {
+                        self.radius = final_radius;
+                        self.minibrot_hold += delta_time;
+                        if self.minibrot_hold >= MINIBROT_HOLD_TIME {
+                            self.begin_zoom_out(DiveEnd::Minibrot);
+                        }
+                        return;
+                    }
+                }
+
+                // Check if we need to transition out
+                if self.radius < self.focus.depth_limit {
+                    self.begin_zoom_out(DiveEnd::DepthLimit);
+                } else if CONFIG.max_dive_duration > 0.0
+                    && self.dive_time >= CONFIG.max_dive_duration
+                {
+                    self.begin_zoom_out(DiveEnd::TimeLimit);
+                }
+                self.radius *= RADIUS_SCALING.powf(delta_time * self.zoom_speed * self.zoom_ease);
+            }
+            ZoomState::ZoomingOut => {
+                self.best_start_candidate
+                    .try_improve(self.focus.focus_radius);
+                let mut camera = self.camera();
+                let left = self.transition.leave(&mut camera, delta_time);
+                self.set_camera(camera);
+                if left {
+                    self.fractal = Fractal::Mandelbrot;
+                    self.set_center(self.center);
+                    self.zoom_state = ZoomState::Panning;
+                    // Interludes only follow transitions that end showing the whole set.
+                    if CONFIG.julia_interludes && self.radius >= START_RADIUS {
+                        let morph = JuliaMorph::random(CONFIG.julia_interlude_duration);
+                        self.fractal = Fractal::Julia(morph.parameter());
+                        self.interlude = Some(morph);
+                        self.set_center(ComplexNumber::default());
+                        self.zoom_state = ZoomState::Interlude;
+                    }
+                }
+            }
+            ZoomState::Panning => {
+                let target = self.best_start_candidate.starting_point();
+                let mut camera = self.camera();
+                let arrived = self.transition.arrive(&mut camera, target, delta_time);
+                self.set_camera(camera);
+                if arrived {
+                    self.set_center(self.best_start_candidate.starting_point());
+                    self.center_low = self.best_start_candidate.starting_point_low();
+                    let score = self.best_start_candidate.score();
+                    self.begin_dive((score > 0.0).then_some(score));
+                    // The minibrots are found with the derivative of z² + c.
+                    self.minibrot_dive = fractal_formula().is_quadratic()
+                        && gen_range(0.0, 1.0) < CONFIG.minibrot_dive_chance;
+                    self.zoom_state = ZoomState::StartZooming;
+                }
+            }
+            ZoomState::Interlude => {
+                self.best_start_candidate
+                    .try_improve(self.focus.focus_radius);
+                let finished = self
+                    .interlude
+                    .as_mut()
+                    .is_none_or(|morph| morph.advance(delta_time));
+                match self.interlude {
+                    Some(morph) if !finished => self.fractal = Fractal::Julia(morph.parameter()),
+                    _ => {
+                        self.interlude = None;
+                        self.fractal = Fractal::Mandelbrot;
+                        self.zoom_state = ZoomState::Panning;
+                    }
+                }
+            }
+            ZoomState::Manual => {}
+        };
+    }
+
+    /// Switches between manual exploration and the automatic. The automatic continues from the current view.
+    pub fn toggle_manual(&mut self) {
+        if self.zoom_state == ZoomState::Manual {
+            self.resume_automatic();
+            return;
+        }
+        self.velocity = (0.0, 0.0);
+        self.dive_retargets += self.focus_scan.retargets();
+        self.focus_scan.reset();
+        self.zoom_state = ZoomState::Manual;
+    }
+
+    /// Hands manual mode back to the automatic, which dives on from the current view. Wide views first zoom in
+    /// to the focus radius like a new dive, and the zoom eases in, so the hand over shows no jump.
+    pub fn resume_automatic(&mut self) {
+        if self.zoom_state != ZoomState::Manual {
+            return;
+        }
+        self.velocity = (0.0, 0.0);
+        self.focus_scan.reset();
+        self.zoom_ease = 0.0;
+        if self.radius > self.focus.focus_radius {
+            self.begin_dive(None);
+            self.dive_start_radius = self.focus.focus_radius;
+            self.zoom_state = ZoomState::StartZooming;
+        } else {
+            self.zoom_state = ZoomState::ZoomingInAndFollowing;
+        }
+    }
+
+    /// Switches to manual mode and places the view directly, as done by touch gestures.
+    pub fn set_manual_view(&mut self, center: ComplexNumber, radius: f64) {
+        self.velocity = (0.0, 0.0);
+        self.zoom_state = ZoomState::Manual;
+        self.set_center(center);
+        self.radius = radius.clamp(DEEPEST_RADIUS, START_RADIUS);
+    }
+
+    /// Shows the given view of the given fractal without zooming on our own, as done by video wall followers.
+    pub fn follow(&mut self, fractal: Fractal, center: ComplexNumber, radius: f64, rotation: f64) {
+        self.velocity = (0.0, 0.0);
+        self.zoom_state = ZoomState::Manual;
+        self.fractal = fractal;
+        self.set_center(center);
+        self.radius = radius;
+        self.rotation = rotation;
+    }
+
+    /// Applies the steering input, if we are in manual mode.
+    pub fn steer(&mut self, input: ManualInput, delta_time: f64) {
+        if self.zoom_state != ZoomState::Manual {
+            return;
+        }
+        // The input moves along the screen axes, which turn with the view.
+        let pan = self.radius * MANUAL_PAN_SPEED * delta_time;
+        self.move_center(
+            ComplexNumber::new(input.pan_x * pan, input.pan_y * pan)
+                * ComplexNumber::from_angle(self.rotation),
+        );
+        self.radius *= RADIUS_SCALING.powf(input.zoom * MANUAL_ZOOM_SPEED * delta_time)
+            * MANUAL_ZOOM_STEP.powf(input.zoom_steps);
+        self.radius = self.radius.clamp(DEEPEST_RADIUS, START_RADIUS);
+    }
+
+    /// The zoom in speed as multiple of the default speed.
+    pub fn zoom_speed(&self) -> f64 {
+        self.zoom_speed
+    }
+
+    /// Sets the zoom in speed as multiple of the default speed.
+    pub fn set_zoom_speed(&mut self, zoom_speed: f64) {
+        self.zoom_speed = zoom_speed.clamp(0.0, MAX_ZOOM_SPEED);
+    }
+
+    /// The parameters of the autofocus.
+    pub fn focus_parameters(&self) -> FocusParameters {
+        self.focus
+    }
+
+    /// Replaces the parameters of the autofocus, as when the director is recreated.
+    pub fn set_focus_parameters(&mut self, focus: FocusParameters) {
+        self.focus = focus;
+    }
+
+    /// Changes a parameter of the autofocus, which takes effect with the next scan, start point search or dive.
+    pub fn set_focus_parameter(&mut self, parameter: FocusParameter, value: f64) {
+        self.focus.set(parameter, value);
+    }
+
+    /// Leaves the current dive early and moves on to the next target. Does nothing if we are already on the way.
+    pub fn skip_to_next_target(&mut self) {
+        if matches!(
+            self.zoom_state,
+            ZoomState::StartZooming | ZoomState::ZoomingInAndFollowing | ZoomState::Manual
+        ) {
+            self.begin_zoom_out(DiveEnd::Skipped);
+        }
+    }
+
+    /// Moves to the given location by zooming out, panning over and zooming in again. The center is given with its
+    /// part below the resolution of f64. The dive then continues from there with the autofocus.
+    pub fn go_to(&mut self, center: ComplexNumber, center_low: ComplexNumber, radius: f64) {
+        // A NaN radius would never end the start zoom, so broken locations from the controls are ignored.
+        if !(center.real.is_finite()
+            && center.imag.is_finite()
+            && radius.is_finite()
+            && radius > 0.0)
+        {
+            return;
+        }
+        if matches!(
+            self.zoom_state,
+            ZoomState::StartZooming | ZoomState::ZoomingInAndFollowing | ZoomState::Manual
+        ) {
+            self.begin_zoom_out(DiveEnd::GoTo);
+        }
+        self.best_start_candidate
+            .set_starting_point(center, center_low);
+        self.dive_start_radius = radius.clamp(DEEPEST_RADIUS, START_RADIUS);
+    }
+
+    /// Dives through the Julia set of c, the autofocus takes over right away. When the dive ends, the zoom out
+    /// returns to the Mandelbrot set.
+    pub fn start_julia_dive(&mut self, c: ComplexNumber) {
+        self.velocity = (0.0, 0.0);
+        self.focus_scan.reset();
+        self.fractal = Fractal::Julia(c);
+        self.set_center(ComplexNumber::default());
+        self.radius = START_RADIUS;
+        self.begin_dive(None);
+        self.zoom_state = ZoomState::ZoomingInAndFollowing;
+    }
+
+    /// Starts keeping track of a new dive, which began at a start point with the given score.
+    fn begin_dive(&mut self, start_score: Option<f32>) {
+        self.dive_time = 0.0;
+        self.dive_start_score = start_score;
+        self.dive_retargets = Retargets::default();
+    }
+
+    /// Ends the dive for the reason, switches into the zoom out state and starts searching for the next point of
+    /// interest.
+    fn begin_zoom_out(&mut self, end: DiveEnd) {
+        self.velocity = (0.0, 0.0);
+        self.dive_retargets += self.focus_scan.retargets();
+        let depth = (START_RADIUS / self.radius).log2().max(0.0) as f32;
+        self.finished_dive = Some(DiveSummary {
+            end,
+            duration: self.dive_time,
+            depth,
+            start_score: self.dive_start_score,
+            retargets: self.dive_retargets,
+        });
+        self.focus_scan.reset();
+        self.minibrot_dive = false;
+        self.minibrot = None;
+        self.minibrot_hold = 0.0;
+        if self.fractal == Fractal::Mandelbrot {
+            self.best_start_candidate.record_dive(self.center, depth);
+        }
+        // In zooming out we search our new point.
+        self.best_start_candidate
+            .reset_iteration(self.focus.start_min_score);
+        let kind = CONFIG.transition.unwrap_or_else(TransitionKind::random);
+        // Flying at depth would show the cut from a Julia set back to the Mandelbrot set.
+        self.transition = match kind {
+            TransitionKind::FlyThrough if self.fractal != Fractal::Mandelbrot => {
+                TransitionKind::ZoomOut
+            }
+            kind => kind,
+        }
+        .create();
+        self.zoom_state = ZoomState::ZoomingOut;
+    }
+}